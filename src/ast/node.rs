@@ -4,13 +4,15 @@ pub enum Node {
     Program(Program),
 
     // Statement nodes
+    Block(Block),
     Function(Function),
     Assignment(Assignment),
-    #[allow(dead_code)]
+    Declaration(Declaration),
     If(If),
     #[allow(dead_code)]
     While(While),
     Return(Return),
+    Import(Import),
     ExpressionStatement(Expression),
 
     // Expression nodes
@@ -19,17 +21,43 @@ pub enum Node {
     Literal(Literal),
     Identifier(Identifier),
     Call(Call),
+    Subscript(Subscript),
+    Await(Await),
+    Starred(Starred),
+    Tuple(Tuple),
+    List(List),
+    Dict(Dict),
+    Set(Set),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Program {
     pub statements: Vec<Node>,
+    pub docstring: Option<String>,
+}
+
+/// An indented suite containing more than one statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub statements: Vec<Node>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Assignment {
-    pub name: String,
+    /// What the value is stored into, left to right: names, dotted attribute
+    /// names such as `obj.x`, or subscripts. `a = b = 0` has two targets.
+    pub targets: Vec<Node>,
     pub value: Box<Node>,
+    pub annotation: Option<String>,
+    /// Source line the statement starts on (1-based)
+    pub line: usize,
+}
+
+/// A bare annotation such as `x: int`, which declares a type without binding a value
+#[derive(Debug, Clone, PartialEq)]
+pub struct Declaration {
+    pub name: String,
+    pub annotation: String,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -37,22 +65,38 @@ pub struct If {
     pub condition: Box<Node>,
     pub then_branch: Box<Node>,
     pub else_branch: Option<Box<Node>>,
+    /// Source line the statement starts on (1-based)
+    pub line: usize,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct While {
     pub condition: Box<Node>,
     pub body: Box<Node>,
+    /// Source line the statement starts on (1-based)
+    pub line: usize,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Return {
     pub value: Option<Box<Node>>,
+    /// Source line the statement starts on (1-based)
+    pub line: usize,
+}
+
+/// `import a.b`, only meaningful when calls can reach CPython
+#[derive(Debug, Clone, PartialEq)]
+pub struct Import {
+    pub module: String,
+    /// Source line the statement starts on (1-based)
+    pub line: usize,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Expression {
     pub expression: Box<Node>,
+    /// Source line the statement starts on (1-based)
+    pub line: usize,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -71,21 +115,13 @@ pub enum BinaryOperator {
     FloorDivide,
     Modulo,
     Power,
-    #[allow(dead_code)]
     Equal,
-    #[allow(dead_code)]
     NotEqual,
-    #[allow(dead_code)]
     Less,
-    #[allow(dead_code)]
     Greater,
-    #[allow(dead_code)]
     LessEqual,
-    #[allow(dead_code)]
     GreaterEqual,
-    #[allow(dead_code)]
     And,
-    #[allow(dead_code)]
     Or,
 }
 
@@ -99,7 +135,6 @@ pub struct Unary {
 pub enum UnaryOperator {
     Plus,
     Minus,
-    #[allow(dead_code)]
     Not,
 }
 
@@ -138,13 +173,66 @@ pub struct Identifier {
 pub struct Function {
     pub name: String,
     pub parameters: Vec<String>,
+    /// Annotation of each parameter, parallel to `parameters`
+    pub parameter_types: Vec<Option<String>>,
+    pub return_type: Option<String>,
     pub body: Box<Node>,
+    pub docstring: Option<String>,
+    /// Declared with `async def`
+    pub is_async: bool,
+    /// Source line the statement starts on (1-based)
+    pub line: usize,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Call {
     pub callee: String,
     pub arguments: Vec<Node>,
+    /// Keyword arguments `name=value`, in source order; `**mapping` has no name
+    pub keywords: Vec<(Option<String>, Node)>,
+}
+
+/// `await value`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Await {
+    pub value: Box<Node>,
+}
+
+/// `value[index]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subscript {
+    pub value: Box<Node>,
+    pub index: Box<Node>,
+}
+
+/// `*value`, an argument whose items are passed as separate positional arguments
+#[derive(Debug, Clone, PartialEq)]
+pub struct Starred {
+    pub value: Box<Node>,
+}
+
+/// `(a, b)`, `(a,)` or `()`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tuple {
+    pub elements: Vec<Node>,
+}
+
+/// `[a, b]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct List {
+    pub elements: Vec<Node>,
+}
+
+/// `{key: value, ...}`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dict {
+    pub entries: Vec<(Node, Node)>,
+}
+
+/// `{a, b}`; `{}` is an empty dict
+#[derive(Debug, Clone, PartialEq)]
+pub struct Set {
+    pub elements: Vec<Node>,
 }
 
 impl Default for Program {
@@ -157,6 +245,48 @@ impl Program {
     pub fn new() -> Self {
         Program {
             statements: Vec::new(),
+            docstring: None,
+        }
+    }
+}
+
+impl Assignment {
+    /// Names bound by the plain-name targets, skipping attributes and subscripts
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.targets.iter().filter_map(|target| match target {
+            Node::Identifier(identifier) if !identifier.name.contains('.') => {
+                Some(identifier.name.as_str())
+            }
+            _ => None,
+        })
+    }
+}
+
+impl Node {
+    /// Source line of a statement that generates code
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Node::Assignment(assignment) => Some(assignment.line),
+            Node::If(if_stmt) => Some(if_stmt.line),
+            Node::While(while_stmt) => Some(while_stmt.line),
+            Node::Return(return_stmt) => Some(return_stmt.line),
+            Node::Import(import) => Some(import.line),
+            Node::ExpressionStatement(expr_stmt) => Some(expr_stmt.line),
+            Node::Function(function) => Some(function.line),
+            _ => None,
+        }
+    }
+
+    /// The text of a docstring if this statement is a bare string literal
+    pub fn as_docstring(&self) -> Option<&str> {
+        match self {
+            Node::ExpressionStatement(expr_stmt) => match &*expr_stmt.expression {
+                Node::Literal(Literal {
+                    value: LiteralValue::String(value),
+                }) => Some(value),
+                _ => None,
+            },
+            _ => None,
         }
     }
 }
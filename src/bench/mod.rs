@@ -0,0 +1,64 @@
+use crate::codegen::heap_stats::{HEAP_STATS_VARIABLE, SUMMARY_PREFIX};
+use std::io;
+use std::process::{Command, Output};
+
+/// Heap use of one type of object over a program run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeStats {
+    /// Python type name, such as "str"
+    pub type_name: String,
+    /// Objects allocated with `pycc_alloc`
+    pub allocations: u64,
+    /// Bytes requested by those allocations
+    pub bytes: u64,
+}
+
+/// Heap statistics a compiled program printed because `PYCC_HEAP_STATS=1` was set
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeapStats {
+    pub types: Vec<TypeStats>,
+}
+
+impl HeapStats {
+    /// Pick the summary lines out of a program's stderr
+    ///
+    /// A program that never allocated prints no summary and yields no types.
+    pub fn parse(stderr: &str) -> HeapStats {
+        let types = stderr
+            .lines()
+            .filter_map(|line| line.strip_prefix(SUMMARY_PREFIX))
+            .filter_map(|summary| {
+                let mut fields = summary.split_whitespace();
+                let type_name = fields.next()?.to_string();
+                let allocations = fields.next()?.strip_prefix("allocations=")?.parse().ok()?;
+                let bytes = fields.next()?.strip_prefix("bytes=")?.parse().ok()?;
+                Some(TypeStats {
+                    type_name,
+                    allocations,
+                    bytes,
+                })
+            })
+            .collect();
+        HeapStats { types }
+    }
+
+    /// Objects allocated across all types
+    pub fn allocations(&self) -> u64 {
+        self.types.iter().map(|stats| stats.allocations).sum()
+    }
+
+    /// Bytes allocated across all types
+    pub fn bytes(&self) -> u64 {
+        self.types.iter().map(|stats| stats.bytes).sum()
+    }
+}
+
+/// Run a compiled program with heap statistics enabled
+///
+/// Returns the program's output alongside the statistics parsed from it, so
+/// benchmarks can check results and allocations from the same run.
+pub fn measure(program: &mut Command) -> io::Result<(Output, HeapStats)> {
+    let output = program.env(HEAP_STATS_VARIABLE, "1").output()?;
+    let stats = HeapStats::parse(&String::from_utf8_lossy(&output.stderr));
+    Ok((output, stats))
+}
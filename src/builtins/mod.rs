@@ -0,0 +1,91 @@
+use crate::typecheck::Type;
+
+/// Code the generator emits for a builtin it compiles natively
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lowering {
+    Print,
+    Input,
+    Repr,
+    Hash,
+    Id,
+    Int,
+}
+
+/// A function callable without an import
+///
+/// The registry is the one place that knows about builtins: the type checker
+/// takes their result types from it and the code generator their arity and
+/// lowering, so a builtin added here is understood by both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Builtin {
+    pub name: &'static str,
+    /// Fewest positional arguments accepted
+    pub min_arguments: usize,
+    /// Most positional arguments accepted, `None` when variadic
+    pub max_arguments: Option<usize>,
+    /// Type of the call's result
+    pub return_type: Type,
+    /// How compiled code implements it, `None` when only CPython can (`--python-interop`)
+    pub lowering: Option<Lowering>,
+}
+
+const fn builtin(
+    name: &'static str,
+    min_arguments: usize,
+    max_arguments: Option<usize>,
+    return_type: Type,
+    lowering: Option<Lowering>,
+) -> Builtin {
+    Builtin {
+        name,
+        min_arguments,
+        max_arguments,
+        return_type,
+        lowering,
+    }
+}
+
+/// Every builtin pycc knows, compiled or not
+pub const BUILTINS: &[Builtin] = &[
+    builtin("print", 0, None, Type::None, Some(Lowering::Print)),
+    builtin("input", 0, Some(1), Type::Str, Some(Lowering::Input)),
+    builtin("repr", 1, Some(1), Type::Str, Some(Lowering::Repr)),
+    builtin("hash", 1, Some(1), Type::Int, Some(Lowering::Hash)),
+    builtin("id", 1, Some(1), Type::Int, Some(Lowering::Id)),
+    builtin("int", 1, Some(1), Type::Int, Some(Lowering::Int)),
+    builtin("len", 1, Some(1), Type::Int, None),
+    builtin("abs", 1, Some(1), Type::Any, None),
+    builtin("float", 0, Some(1), Type::Float, None),
+    builtin("str", 0, Some(1), Type::Str, None),
+    builtin("bool", 0, Some(1), Type::Bool, None),
+];
+
+/// The builtin called `name`, if there is one
+pub fn lookup(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+impl Builtin {
+    /// CPython's complaint about calling the builtin with `given` positional arguments, if any
+    pub fn arity_error(&self, given: usize) -> Option<String> {
+        let name = self.name;
+        let plural = |count: usize| if count == 1 { "" } else { "s" };
+        match self.max_arguments {
+            Some(max) if max == self.min_arguments && given != max => Some(match max {
+                0 => format!("{name}() takes no arguments ({given} given)"),
+                1 => format!("{name}() takes exactly one argument ({given} given)"),
+                _ => format!("{name}() takes exactly {max} arguments ({given} given)"),
+            }),
+            Some(max) if given > max => Some(format!(
+                "{name} expected at most {max} argument{}, got {given}",
+                plural(max)
+            )),
+            _ if given < self.min_arguments => Some(format!(
+                "{name} expected at least {} argument{}, got {given}",
+                self.min_arguments,
+                plural(self.min_arguments)
+            )),
+            _ => None,
+        }
+    }
+}
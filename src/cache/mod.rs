@@ -0,0 +1,268 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempPath;
+
+/// Default name of the per-project cache directory
+pub const CACHE_DIR_NAME: &str = "__pycc_cache__";
+
+/// Tag in artifact names, so different compiler versions never share an entry
+pub const CACHE_TAG: &str = concat!("pycc-", env!("CARGO_PKG_VERSION"));
+
+/// File in the cache directory listing the executables linked from it
+const OUTPUTS_FILE: &str = "outputs";
+
+/// Object-file cache with one entry per program, like `__pycache__`
+///
+/// A program's input files are compiled into one object, so entries are named
+/// after the program's first module, and after the key it was built for, so a
+/// name only ever refers to one build. Objects are written to a temporary
+/// file and renamed into place, so an interrupted build never leaves a
+/// partial object under a valid name, and committing a module's new entry
+/// removes the stale ones.
+pub struct BuildCache {
+    dir: PathBuf,
+}
+
+impl BuildCache {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        BuildCache {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Create a cache rooted next to the given source file
+    pub fn for_source<P: AsRef<Path>>(source_path: P) -> Self {
+        let parent = source_path
+            .as_ref()
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+        BuildCache::new(parent.join(CACHE_DIR_NAME))
+    }
+
+    /// Compute the cache key for a module
+    ///
+    /// The key changes whenever the source, the options fingerprint or the
+    /// compiler version changes, so stale objects are never reused.
+    pub fn key(source: &str, options: &str) -> String {
+        hash_parts([source, options, env!("CARGO_PKG_VERSION")])
+    }
+
+    /// Compute the cache key for a program built from several modules
+    ///
+    /// Like `key`, but each module's name is hashed along with its source, in
+    /// order, so renaming or reordering the input files selects a new entry
+    /// even when the joined sources are the same.
+    pub fn program_key(modules: &[(&str, &str)], options: &str) -> String {
+        let parts = modules.iter().flat_map(|(name, source)| [*name, *source]);
+        hash_parts(parts.chain([options, env!("CARGO_PKG_VERSION")]))
+    }
+
+    /// Path where the object for `module` built for `key` is (or would be) stored
+    pub fn object_path(&self, module: &str, key: &str) -> PathBuf {
+        self.dir.join(format!("{module}.{CACHE_TAG}.{key}.o"))
+    }
+
+    /// Return the cached object for `module` if it was built for `key`
+    pub fn lookup(&self, module: &str, key: &str) -> Option<PathBuf> {
+        let path = self.object_path(module, key);
+        path.is_file().then_some(path)
+    }
+
+    /// Make sure the cache directory exists and return a temporary file to
+    /// write the object for `module` to
+    ///
+    /// The file is removed when dropped; call `commit` once the object is
+    /// written to move it into place.
+    pub fn prepare(&self, module: &str) -> Result<TempPath, String> {
+        fs::create_dir_all(&self.dir).map_err(|e| {
+            format!(
+                "Failed to create cache directory {}: {e}",
+                self.dir.display()
+            )
+        })?;
+        tempfile::Builder::new()
+            .prefix(&format!("{module}."))
+            .suffix(".o.tmp")
+            .tempfile_in(&self.dir)
+            .map(|file| file.into_temp_path())
+            .map_err(|e| {
+                format!(
+                    "Failed to create a temporary object in {}: {e}",
+                    self.dir.display()
+                )
+            })
+    }
+
+    /// Rename the object written for `module` to its entry for `key`, and
+    /// remove the module's entries for other keys
+    ///
+    /// The rename is atomic, so concurrent builds and `lookup` only ever see
+    /// complete objects. Returns the path of the committed entry.
+    pub fn commit(&self, module: &str, key: &str, object: TempPath) -> Result<PathBuf, String> {
+        let path = self.object_path(module, key);
+        object.persist(&path).map_err(|e| {
+            format!(
+                "Failed to write cache entry {}: {}",
+                path.display(),
+                e.error
+            )
+        })?;
+
+        let prefix = format!("{module}.{CACHE_TAG}.");
+        let stale = fs::read_dir(&self.dir)
+            .map_err(|e| format!("Failed to read {}: {e}", self.dir.display()))?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|entry| *entry != path)
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(|name| name.strip_prefix(&prefix))
+                    .and_then(|rest| rest.strip_suffix(".o"))
+                    .is_some_and(|key| {
+                        key.len() == 16 && key.bytes().all(|byte| byte.is_ascii_hexdigit())
+                    })
+            });
+        for entry in stale {
+            // Another build may have removed it already
+            let _ = fs::remove_file(entry);
+        }
+        Ok(path)
+    }
+
+    /// Remember that `output` was linked from this cache, so `clean` can remove it
+    pub fn record_output(&self, output: &Path) -> Result<(), String> {
+        let output = output
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve output {}: {e}", output.display()))?;
+        let mut outputs = self.recorded_outputs();
+        if outputs.contains(&output) {
+            return Ok(());
+        }
+        outputs.push(output);
+
+        fs::create_dir_all(&self.dir).map_err(|e| {
+            format!(
+                "Failed to create cache directory {}: {e}",
+                self.dir.display()
+            )
+        })?;
+        let contents: String = outputs
+            .iter()
+            .map(|path| format!("{}\n", path.display()))
+            .collect();
+        let manifest = self.dir.join(OUTPUTS_FILE);
+        fs::write(&manifest, contents)
+            .map_err(|e| format!("Failed to write {}: {e}", manifest.display()))
+    }
+
+    /// Executables previously linked from this cache
+    pub fn recorded_outputs(&self) -> Vec<PathBuf> {
+        fs::read_to_string(self.dir.join(OUTPUTS_FILE))
+            .map(|contents| contents.lines().map(PathBuf::from).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Cache directory name used before `__pycc_cache__`
+const LEGACY_CACHE_DIR_NAME: &str = ".pycc-cache";
+
+/// Remove the pycc cache in `dir` and the executables it recorded
+///
+/// Only cache directories and outputs listed in their manifests are removed,
+/// so files with pycc-like names that pycc did not write are left alone. With
+/// `recursive`, caches in subdirectories are cleaned too. Returns the removed
+/// paths; with `dry_run` nothing is deleted.
+pub fn clean(dir: &Path, recursive: bool, dry_run: bool) -> Result<Vec<PathBuf>, String> {
+    let mut removed = Vec::new();
+    clean_dir(dir, recursive, dry_run, &mut removed)?;
+    Ok(removed)
+}
+
+fn clean_dir(
+    dir: &Path,
+    recursive: bool,
+    dry_run: bool,
+    removed: &mut Vec<PathBuf>,
+) -> Result<(), String> {
+    for name in [CACHE_DIR_NAME, LEGACY_CACHE_DIR_NAME] {
+        let cache_dir = dir.join(name);
+        // Symlinks are never followed or removed
+        if !fs::symlink_metadata(&cache_dir).is_ok_and(|metadata| metadata.is_dir()) {
+            continue;
+        }
+        let outputs = BuildCache::new(&cache_dir).recorded_outputs();
+        for output in outputs {
+            if fs::symlink_metadata(&output).is_ok_and(|metadata| metadata.is_file()) {
+                remove(&output, false, dry_run, removed)?;
+            }
+        }
+        remove(&cache_dir, true, dry_run, removed)?;
+    }
+    if !recursive {
+        return Ok(());
+    }
+
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {e}", dir.display()))?;
+    let mut subdirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let skipped = path.file_name().is_some_and(|name| {
+                let name = name.to_string_lossy();
+                name.starts_with('.') || name == CACHE_DIR_NAME
+            });
+            !skipped && fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir())
+        })
+        .collect();
+    subdirs.sort();
+    for subdir in subdirs {
+        clean_dir(&subdir, recursive, dry_run, removed)?;
+    }
+    Ok(())
+}
+
+fn remove(
+    path: &Path,
+    is_dir: bool,
+    dry_run: bool,
+    removed: &mut Vec<PathBuf>,
+) -> Result<(), String> {
+    if !dry_run {
+        let result = if is_dir {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        };
+        result.map_err(|e| format!("Failed to remove {}: {e}", path.display()))?;
+    }
+    removed.push(path.to_path_buf());
+    Ok(())
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// FNV-1a is used instead of `DefaultHasher` because its output is stable
+// across Rust releases, which matters for keys persisted on disk.
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// Hash the parts in order into a 16-digit hex key
+fn hash_parts<'a>(parts: impl IntoIterator<Item = &'a str>) -> String {
+    let mut hash = FNV_OFFSET_BASIS;
+    for part in parts {
+        hash = fnv1a(hash, part.as_bytes());
+        // Separator so ("ab", "c") and ("a", "bc") hash differently
+        hash = fnv1a(hash, &[0xff]);
+    }
+    format!("{hash:016x}")
+}
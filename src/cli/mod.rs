@@ -1,3 +1,5 @@
+use crate::codegen::{CodeModel, CodegenOptions, Entry, Fallback, LtoMode, RelocModel, Sanitizer};
+use crate::highlight;
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
@@ -11,11 +13,11 @@ pub struct Cli {
 
 #[derive(Subcommand)]
 pub enum Commands {
-    /// Compile a Python file to LLVM IR or executable
+    /// Compile Python files to LLVM IR or executable
     Compile {
-        /// Input file to compile
-        #[arg(value_name = "FILE")]
-        input_file: PathBuf,
+        /// Input files to compile; their module code runs in the order given
+        #[arg(value_name = "FILE", required = true, num_args = 1..)]
+        input_files: Vec<PathBuf>,
 
         /// Output file name
         #[arg(short, long, value_name = "OUTPUT")]
@@ -25,8 +27,190 @@ pub enum Commands {
         #[arg(long)]
         emit_llvm: bool,
 
-        /// Optimization level (0-3)
-        #[arg(short = 'O', long, value_name = "LEVEL", default_value = "0")]
-        optimization: u8,
+        /// Optimization level: 0-3, or s/z to optimize for size
+        #[arg(short = 'O', long, value_name = "LEVEL", default_value = "0", value_parser = CodegenOptions::parse_optimization)]
+        optimization: (u8, u8),
+
+        /// Link-time optimization: off, thin or full (emits bitcode and links with clang)
+        #[arg(long, value_name = "MODE", default_value = "off", value_parser = LtoMode::parse)]
+        lto: LtoMode,
+
+        /// Target CPU to generate code for ("native" for the host CPU)
+        #[arg(long, value_name = "CPU", default_value = "generic")]
+        target_cpu: String,
+
+        /// Target features to enable or disable, e.g. "+avx2,-sse4a"
+        #[arg(long, value_name = "FEATURES", default_value = "")]
+        target_features: String,
+
+        /// Code model: small, medium or large (how far apart code and data may be placed)
+        #[arg(long, value_name = "MODEL", default_value = "default", value_parser = CodeModel::parse)]
+        code_model: CodeModel,
+
+        /// Relocation model of the generated code: static or pic (position independent)
+        #[arg(long, value_name = "MODEL", default_value = "default", value_parser = RelocModel::parse)]
+        reloc_model: RelocModel,
+
+        /// Always recompile, bypassing the incremental build cache
+        #[arg(long)]
+        no_cache: bool,
+
+        /// Type check annotations first and give annotated functions native signatures
+        #[arg(long)]
+        typed: bool,
+
+        /// Abort on integer overflow instead of silently wrapping
+        #[arg(long)]
+        overflow_checks: bool,
+
+        /// Inliner cost threshold for Python functions (LLVM's default is 225)
+        #[arg(long, value_name = "N")]
+        inline_threshold: Option<u32>,
+
+        /// Map generated code back to Python source lines (see `pycc explain`)
+        #[arg(short = 'g', long)]
+        debug_info: bool,
+
+        /// Count executed lines and write an lcov report to coverage.lcov at exit
+        #[arg(long)]
+        coverage: bool,
+
+        /// Count calls and time per function and print a profile table at exit
+        #[arg(long)]
+        instrument_profile: bool,
+
+        /// Build with a runtime sanitizer: none or address (requires the compiler's libasan)
+        #[arg(long, value_name = "SANITIZER", default_value = "none", value_parser = Sanitizer::parse)]
+        sanitize: Sanitizer,
+
+        /// On unsupported code: none (fail) or interp (run the whole program with embedded CPython)
+        #[arg(
+            long,
+            value_name = "MODE",
+            default_value = "none",
+            value_parser = Fallback::parse,
+            hide = cfg!(not(feature = "python"))
+        )]
+        fallback: Fallback,
+
+        /// Call CPython through libpython for functions and modules pycc cannot compile
+        #[arg(long, hide = cfg!(not(feature = "python")))]
+        python_interop: bool,
+
+        /// Call FUNCTION of MODULE with the command-line arguments after the module code runs
+        #[arg(long, value_name = "MODULE:FUNCTION", value_parser = Entry::parse)]
+        entry: Option<Entry>,
+
+        /// Value of `__file__` in the program (default: the first input file's absolute path)
+        #[arg(long, value_name = "NAME")]
+        file_name: Option<String>,
+
+        /// Link a self-contained executable with a static C library (musl through musl-gcc if installed)
+        #[arg(long = "static")]
+        static_link: bool,
+
+        /// Remove the symbol table from the executable
+        #[arg(long)]
+        strip: bool,
+
+        /// Leave the build timestamp out of the embedded build info for reproducible output
+        #[arg(long)]
+        deterministic: bool,
+
+        /// Pass ARG on to the linker driver (repeatable)
+        #[arg(long = "link-arg", value_name = "ARG")]
+        link_args: Vec<String>,
+
+        /// Write the IR to FILE if the LLVM verifier rejects it (for reporting pycc bugs)
+        #[arg(long, value_name = "FILE")]
+        keep_invalid_ir: Option<String>,
+
+        /// Report the size of the linked executable
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// Compile a Python file and run it, exiting with the program's status
+    Run {
+        /// Python file to run
+        #[arg(value_name = "FILE")]
+        input_file: PathBuf,
+
+        /// Feed the program's standard input from FILE instead of the terminal
+        #[arg(long, value_name = "FILE")]
+        stdin: Option<PathBuf>,
+
+        /// Log each executed statement and the values it assigns to stderr
+        #[arg(long)]
+        trace: bool,
+
+        /// Print the program's elapsed time, CPU time, peak memory and step count to stderr
+        #[arg(long)]
+        stats: bool,
+
+        /// Arguments passed to the program
+        #[arg(
+            value_name = "ARGS",
+            trailing_var_arg = true,
+            allow_hyphen_values = true
+        )]
+        args: Vec<String>,
+    },
+    /// Remove the build cache and the executables linked from it
+    Clean {
+        /// Directory whose cache is removed
+        #[arg(value_name = "DIR", default_value = ".")]
+        dir: PathBuf,
+
+        /// Also clean caches in subdirectories
+        #[arg(short, long)]
+        recursive: bool,
+
+        /// List what would be removed without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Print a Python file with syntax highlighting
+    Highlight {
+        /// Python file to highlight
+        #[arg(value_name = "FILE")]
+        input_file: PathBuf,
+
+        /// Output format: ansi, html or json
+        #[arg(long, value_name = "FORMAT", default_value = "ansi", value_parser = highlight::Format::parse)]
+        format: highlight::Format,
+    },
+    /// Show the pycc and LLVM versions, targets and available features
+    Info {
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Run a Language Server Protocol server on stdin/stdout
+    Lsp,
+    /// Show LLVM IR emitted with -g interleaved with the Python lines it came from
+    Explain {
+        /// LLVM IR file written by `pycc compile -g --emit-llvm`
+        #[arg(value_name = "FILE")]
+        ir_file: PathBuf,
+
+        /// Python source to show, instead of the file recorded in the IR
+        #[arg(long, value_name = "SOURCE")]
+        source: Option<PathBuf>,
+    },
+    /// Run the `>>>` examples in docstrings and compare their output with the expected one
+    Doctest {
+        /// Python file whose docstrings hold the examples
+        #[arg(value_name = "FILE")]
+        input_file: PathBuf,
+
+        /// Also compile the module and examples with pycc and check that output too
+        #[arg(long)]
+        compiled: bool,
+    },
+    /// Check that type annotations are consistent with their usage
+    Typecheck {
+        /// Input file to check
+        #[arg(value_name = "FILE")]
+        input_file: PathBuf,
     },
 }
@@ -0,0 +1,188 @@
+use crate::ast::Node;
+use crate::codegen::{CodeGenerator, CodegenError, CodegenOptions, CodegenWarning, Fallback};
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use inkwell::context::Context;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Generate and optimize the code of a parsed program
+///
+/// `configure` prepares each generator beyond `options`, e.g. with the source
+/// file or the local modules of a multi-file build. With `--fallback interp`,
+/// a program that fails to compile embeds the interpreter running `source`
+/// instead, and the reason is appended to `warnings`. Warnings about the
+/// program itself come from `lint::warnings`, which callers run beforehand.
+pub fn generate<'ctx>(
+    context: &'ctx Context,
+    module_name: &str,
+    program: &Node,
+    source: &str,
+    options: &CodegenOptions,
+    configure: impl Fn(&mut CodeGenerator<'ctx>),
+    warnings: &mut Vec<CodegenWarning>,
+) -> Result<CodeGenerator<'ctx>, CodegenError> {
+    let generator = || {
+        let mut codegen = CodeGenerator::new(context, module_name);
+        codegen.set_options(options.clone());
+        configure(&mut codegen);
+        codegen
+    };
+
+    let mut codegen = generator();
+    if let Err(e) = codegen.compile(program) {
+        if options.fallback != Fallback::Interp {
+            return Err(e);
+        }
+        warnings.push(CodegenWarning {
+            line: e.line.unwrap_or(0),
+            message: format!("{e}; running the program with the embedded interpreter"),
+            module: e.module.clone(),
+        });
+        return generate_fallback(context, module_name, source, options, &configure);
+    }
+    codegen.optimize()?;
+    Ok(codegen)
+}
+
+/// Generate a program that embeds the interpreter running `source`
+///
+/// This is what `--fallback interp` builds for a program that fails to parse
+/// or to compile, so that `pycc compile` and `compile_and_link` agree on which
+/// programs fall back.
+pub fn generate_fallback<'ctx>(
+    context: &'ctx Context,
+    module_name: &str,
+    source: &str,
+    options: &CodegenOptions,
+    configure: impl Fn(&mut CodeGenerator<'ctx>),
+) -> Result<CodeGenerator<'ctx>, CodegenError> {
+    let mut codegen = CodeGenerator::new(context, module_name);
+    codegen.set_options(options.clone());
+    configure(&mut codegen);
+    codegen.compile_interpreter_fallback(source)?;
+    codegen.optimize()?;
+    Ok(codegen)
+}
+
+/// Compile `source` and link it into a new executable in the temporary directory
+///
+/// The executable is linked by the same command as `pycc compile`, so it gets
+/// the same runtime, sanitizer and libpython flags. With `--fallback interp`,
+/// a program that fails to parse or compile embeds the interpreter instead.
+/// The caller owns the returned file and removes it when done.
+pub fn compile_and_link(source: &str, options: &CodegenOptions) -> Result<PathBuf, String> {
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program();
+    let context = inkwell::context::Context::create();
+    let codegen = match parser.errors().first() {
+        Some(error) if options.fallback != Fallback::Interp => {
+            return Err(format!(
+                "line {}: syntax error: {}",
+                error.line, error.message
+            ));
+        }
+        Some(_) => generate_fallback(&context, "pycc_module", source, options, |_| {})
+            .map_err(|e| e.to_string())?,
+        None => generate(
+            &context,
+            "pycc_module",
+            &program,
+            source,
+            options,
+            |_| {},
+            &mut Vec::new(),
+        )
+        .map_err(|e| match e.line {
+            Some(line) => format!("line {line}: {e}"),
+            None => e.to_string(),
+        })?,
+    };
+
+    let executable = tempfile::Builder::new()
+        .prefix("pycc-build-")
+        .tempfile()
+        .map_err(|e| format!("Failed to create a temporary executable: {e}"))?
+        .into_temp_path();
+    codegen.write_executable(&executable.to_string_lossy())?;
+    executable
+        .keep()
+        .map_err(|e| format!("Failed to keep the executable: {e}"))
+}
+
+/// Run a compiled program with `stdin` as its input and collect its output
+pub fn run_compiled(path: &Path, stdin: &[u8]) -> io::Result<Output> {
+    let mut program = Command::new(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Feed the input on its own thread, so a program that writes before it
+    // has read everything cannot fill the pipes and deadlock
+    thread::scope(|scope| {
+        if let Some(mut input) = program.stdin.take() {
+            scope.spawn(move || {
+                // A program may exit without reading its input, closing the pipe early
+                let _ = input.write_all(stdin);
+            });
+        }
+        program.wait_with_output()
+    })
+}
+
+/// Like `run_compiled`, killing the program once it has run for `timeout`
+///
+/// Returns `None` when the program was killed. Its output is dropped then,
+/// since processes it started may still hold the pipes open.
+pub fn run_compiled_with_timeout(
+    path: &Path,
+    stdin: &[u8],
+    timeout: Duration,
+) -> io::Result<Option<Output>> {
+    let mut program = Command::new(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut input) = program.stdin.take() {
+        let stdin = stdin.to_vec();
+        thread::spawn(move || {
+            // A program may exit without reading its input, closing the pipe early
+            let _ = input.write_all(&stdin);
+        });
+    }
+    let stdout = read_pipe(program.stdout.take());
+    let stderr = read_pipe(program.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = program.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = program.kill();
+            program.wait()?;
+            return Ok(None);
+        }
+        thread::sleep(Duration::from_millis(5));
+    };
+    Ok(Some(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    }))
+}
+
+/// Read a child's output pipe to the end on its own thread
+fn read_pipe(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut output);
+        }
+        output
+    })
+}
@@ -0,0 +1,58 @@
+use crate::codegen::options::CodegenOptions;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Global every compiled program carries its build description in
+pub const BUILD_INFO_SYMBOL: &str = "__pycc_build_info";
+
+/// Name and version of the compiler, as recorded in the module's `llvm.ident`
+pub fn compiler_ident() -> String {
+    format!("pycc version {}", env!("CARGO_PKG_VERSION"))
+}
+
+/// Build time to record, in seconds since the Unix epoch
+///
+/// Deterministic builds record none. Otherwise `SOURCE_DATE_EPOCH`, the
+/// reproducible-builds convention for pinning it, wins over the clock.
+pub fn build_timestamp(options: &CodegenOptions) -> Option<u64> {
+    if options.deterministic {
+        return None;
+    }
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.trim().parse().ok())
+        .or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|elapsed| elapsed.as_secs())
+        })
+}
+
+/// Fields describing a build, in the order they are embedded
+pub fn fields(
+    source_file: &str,
+    options: &CodegenOptions,
+    timestamp: Option<u64>,
+) -> Vec<(&'static str, String)> {
+    let mut fields = vec![
+        ("version", env!("CARGO_PKG_VERSION").to_string()),
+        ("source", source_file.to_string()),
+        ("options", options.fingerprint()),
+    ];
+    if let Some(timestamp) = timestamp {
+        fields.push(("built", timestamp.to_string()));
+    }
+    fields
+}
+
+/// One-line description stored in `__pycc_build_info`
+///
+/// It starts with `pycc <version>` like `--version` output, so
+/// `strings program | grep '^pycc '` finds it even in a stripped binary.
+pub fn describe(source_file: &str, options: &CodegenOptions, timestamp: Option<u64>) -> String {
+    let mut description = format!("pycc {}", env!("CARGO_PKG_VERSION"));
+    for (key, value) in fields(source_file, options, timestamp).into_iter().skip(1) {
+        description.push_str(&format!(" {key}={value}"));
+    }
+    description
+}
@@ -1,16 +1,74 @@
-use crate::ast::{Binary, BinaryOperator, Identifier, Literal, LiteralValue, Node};
+use crate::ast::{
+    Binary, BinaryOperator, Call, Identifier, List, Literal, LiteralValue, Node, Tuple,
+};
+use crate::builtins::{self, Lowering};
+use crate::codegen::build_info;
+use crate::codegen::constants;
+use crate::codegen::coverage;
+use crate::codegen::debug::DebugInfo;
+use crate::codegen::error::CodegenError;
+use crate::codegen::escape;
+use crate::codegen::heap_stats;
+use crate::codegen::options::{CodegenOptions, Entry, LocalModule, Platform, Sanitizer};
+use crate::codegen::value::{CompilerValue, FunctionBinding, ProfileCounters, TypeTag, Variable};
+use crate::lexer::lexer::{is_identifier_continue, is_identifier_start};
+use crate::typecheck::{FunctionSignature, Type};
 use inkwell::builder::Builder;
 use inkwell::context::Context;
+use inkwell::intrinsics::Intrinsic;
 use inkwell::module::Module;
-use inkwell::values::{BasicValueEnum, PointerValue};
-use std::collections::HashMap;
+use inkwell::types::{BasicType, BasicTypeEnum};
+use inkwell::values::{BasicValueEnum, FloatValue, FunctionValue, IntValue, PointerValue};
+use std::collections::{HashMap, HashSet};
 
 pub struct CodeGenerator<'ctx> {
     context: &'ctx Context,
     module: Module<'ctx>,
     builder: Builder<'ctx>,
-    variables: HashMap<String, (PointerValue<'ctx>, BasicValueEnum<'ctx>)>,
+    variables: HashMap<String, Variable<'ctx>>,
+    /// Function each name is bound to, including top-level ones declared ahead of their `def`
+    functions: HashMap<String, FunctionBinding<'ctx>>,
+    /// Names whose module-level `def` has already executed
+    defined_functions: HashSet<String>,
+    /// Whether code is being generated for the module body rather than a function
+    module_level: bool,
+    /// Module-level names bound once to a literal, visible inside functions
+    module_constants: HashMap<String, LiteralValue>,
+    /// Parameters and names assigned in the function being compiled, which hide module constants
+    local_names: HashSet<String>,
+    /// `__name__` and `__file__`, unless the module binds them itself
+    module_dunders: HashMap<String, LiteralValue>,
     string_counter: usize,
+    options: CodegenOptions,
+    /// Locals of the function being compiled whose strings outlive its frame
+    escaping_variables: HashSet<String>,
+    /// Whether the expression about to be compiled produces an escaping string
+    string_escapes: bool,
+    /// Source line mapping, present when compiling with debug info
+    debug_info: Option<DebugInfo<'ctx>>,
+    /// Lines with a coverage counter, in counter order
+    coverage_lines: Vec<usize>,
+    /// Counters of every profiled function, in definition order
+    profile_counters: Vec<ProfileCounters<'ctx>>,
+    /// Counters of the function being compiled and the slot holding its start time
+    current_profile: Option<(usize, PointerValue<'ctx>)>,
+    /// Type CPython call results are converted to in the current statement
+    #[cfg_attr(not(feature = "python"), allow(dead_code))]
+    python_result_type: Type,
+    /// Modules compiled into the same program, whose functions are called directly
+    local_modules: HashSet<String>,
+    /// The input files behind the program's statements, in order
+    module_spans: Vec<LocalModule>,
+    /// Local module binding each top-level name
+    module_names: HashMap<String, String>,
+    /// Local module of the top-level statement being compiled
+    current_module: Option<String>,
+    /// Imported standard library modules pycc implements natively
+    native_modules: HashSet<String>,
+    /// Global string constants by their text, so each is emitted once
+    string_constants: HashMap<String, PointerValue<'ctx>>,
+    /// Lines of the Python source, shown by `--trace`
+    source_lines: Vec<String>,
 }
 
 impl<'ctx> CodeGenerator<'ctx> {
@@ -23,162 +81,5759 @@ impl<'ctx> CodeGenerator<'ctx> {
             module,
             builder,
             variables: HashMap::new(),
+            functions: HashMap::new(),
+            defined_functions: HashSet::new(),
+            module_level: true,
+            module_constants: HashMap::new(),
+            local_names: HashSet::new(),
+            module_dunders: HashMap::new(),
             string_counter: 0,
+            options: CodegenOptions::default(),
+            escaping_variables: HashSet::new(),
+            string_escapes: false,
+            debug_info: None,
+            coverage_lines: Vec::new(),
+            profile_counters: Vec::new(),
+            current_profile: None,
+            python_result_type: Type::Str,
+            local_modules: HashSet::new(),
+            module_spans: Vec::new(),
+            module_names: HashMap::new(),
+            current_module: None,
+            native_modules: HashSet::new(),
+            string_constants: HashMap::new(),
+            source_lines: Vec::new(),
         }
     }
 
-    pub fn compile(&mut self, program: &Node) -> Result<(), String> {
+    pub fn set_options(&mut self, options: CodegenOptions) {
+        self.options = options;
+    }
+
+    /// Treat `import name` and `name.function(...)` as referring to code in this program
+    ///
+    /// The program's statements are the modules' statements concatenated in
+    /// this order, which lets errors and warnings name the module they are in.
+    pub fn set_local_modules(&mut self, modules: &[LocalModule]) {
+        self.local_modules = modules.iter().map(|module| module.name.clone()).collect();
+        self.module_spans = modules.to_vec();
+    }
+
+    /// Record the path of the Python source, used as the file of debug info
+    pub fn set_source_file(&mut self, path: &str) {
+        self.module.set_source_file_name(path);
+    }
+
+    /// Record the Python source, so `--trace` can show each statement's text
+    pub fn set_source_text(&mut self, source: &str) {
+        self.source_lines = source.lines().map(str::to_string).collect();
+    }
+
+    pub fn compile(&mut self, program: &Node) -> Result<(), CodegenError> {
         match program {
             Node::Program(program) => {
-                // Create main function
+                // Create main function, taking argc and argv when they are passed on
                 let int_type = self.context.i32_type();
-                let fn_type = int_type.fn_type(&[], false);
+                let fn_type = if self.options.entry.is_some() {
+                    let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+                    int_type.fn_type(&[int_type.into(), ptr_type.into()], false)
+                } else {
+                    int_type.fn_type(&[], false)
+                };
                 let function = self.module.add_function("main", fn_type, None);
                 let basic_block = self.context.append_basic_block(function, "entry");
                 self.builder.position_at_end(basic_block);
+                if self.options.debug_info {
+                    let optimized = self.options.optimization > 0;
+                    let mut debug_info = DebugInfo::new(self.context, &self.module, optimized);
+                    debug_info.scope =
+                        debug_info.create_function_scope(function, "<module>", 1, optimized);
+                    self.builder
+                        .set_current_debug_location(debug_info.location(self.context, 1));
+                    self.debug_info = Some(debug_info);
+                }
+                if self.options.python_interop {
+                    self.build_python_install()?;
+                }
+                if program.statements.iter().any(defines_function) {
+                    self.build_sigint_handler_install()?;
+                }
+                if self.options.coverage {
+                    self.build_coverage_install(&program.statements)?;
+                }
+                if self.options.instrument_profile {
+                    self.build_profile_install()?;
+                }
+                if self.options.count_steps {
+                    self.build_step_count_install()?;
+                }
+                self.build_stdio_install()?;
+
+                // The module docstring is exposed as __doc__ (None when absent)
+                let doc_value = match &program.docstring {
+                    Some(docstring) => CompilerValue::new(
+                        self.builder
+                            .build_global_string_ptr(docstring, "module_doc")?
+                            .as_pointer_value()
+                            .into(),
+                        Type::Str,
+                    ),
+                    None => self.build_none(),
+                };
+                self.define_variable("__doc__", doc_value)?;
+                self.module_dunders =
+                    constants::module_dunders(&program.statements, &self.file_name());
+                self.module_constants = constants::module_constants(&program.statements);
+                self.module_constants.extend(self.module_dunders.clone());
+                self.assign_module_names(&program.statements)?;
+
+                // Declare top-level functions up front so bodies can call functions
+                // defined further down; calling them before their `def` runs is still an error
+                for statement in &program.statements {
+                    if let Node::Function(function) = statement
+                        && !self.functions.contains_key(&function.name)
+                    {
+                        self.declare_function(function);
+                    }
+                }
 
                 // Generate code for each statement
-                for statement in &program.statements {
-                    self.compile_statement(statement)?;
+                for (index, statement) in program.statements.iter().enumerate() {
+                    self.current_module = self.module_at(index);
+                    let result = self.compile_statement(statement);
+                    result.map_err(|e| match &self.current_module {
+                        Some(module) => e.in_module(module),
+                        None => e,
+                    })?;
+                }
+                self.current_module = None;
+
+                if let Some(entry) = self.options.entry.clone()
+                    && self
+                        .builder
+                        .get_insert_block()
+                        .and_then(|block| block.get_terminator())
+                        .is_none()
+                {
+                    self.build_entry_call(&entry, function)?;
                 }
 
                 // Return 0 by default if no return statement was executed
-                self.builder
-                    .build_return(Some(&int_type.const_int(0, false)))
-                    .unwrap();
+                if self
+                    .builder
+                    .get_insert_block()
+                    .and_then(|block| block.get_terminator())
+                    .is_none()
+                {
+                    self.builder
+                        .build_return(Some(&int_type.const_int(0, false)))?;
+                }
 
-                Ok(())
+                if self.options.instrument_profile {
+                    self.build_profile_report_function()?;
+                }
+                if self.options.sanitize == Sanitizer::Address {
+                    self.add_sanitizer_attributes();
+                }
+                if let Some(debug_info) = &self.debug_info {
+                    debug_info.finalize();
+                }
+                self.embed_build_info()?;
+                self.verify("after code generation")
+            }
+            _ => Err("Expected a program node".into()),
+        }
+    }
+
+    /// Record which local module binds each top-level name
+    ///
+    /// Input files share one namespace, so a name bound by two of them is
+    /// rejected rather than letting one module's definition replace the other's.
+    fn assign_module_names(&mut self, statements: &[Node]) -> Result<(), CodegenError> {
+        let mut start = 0;
+        for module in &self.module_spans {
+            let end = (start + module.statements).min(statements.len());
+            let mut names: Vec<_> = constants::bound_names(&statements[start..end])
+                .into_iter()
+                .collect();
+            names.sort();
+            for name in names {
+                if let Some(other) = self.module_names.get(&name) {
+                    return Err(CodegenError::new(format!(
+                        "'{name}' is defined in both module '{other}' and module '{}'; input files share one namespace, so rename one of them",
+                        module.name
+                    ))
+                    .in_module(&module.name));
+                }
+                self.module_names.insert(name, module.name.clone());
             }
-            _ => Err("Expected a program node".to_string()),
+            start = end;
+        }
+        Ok(())
+    }
+
+    /// Local module the top-level statement at `index` comes from
+    fn module_at(&self, index: usize) -> Option<String> {
+        let mut end = 0;
+        self.module_spans.iter().find_map(|module| {
+            end += module.statements;
+            (index < end).then(|| module.name.clone())
+        })
+    }
+
+    /// Call the entry function with `main`'s arguments and return its exit status
+    ///
+    /// Each argument is converted to the type of its parameter. An int result
+    /// becomes the exit status, anything else exits with 0.
+    fn build_entry_call(
+        &mut self,
+        entry: &Entry,
+        main: FunctionValue<'ctx>,
+    ) -> Result<(), CodegenError> {
+        let binding = self
+            .functions
+            .get(&entry.function)
+            .cloned()
+            .ok_or_else(|| format!("Entry function '{}' is not defined", entry.function))?;
+        if !self.module_spans.is_empty()
+            && self.module_names.get(&entry.function) != Some(&entry.module)
+        {
+            return Err(format!(
+                "Entry function '{}' is not defined in module '{}'",
+                entry.function, entry.module
+            )
+            .into());
+        }
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let argc = nth_param(main, 0)?.into_int_value();
+        let argv = nth_param(main, 1)?.into_pointer_value();
+
+        let arity_matches = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            argc,
+            i32_type.const_int(binding.parameters.len() as u64 + 1, false),
+            "arity_matches",
+        )?;
+        let usage_block = self.context.append_basic_block(main, "entry_usage");
+        let call_block = self.context.append_basic_block(main, "entry_call");
+        self.builder
+            .build_conditional_branch(arity_matches, call_block, usage_block)?;
+        self.builder.position_at_end(usage_block);
+        self.build_entry_usage(argv, &binding.parameters)?;
+        self.builder.position_at_end(call_block);
+
+        let strtoll_fn = self.get_or_declare_function(
+            "strtoll",
+            i64_type.fn_type(&[ptr_type.into(), ptr_type.into(), i32_type.into()], false),
+        );
+        let strtod_fn = self.get_or_declare_function(
+            "strtod",
+            self.context
+                .f64_type()
+                .fn_type(&[ptr_type.into(), ptr_type.into()], false),
+        );
+        let end_slot = self.builder.build_alloca(ptr_type, "arg_end")?;
+
+        let mut args = Vec::new();
+        for (i, param_type) in binding
+            .value
+            .get_type()
+            .get_param_types()
+            .iter()
+            .enumerate()
+        {
+            let slot = unsafe {
+                self.builder.build_gep(
+                    ptr_type,
+                    argv,
+                    &[i64_type.const_int(i as u64 + 1, false)],
+                    "arg_slot",
+                )?
+            };
+            let arg = self
+                .builder
+                .build_load(ptr_type, slot, "arg")?
+                .into_pointer_value();
+            let (converter, extra_args): (_, &[inkwell::values::BasicMetadataValueEnum]) =
+                match param_type {
+                    inkwell::types::BasicMetadataTypeEnum::PointerType(_) => {
+                        args.push(arg.into());
+                        continue;
+                    }
+                    inkwell::types::BasicMetadataTypeEnum::IntType(int_type)
+                        if int_type.get_bit_width() == 64 =>
+                    {
+                        (strtoll_fn, &[i32_type.const_int(10, false).into()])
+                    }
+                    inkwell::types::BasicMetadataTypeEnum::FloatType(_) => (strtod_fn, &[]),
+                    _ => {
+                        return Err(format!(
+                            "Unsupported type for parameter '{}' of entry function '{}'",
+                            binding.parameters[i], entry.function
+                        )
+                        .into());
+                    }
+                };
+            let mut call_args = vec![arg.into(), end_slot.into()];
+            call_args.extend_from_slice(extra_args);
+            let value = self
+                .builder
+                .build_call(converter, &call_args, "arg_value")?
+                .try_as_basic_value()
+                .unwrap_basic();
+
+            // Like int() and float(), reject empty text and trailing characters
+            let end = self
+                .builder
+                .build_load(ptr_type, end_slot, "arg_end")?
+                .into_pointer_value();
+            let rest = self
+                .builder
+                .build_load(self.context.i8_type(), end, "arg_rest")?
+                .into_int_value();
+            let consumed_all = self.builder.build_int_compare(
+                inkwell::IntPredicate::EQ,
+                rest,
+                self.context.i8_type().const_zero(),
+                "consumed_all",
+            )?;
+            let consumed_any = self.builder.build_int_compare(
+                inkwell::IntPredicate::NE,
+                end,
+                arg,
+                "consumed_any",
+            )?;
+            let valid = self
+                .builder
+                .build_and(consumed_all, consumed_any, "arg_valid")?;
+            let invalid_block = self.context.append_basic_block(main, "entry_invalid_arg");
+            let next_block = self.context.append_basic_block(main, "entry_arg_ok");
+            self.builder
+                .build_conditional_branch(valid, next_block, invalid_block)?;
+            self.builder.position_at_end(invalid_block);
+            self.build_fatal_error(
+                &format!(
+                    "ValueError: invalid value for argument '{}'\n",
+                    binding.parameters[i]
+                ),
+                &format!("entry_invalid_{i}_message"),
+            )?;
+            self.builder.position_at_end(next_block);
+            args.push(value.into());
         }
+
+        let result = self
+            .builder
+            .build_call(binding.value, &args, "entry_result")?
+            .try_as_basic_value()
+            .basic();
+        let status = match result {
+            Some(BasicValueEnum::IntValue(value)) if value.get_type().get_bit_width() == 64 => self
+                .builder
+                .build_int_truncate(value, i32_type, "exit_status")?,
+            _ => i32_type.const_zero(),
+        };
+        self.builder.build_return(Some(&status))?;
+        Ok(())
+    }
+
+    /// Print `usage: PROGRAM PARAM...` and exit with status 1
+    ///
+    /// Like argparse, the program is named by the last component of `argv[0]`.
+    fn build_entry_usage(
+        &mut self,
+        argv: PointerValue<'ctx>,
+        parameters: &[String],
+    ) -> Result<(), CodegenError> {
+        let i8_type = self.context.i8_type();
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+
+        let strrchr_fn = self.get_or_declare_function(
+            "strrchr",
+            ptr_type.fn_type(&[ptr_type.into(), i32_type.into()], false),
+        );
+        let fprintf_fn = self.get_or_declare_function(
+            "fprintf",
+            i32_type.fn_type(&[ptr_type.into(), ptr_type.into()], true),
+        );
+        let fflush_fn =
+            self.get_or_declare_function("fflush", i32_type.fn_type(&[ptr_type.into()], false));
+        let exit_fn = self.get_or_declare_function(
+            "exit",
+            self.context.void_type().fn_type(&[i32_type.into()], false),
+        );
+
+        let program = self
+            .builder
+            .build_load(ptr_type, argv, "program")?
+            .into_pointer_value();
+        let slash = self
+            .builder
+            .build_call(
+                strrchr_fn,
+                &[
+                    program.into(),
+                    i32_type.const_int(u64::from(b'/'), false).into(),
+                ],
+                "slash",
+            )?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value();
+        let has_slash = self.builder.build_is_not_null(slash, "has_slash")?;
+        let after_slash = unsafe {
+            self.builder.build_gep(
+                i8_type,
+                slash,
+                &[i64_type.const_int(1, false)],
+                "after_slash",
+            )?
+        };
+        let name = self
+            .builder
+            .build_select(has_slash, after_slash, program, "program_name")?;
+
+        let format = std::iter::once("usage: %s")
+            .chain(parameters.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let format = self.constant_string(&format!("{format}\n"), "entry_usage_format")?;
+        // Output printed so far must precede the usage line
+        self.builder
+            .build_call(fflush_fn, &[ptr_type.const_null().into()], "")?;
+        let stderr = self.build_stream("stderr")?;
+        self.builder
+            .build_call(fprintf_fn, &[stderr.into(), format.into(), name.into()], "")?;
+        self.builder
+            .build_call(exit_fn, &[i32_type.const_int(1, false).into()], "")?;
+        self.builder.build_unreachable()?;
+        Ok(())
+    }
+
+    /// Generate a `main` that runs `source` with an embedded CPython
+    ///
+    /// Used for programs `compile` rejects; link with `Fallback::Interp`'s
+    /// linker flags. The whole program is interpreted, rather than only the
+    /// statements that failed to compile. Exceptions print a traceback and
+    /// exit with status 1.
+    #[cfg(feature = "python")]
+    pub fn compile_interpreter_fallback(&mut self, source: &str) -> Result<(), CodegenError> {
+        let i32_type = self.context.i32_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let void_type = self.context.void_type();
+
+        let initialize_fn =
+            self.get_or_declare_function("Py_Initialize", void_type.fn_type(&[], false));
+        let run_fn = self.get_or_declare_function(
+            "PyRun_SimpleStringFlags",
+            i32_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+        );
+        let finalize_fn =
+            self.get_or_declare_function("Py_FinalizeEx", i32_type.fn_type(&[], false));
+
+        let function = self
+            .module
+            .add_function("main", i32_type.fn_type(&[], false), None);
+        let entry_block = self.context.append_basic_block(function, "entry");
+        self.builder.position_at_end(entry_block);
+
+        let source_ptr = self
+            .builder
+            .build_global_string_ptr(source, "fallback_source")?
+            .as_pointer_value();
+        self.builder.build_call(initialize_fn, &[], "")?;
+        self.build_python_main_file()?;
+        let status = self
+            .builder
+            .build_call(
+                run_fn,
+                &[source_ptr.into(), ptr_type.const_null().into()],
+                "status",
+            )?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_int_value();
+        self.builder.build_call(finalize_fn, &[], "")?;
+        let failed = self.builder.build_int_compare(
+            inkwell::IntPredicate::NE,
+            status,
+            i32_type.const_zero(),
+            "failed",
+        )?;
+        let exit_code = self
+            .builder
+            .build_int_z_extend(failed, i32_type, "exit_code")?;
+        self.builder.build_return(Some(&exit_code))?;
+        self.embed_build_info()?;
+        self.verify("after code generation")
+    }
+
+    /// Without the `python` feature there is no CPython to embed
+    #[cfg(not(feature = "python"))]
+    pub fn compile_interpreter_fallback(&mut self, _source: &str) -> Result<(), CodegenError> {
+        Err(python_feature_disabled("--fallback interp"))
+    }
+
+    /// Set `__file__` of CPython's `__main__` module, which running a string leaves unset
+    #[cfg(feature = "python")]
+    fn build_python_main_file(&mut self) -> Result<(), CodegenError> {
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let add_module_fn = self.get_or_declare_function(
+            "PyImport_AddModule",
+            ptr_type.fn_type(&[ptr_type.into()], false),
+        );
+        let decode_fn = self.get_or_declare_function(
+            "PyUnicode_DecodeFSDefault",
+            ptr_type.fn_type(&[ptr_type.into()], false),
+        );
+        let set_attr_fn = self.get_or_declare_function(
+            "PyObject_SetAttrString",
+            self.context
+                .i32_type()
+                .fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into()], false),
+        );
+
+        let module_name = self.python_name_ptr("__main__")?;
+        let attribute = self.python_name_ptr("__file__")?;
+        let file_name = self.file_name();
+        let file_name = self.python_name_ptr(&file_name)?;
+        let call =
+            |function: FunctionValue<'ctx>,
+             arguments: &[inkwell::values::BasicMetadataValueEnum<'ctx>]| {
+                self.builder
+                    .build_call(function, arguments, "")?
+                    .try_as_basic_value()
+                    .unwrap_basic()
+            };
+        // The module is a borrowed reference
+        let module = call(add_module_fn, &[module_name.into()]);
+        let value = call(decode_fn, &[file_name.into()]).into_pointer_value();
+        call(
+            set_attr_fn,
+            &[module.into(), attribute.into(), value.into()],
+        );
+        self.build_python_release(value)?;
+        Ok(())
+    }
+
+    /// Value of `__file__`: the `file_name` option, or the module's source file name
+    fn file_name(&self) -> String {
+        self.options.file_name.clone().unwrap_or_else(|| {
+            self.module
+                .get_source_file_name()
+                .to_string_lossy()
+                .into_owned()
+        })
+    }
+
+    /// Record the source file, compiler version, options and build time
+    ///
+    /// They go into the `pycc.build_info` named metadata and `llvm.ident` of
+    /// the module, and into the `__pycc_build_info` string of the executable.
+    /// `llvm.used` keeps the string through optimization and LTO even though
+    /// the program never reads it.
+    fn embed_build_info(&mut self) -> Result<(), CodegenError> {
+        let source_file = self
+            .module
+            .get_source_file_name()
+            .to_string_lossy()
+            .into_owned();
+        let timestamp = build_info::build_timestamp(&self.options);
+
+        for (key, value) in build_info::fields(&source_file, &self.options, timestamp) {
+            let field = self.context.metadata_node(&[
+                self.context.metadata_string(key).into(),
+                self.context.metadata_string(&value).into(),
+            ]);
+            self.module
+                .add_global_metadata("pycc.build_info", &field)
+                .map_err(|e| format!("internal compiler error: {e}"))?;
+        }
+        let ident = self.context.metadata_node(&[self
+            .context
+            .metadata_string(&build_info::compiler_ident())
+            .into()]);
+        self.module
+            .add_global_metadata("llvm.ident", &ident)
+            .map_err(|e| format!("internal compiler error: {e}"))?;
+
+        let description = self.context.const_string(
+            build_info::describe(&source_file, &self.options, timestamp).as_bytes(),
+            true,
+        );
+        let global =
+            self.module
+                .add_global(description.get_type(), None, build_info::BUILD_INFO_SYMBOL);
+        global.set_constant(true);
+        global.set_initializer(&description);
+
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let used_type = ptr_type.array_type(1);
+        let used = self.module.add_global(used_type, None, "llvm.used");
+        used.set_linkage(inkwell::module::Linkage::Appending);
+        used.set_section(Some("llvm.metadata"));
+        used.set_initializer(&ptr_type.const_array(&[global.as_pointer_value()]));
+        Ok(())
     }
 
-    fn compile_statement(&mut self, statement: &Node) -> Result<(), String> {
+    /// Compile a statement, attributing any error to its line
+    fn compile_statement(&mut self, statement: &Node) -> Result<(), CodegenError> {
+        let result = self.lower_statement(statement);
+        match statement.line() {
+            Some(line) => result.map_err(|e| e.at_line(line)),
+            None => result,
+        }
+    }
+
+    fn lower_statement(&mut self, statement: &Node) -> Result<(), CodegenError> {
+        if let (Some(debug_info), Some(line)) = (&self.debug_info, statement.line()) {
+            self.builder
+                .set_current_debug_location(debug_info.location(self.context, line));
+        }
+        if let Some(line) = statement.line() {
+            self.build_coverage_count(line)?;
+            if self.options.count_steps {
+                self.build_step_count()?;
+            }
+            if self.options.trace {
+                let text = line
+                    .checked_sub(1)
+                    .and_then(|index| self.source_lines.get(index))
+                    .map_or_else(String::new, |text| text.trim().to_string());
+                self.build_trace(line, &text, None)?;
+            }
+        }
+        self.python_result_type = Type::Str;
+
         match statement {
             Node::Assignment(assignment) => {
+                self.string_escapes = assignment
+                    .names()
+                    .any(|name| self.escaping_variables.contains(name));
+                if let Some(annotation) = &assignment.annotation {
+                    self.python_result_type = Type::from_annotation(annotation);
+                }
                 let value = self.compile_expression(&assignment.value)?;
-
-                // For division results, ensure we use float type even if operands are integers
-                let is_division = if let Node::Binary(binary) = &*assignment.value {
-                    matches!(binary.operator, BinaryOperator::Divide)
+                for target in &assignment.targets {
+                    self.store_target(target, value)?;
+                }
+                if self.options.trace {
+                    for name in assignment.names() {
+                        self.build_trace(assignment.line, &format!("{name} ="), Some(value))?;
+                    }
+                }
+                Ok(())
+            }
+            Node::ExpressionStatement(expr_stmt) => self.compile_discarded(&expr_stmt.expression),
+            Node::Block(block) => {
+                for statement in &block.statements {
+                    self.compile_statement(statement)?;
+                }
+                Ok(())
+            }
+            Node::If(if_stmt) => self.compile_if(if_stmt),
+            Node::Import(import) => self.compile_python_import(import),
+            Node::Function(function) => {
+                self.compile_function(function)?;
+                Ok(())
+            }
+            Node::Return(return_stmt) => {
+                // Handle return statement
+                if let Some(value) = &return_stmt.value {
+                    self.string_escapes = true;
+                    let return_type = self
+                        .builder
+                        .get_insert_block()
+                        .and_then(|block| block.get_parent())
+                        .and_then(|function| function.get_type().get_return_type());
+                    self.python_result_type = match return_type {
+                        Some(BasicTypeEnum::FloatType(_)) => Type::Float,
+                        Some(BasicTypeEnum::IntType(int_type)) if int_type.get_bit_width() == 1 => {
+                            Type::Bool
+                        }
+                        Some(BasicTypeEnum::IntType(_)) => Type::Int,
+                        _ => Type::Str,
+                    };
+                    let return_value = self.compile_expression(value)?.llvm_value;
+                    let return_value = match return_type {
+                        Some(ty) => self.coerce_to(return_value, ty)?,
+                        None => return_value,
+                    };
+                    self.build_function_return(return_value)?;
+                    Ok(())
                 } else {
-                    false
-                };
+                    // A bare return yields None, whose representation is the zero value
+                    let return_type = self
+                        .builder
+                        .get_insert_block()
+                        .and_then(|block| block.get_parent())
+                        .and_then(|function| function.get_type().get_return_type())
+                        .ok_or("'return' outside function")?;
+                    self.build_function_return(return_type.const_zero())?;
+                    Ok(())
+                }
+            }
+            _ => Ok(()), // Ignore unsupported statements for now
+        }
+    }
+
+    fn compile_if(&mut self, if_stmt: &crate::ast::If) -> Result<(), CodegenError> {
+        let condition = self.compile_expression(&if_stmt.condition)?;
+        let condition = self.build_truthiness(condition.llvm_value)?;
+
+        let function = self.current_function()?;
+        let then_block = self.context.append_basic_block(function, "if_then");
+        let else_block = self.context.append_basic_block(function, "if_else");
+        let merge_block = self.context.append_basic_block(function, "if_merge");
+
+        self.builder
+            .build_conditional_branch(condition, then_block, else_block)?;
+
+        // Each branch starts from the bindings in effect before the `if`
+        let outer_variables = self.variables.clone();
+        let outer_functions = self.functions.clone();
+
+        self.builder.position_at_end(then_block);
+        self.compile_statement(&if_stmt.then_branch)?;
+        let then_end = self.current_block()?;
+        let then_variables = std::mem::replace(&mut self.variables, outer_variables);
+        let then_functions = std::mem::replace(&mut self.functions, outer_functions.clone());
+
+        self.builder.position_at_end(else_block);
+        if let Some(else_branch) = &if_stmt.else_branch {
+            self.compile_statement(else_branch)?;
+        }
+        let else_end = self.current_block()?;
+        let else_variables = std::mem::take(&mut self.variables);
+
+        self.variables =
+            self.merge_branch_variables((then_end, then_variables), (else_end, else_variables))?;
+
+        // A `def` in one branch binds the name afterwards; calling it when that
+        // branch did not run is not detected. Calls can only be bound statically,
+        // so defining the same name in both branches is rejected.
+        for (name, binding) in then_functions {
+            let rebound = outer_functions
+                .get(&name)
+                .is_none_or(|outer| outer.value != binding.value);
+            if !rebound {
+                continue;
+            }
+            let rebound_in_else = match (self.functions.get(&name), outer_functions.get(&name)) {
+                (Some(else_binding), Some(outer)) => else_binding.value != outer.value,
+                (Some(_), None) => true,
+                _ => false,
+            };
+            if rebound_in_else {
+                return Err(format!(
+                    "'{name}' is defined in both branches of an if (pycc limitation)"
+                )
+                .into());
+            }
+            self.functions.insert(name, binding);
+        }
+
+        self.builder.position_at_end(then_end);
+        self.branch_if_unterminated(merge_block)?;
+        self.builder.position_at_end(else_end);
+        self.branch_if_unterminated(merge_block)?;
+
+        self.builder.position_at_end(merge_block);
+        Ok(())
+    }
+
+    /// Combine the bindings at the end of two branches that join again
+    ///
+    /// A branch that already returned contributes nothing. When both branches
+    /// bind a name to different slots of the same LLVM type, the else branch
+    /// copies its value into the then branch's slot. Bindings of different
+    /// LLVM types cannot be joined without boxing and are rejected.
+    fn merge_branch_variables(
+        &mut self,
+        (then_end, then_variables): (
+            inkwell::basic_block::BasicBlock<'ctx>,
+            HashMap<String, Variable<'ctx>>,
+        ),
+        (else_end, else_variables): (
+            inkwell::basic_block::BasicBlock<'ctx>,
+            HashMap<String, Variable<'ctx>>,
+        ),
+    ) -> Result<HashMap<String, Variable<'ctx>>, CodegenError> {
+        if then_end.get_terminator().is_some() {
+            return Ok(else_variables);
+        }
+        if else_end.get_terminator().is_some() {
+            return Ok(then_variables);
+        }
+
+        let mut merged = then_variables;
+        for (name, else_variable) in else_variables {
+            let Some(then_variable) = merged.get_mut(&name) else {
+                merged.insert(name, else_variable);
+                continue;
+            };
+
+            if !same_representation(then_variable.llvm_type, else_variable.llvm_type) {
+                return Err(format!(
+                    "'{name}' holds values of different types depending on the branch taken (pycc limitation)"
+                ).into());
+            }
+            if then_variable.slot != else_variable.slot {
+                self.builder.position_at_end(else_end);
+                let value = self.builder.build_load(
+                    else_variable.llvm_type,
+                    else_variable.slot,
+                    "branch_value",
+                )?;
+                self.builder.build_store(then_variable.slot, value)?;
+            }
+            if then_variable.py_type != else_variable.py_type {
+                then_variable.py_type = Type::Any;
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Convert a value to an i1 following Python truthiness rules
+    fn build_truthiness(
+        &mut self,
+        value: BasicValueEnum<'ctx>,
+    ) -> Result<inkwell::values::IntValue<'ctx>, CodegenError> {
+        match value {
+            BasicValueEnum::IntValue(int_val) => {
+                if int_val.get_type().get_bit_width() == 1 {
+                    return Ok(int_val);
+                }
+                // Zero and None (0) are falsy
+                let zero = int_val.get_type().const_int(0, false);
+                Ok(self.builder.build_int_compare(
+                    inkwell::IntPredicate::NE,
+                    int_val,
+                    zero,
+                    "truthy",
+                )?)
+            }
+            BasicValueEnum::FloatValue(float_val) => {
+                let zero = float_val.get_type().const_float(0.0);
+                Ok(self.builder.build_float_compare(
+                    inkwell::FloatPredicate::UNE,
+                    float_val,
+                    zero,
+                    "truthy",
+                )?)
+            }
+            BasicValueEnum::PointerValue(ptr_val) => {
+                // Strings are truthy when non-empty
+                let i8_type = self.context.i8_type();
+                let first_char = self
+                    .builder
+                    .build_load(i8_type, ptr_val, "first_char")?
+                    .into_int_value();
+                Ok(self.builder.build_int_compare(
+                    inkwell::IntPredicate::NE,
+                    first_char,
+                    i8_type.const_int(0, false),
+                    "truthy",
+                )?)
+            }
+            _ => Err("Unsupported condition type".into()),
+        }
+    }
+
+    /// Branch to `target` unless the current block already ends in a terminator
+    fn branch_if_unterminated(
+        &mut self,
+        target: inkwell::basic_block::BasicBlock<'ctx>,
+    ) -> Result<(), CodegenError> {
+        let terminated = self
+            .builder
+            .get_insert_block()
+            .and_then(|block| block.get_terminator())
+            .is_some();
+        if !terminated {
+            self.builder.build_unconditional_branch(target)?;
+        }
+        Ok(())
+    }
+
+    /// Bind `name` to `value` in a fresh stack slot of the current function
+    fn define_variable(
+        &mut self,
+        name: &str,
+        value: CompilerValue<'ctx>,
+    ) -> Result<(), CodegenError> {
+        let llvm_type = value.llvm_value.get_type();
+        let slot = self.create_entry_block_alloca(llvm_type, name)?;
+        self.builder.build_store(slot, value.llvm_value)?;
+        self.variables.insert(
+            name.to_string(),
+            Variable {
+                slot,
+                llvm_type,
+                py_type: value.py_type,
+            },
+        );
+        Ok(())
+    }
+
+    /// Store `value` into `name`, reusing its stack slot when the representation is unchanged
+    ///
+    /// Reusing the slot lets assignments in different branches update the same variable.
+    fn assign_variable(
+        &mut self,
+        name: &str,
+        value: CompilerValue<'ctx>,
+    ) -> Result<(), CodegenError> {
+        let llvm_type = value.llvm_value.get_type();
+        match self.variables.get_mut(name) {
+            Some(variable) if same_representation(variable.llvm_type, llvm_type) => {
+                self.builder.build_store(variable.slot, value.llvm_value)?;
+                variable.py_type = value.py_type;
+            }
+            _ => self.define_variable(name, value)?,
+        }
+        Ok(())
+    }
+
+    /// The `None` value, represented as an i64 zero
+    fn build_none(&self) -> CompilerValue<'ctx> {
+        CompilerValue::new(self.context.i64_type().const_zero().into(), Type::None)
+    }
+
+    /// Integer `+`, `-` or `*`
+    ///
+    /// Values are native i64 until bigints land, so results silently wrap. With
+    /// overflow checks enabled the `llvm.*.with.overflow` intrinsics are used
+    /// instead and the program aborts rather than diverge from CPython.
+    fn build_int_arithmetic(
+        &mut self,
+        operation: IntArithmetic,
+        l: IntValue<'ctx>,
+        r: IntValue<'ctx>,
+        name: &str,
+    ) -> Result<IntValue<'ctx>, CodegenError> {
+        if !self.options.overflow_checks {
+            let result = match operation {
+                IntArithmetic::Add => self.builder.build_int_add(l, r, name),
+                IntArithmetic::Subtract => self.builder.build_int_sub(l, r, name),
+                IntArithmetic::Multiply => self.builder.build_int_mul(l, r, name),
+            };
+            return Ok(result?);
+        }
+
+        let intrinsic_name = match operation {
+            IntArithmetic::Add => "llvm.sadd.with.overflow",
+            IntArithmetic::Subtract => "llvm.ssub.with.overflow",
+            IntArithmetic::Multiply => "llvm.smul.with.overflow",
+        };
+        let intrinsic_fn = Intrinsic::find(intrinsic_name)
+            .and_then(|intrinsic| intrinsic.get_declaration(&self.module, &[l.get_type().into()]))
+            .ok_or_else(|| format!("Intrinsic {intrinsic_name} is not available"))?;
+
+        let result = self
+            .builder
+            .build_call(intrinsic_fn, &[l.into(), r.into()], name)?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_struct_value();
+        let value = self
+            .builder
+            .build_extract_value(result, 0, name)?
+            .into_int_value();
+        let overflowed = self
+            .builder
+            .build_extract_value(result, 1, "overflowed")?
+            .into_int_value();
+
+        let function = self.current_function()?;
+        let trap_block = self.context.append_basic_block(function, "overflow_trap");
+        let continue_block = self.context.append_basic_block(function, "overflow_ok");
+        self.builder
+            .build_conditional_branch(overflowed, trap_block, continue_block)?;
+
+        self.builder.position_at_end(trap_block);
+        self.build_overflow_trap()?;
+
+        self.builder.position_at_end(continue_block);
+        Ok(value)
+    }
+
+    /// Integer `//`, rounding towards negative infinity like Python
+    ///
+    /// `sdiv` truncates towards zero, so the quotient is one too large whenever
+    /// the division is inexact and the operands have different signs.
+    fn build_int_floor_div(
+        &mut self,
+        l: IntValue<'ctx>,
+        r: IntValue<'ctx>,
+    ) -> Result<IntValue<'ctx>, CodegenError> {
+        self.build_zero_division_check(
+            r,
+            "ZeroDivisionError: integer division or modulo by zero\n",
+            "floor_div_zero_message",
+        )?;
+        let (divisor, negate) = self.build_safe_divisor(r)?;
+        if self.options.overflow_checks {
+            // i64::MIN // -1 is the one quotient that does not fit
+            let min = l.get_type().const_int(i64::MIN as u64, false);
+            let is_min =
+                self.builder
+                    .build_int_compare(inkwell::IntPredicate::EQ, l, min, "is_min")?;
+            let overflowed = self.builder.build_and(is_min, negate, "overflowed")?;
+            let function = self.current_function()?;
+            let trap_block = self.context.append_basic_block(function, "overflow_trap");
+            let continue_block = self.context.append_basic_block(function, "overflow_ok");
+            self.builder
+                .build_conditional_branch(overflowed, trap_block, continue_block)?;
+            self.builder.position_at_end(trap_block);
+            self.build_overflow_trap()?;
+            self.builder.position_at_end(continue_block);
+        }
+
+        let quotient = self.builder.build_int_signed_div(l, divisor, "divtmp")?;
+        let remainder = self.builder.build_int_signed_rem(l, divisor, "remtmp")?;
+        let needs_adjust = self.build_remainder_needs_adjust(remainder, r)?;
+        let adjusted = self.builder.build_int_sub(
+            quotient,
+            l.get_type().const_int(1, false),
+            "floordivadj",
+        )?;
+        let floored = self
+            .builder
+            .build_select(needs_adjust, adjusted, quotient, "floordivtmp")?
+            .into_int_value();
+        // A divisor of -1 was replaced by 1, so negate; i64::MIN wraps like other arithmetic
+        let negated =
+            self.builder
+                .build_int_sub(l.get_type().const_zero(), floored, "floordivneg")?;
+        Ok(self
+            .builder
+            .build_select(negate, negated, floored, "floordivres")?
+            .into_int_value())
+    }
+
+    /// Integer `%`, whose result takes the sign of the divisor like Python
+    fn build_int_mod(
+        &mut self,
+        l: IntValue<'ctx>,
+        r: IntValue<'ctx>,
+    ) -> Result<IntValue<'ctx>, CodegenError> {
+        self.build_zero_division_check(
+            r,
+            "ZeroDivisionError: integer modulo by zero\n",
+            "mod_zero_message",
+        )?;
+        // Anything modulo -1 is 0, as it is modulo 1
+        let (r, _) = self.build_safe_divisor(r)?;
+        let remainder = self.builder.build_int_signed_rem(l, r, "remtmp")?;
+        let needs_adjust = self.build_remainder_needs_adjust(remainder, r)?;
+        let adjusted = self.builder.build_int_add(remainder, r, "modadj")?;
+        Ok(self
+            .builder
+            .build_select(needs_adjust, adjusted, remainder, "modtmp")?
+            .into_int_value())
+    }
+
+    /// Raise ZeroDivisionError with `message` when `divisor` is zero at run time
+    ///
+    /// `sdiv` and `srem` by zero are undefined behaviour (SIGFPE on x86), so
+    /// divisors not known at compile time are checked first. Constant zero
+    /// divisors are rejected at compile time instead.
+    fn build_zero_division_check(
+        &mut self,
+        divisor: IntValue<'ctx>,
+        message: &str,
+        global_name: &str,
+    ) -> Result<(), CodegenError> {
+        if divisor.get_zero_extended_constant().is_some() {
+            return Ok(());
+        }
+        let is_zero = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            divisor,
+            divisor.get_type().const_zero(),
+            "is_zero",
+        )?;
+        let function = self.current_function()?;
+        let zero_block = self.context.append_basic_block(function, "zero_division");
+        let continue_block = self.context.append_basic_block(function, "divisor_ok");
+        self.builder
+            .build_conditional_branch(is_zero, zero_block, continue_block)?;
+        self.builder.position_at_end(zero_block);
+        self.build_fatal_error(message, global_name)?;
+        self.builder.position_at_end(continue_block);
+        Ok(())
+    }
+
+    /// A divisor `sdiv` and `srem` accept for every dividend, and whether it was -1
+    ///
+    /// `i64::MIN / -1` overflows, which is undefined behaviour for `sdiv` and
+    /// `srem`, so -1 is replaced by 1 and the caller negates the quotient.
+    fn build_safe_divisor(
+        &self,
+        divisor: IntValue<'ctx>,
+    ) -> Result<(IntValue<'ctx>, IntValue<'ctx>), CodegenError> {
+        let int_type = divisor.get_type();
+        let is_minus_one = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            divisor,
+            int_type.const_all_ones(),
+            "is_minus_one",
+        )?;
+        let safe = self
+            .builder
+            .build_select(
+                is_minus_one,
+                int_type.const_int(1, false),
+                divisor,
+                "divisor",
+            )?
+            .into_int_value();
+        Ok((safe, is_minus_one))
+    }
+
+    /// Whether a truncated remainder is non-zero with a sign different from the divisor
+    fn build_remainder_needs_adjust(
+        &self,
+        remainder: IntValue<'ctx>,
+        divisor: IntValue<'ctx>,
+    ) -> Result<IntValue<'ctx>, CodegenError> {
+        let zero = remainder.get_type().const_zero();
+        let nonzero = self.builder.build_int_compare(
+            inkwell::IntPredicate::NE,
+            remainder,
+            zero,
+            "rem_nonzero",
+        )?;
+        let signs = self.builder.build_xor(remainder, divisor, "signs")?;
+        let signs_differ = self.builder.build_int_compare(
+            inkwell::IntPredicate::SLT,
+            signs,
+            zero,
+            "signs_differ",
+        )?;
+        Ok(self
+            .builder
+            .build_and(nonzero, signs_differ, "needs_adjust")?)
+    }
+
+    /// Float `//`, the floor of the true quotient
+    fn build_float_floor_div(
+        &self,
+        l: FloatValue<'ctx>,
+        r: FloatValue<'ctx>,
+    ) -> Result<FloatValue<'ctx>, CodegenError> {
+        let quotient = self.builder.build_float_div(l, r, "fdivtmp")?;
+        let floor_fn = Intrinsic::find("llvm.floor")
+            .and_then(|intrinsic| intrinsic.get_declaration(&self.module, &[l.get_type().into()]))
+            .ok_or_else(|| "Intrinsic llvm.floor is not available".to_string())?;
+        Ok(self
+            .builder
+            .build_call(floor_fn, &[quotient.into()], "ffloordivtmp")?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_float_value())
+    }
+
+    /// Float `%`, whose result takes the sign of the divisor like Python
+    fn build_float_mod(
+        &self,
+        l: FloatValue<'ctx>,
+        r: FloatValue<'ctx>,
+    ) -> Result<FloatValue<'ctx>, CodegenError> {
+        let remainder = self.builder.build_float_rem(l, r, "fremtmp")?;
+        let zero = l.get_type().const_zero();
+        let nonzero = self.builder.build_float_compare(
+            inkwell::FloatPredicate::UNE,
+            remainder,
+            zero,
+            "rem_nonzero",
+        )?;
+        let remainder_negative = self.builder.build_float_compare(
+            inkwell::FloatPredicate::OLT,
+            remainder,
+            zero,
+            "rem_negative",
+        )?;
+        let divisor_negative = self.builder.build_float_compare(
+            inkwell::FloatPredicate::OLT,
+            r,
+            zero,
+            "divisor_negative",
+        )?;
+        let signs_differ =
+            self.builder
+                .build_xor(remainder_negative, divisor_negative, "signs_differ")?;
+        let needs_adjust = self
+            .builder
+            .build_and(nonzero, signs_differ, "needs_adjust")?;
+        let adjusted = self.builder.build_float_add(remainder, r, "fmodadj")?;
+        Ok(self
+            .builder
+            .build_select(needs_adjust, adjusted, remainder, "fmodtmp")?
+            .into_float_value())
+    }
+
+    /// Report an integer overflow on stderr and exit with status 1
+    fn build_overflow_trap(&mut self) -> Result<(), CodegenError> {
+        self.build_fatal_error(
+            "OverflowError: integer overflow (pycc limitation)\n",
+            "overflow_message",
+        )?;
+        Ok(())
+    }
+
+    /// Write `message` to stderr, exit with status 1 and terminate the block
+    fn build_fatal_error(&mut self, message: &str, global_name: &str) -> Result<(), CodegenError> {
+        self.build_fatal_error_with_status(message, global_name, 1)
+    }
+
+    /// Write `message` to stderr, exit with `status` and terminate the block
+    fn build_fatal_error_with_status(
+        &mut self,
+        message: &str,
+        global_name: &str,
+        status: u64,
+    ) -> Result<(), CodegenError> {
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+
+        // Get or declare write function for unbuffered output to stderr
+        let write_fn = if let Some(func) = self.module.get_function("write") {
+            func
+        } else {
+            let write_fn_type =
+                i64_type.fn_type(&[i32_type.into(), ptr_type.into(), i64_type.into()], false);
+            self.module.add_function("write", write_fn_type, None)
+        };
+
+        // Get or declare exit function
+        let exit_fn = if let Some(func) = self.module.get_function("exit") {
+            func
+        } else {
+            let exit_fn_type = self.context.void_type().fn_type(&[i32_type.into()], false);
+            self.module.add_function("exit", exit_fn_type, None)
+        };
+
+        let message_ptr = match self.module.get_global(global_name) {
+            Some(global) => global.as_pointer_value(),
+            None => self
+                .builder
+                .build_global_string_ptr(message, global_name)?
+                .as_pointer_value(),
+        };
+
+        // Output printed so far must precede the error
+        let fflush_fn =
+            self.get_or_declare_function("fflush", i32_type.fn_type(&[ptr_type.into()], false));
+        self.builder
+            .build_call(fflush_fn, &[ptr_type.const_null().into()], "")?;
+        self.builder.build_call(
+            write_fn,
+            &[
+                i32_type.const_int(2, false).into(),
+                message_ptr.into(),
+                i64_type.const_int(message.len() as u64, false).into(),
+            ],
+            "write_error",
+        )?;
+        self.builder
+            .build_call(exit_fn, &[i32_type.const_int(status, false).into()], "")?;
+        self.builder.build_unreachable()?;
+        Ok(())
+    }
+
+    /// Integer `**`
+    ///
+    /// A constant negative exponent gives a float like in Python. Otherwise the
+    /// result stays an integer and is computed by the `pycc_pow` helper.
+    fn build_int_power(
+        &mut self,
+        l: IntValue<'ctx>,
+        r: IntValue<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>, CodegenError> {
+        if r.get_sign_extended_constant()
+            .is_some_and(|exponent| exponent < 0)
+        {
+            if l.get_zero_extended_constant() == Some(0) {
+                return Err("0.0 cannot be raised to a negative power".into());
+            }
+            let float_type = self.context.f64_type();
+            let l_float = self
+                .builder
+                .build_signed_int_to_float(l, float_type, "l_float")?;
+            let r_float = self
+                .builder
+                .build_signed_int_to_float(r, float_type, "r_float")?;
+            return Ok(self.build_float_power(l_float, r_float)?.into());
+        }
+
+        let pow_fn = self.get_or_build_pow_function()?;
+        Ok(self
+            .builder
+            .build_call(pow_fn, &[l.into(), r.into()], "powtmp")?
+            .try_as_basic_value()
+            .unwrap_basic())
+    }
+
+    /// Float `**` through the `llvm.pow` intrinsic
+    fn build_float_power(
+        &self,
+        l: FloatValue<'ctx>,
+        r: FloatValue<'ctx>,
+    ) -> Result<FloatValue<'ctx>, CodegenError> {
+        let pow_fn = Intrinsic::find("llvm.pow")
+            .and_then(|intrinsic| intrinsic.get_declaration(&self.module, &[l.get_type().into()]))
+            .ok_or_else(|| "Intrinsic llvm.pow is not available".to_string())?;
+        Ok(self
+            .builder
+            .build_call(pow_fn, &[l.into(), r.into()], "fpowtmp")?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_float_value())
+    }
+
+    /// Get or define `i64 pycc_pow(i64 base, i64 exponent)`
+    ///
+    /// Exponentiation by squaring. Multiplications go through
+    /// `build_int_arithmetic`, so `--overflow-checks` also covers `**`. A
+    /// negative exponent only known at run time would need a float result, so
+    /// it is reported as an error instead.
+    fn get_or_build_pow_function(
+        &mut self,
+    ) -> Result<inkwell::values::FunctionValue<'ctx>, CodegenError> {
+        if let Some(func) = self.module.get_function("pycc_pow") {
+            return Ok(func);
+        }
+
+        let i64_type = self.context.i64_type();
+        let fn_type = i64_type.fn_type(&[i64_type.into(), i64_type.into()], false);
+        let function =
+            self.module
+                .add_function("pycc_pow", fn_type, Some(inkwell::module::Linkage::Private));
+
+        // Save current position
+        let current_position = self.save_builder_position();
+
+        let entry_block = self.context.append_basic_block(function, "entry");
+        let negative_block = self
+            .context
+            .append_basic_block(function, "negative_exponent");
+        let loop_block = self.context.append_basic_block(function, "pow_loop");
+        let body_block = self.context.append_basic_block(function, "pow_body");
+        let multiply_block = self.context.append_basic_block(function, "pow_multiply");
+        let square_block = self.context.append_basic_block(function, "pow_square");
+        let square_base_block = self.context.append_basic_block(function, "pow_square_base");
+        let done_block = self.context.append_basic_block(function, "pow_done");
+
+        self.builder.position_at_end(entry_block);
+        let base = nth_param(function, 0)?.into_int_value();
+        let exponent = nth_param(function, 1)?.into_int_value();
+        let zero = i64_type.const_zero();
+        let one = i64_type.const_int(1, false);
+        let result_ptr = self.builder.build_alloca(i64_type, "result")?;
+        let base_ptr = self.builder.build_alloca(i64_type, "base")?;
+        let exponent_ptr = self.builder.build_alloca(i64_type, "exponent")?;
+        self.builder.build_store(result_ptr, one)?;
+        self.builder.build_store(base_ptr, base)?;
+        self.builder.build_store(exponent_ptr, exponent)?;
+        let is_negative = self.builder.build_int_compare(
+            inkwell::IntPredicate::SLT,
+            exponent,
+            zero,
+            "is_negative",
+        )?;
+        self.builder
+            .build_conditional_branch(is_negative, negative_block, loop_block)?;
+
+        self.builder.position_at_end(negative_block);
+        self.build_fatal_error(
+            "ValueError: negative integer exponent needs a float result (pycc limitation)\n",
+            "negative_exponent_message",
+        )?;
+
+        // while exponent > 0
+        self.builder.position_at_end(loop_block);
+        let exponent = self
+            .builder
+            .build_load(i64_type, exponent_ptr, "exponent")?
+            .into_int_value();
+        let has_bits = self.builder.build_int_compare(
+            inkwell::IntPredicate::SGT,
+            exponent,
+            zero,
+            "has_bits",
+        )?;
+        self.builder
+            .build_conditional_branch(has_bits, body_block, done_block)?;
+
+        // if exponent & 1: result *= base
+        self.builder.position_at_end(body_block);
+        let low_bit = self.builder.build_and(exponent, one, "low_bit")?;
+        let is_odd =
+            self.builder
+                .build_int_compare(inkwell::IntPredicate::NE, low_bit, zero, "is_odd")?;
+        self.builder
+            .build_conditional_branch(is_odd, multiply_block, square_block)?;
+
+        self.builder.position_at_end(multiply_block);
+        let result = self
+            .builder
+            .build_load(i64_type, result_ptr, "result")?
+            .into_int_value();
+        let base = self
+            .builder
+            .build_load(i64_type, base_ptr, "base")?
+            .into_int_value();
+        let result = self.build_int_arithmetic(IntArithmetic::Multiply, result, base, "result")?;
+        self.builder.build_store(result_ptr, result)?;
+        self.builder.build_unconditional_branch(square_block)?;
+
+        // exponent >>= 1, squaring the base only while it is still needed so
+        // the last square cannot report a spurious overflow
+        self.builder.position_at_end(square_block);
+        let exponent = self
+            .builder
+            .build_right_shift(exponent, one, true, "exponent")?;
+        self.builder.build_store(exponent_ptr, exponent)?;
+        let more_bits = self.builder.build_int_compare(
+            inkwell::IntPredicate::SGT,
+            exponent,
+            zero,
+            "more_bits",
+        )?;
+        self.builder
+            .build_conditional_branch(more_bits, square_base_block, done_block)?;
+
+        self.builder.position_at_end(square_base_block);
+        let base = self
+            .builder
+            .build_load(i64_type, base_ptr, "base")?
+            .into_int_value();
+        let base = self.build_int_arithmetic(IntArithmetic::Multiply, base, base, "base")?;
+        self.builder.build_store(base_ptr, base)?;
+        self.builder.build_unconditional_branch(loop_block)?;
+
+        self.builder.position_at_end(done_block);
+        let result = self.builder.build_load(i64_type, result_ptr, "result")?;
+        self.builder.build_return(Some(&result))?;
+
+        // Restore previous position
+        self.restore_builder_position(current_position);
+
+        Ok(function)
+    }
+
+    /// LLVM representation of a statically known type
+    fn llvm_type(&self, ty: Type) -> BasicTypeEnum<'ctx> {
+        match ty {
+            Type::Float => self.context.f64_type().into(),
+            Type::Str => self
+                .context
+                .ptr_type(inkwell::AddressSpace::default())
+                .into(),
+            Type::Bool => self.context.bool_type().into(),
+            // None is encoded as the integer 0
+            Type::Int | Type::None | Type::Any => self.context.i64_type().into(),
+        }
+    }
+
+    /// Widen a value to the type a parameter or return slot expects, e.g. an
+    /// int argument passed to a parameter annotated as float
+    fn coerce_to(
+        &self,
+        value: BasicValueEnum<'ctx>,
+        ty: BasicTypeEnum<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>, CodegenError> {
+        let value = if ty == self.context.bool_type().into() {
+            value
+        } else {
+            self.coerce_bool_to_int(value)?
+        };
+        Ok(match (value, ty) {
+            (BasicValueEnum::IntValue(int_val), BasicTypeEnum::FloatType(float_type)) => self
+                .builder
+                .build_signed_int_to_float(int_val, float_type, "int_to_float")?
+                .into(),
+            _ => value,
+        })
+    }
+
+    /// Widen an i1 bool to an i64 int (`True` is 1), leaving other values as they are
+    fn coerce_bool_to_int(
+        &self,
+        value: BasicValueEnum<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>, CodegenError> {
+        Ok(match value {
+            BasicValueEnum::IntValue(int_val) if int_val.get_type().get_bit_width() == 1 => self
+                .builder
+                .build_int_z_extend(int_val, self.context.i64_type(), "bool_to_int")?
+                .into(),
+            _ => value,
+        })
+    }
+
+    /// Pointer to the constant string "True" or "False" for an i1 value
+    fn build_bool_string(&self, value: IntValue<'ctx>) -> Result<PointerValue<'ctx>, CodegenError> {
+        let global_string = |text: &str, name: &str| -> Result<PointerValue<'ctx>, CodegenError> {
+            Ok(match self.module.get_global(name) {
+                Some(global) => global.as_pointer_value(),
+                None => self
+                    .builder
+                    .build_global_string_ptr(text, name)?
+                    .as_pointer_value(),
+            })
+        };
+        let true_str = global_string("True", "bool_true_str")?;
+        let false_str = global_string("False", "bool_false_str")?;
+        Ok(self
+            .builder
+            .build_select(value, true_str, false_str, "bool_str")?
+            .into_pointer_value())
+    }
+
+    /// Get a declared function, or declare it with the given type
+    ///
+    /// The types must match the declarations used elsewhere in this file, e.g.
+    /// `strlen` returns i32 and `malloc` takes an i64.
+    fn get_or_declare_function(
+        &self,
+        name: &str,
+        fn_type: inkwell::types::FunctionType<'ctx>,
+    ) -> inkwell::values::FunctionValue<'ctx> {
+        match self.module.get_function(name) {
+            Some(func) => func,
+            None => self.module.add_function(name, fn_type, None),
+        }
+    }
+
+    /// `repr(value)` as a string pointer
+    ///
+    /// Strings are quoted and escaped and floats use the shortest
+    /// representation that round-trips, matching CPython.
+    fn build_repr(
+        &mut self,
+        value: CompilerValue<'ctx>,
+    ) -> Result<PointerValue<'ctx>, CodegenError> {
+        match value.llvm_value {
+            BasicValueEnum::PointerValue(ptr_val) => {
+                let repr_fn = self.get_or_build_repr_str_function()?;
+                Ok(self
+                    .builder
+                    .build_call(repr_fn, &[ptr_val.into()], "reprtmp")?
+                    .try_as_basic_value()
+                    .unwrap_basic()
+                    .into_pointer_value())
+            }
+            BasicValueEnum::FloatValue(float_val) => self.build_float_repr(float_val),
+            BasicValueEnum::IntValue(_) => Ok(self.value_to_string(value)?.into_pointer_value()),
+            _ => Err("Unsupported argument to repr()".into()),
+        }
+    }
+
+    /// Shortest round-tripping text for a float, as printed by CPython
+    fn build_float_repr(
+        &mut self,
+        value: FloatValue<'ctx>,
+    ) -> Result<PointerValue<'ctx>, CodegenError> {
+        let repr_fn = self.get_or_build_repr_float_function()?;
+        Ok(self
+            .builder
+            .build_call(repr_fn, &[value.into()], "float_repr")?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value())
+    }
+
+    /// `hash(value)`
+    ///
+    /// Integers hash like CPython (reduced modulo 2**61 - 1), bools as 0 and
+    /// 1, and integral floats like the equal integer, so equal values hash
+    /// equally. Other floats hash their bit pattern and strings use FNV-1a.
+    /// As in CPython, -1 is never returned.
+    fn build_hash(&mut self, value: BasicValueEnum<'ctx>) -> Result<IntValue<'ctx>, CodegenError> {
+        let i64_type = self.context.i64_type();
+        let hash = match self.coerce_bool_to_int(value)? {
+            BasicValueEnum::IntValue(int_val) => self.build_int_hash(int_val)?,
+            BasicValueEnum::FloatValue(float_val) => {
+                let as_int =
+                    self.builder
+                        .build_float_to_signed_int(float_val, i64_type, "float_as_int")?;
+                let back =
+                    self.builder
+                        .build_signed_int_to_float(as_int, float_val.get_type(), "back")?;
+                let is_integral = self.builder.build_float_compare(
+                    inkwell::FloatPredicate::OEQ,
+                    back,
+                    float_val,
+                    "is_integral",
+                )?;
+                let int_hash = self.build_int_hash(as_int)?;
+                let bits = self
+                    .builder
+                    .build_bit_cast(float_val, i64_type, "float_bits")?
+                    .into_int_value();
+                let high = self.builder.build_right_shift(
+                    bits,
+                    i64_type.const_int(32, false),
+                    false,
+                    "high_bits",
+                )?;
+                let bits_hash = self.builder.build_xor(bits, high, "bits_hash")?;
+                self.builder
+                    .build_select(is_integral, int_hash, bits_hash, "float_hash")?
+                    .into_int_value()
+            }
+            BasicValueEnum::PointerValue(ptr_val) => {
+                let hash_fn = self.get_or_build_hash_str_function()?;
+                self.builder
+                    .build_call(hash_fn, &[ptr_val.into()], "str_hash")?
+                    .try_as_basic_value()
+                    .unwrap_basic()
+                    .into_int_value()
+            }
+            _ => return Err("Unsupported argument to hash()".into()),
+        };
+
+        // -1 is reserved for errors in CPython's hash protocol
+        let minus_one = i64_type.const_int((-1i64) as u64, true);
+        let is_minus_one = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            hash,
+            minus_one,
+            "is_minus_one",
+        )?;
+        Ok(self
+            .builder
+            .build_select(
+                is_minus_one,
+                i64_type.const_int((-2i64) as u64, true),
+                hash,
+                "hash",
+            )?
+            .into_int_value())
+    }
+
+    /// `value mod (2**61 - 1)` keeping the sign of `value`, CPython's integer hash
+    fn build_int_hash(&self, value: IntValue<'ctx>) -> Result<IntValue<'ctx>, CodegenError> {
+        let modulus = value.get_type().const_int((1u64 << 61) - 1, false);
+        Ok(self
+            .builder
+            .build_int_signed_rem(value, modulus, "int_hash")?)
+    }
+
+    /// `id(value)`
+    ///
+    /// Strings are identified by their address. Numbers and bools are
+    /// unboxed, so their identity is their 64-bit pattern, which is stable
+    /// and shared by equal values like CPython's cached small ints.
+    fn build_id(&mut self, value: BasicValueEnum<'ctx>) -> Result<IntValue<'ctx>, CodegenError> {
+        let i64_type = self.context.i64_type();
+        match self.coerce_bool_to_int(value)? {
+            BasicValueEnum::IntValue(int_val) => Ok(int_val),
+            BasicValueEnum::FloatValue(float_val) => Ok(self
+                .builder
+                .build_bit_cast(float_val, i64_type, "float_bits")?
+                .into_int_value()),
+            BasicValueEnum::PointerValue(ptr_val) => Ok(self
+                .builder
+                .build_ptr_to_int(ptr_val, i64_type, "address")?),
+            _ => Err("Unsupported argument to id()".into()),
+        }
+    }
+
+    /// Get or define `i64 pycc_hash_str(ptr)`, FNV-1a over the string's bytes
+    fn get_or_build_hash_str_function(
+        &mut self,
+    ) -> Result<inkwell::values::FunctionValue<'ctx>, CodegenError> {
+        if let Some(func) = self.module.get_function("pycc_hash_str") {
+            return Ok(func);
+        }
+
+        let i8_type = self.context.i8_type();
+        let i64_type = self.context.i64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+
+        let function = self.module.add_function(
+            "pycc_hash_str",
+            i64_type.fn_type(&[ptr_type.into()], false),
+            Some(inkwell::module::Linkage::Private),
+        );
+
+        // Save current position
+        let current_position = self.save_builder_position();
+
+        let entry_block = self.context.append_basic_block(function, "entry");
+        let loop_block = self.context.append_basic_block(function, "hash_loop");
+        let body_block = self.context.append_basic_block(function, "hash_byte");
+        let done_block = self.context.append_basic_block(function, "hash_done");
+
+        self.builder.position_at_end(entry_block);
+        let source = nth_param(function, 0)?.into_pointer_value();
+        let hash_ptr = self.builder.build_alloca(i64_type, "hash")?;
+        let index_ptr = self.builder.build_alloca(i64_type, "index")?;
+        self.builder
+            .build_store(hash_ptr, i64_type.const_int(0xcbf2_9ce4_8422_2325, false))?;
+        self.builder.build_store(index_ptr, i64_type.const_zero())?;
+        self.builder.build_unconditional_branch(loop_block)?;
+
+        // Stop at the terminating NUL
+        self.builder.position_at_end(loop_block);
+        let index = self
+            .builder
+            .build_load(i64_type, index_ptr, "index")?
+            .into_int_value();
+        let byte_ptr = unsafe {
+            self.builder
+                .build_gep(i8_type, source, &[index], "byte_ptr")?
+        };
+        let byte = self
+            .builder
+            .build_load(i8_type, byte_ptr, "byte")?
+            .into_int_value();
+        let at_end = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            byte,
+            i8_type.const_zero(),
+            "at_end",
+        )?;
+        self.builder
+            .build_conditional_branch(at_end, done_block, body_block)?;
+
+        // hash = (hash ^ byte) * FNV prime
+        self.builder.position_at_end(body_block);
+        let hash = self
+            .builder
+            .build_load(i64_type, hash_ptr, "hash")?
+            .into_int_value();
+        let byte = self.builder.build_int_z_extend(byte, i64_type, "byte64")?;
+        let hash = self.builder.build_xor(hash, byte, "hash")?;
+        let hash = self.builder.build_int_mul(
+            hash,
+            i64_type.const_int(0x0000_0100_0000_01b3, false),
+            "hash",
+        )?;
+        self.builder.build_store(hash_ptr, hash)?;
+        let index_next =
+            self.builder
+                .build_int_add(index, i64_type.const_int(1, false), "index_next")?;
+        self.builder.build_store(index_ptr, index_next)?;
+        self.builder.build_unconditional_branch(loop_block)?;
+
+        self.builder.position_at_end(done_block);
+        let hash = self.builder.build_load(i64_type, hash_ptr, "hash")?;
+        self.builder.build_return(Some(&hash))?;
+
+        // Restore previous position
+        self.restore_builder_position(current_position);
+
+        Ok(function)
+    }
+
+    /// Get or define `ptr pycc_repr_float(double)`
+    ///
+    /// Tries increasing precisions with `%.*e` until the text parses back to
+    /// the same value, then switches to fixed notation when the decimal
+    /// exponent is in [-4, 16) like CPython's `float_repr_style = 'short'`.
+    /// Integral results get a trailing ".0".
+    fn get_or_build_repr_float_function(
+        &mut self,
+    ) -> Result<inkwell::values::FunctionValue<'ctx>, CodegenError> {
+        if let Some(func) = self.module.get_function("pycc_repr_float") {
+            return Ok(func);
+        }
+
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let f64_type = self.context.f64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+
+        let alloc_fn = self.get_or_build_alloc_function()?;
+        let snprintf_fn = self.get_or_declare_function(
+            "snprintf",
+            i32_type.fn_type(&[ptr_type.into(), i32_type.into(), ptr_type.into()], true),
+        );
+        let strtod_fn = self.get_or_declare_function(
+            "strtod",
+            f64_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+        );
+        let strtol_fn = self.get_or_declare_function(
+            "strtol",
+            i64_type.fn_type(&[ptr_type.into(), ptr_type.into(), i32_type.into()], false),
+        );
+        let strchr_fn = self.get_or_declare_function(
+            "strchr",
+            ptr_type.fn_type(&[ptr_type.into(), i32_type.into()], false),
+        );
+
+        let function = self.module.add_function(
+            "pycc_repr_float",
+            ptr_type.fn_type(&[f64_type.into()], false),
+            Some(inkwell::module::Linkage::Private),
+        );
+
+        // Save current position
+        let current_position = self.save_builder_position();
+
+        let entry_block = self.context.append_basic_block(function, "entry");
+        let try_block = self.context.append_basic_block(function, "try_precision");
+        let next_block = self.context.append_basic_block(function, "next_precision");
+        let found_block = self.context.append_basic_block(function, "found");
+        let fixed_block = self.context.append_basic_block(function, "fixed");
+        let append_block = self
+            .context
+            .append_basic_block(function, "append_point_zero");
+        let done_block = self.context.append_basic_block(function, "done");
+
+        self.builder.position_at_end(entry_block);
+        let value = nth_param(function, 0)?.into_float_value();
+        let buffer_size = 48;
+        let buffer = self
+            .builder
+            .build_call(
+                alloc_fn,
+                &[i64_type.const_int(buffer_size, false).into()],
+                "buffer",
+            )?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value();
+        let precision_ptr = self.builder.build_alloca(i32_type, "precision")?;
+        self.builder
+            .build_store(precision_ptr, i32_type.const_int(1, false))?;
+        let exp_format = self
+            .builder
+            .build_global_string_ptr("%.*e", "repr_float_exp_fmt")?
+            .as_pointer_value();
+        let fixed_format = self
+            .builder
+            .build_global_string_ptr("%.*f", "repr_float_fixed_fmt")?
+            .as_pointer_value();
+        let point_zero = self
+            .builder
+            .build_global_string_ptr(".0", "repr_float_point_zero")?
+            .as_pointer_value();
+        let null = ptr_type.const_null();
+        self.builder.build_unconditional_branch(try_block)?;
+
+        // snprintf(buffer, size, "%.*e", precision - 1, value)
+        self.builder.position_at_end(try_block);
+        let precision = self
+            .builder
+            .build_load(i32_type, precision_ptr, "precision")?
+            .into_int_value();
+        let digits_after_point =
+            self.builder
+                .build_int_sub(precision, i32_type.const_int(1, false), "digits")?;
+        self.builder.build_call(
+            snprintf_fn,
+            &[
+                buffer.into(),
+                i32_type.const_int(buffer_size, false).into(),
+                exp_format.into(),
+                digits_after_point.into(),
+                value.into(),
+            ],
+            "format_exp",
+        )?;
+        let parsed = self
+            .builder
+            .build_call(strtod_fn, &[buffer.into(), null.into()], "parsed")?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_float_value();
+        let round_trips = self.builder.build_float_compare(
+            inkwell::FloatPredicate::OEQ,
+            parsed,
+            value,
+            "round_trips",
+        )?;
+        let max_precision = self.builder.build_int_compare(
+            inkwell::IntPredicate::SGE,
+            precision,
+            i32_type.const_int(17, false),
+            "max_precision",
+        )?;
+        let stop = self.builder.build_or(round_trips, max_precision, "stop")?;
+        self.builder
+            .build_conditional_branch(stop, found_block, next_block)?;
+
+        self.builder.position_at_end(next_block);
+        let next_precision = self.builder.build_int_add(
+            precision,
+            i32_type.const_int(1, false),
+            "next_precision",
+        )?;
+        self.builder.build_store(precision_ptr, next_precision)?;
+        self.builder.build_unconditional_branch(try_block)?;
+
+        // nan and inf have no exponent and are returned as they are
+        self.builder.position_at_end(found_block);
+        let exponent_marker = self
+            .builder
+            .build_call(
+                strchr_fn,
+                &[
+                    buffer.into(),
+                    i32_type.const_int(u64::from(b'e'), false).into(),
+                ],
+                "exponent_marker",
+            )?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value();
+        let has_exponent = self
+            .builder
+            .build_is_not_null(exponent_marker, "has_exponent")?;
+        let exponent_text = unsafe {
+            self.builder.build_gep(
+                self.context.i8_type(),
+                exponent_marker,
+                &[i64_type.const_int(1, false)],
+                "exponent_text",
+            )?
+        };
+        let exponent_fixed_block = self.context.append_basic_block(function, "check_exponent");
+        self.builder
+            .build_conditional_branch(has_exponent, exponent_fixed_block, done_block)?;
+
+        self.builder.position_at_end(exponent_fixed_block);
+        let exponent = self
+            .builder
+            .build_call(
+                strtol_fn,
+                &[
+                    exponent_text.into(),
+                    null.into(),
+                    i32_type.const_int(10, false).into(),
+                ],
+                "exponent",
+            )?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_int_value();
+        let not_tiny = self.builder.build_int_compare(
+            inkwell::IntPredicate::SGE,
+            exponent,
+            i64_type.const_int((-4i64) as u64, true),
+            "not_tiny",
+        )?;
+        let not_huge = self.builder.build_int_compare(
+            inkwell::IntPredicate::SLT,
+            exponent,
+            i64_type.const_int(16, false),
+            "not_huge",
+        )?;
+        let use_fixed = self.builder.build_and(not_tiny, not_huge, "use_fixed")?;
+        self.builder
+            .build_conditional_branch(use_fixed, fixed_block, done_block)?;
+
+        // snprintf(buffer, size, "%.*f", max(precision - 1 - exponent, 0), value)
+        self.builder.position_at_end(fixed_block);
+        let exponent = self
+            .builder
+            .build_int_truncate(exponent, i32_type, "exponent32")?;
+        let decimals = self
+            .builder
+            .build_int_sub(digits_after_point, exponent, "decimals")?;
+        let negative = self.builder.build_int_compare(
+            inkwell::IntPredicate::SLT,
+            decimals,
+            i32_type.const_zero(),
+            "negative",
+        )?;
+        let decimals =
+            self.builder
+                .build_select(negative, i32_type.const_zero(), decimals, "decimals")?;
+        self.builder.build_call(
+            snprintf_fn,
+            &[
+                buffer.into(),
+                i32_type.const_int(buffer_size, false).into(),
+                fixed_format.into(),
+                decimals.into(),
+                value.into(),
+            ],
+            "format_fixed",
+        )?;
+        let point = self
+            .builder
+            .build_call(
+                strchr_fn,
+                &[
+                    buffer.into(),
+                    i32_type.const_int(u64::from(b'.'), false).into(),
+                ],
+                "point",
+            )?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value();
+        let has_point = self.builder.build_is_not_null(point, "has_point")?;
+        self.builder
+            .build_conditional_branch(has_point, done_block, append_block)?;
+
+        self.builder.position_at_end(append_block);
+        // Copies ".0" with its terminator; the digits are far shorter than the buffer
+        let length = self.build_string_length(buffer, "length")?;
+        self.build_copy_at(buffer, length, point_zero, i64_type.const_int(3, false))?;
+        self.builder.build_unconditional_branch(done_block)?;
+
+        self.builder.position_at_end(done_block);
+        self.builder.build_return(Some(&buffer))?;
+
+        // Restore previous position
+        self.restore_builder_position(current_position);
+
+        Ok(function)
+    }
+
+    /// Get or define `ptr pycc_repr_str(ptr)`
+    ///
+    /// Uses single quotes unless the string contains a single quote and no
+    /// double quote, escapes backslashes, the chosen quote, `\n`, `\r` and `\t`,
+    /// and writes other control characters as `\xNN`.
+    fn get_or_build_repr_str_function(
+        &mut self,
+    ) -> Result<inkwell::values::FunctionValue<'ctx>, CodegenError> {
+        if let Some(func) = self.module.get_function("pycc_repr_str") {
+            return Ok(func);
+        }
+
+        let i8_type = self.context.i8_type();
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+
+        let strlen_fn =
+            self.get_or_declare_function("strlen", i32_type.fn_type(&[ptr_type.into()], false));
+        let alloc_fn = self.get_or_build_alloc_function()?;
+        let strchr_fn = self.get_or_declare_function(
+            "strchr",
+            ptr_type.fn_type(&[ptr_type.into(), i32_type.into()], false),
+        );
+        let snprintf_fn = self.get_or_declare_function(
+            "snprintf",
+            i32_type.fn_type(&[ptr_type.into(), i32_type.into(), ptr_type.into()], true),
+        );
+
+        let function = self.module.add_function(
+            "pycc_repr_str",
+            ptr_type.fn_type(&[ptr_type.into()], false),
+            Some(inkwell::module::Linkage::Private),
+        );
+
+        // Save current position
+        let current_position = self.save_builder_position();
+
+        let entry_block = self.context.append_basic_block(function, "entry");
+        let loop_block = self.context.append_basic_block(function, "repr_loop");
+        let body_block = self.context.append_basic_block(function, "repr_char");
+        let escape_block = self.context.append_basic_block(function, "repr_escape");
+        let check_control_block = self
+            .context
+            .append_basic_block(function, "repr_check_control");
+        let hex_block = self.context.append_basic_block(function, "repr_hex");
+        let copy_block = self.context.append_basic_block(function, "repr_copy");
+        let next_block = self.context.append_basic_block(function, "repr_next");
+        let done_block = self.context.append_basic_block(function, "repr_done");
+
+        self.builder.position_at_end(entry_block);
+        let source = nth_param(function, 0)?.into_pointer_value();
+        let length = self
+            .builder
+            .build_call(strlen_fn, &[source.into()], "length")?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_int_value();
+        let length = self
+            .builder
+            .build_int_z_extend(length, i64_type, "length64")?;
+        // Worst case every byte becomes \xNN, plus two quotes and the terminator
+        let capacity =
+            self.builder
+                .build_int_mul(length, i64_type.const_int(4, false), "capacity")?;
+        let capacity =
+            self.builder
+                .build_int_add(capacity, i64_type.const_int(3, false), "capacity")?;
+        let buffer = self
+            .builder
+            .build_call(alloc_fn, &[capacity.into()], "buffer")?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value();
+
+        let find = |this: &Self, ch: u8, name: &str| -> Result<IntValue<'ctx>, CodegenError> {
+            let found = this
+                .builder
+                .build_call(
+                    strchr_fn,
+                    &[
+                        source.into(),
+                        i32_type.const_int(u64::from(ch), false).into(),
+                    ],
+                    name,
+                )?
+                .try_as_basic_value()
+                .unwrap_basic()
+                .into_pointer_value();
+            Ok(this.builder.build_is_not_null(found, name)?)
+        };
+        let has_single = find(self, b'\'', "has_single")?;
+        let has_double = find(self, b'"', "has_double")?;
+        let no_double = self.builder.build_not(has_double, "no_double")?;
+        let use_double = self
+            .builder
+            .build_and(has_single, no_double, "use_double")?;
+        let quote = self
+            .builder
+            .build_select(
+                use_double,
+                i8_type.const_int(u64::from(b'"'), false),
+                i8_type.const_int(u64::from(b'\''), false),
+                "quote",
+            )?
+            .into_int_value();
+
+        let index_ptr = self.builder.build_alloca(i64_type, "index")?;
+        let out_ptr = self.builder.build_alloca(i64_type, "out")?;
+        self.builder.build_store(index_ptr, i64_type.const_zero())?;
+        self.builder
+            .build_store(out_ptr, i64_type.const_int(1, false))?;
+        self.builder.build_store(buffer, quote)?;
+        let hex_format = self
+            .builder
+            .build_global_string_ptr("\\x%02x", "repr_hex_fmt")?
+            .as_pointer_value();
+        self.builder.build_unconditional_branch(loop_block)?;
+
+        // while index < length
+        self.builder.position_at_end(loop_block);
+        let index = self
+            .builder
+            .build_load(i64_type, index_ptr, "index")?
+            .into_int_value();
+        let in_range = self.builder.build_int_compare(
+            inkwell::IntPredicate::ULT,
+            index,
+            length,
+            "in_range",
+        )?;
+        self.builder
+            .build_conditional_branch(in_range, body_block, done_block)?;
+
+        // Map the byte to the letter of its escape sequence, or 0 for none
+        self.builder.position_at_end(body_block);
+        let char_ptr = unsafe {
+            self.builder
+                .build_gep(i8_type, source, &[index], "char_ptr")?
+        };
+        let ch = self
+            .builder
+            .build_load(i8_type, char_ptr, "char")?
+            .into_int_value();
+        let out = self
+            .builder
+            .build_load(i64_type, out_ptr, "out")?
+            .into_int_value();
+        let mut escape = i8_type.const_zero();
+        for (byte, letter) in [
+            (
+                i8_type.const_int(u64::from(b'\t'), false),
+                i8_type.const_int(u64::from(b't'), false),
+            ),
+            (
+                i8_type.const_int(u64::from(b'\r'), false),
+                i8_type.const_int(u64::from(b'r'), false),
+            ),
+            (
+                i8_type.const_int(u64::from(b'\n'), false),
+                i8_type.const_int(u64::from(b'n'), false),
+            ),
+            (
+                i8_type.const_int(u64::from(b'\\'), false),
+                i8_type.const_int(u64::from(b'\\'), false),
+            ),
+            (quote, quote),
+        ] {
+            let matches =
+                self.builder
+                    .build_int_compare(inkwell::IntPredicate::EQ, ch, byte, "is_escape")?;
+            escape = self
+                .builder
+                .build_select(matches, letter, escape, "escape")?
+                .into_int_value();
+        }
+        let needs_escape = self.builder.build_int_compare(
+            inkwell::IntPredicate::NE,
+            escape,
+            i8_type.const_zero(),
+            "needs_escape",
+        )?;
+        self.builder
+            .build_conditional_branch(needs_escape, escape_block, check_control_block)?;
+
+        // Write a backslash followed by the escape letter
+        self.builder.position_at_end(escape_block);
+        let out_slot = unsafe {
+            self.builder
+                .build_gep(i8_type, buffer, &[out], "out_slot")?
+        };
+        self.builder
+            .build_store(out_slot, i8_type.const_int(u64::from(b'\\'), false))?;
+        let out_next = self
+            .builder
+            .build_int_add(out, i64_type.const_int(1, false), "out_next")?;
+        let out_slot = unsafe {
+            self.builder
+                .build_gep(i8_type, buffer, &[out_next], "out_slot")?
+        };
+        self.builder.build_store(out_slot, escape)?;
+        let out_next = self
+            .builder
+            .build_int_add(out, i64_type.const_int(2, false), "out_next")?;
+        self.builder.build_store(out_ptr, out_next)?;
+        self.builder.build_unconditional_branch(next_block)?;
+
+        // Other control characters (below 0x20 and DEL) are written as \xNN
+        self.builder.position_at_end(check_control_block);
+        let below_space = self.builder.build_int_compare(
+            inkwell::IntPredicate::ULT,
+            ch,
+            i8_type.const_int(0x20, false),
+            "below_space",
+        )?;
+        let is_delete = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            ch,
+            i8_type.const_int(0x7f, false),
+            "is_delete",
+        )?;
+        let is_control = self
+            .builder
+            .build_or(below_space, is_delete, "is_control")?;
+        self.builder
+            .build_conditional_branch(is_control, hex_block, copy_block)?;
+
+        self.builder.position_at_end(hex_block);
+        let out_slot = unsafe {
+            self.builder
+                .build_gep(i8_type, buffer, &[out], "out_slot")?
+        };
+        let ch_int = self.builder.build_int_z_extend(ch, i32_type, "char_int")?;
+        self.builder.build_call(
+            snprintf_fn,
+            &[
+                out_slot.into(),
+                i32_type.const_int(5, false).into(),
+                hex_format.into(),
+                ch_int.into(),
+            ],
+            "format_hex",
+        )?;
+        let out_next = self
+            .builder
+            .build_int_add(out, i64_type.const_int(4, false), "out_next")?;
+        self.builder.build_store(out_ptr, out_next)?;
+        self.builder.build_unconditional_branch(next_block)?;
+
+        self.builder.position_at_end(copy_block);
+        let out_slot = unsafe {
+            self.builder
+                .build_gep(i8_type, buffer, &[out], "out_slot")?
+        };
+        self.builder.build_store(out_slot, ch)?;
+        let out_next = self
+            .builder
+            .build_int_add(out, i64_type.const_int(1, false), "out_next")?;
+        self.builder.build_store(out_ptr, out_next)?;
+        self.builder.build_unconditional_branch(next_block)?;
+
+        self.builder.position_at_end(next_block);
+        let index_next =
+            self.builder
+                .build_int_add(index, i64_type.const_int(1, false), "index_next")?;
+        self.builder.build_store(index_ptr, index_next)?;
+        self.builder.build_unconditional_branch(loop_block)?;
+
+        // Closing quote and terminator
+        self.builder.position_at_end(done_block);
+        let out = self
+            .builder
+            .build_load(i64_type, out_ptr, "out")?
+            .into_int_value();
+        let out_slot = unsafe {
+            self.builder
+                .build_gep(i8_type, buffer, &[out], "out_slot")?
+        };
+        self.builder.build_store(out_slot, quote)?;
+        let out_next = self
+            .builder
+            .build_int_add(out, i64_type.const_int(1, false), "out_next")?;
+        let out_slot = unsafe {
+            self.builder
+                .build_gep(i8_type, buffer, &[out_next], "out_slot")?
+        };
+        self.builder.build_store(out_slot, i8_type.const_zero())?;
+        self.builder.build_return(Some(&buffer))?;
+
+        // Restore previous position
+        self.restore_builder_position(current_position);
+
+        Ok(function)
+    }
+
+    /// Allocate a stack slot in the entry block of the current function
+    ///
+    /// Keeping every alloca in the entry block means variables assigned inside
+    /// a branch still dominate their uses after the branch merges.
+    /// Allocate `size` bytes for a string result
+    ///
+    /// Strings that escape the current frame live on the heap; everything else
+    /// is a stack buffer released when the function returns.
+    fn allocate_string_buffer(
+        &mut self,
+        size: IntValue<'ctx>,
+        escapes: bool,
+        name: &str,
+    ) -> Result<PointerValue<'ctx>, CodegenError> {
+        let i8_type = self.context.i8_type();
+        Ok(if escapes {
+            let alloc_fn = self.get_or_build_alloc_function()?;
+            self.builder
+                .build_call(alloc_fn, &[size.into()], name)?
+                .try_as_basic_value()
+                .unwrap_basic()
+                .into_pointer_value()
+        } else {
+            self.builder.build_array_alloca(i8_type, size, name)?
+        })
+    }
+
+    /// Length of the C string `text`, widened to i64
+    fn build_string_length(
+        &mut self,
+        text: PointerValue<'ctx>,
+        name: &str,
+    ) -> Result<IntValue<'ctx>, CodegenError> {
+        let i32_type = self.context.i32_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let strlen_fn =
+            self.get_or_declare_function("strlen", i32_type.fn_type(&[ptr_type.into()], false));
+        let length = self
+            .builder
+            .build_call(strlen_fn, &[text.into()], name)?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_int_value();
+        Ok(self
+            .builder
+            .build_int_z_extend(length, self.context.i64_type(), name)?)
+    }
+
+    /// Copy `length` bytes from `source` to `destination + offset`
+    ///
+    /// Strings are built with known lengths into buffers sized for them, so
+    /// every copy is bounded and a sanitizer sees exactly the bytes touched.
+    fn build_copy_at(
+        &mut self,
+        destination: PointerValue<'ctx>,
+        offset: IntValue<'ctx>,
+        source: PointerValue<'ctx>,
+        length: IntValue<'ctx>,
+    ) -> Result<(), CodegenError> {
+        let i8_type = self.context.i8_type();
+        let i64_type = self.context.i64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let memcpy_fn = self.get_or_declare_function(
+            "memcpy",
+            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into(), i64_type.into()], false),
+        );
+        let target = unsafe {
+            self.builder
+                .build_gep(i8_type, destination, &[offset], "copy_target")?
+        };
+        self.builder.build_call(
+            memcpy_fn,
+            &[target.into(), source.into(), length.into()],
+            "",
+        )?;
+        Ok(())
+    }
+
+    fn create_entry_block_alloca(
+        &self,
+        ty: BasicTypeEnum<'ctx>,
+        name: &str,
+    ) -> Result<PointerValue<'ctx>, CodegenError> {
+        let entry = self
+            .current_function()?
+            .get_first_basic_block()
+            .ok_or("internal compiler error: function has no entry block")?;
+
+        let entry_builder = self.context.create_builder();
+        match entry.get_first_instruction() {
+            Some(first) => entry_builder.position_before(&first),
+            None => entry_builder.position_at_end(entry),
+        }
+        Ok(entry_builder.build_alloca(ty, name)?)
+    }
+
+    /// Python and LLVM signature of a function
+    fn function_types(
+        &self,
+        function: &crate::ast::Function,
+    ) -> (
+        FunctionSignature,
+        Vec<BasicTypeEnum<'ctx>>,
+        BasicTypeEnum<'ctx>,
+    ) {
+        // Untyped functions use i64 for every parameter and the return value;
+        // with typed codegen, annotated ones get native LLVM types instead
+        let mut signature = FunctionSignature::from_function(function);
+        if !self.options.typed {
+            signature.parameters.fill(Type::Any);
+            signature.return_type = Type::Any;
+        }
+        // Without a `return <value>` anywhere, every call evaluates to None
+        if function.return_type.is_none() && !returns_value(&function.body) {
+            signature.return_type = Type::None;
+        }
+        let (param_types, return_type) = if self.options.typed {
+            (
+                signature
+                    .parameters
+                    .iter()
+                    .map(|ty| self.llvm_type(*ty))
+                    .collect(),
+                self.llvm_type(signature.return_type),
+            )
+        } else {
+            let int_type = self.context.i64_type().into();
+            (vec![int_type; function.parameters.len()], int_type)
+        };
+        (signature, param_types, return_type)
+    }
+
+    /// Add an LLVM function for `function` and bind its name to it
+    ///
+    /// A name that is already taken gets a uniquely suffixed LLVM symbol, so
+    /// redefining a function rebinds the name for the code that follows.
+    fn declare_function(&mut self, function: &crate::ast::Function) -> FunctionValue<'ctx> {
+        let (signature, param_types, return_type) = self.function_types(function);
+        let param_metadata_types: Vec<_> = param_types.iter().map(|ty| (*ty).into()).collect();
+        let fn_type = return_type.fn_type(&param_metadata_types, false);
+
+        let function_value = self.module.add_function(
+            &format!("{PYTHON_SYMBOL_PREFIX}{}", function.name),
+            fn_type,
+            None,
+        );
+        if self.options.optimization > 0 {
+            self.add_inlining_attributes(function, function_value);
+        }
+        self.functions.insert(
+            function.name.clone(),
+            FunctionBinding {
+                value: function_value,
+                parameters: function.parameters.clone(),
+                return_type: signature.return_type,
+            },
+        );
+        function_value
+    }
+
+    /// Encourage the inliner to remove the call overhead of small functions
+    ///
+    /// Single-statement functions that do not call themselves are always
+    /// inlined; others up to `SMALL_FUNCTION_STATEMENTS` statements get a hint.
+    fn add_inlining_attributes(
+        &self,
+        function: &crate::ast::Function,
+        function_value: FunctionValue<'ctx>,
+    ) {
+        use inkwell::attributes::{Attribute, AttributeLoc};
+
+        let statements = statement_count(&function.body);
+        let hint = if statements == 1 && !calls_function(&function.body, &function.name) {
+            Some("alwaysinline")
+        } else if statements <= SMALL_FUNCTION_STATEMENTS {
+            Some("inlinehint")
+        } else {
+            None
+        };
+        if let Some(hint) = hint {
+            let kind = Attribute::get_named_enum_kind_id(hint);
+            function_value.add_attribute(
+                AttributeLoc::Function,
+                self.context.create_enum_attribute(kind, 0),
+            );
+        }
+
+        // Read by LLVM's inline cost analysis when deciding on calls to this function
+        if let Some(threshold) = self.options.inline_threshold {
+            function_value.add_attribute(
+                AttributeLoc::Function,
+                self.context
+                    .create_string_attribute("function-inline-threshold", &threshold.to_string()),
+            );
+        }
+    }
+
+    fn compile_function(&mut self, function: &crate::ast::Function) -> Result<(), CodegenError> {
+        if function.is_async {
+            return Err(format!(
+                "Unsupported async function '{}': coroutines cannot be compiled yet (use --fallback interp to run the program)",
+                function.name
+            ).into());
+        }
+        // Save current position
+        let current_position = self.save_builder_position();
+
+        // Fill in the up-front declaration unless another `def` already did
+        let (signature, param_types, return_type) = self.function_types(function);
+        let param_metadata_types: Vec<_> = param_types.iter().map(|ty| (*ty).into()).collect();
+        let fn_type = return_type.fn_type(&param_metadata_types, false);
+        let function_value = match self.functions.get(&function.name) {
+            Some(binding)
+                if binding.value.count_basic_blocks() == 0
+                    && binding.value.get_type() == fn_type
+                    && binding.parameters == function.parameters =>
+            {
+                binding.value
+            }
+            _ => self.declare_function(function),
+        };
+        if self.module_level {
+            self.defined_functions.insert(function.name.clone());
+        }
+        let outer_module_level = std::mem::replace(&mut self.module_level, false);
+
+        // Locals live in the function's own frame, module-level slots are not visible
+        let outer_variables = std::mem::take(&mut self.variables);
+        let outer_locals =
+            std::mem::replace(&mut self.local_names, constants::local_names(function));
+        let outer_escaping = std::mem::replace(
+            &mut self.escaping_variables,
+            escape::escaping_variables(&function.body),
+        );
+
+        // Create basic block
+        let basic_block = self.context.append_basic_block(function_value, "entry");
+        self.builder.position_at_end(basic_block);
+        let outer_debug_scope = self.enter_debug_scope(function_value, function);
+        self.build_recursion_check(function_value)?;
+        self.build_interrupt_check(function_value)?;
+        let profile = self.build_profile_entry(function)?;
+        let outer_profile = std::mem::replace(&mut self.current_profile, profile);
+
+        // Create allocations for parameters
+        for (i, param_name) in function.parameters.iter().enumerate() {
+            let param = nth_param(function_value, i as u32)?;
+            let ptr = self.builder.build_alloca(param_types[i], param_name)?;
+            self.builder.build_store(ptr, param)?;
+            self.variables.insert(
+                param_name.clone(),
+                Variable {
+                    slot: ptr,
+                    llvm_type: param_types[i],
+                    py_type: signature.parameters[i],
+                },
+            );
+        }
+
+        // Compile function body
+        let body_result = self.compile_statement(&function.body);
+        self.variables = outer_variables;
+        self.local_names = outer_locals;
+        self.escaping_variables = outer_escaping;
+
+        // Add return instruction if not already present
+        if body_result.is_ok()
+            && self
+                .builder
+                .get_insert_block()
+                .and_then(|block| block.get_terminator())
+                .is_none()
+        {
+            self.build_function_return(return_type.const_zero())?;
+        }
+        self.module_level = outer_module_level;
+        self.current_profile = outer_profile;
+        if let (Some(debug_info), Some(scope)) = (&mut self.debug_info, outer_debug_scope) {
+            debug_info.scope = scope;
+        }
+        body_result?;
+
+        // Restore previous position
+        self.restore_builder_position(current_position);
+
+        Ok(())
+    }
+
+    /// Opt every generated function into AddressSanitizer instrumentation
+    fn add_sanitizer_attributes(&self) {
+        use inkwell::attributes::{Attribute, AttributeLoc};
+
+        let kind = Attribute::get_named_enum_kind_id("sanitize_address");
+        for function in self.module.get_functions() {
+            if function.count_basic_blocks() > 0 {
+                function.add_attribute(
+                    AttributeLoc::Function,
+                    self.context.create_enum_attribute(kind, 0),
+                );
+            }
+        }
+    }
+
+    /// Print the profile table at exit; its body is generated once all functions are known
+    fn build_profile_install(&mut self) -> Result<(), CodegenError> {
+        let i32_type = self.context.i32_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let report_fn = self.module.add_function(
+            "pycc_profile_report",
+            self.context.void_type().fn_type(&[], false),
+            Some(inkwell::module::Linkage::Private),
+        );
+        let atexit_fn =
+            self.get_or_declare_function("atexit", i32_type.fn_type(&[ptr_type.into()], false));
+        self.builder.build_call(
+            atexit_fn,
+            &[report_fn.as_global_value().as_pointer_value().into()],
+            "",
+        )?;
+        Ok(())
+    }
+
+    /// Count a call of `function` and record when it started
+    fn build_profile_entry(
+        &mut self,
+        function: &crate::ast::Function,
+    ) -> Result<Option<(usize, PointerValue<'ctx>)>, CodegenError> {
+        if !self.options.instrument_profile {
+            return Ok(None);
+        }
+
+        let i64_type = self.context.i64_type();
+        let index = self.profile_counters.len();
+        let counter = |kind: &str| {
+            let global = self.module.add_global(
+                i64_type,
+                None,
+                &format!("pycc_profile_{kind}.{}", function.name),
+            );
+            global.set_linkage(inkwell::module::Linkage::Private);
+            global.set_initializer(&i64_type.const_zero());
+            global.as_pointer_value()
+        };
+        let counters = ProfileCounters {
+            name: function.name.clone(),
+            line: function.line,
+            calls: counter("calls"),
+            nanoseconds: counter("nanoseconds"),
+            depth: counter("depth"),
+        };
+
+        let increment = |counter: PointerValue<'ctx>, name: &str| -> Result<(), CodegenError> {
+            let value = self
+                .builder
+                .build_load(i64_type, counter, name)?
+                .into_int_value();
+            let value = self
+                .builder
+                .build_int_add(value, i64_type.const_int(1, false), name)?;
+            self.builder.build_store(counter, value)?;
+            Ok(())
+        };
+        increment(counters.calls, "calls")?;
+        increment(counters.depth, "profile_depth")?;
+
+        let start_slot = self.create_entry_block_alloca(i64_type.into(), "profile_start")?;
+        let start = self.build_now_ns()?;
+        self.builder.build_store(start_slot, start)?;
+
+        self.profile_counters.push(counters);
+        Ok(Some((index, start_slot)))
+    }
+
+    /// Add the time spent in the current call unless it is nested in another of the same function
+    fn build_profile_exit(&mut self) -> Result<(), CodegenError> {
+        let Some((index, start_slot)) = self.current_profile else {
+            return Ok(());
+        };
+        let i64_type = self.context.i64_type();
+        let counters = self.profile_counters[index].clone();
+
+        let depth = self
+            .builder
+            .build_load(i64_type, counters.depth, "profile_depth")?
+            .into_int_value();
+        let depth =
+            self.builder
+                .build_int_sub(depth, i64_type.const_int(1, false), "profile_depth")?;
+        self.builder.build_store(counters.depth, depth)?;
+
+        let now = self.build_now_ns()?;
+        let start = self
+            .builder
+            .build_load(i64_type, start_slot, "profile_start")?
+            .into_int_value();
+        let elapsed = self.builder.build_int_sub(now, start, "elapsed")?;
+        let outermost = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            depth,
+            i64_type.const_zero(),
+            "outermost",
+        )?;
+        let elapsed = self
+            .builder
+            .build_select(outermost, elapsed, i64_type.const_zero(), "elapsed")?
+            .into_int_value();
+        let total = self
+            .builder
+            .build_load(i64_type, counters.nanoseconds, "nanoseconds")?
+            .into_int_value();
+        let total = self.builder.build_int_add(total, elapsed, "nanoseconds")?;
+        self.builder.build_store(counters.nanoseconds, total)?;
+        Ok(())
+    }
+
+    /// Monotonic clock reading in nanoseconds
+    fn build_now_ns(&mut self) -> Result<IntValue<'ctx>, CodegenError> {
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let clock_gettime_fn = self.get_or_declare_function(
+            "clock_gettime",
+            i32_type.fn_type(&[i32_type.into(), ptr_type.into()], false),
+        );
+
+        // struct timespec { time_t tv_sec; long tv_nsec; }
+        let timespec_type = self
+            .context
+            .struct_type(&[i64_type.into(), i64_type.into()], false);
+        let timespec = self.create_entry_block_alloca(timespec_type.into(), "timespec")?;
+        self.builder.build_call(
+            clock_gettime_fn,
+            &[
+                i32_type
+                    .const_int(Platform::host().clock_monotonic(), false)
+                    .into(),
+                timespec.into(),
+            ],
+            "",
+        )?;
+
+        let field = |index: u32, name: &str| -> Result<IntValue<'ctx>, CodegenError> {
+            let pointer = self
+                .builder
+                .build_struct_gep(timespec_type, timespec, index, name)?;
+            Ok(self
+                .builder
+                .build_load(i64_type, pointer, name)?
+                .into_int_value())
+        };
+        let seconds = field(0, "tv_sec")?;
+        let nanoseconds = field(1, "tv_nsec")?;
+        let seconds_ns = self.builder.build_int_mul(
+            seconds,
+            i64_type.const_int(1_000_000_000, false),
+            "sec_ns",
+        )?;
+        Ok(self
+            .builder
+            .build_int_add(seconds_ns, nanoseconds, "now_ns")?)
+    }
+
+    /// Define `pycc_profile_report`, which prints calls and cumulative time per function
+    fn build_profile_report_function(&mut self) -> Result<(), CodegenError> {
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let f64_type = self.context.f64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let dprintf_fn = self.get_or_declare_function(
+            "dprintf",
+            i32_type.fn_type(&[i32_type.into(), ptr_type.into()], true),
+        );
+        let function = self
+            .module
+            .get_function("pycc_profile_report")
+            .ok_or("internal compiler error: pycc_profile_report is not declared")?;
+
+        // Save current position
+        let current_position = self.save_builder_position();
+
+        let entry_block = self.context.append_basic_block(function, "entry");
+        self.builder.position_at_end(entry_block);
+
+        // Print after the program's own output, which is still buffered at exit
+        let fflush_fn =
+            self.get_or_declare_function("fflush", i32_type.fn_type(&[ptr_type.into()], false));
+        self.builder
+            .build_call(fflush_fn, &[ptr_type.const_null().into()], "")?;
+
+        let stderr = i32_type.const_int(2, false);
+        let header = self
+            .builder
+            .build_global_string_ptr("      calls   cumulative ms  function\n", "profile_header")?
+            .as_pointer_value();
+        self.builder
+            .build_call(dprintf_fn, &[stderr.into(), header.into()], "")?;
+        let row_fmt = self
+            .builder
+            .build_global_string_ptr("%11ld %15.3f  %s (line %d)\n", "profile_row_fmt")?
+            .as_pointer_value();
+
+        for counters in self.profile_counters.clone() {
+            let calls = self.builder.build_load(i64_type, counters.calls, "calls")?;
+            let nanoseconds = self
+                .builder
+                .build_load(i64_type, counters.nanoseconds, "nanoseconds")?
+                .into_int_value();
+            let nanoseconds =
+                self.builder
+                    .build_signed_int_to_float(nanoseconds, f64_type, "nanoseconds")?;
+            let milliseconds = self.builder.build_float_div(
+                nanoseconds,
+                f64_type.const_float(1e6),
+                "milliseconds",
+            )?;
+            let name = self
+                .builder
+                .build_global_string_ptr(&counters.name, "profile_name")?
+                .as_pointer_value();
+            self.builder.build_call(
+                dprintf_fn,
+                &[
+                    stderr.into(),
+                    row_fmt.into(),
+                    calls.into(),
+                    milliseconds.into(),
+                    name.into(),
+                    i32_type.const_int(counters.line as u64, false).into(),
+                ],
+                "",
+            )?;
+        }
+        self.builder.build_return(None)?;
+
+        // Restore previous position
+        self.restore_builder_position(current_position);
+        Ok(())
+    }
+
+    /// Give every statement line a counter and write an lcov report at exit
+    fn build_coverage_install(&mut self, statements: &[Node]) -> Result<(), CodegenError> {
+        self.coverage_lines = coverage::statement_lines(statements).into_iter().collect();
+
+        let i32_type = self.context.i32_type();
+        let counts_type = self
+            .context
+            .i64_type()
+            .array_type(self.coverage_lines.len() as u32);
+        let counts = self
+            .module
+            .add_global(counts_type, None, "pycc_coverage_counts");
+        counts.set_linkage(inkwell::module::Linkage::Private);
+        counts.set_initializer(&counts_type.const_zero());
+
+        let dump_fn = self.build_coverage_dump_function()?;
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let atexit_fn =
+            self.get_or_declare_function("atexit", i32_type.fn_type(&[ptr_type.into()], false));
+        self.builder.build_call(
+            atexit_fn,
+            &[dump_fn.as_global_value().as_pointer_value().into()],
+            "",
+        )?;
+        Ok(())
+    }
+
+    /// Count one execution of `line` when coverage is enabled
+    fn build_coverage_count(&mut self, line: usize) -> Result<(), CodegenError> {
+        let Ok(index) = self.coverage_lines.binary_search(&line) else {
+            return Ok(());
+        };
+        // Nothing runs after a terminator, such as statements following a `return`
+        if self
+            .builder
+            .get_insert_block()
+            .and_then(|block| block.get_terminator())
+            .is_some()
+        {
+            return Ok(());
+        }
+
+        let counter = self.coverage_counter(index)?;
+        let i64_type = self.context.i64_type();
+        let count = self
+            .builder
+            .build_load(i64_type, counter, "coverage_count")?
+            .into_int_value();
+        let count =
+            self.builder
+                .build_int_add(count, i64_type.const_int(1, false), "coverage_count")?;
+        self.builder.build_store(counter, count)?;
+        Ok(())
+    }
+
+    /// Write `FILE:LINE: TEXT` to stderr for `--trace`, followed by the repr of `value`
+    ///
+    /// Values without a repr pycc can compute natively, such as lists, are
+    /// left out.
+    fn build_trace(
+        &mut self,
+        line: usize,
+        text: &str,
+        value: Option<CompilerValue<'ctx>>,
+    ) -> Result<(), CodegenError> {
+        // Nothing runs after a terminator, such as statements following a `return`
+        if self
+            .builder
+            .get_insert_block()
+            .and_then(|block| block.get_terminator())
+            .is_some()
+        {
+            return Ok(());
+        }
+
+        let i32_type = self.context.i32_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let fprintf_fn = self.get_or_declare_function(
+            "fprintf",
+            i32_type.fn_type(&[ptr_type.into(), ptr_type.into()], true),
+        );
+
+        let file = self
+            .module
+            .get_source_file_name()
+            .to_string_lossy()
+            .into_owned();
+        let prefix = format!("{file}:{line}: {text}").replace('%', "%%");
+        let mut args: Vec<inkwell::values::BasicMetadataValueEnum<'ctx>> = Vec::new();
+        let format = match value {
+            Some(value) => {
+                let repr = match value.py_type {
+                    Type::None => self.constant_string("None", "none_str")?,
+                    Type::Int | Type::Float | Type::Bool | Type::Str => self.build_repr(value)?,
+                    _ => return Ok(()),
+                };
+                args.push(repr.into());
+                format!("{prefix} %s\n")
+            }
+            None => format!("{prefix}\n"),
+        };
+        let format = self.constant_string(&format, "trace_format")?;
+        let stderr = self.build_stream("stderr")?;
+        args.splice(0..0, [stderr.into(), format.into()]);
+        self.builder.build_call(fprintf_fn, &args, "")?;
+        Ok(())
+    }
+
+    /// Pointer to the counter at `index` of `pycc_coverage_counts`
+    fn coverage_counter(&self, index: usize) -> Result<PointerValue<'ctx>, CodegenError> {
+        let i64_type = self.context.i64_type();
+        let counts = self
+            .module
+            .get_global("pycc_coverage_counts")
+            .ok_or("internal compiler error: pycc_coverage_counts is not declared")?;
+        let counts_type = i64_type.array_type(self.coverage_lines.len() as u32);
+        Ok(unsafe {
+            self.builder.build_in_bounds_gep(
+                counts_type,
+                counts.as_pointer_value(),
+                &[
+                    i64_type.const_zero(),
+                    i64_type.const_int(index as u64, false),
+                ],
+                "coverage_counter",
+            )?
+        })
+    }
+
+    /// Define `void pycc_coverage_dump()`, which writes the counters as lcov
+    ///
+    /// The report goes to `$PYCC_COVERAGE_FILE`, or `coverage.lcov` in the
+    /// working directory.
+    fn build_coverage_dump_function(&mut self) -> Result<FunctionValue<'ctx>, CodegenError> {
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+
+        let getenv_fn =
+            self.get_or_declare_function("getenv", ptr_type.fn_type(&[ptr_type.into()], false));
+        let fopen_fn = self.get_or_declare_function(
+            "fopen",
+            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+        );
+        let fprintf_fn = self.get_or_declare_function(
+            "fprintf",
+            i32_type.fn_type(&[ptr_type.into(), ptr_type.into()], true),
+        );
+        let fclose_fn =
+            self.get_or_declare_function("fclose", i32_type.fn_type(&[ptr_type.into()], false));
+
+        let function = self.module.add_function(
+            "pycc_coverage_dump",
+            self.context.void_type().fn_type(&[], false),
+            Some(inkwell::module::Linkage::Private),
+        );
+
+        // Save current position
+        let current_position = self.save_builder_position();
+
+        let entry_block = self.context.append_basic_block(function, "entry");
+        let write_block = self.context.append_basic_block(function, "write_report");
+        let done_block = self.context.append_basic_block(function, "done");
+
+        self.builder.position_at_end(entry_block);
+        let global_string = |name: &str, value: &str| -> Result<PointerValue<'ctx>, CodegenError> {
+            Ok(self
+                .builder
+                .build_global_string_ptr(value, name)?
+                .as_pointer_value())
+        };
+        let variable_name = global_string("coverage_env", "PYCC_COVERAGE_FILE")?;
+        let default_path = global_string("coverage_default_path", "coverage.lcov")?;
+        let write_mode = global_string("coverage_mode", "w")?;
+        let source_file = self
+            .module
+            .get_source_file_name()
+            .to_string_lossy()
+            .into_owned();
+        let source_path = global_string("coverage_source", &source_file)?;
+        let header_fmt = global_string("coverage_header_fmt", "TN:\nSF:%s\n")?;
+        let line_fmt = global_string("coverage_line_fmt", "DA:%d,%ld\n")?;
+        let footer_fmt = global_string("coverage_footer_fmt", "LF:%d\nLH:%ld\nend_of_record\n")?;
+
+        let env_path = self
+            .builder
+            .build_call(getenv_fn, &[variable_name.into()], "env_path")?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value();
+        let env_unset = self.builder.build_is_null(env_path, "env_unset")?;
+        let path = self
+            .builder
+            .build_select(env_unset, default_path, env_path, "report_path")?;
+        let file = self
+            .builder
+            .build_call(fopen_fn, &[path.into(), write_mode.into()], "report")?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value();
+        let open_failed = self.builder.build_is_null(file, "open_failed")?;
+        self.builder
+            .build_conditional_branch(open_failed, done_block, write_block)?;
+
+        self.builder.position_at_end(write_block);
+        self.builder.build_call(
+            fprintf_fn,
+            &[file.into(), header_fmt.into(), source_path.into()],
+            "",
+        )?;
+        let mut lines_hit = i64_type.const_zero();
+        for (index, line) in self.coverage_lines.clone().into_iter().enumerate() {
+            let counter = self.coverage_counter(index)?;
+            let count = self
+                .builder
+                .build_load(i64_type, counter, "count")?
+                .into_int_value();
+            self.builder.build_call(
+                fprintf_fn,
+                &[
+                    file.into(),
+                    line_fmt.into(),
+                    i32_type.const_int(line as u64, false).into(),
+                    count.into(),
+                ],
+                "",
+            )?;
+            let hit = self.builder.build_int_compare(
+                inkwell::IntPredicate::NE,
+                count,
+                i64_type.const_zero(),
+                "hit",
+            )?;
+            let hit = self.builder.build_int_z_extend(hit, i64_type, "hit")?;
+            lines_hit = self.builder.build_int_add(lines_hit, hit, "lines_hit")?;
+        }
+        self.builder.build_call(
+            fprintf_fn,
+            &[
+                file.into(),
+                footer_fmt.into(),
+                i32_type
+                    .const_int(self.coverage_lines.len() as u64, false)
+                    .into(),
+                lines_hit.into(),
+            ],
+            "",
+        )?;
+        self.builder.build_call(fclose_fn, &[file.into()], "")?;
+        self.builder.build_unconditional_branch(done_block)?;
+
+        self.builder.position_at_end(done_block);
+        self.builder.build_return(None)?;
+
+        // Restore previous position
+        self.restore_builder_position(current_position);
+
+        Ok(function)
+    }
+
+    /// Count executed statements in `pycc_step_count` and write the total at exit
+    ///
+    /// The total goes to `$PYCC_STEPS_FILE`, and nowhere when it is unset.
+    fn build_step_count_install(&mut self) -> Result<(), CodegenError> {
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let count = self.module.add_global(i64_type, None, "pycc_step_count");
+        count.set_linkage(inkwell::module::Linkage::Private);
+        count.set_initializer(&i64_type.const_zero());
+
+        let getenv_fn =
+            self.get_or_declare_function("getenv", ptr_type.fn_type(&[ptr_type.into()], false));
+        let fopen_fn = self.get_or_declare_function(
+            "fopen",
+            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+        );
+        let fprintf_fn = self.get_or_declare_function(
+            "fprintf",
+            i32_type.fn_type(&[ptr_type.into(), ptr_type.into()], true),
+        );
+        let fclose_fn =
+            self.get_or_declare_function("fclose", i32_type.fn_type(&[ptr_type.into()], false));
+        let atexit_fn =
+            self.get_or_declare_function("atexit", i32_type.fn_type(&[ptr_type.into()], false));
+
+        let function = self.module.add_function(
+            "pycc_step_count_dump",
+            self.context.void_type().fn_type(&[], false),
+            Some(inkwell::module::Linkage::Private),
+        );
+        self.builder.build_call(
+            atexit_fn,
+            &[function.as_global_value().as_pointer_value().into()],
+            "",
+        )?;
+
+        // Save current position
+        let current_position = self.save_builder_position();
+
+        let entry_block = self.context.append_basic_block(function, "entry");
+        let open_block = self.context.append_basic_block(function, "open_report");
+        let write_block = self.context.append_basic_block(function, "write_report");
+        let done_block = self.context.append_basic_block(function, "done");
+
+        self.builder.position_at_end(entry_block);
+        let variable_name = self
+            .builder
+            .build_global_string_ptr("PYCC_STEPS_FILE", "steps_env")?
+            .as_pointer_value();
+        let path = self
+            .builder
+            .build_call(getenv_fn, &[variable_name.into()], "steps_path")?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value();
+        let unset = self.builder.build_is_null(path, "steps_unset")?;
+        self.builder
+            .build_conditional_branch(unset, done_block, open_block)?;
+
+        self.builder.position_at_end(open_block);
+        let write_mode = self
+            .builder
+            .build_global_string_ptr("w", "steps_mode")?
+            .as_pointer_value();
+        let file = self
+            .builder
+            .build_call(fopen_fn, &[path.into(), write_mode.into()], "report")?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value();
+        let open_failed = self.builder.build_is_null(file, "open_failed")?;
+        self.builder
+            .build_conditional_branch(open_failed, done_block, write_block)?;
+
+        self.builder.position_at_end(write_block);
+        let format = self
+            .builder
+            .build_global_string_ptr("%ld\n", "steps_fmt")?
+            .as_pointer_value();
+        let steps = self
+            .builder
+            .build_load(i64_type, count.as_pointer_value(), "steps")?;
+        self.builder
+            .build_call(fprintf_fn, &[file.into(), format.into(), steps.into()], "")?;
+        self.builder.build_call(fclose_fn, &[file.into()], "")?;
+        self.builder.build_unconditional_branch(done_block)?;
+
+        self.builder.position_at_end(done_block);
+        self.builder.build_return(None)?;
+
+        // Restore previous position
+        self.restore_builder_position(current_position);
+        Ok(())
+    }
+
+    /// Count one executed statement for `--stats`
+    fn build_step_count(&mut self) -> Result<(), CodegenError> {
+        // Nothing runs after a terminator, such as statements following a `return`
+        if self
+            .builder
+            .get_insert_block()
+            .and_then(|block| block.get_terminator())
+            .is_some()
+        {
+            return Ok(());
+        }
+
+        let counter = self
+            .module
+            .get_global("pycc_step_count")
+            .ok_or("internal compiler error: pycc_step_count is not declared")?
+            .as_pointer_value();
+        let i64_type = self.context.i64_type();
+        let count = self
+            .builder
+            .build_load(i64_type, counter, "step_count")?
+            .into_int_value();
+        let count =
+            self.builder
+                .build_int_add(count, i64_type.const_int(1, false), "step_count")?;
+        self.builder.build_store(counter, count)?;
+        Ok(())
+    }
+
+    /// Get or define `ptr pycc_alloc(i64)`, `malloc` that the runtime frees at exit
+    ///
+    /// Every heap string is recorded in a growable table. The first allocation
+    /// registers `pycc_runtime_teardown` with `atexit`, so programs that never
+    /// allocate pay nothing and leak checkers see no live blocks at exit.
+    fn get_or_build_alloc_function(&mut self) -> Result<FunctionValue<'ctx>, CodegenError> {
+        if let Some(func) = self.module.get_function("pycc_alloc") {
+            return Ok(func);
+        }
+
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+
+        let malloc_fn =
+            self.get_or_declare_function("malloc", ptr_type.fn_type(&[i64_type.into()], false));
+        let realloc_fn = self.get_or_declare_function(
+            "realloc",
+            ptr_type.fn_type(&[ptr_type.into(), i64_type.into()], false),
+        );
+        let atexit_fn =
+            self.get_or_declare_function("atexit", i32_type.fn_type(&[ptr_type.into()], false));
+        let teardown = self.get_or_build_runtime_teardown()?;
+        let (table_global, count_global, capacity_global) = self.heap_table_globals();
+
+        let function = self.module.add_function(
+            "pycc_alloc",
+            ptr_type.fn_type(&[i64_type.into()], false),
+            Some(inkwell::module::Linkage::Private),
+        );
+
+        // Save current position
+        let current_position = self.save_builder_position();
+
+        let entry_block = self.context.append_basic_block(function, "entry");
+        let grow_block = self.context.append_basic_block(function, "grow");
+        let register_block = self
+            .context
+            .append_basic_block(function, "register_teardown");
+        let resize_block = self.context.append_basic_block(function, "resize");
+        let record_block = self.context.append_basic_block(function, "record");
+
+        self.builder.position_at_end(entry_block);
+        let size = nth_param(function, 0)?;
+        let object = self
+            .builder
+            .build_call(malloc_fn, &[size.into()], "object")?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value();
+        let bytes_global = self.heap_bytes_global();
+        let bytes = self
+            .builder
+            .build_load(i64_type, bytes_global, "bytes")?
+            .into_int_value();
+        let total_bytes =
+            self.builder
+                .build_int_add(bytes, size.into_int_value(), "total_bytes")?;
+        self.builder.build_store(bytes_global, total_bytes)?;
+        let count = self
+            .builder
+            .build_load(i64_type, count_global, "count")?
+            .into_int_value();
+        let capacity = self
+            .builder
+            .build_load(i64_type, capacity_global, "capacity")?
+            .into_int_value();
+        let is_full = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            count,
+            capacity,
+            "is_full",
+        )?;
+        self.builder
+            .build_conditional_branch(is_full, grow_block, record_block)?;
+
+        self.builder.position_at_end(grow_block);
+        let is_first = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            capacity,
+            i64_type.const_zero(),
+            "is_first",
+        )?;
+        self.builder
+            .build_conditional_branch(is_first, register_block, resize_block)?;
+
+        self.builder.position_at_end(register_block);
+        self.builder.build_call(
+            atexit_fn,
+            &[teardown.as_global_value().as_pointer_value().into()],
+            "",
+        )?;
+        self.builder.build_unconditional_branch(resize_block)?;
+
+        self.builder.position_at_end(resize_block);
+        let doubled =
+            self.builder
+                .build_int_mul(capacity, i64_type.const_int(2, false), "doubled")?;
+        let new_capacity = self
+            .builder
+            .build_select(
+                is_first,
+                i64_type.const_int(HEAP_TABLE_INITIAL_CAPACITY, false),
+                doubled,
+                "new_capacity",
+            )?
+            .into_int_value();
+        let table_bytes = self.builder.build_int_mul(
+            new_capacity,
+            i64_type.const_int(8, false),
+            "table_bytes",
+        )?;
+        let old_table = self
+            .builder
+            .build_load(ptr_type, table_global, "old_table")?;
+        let new_table = self
+            .builder
+            .build_call(
+                realloc_fn,
+                &[old_table.into(), table_bytes.into()],
+                "new_table",
+            )?
+            .try_as_basic_value()
+            .unwrap_basic();
+        self.builder.build_store(table_global, new_table)?;
+        self.builder.build_store(capacity_global, new_capacity)?;
+        self.builder.build_unconditional_branch(record_block)?;
+
+        self.builder.position_at_end(record_block);
+        let table = self
+            .builder
+            .build_load(ptr_type, table_global, "table")?
+            .into_pointer_value();
+        let slot = unsafe { self.builder.build_gep(ptr_type, table, &[count], "slot")? };
+        self.builder.build_store(slot, object)?;
+        let next_count =
+            self.builder
+                .build_int_add(count, i64_type.const_int(1, false), "next_count")?;
+        self.builder.build_store(count_global, next_count)?;
+        self.builder.build_return(Some(&object))?;
+
+        // Restore previous position
+        self.restore_builder_position(current_position);
+
+        Ok(function)
+    }
+
+    /// Get or define `void pycc_runtime_teardown()`, freeing every `pycc_alloc` block
+    ///
+    /// With `PYCC_HEAP_STATS=1` in the environment it first prints how many
+    /// blocks were allocated and their size to stderr.
+    fn get_or_build_runtime_teardown(&mut self) -> Result<FunctionValue<'ctx>, CodegenError> {
+        if let Some(func) = self.module.get_function("pycc_runtime_teardown") {
+            return Ok(func);
+        }
+
+        let i8_type = self.context.i8_type();
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+
+        let free_fn = self.get_or_declare_function(
+            "free",
+            self.context.void_type().fn_type(&[ptr_type.into()], false),
+        );
+        let getenv_fn =
+            self.get_or_declare_function("getenv", ptr_type.fn_type(&[ptr_type.into()], false));
+        let fprintf_fn = self.get_or_declare_function(
+            "fprintf",
+            i32_type.fn_type(&[ptr_type.into(), ptr_type.into()], true),
+        );
+        let (table_global, count_global, capacity_global) = self.heap_table_globals();
+        let bytes_global = self.heap_bytes_global();
+
+        let function = self.module.add_function(
+            "pycc_runtime_teardown",
+            self.context.void_type().fn_type(&[], false),
+            Some(inkwell::module::Linkage::Private),
+        );
+
+        // Save current position
+        let current_position = self.save_builder_position();
+
+        let entry_block = self.context.append_basic_block(function, "entry");
+        let check_block = self.context.append_basic_block(function, "check_stats");
+        let report_block = self.context.append_basic_block(function, "report_stats");
+        let free_block = self.context.append_basic_block(function, "free_objects");
+        let loop_block = self.context.append_basic_block(function, "free_object");
+        let done_block = self.context.append_basic_block(function, "done");
+
+        self.builder.position_at_end(entry_block);
+        let variable_name = self
+            .builder
+            .build_global_string_ptr(heap_stats::HEAP_STATS_VARIABLE, "heap_stats_env")?
+            .as_pointer_value();
+        let setting = self
+            .builder
+            .build_call(getenv_fn, &[variable_name.into()], "heap_stats")?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value();
+        let is_unset = self.builder.build_is_null(setting, "is_unset")?;
+        self.builder
+            .build_conditional_branch(is_unset, free_block, check_block)?;
+
+        self.builder.position_at_end(check_block);
+        let first = self
+            .builder
+            .build_load(i8_type, setting, "first")?
+            .into_int_value();
+        let is_enabled = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            first,
+            i8_type.const_int(b'1' as u64, false),
+            "is_enabled",
+        )?;
+        self.builder
+            .build_conditional_branch(is_enabled, report_block, free_block)?;
+
+        // Heap strings are the only objects pycc_alloc hands out so far
+        self.builder.position_at_end(report_block);
+        let summary_fmt = self
+            .builder
+            .build_global_string_ptr(
+                &format!(
+                    "{} str allocations=%ld bytes=%ld\n",
+                    heap_stats::SUMMARY_PREFIX
+                ),
+                "heap_stats_fmt",
+            )?
+            .as_pointer_value();
+        let stderr = self.build_stream("stderr")?;
+        let allocations = self
+            .builder
+            .build_load(i64_type, count_global, "allocations")?;
+        let bytes = self.builder.build_load(i64_type, bytes_global, "bytes")?;
+        self.builder.build_call(
+            fprintf_fn,
+            &[
+                stderr.into(),
+                summary_fmt.into(),
+                allocations.into(),
+                bytes.into(),
+            ],
+            "",
+        )?;
+        self.builder.build_unconditional_branch(free_block)?;
+
+        self.builder.position_at_end(free_block);
+        let table = self
+            .builder
+            .build_load(ptr_type, table_global, "table")?
+            .into_pointer_value();
+        let count = self
+            .builder
+            .build_load(i64_type, count_global, "count")?
+            .into_int_value();
+        let is_empty = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            count,
+            i64_type.const_zero(),
+            "is_empty",
+        )?;
+        self.builder
+            .build_conditional_branch(is_empty, done_block, loop_block)?;
+
+        self.builder.position_at_end(loop_block);
+        let index = self.builder.build_phi(i64_type, "index")?;
+        index.add_incoming(&[(&i64_type.const_zero(), free_block)]);
+        let index_value = index.as_basic_value().into_int_value();
+        let slot = unsafe {
+            self.builder
+                .build_gep(ptr_type, table, &[index_value], "slot")?
+        };
+        let object = self.builder.build_load(ptr_type, slot, "object")?;
+        self.builder.build_call(free_fn, &[object.into()], "")?;
+        let next_index =
+            self.builder
+                .build_int_add(index_value, i64_type.const_int(1, false), "next_index")?;
+        index.add_incoming(&[(&next_index, loop_block)]);
+        let is_done = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            next_index,
+            count,
+            "is_done",
+        )?;
+        self.builder
+            .build_conditional_branch(is_done, done_block, loop_block)?;
+
+        self.builder.position_at_end(done_block);
+        self.builder.build_call(free_fn, &[table.into()], "")?;
+        // Later exit handlers may still allocate; they start a fresh table
+        self.builder
+            .build_store(table_global, ptr_type.const_null())?;
+        self.builder
+            .build_store(count_global, i64_type.const_zero())?;
+        self.builder
+            .build_store(capacity_global, i64_type.const_zero())?;
+        self.builder
+            .build_store(bytes_global, i64_type.const_zero())?;
+        self.builder.build_return(None)?;
+
+        // Restore previous position
+        self.restore_builder_position(current_position);
+
+        Ok(function)
+    }
+
+    /// Globals holding the `pycc_alloc` table, its length and its capacity
+    fn heap_table_globals(&self) -> (PointerValue<'ctx>, PointerValue<'ctx>, PointerValue<'ctx>) {
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let i64_type = self.context.i64_type();
+        let table = self.runtime_global("pycc_heap_objects", ptr_type.as_basic_type_enum());
+        let count = self.runtime_global("pycc_heap_count", i64_type.as_basic_type_enum());
+        let capacity = self.runtime_global("pycc_heap_capacity", i64_type.as_basic_type_enum());
+        (table, count, capacity)
+    }
+
+    /// Global counting the bytes requested from `pycc_alloc` since the table was last freed
+    fn heap_bytes_global(&self) -> PointerValue<'ctx> {
+        let i64_type = self.context.i64_type();
+        self.runtime_global("pycc_heap_bytes", i64_type.as_basic_type_enum())
+    }
+
+    /// Private zero-initialized global `name`, created on first use
+    fn runtime_global(&self, name: &str, ty: BasicTypeEnum<'ctx>) -> PointerValue<'ctx> {
+        if let Some(global) = self.module.get_global(name) {
+            return global.as_pointer_value();
+        }
+        let global = self.module.add_global(ty, None, name);
+        global.set_linkage(inkwell::module::Linkage::Private);
+        global.set_initializer(&ty.const_zero());
+        global.as_pointer_value()
+    }
+
+    /// Without the `python` feature there is no CPython to embed
+    #[cfg(not(feature = "python"))]
+    fn build_python_install(&mut self) -> Result<(), CodegenError> {
+        Err(python_feature_disabled("--python-interop"))
+    }
+
+    /// Start CPython for `--python-interop` and shut it down at exit
+    #[cfg(feature = "python")]
+    fn build_python_install(&mut self) -> Result<(), CodegenError> {
+        let i32_type = self.context.i32_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let void_type = self.context.void_type();
+
+        let initialize_fn =
+            self.get_or_declare_function("Py_Initialize", void_type.fn_type(&[], false));
+        let finalize_fn =
+            self.get_or_declare_function("Py_FinalizeEx", i32_type.fn_type(&[], false));
+        let fflush_fn =
+            self.get_or_declare_function("fflush", i32_type.fn_type(&[ptr_type.into()], false));
+        let atexit_fn =
+            self.get_or_declare_function("atexit", i32_type.fn_type(&[ptr_type.into()], false));
+
+        let shutdown_fn = self.module.add_function(
+            "pycc_python_finalize",
+            void_type.fn_type(&[], false),
+            Some(inkwell::module::Linkage::Private),
+        );
+
+        // Save current position
+        let current_position = self.save_builder_position();
+
+        let entry_block = self.context.append_basic_block(shutdown_fn, "entry");
+        self.builder.position_at_end(entry_block);
+        self.builder
+            .build_call(fflush_fn, &[ptr_type.const_null().into()], "")?;
+        self.builder.build_call(finalize_fn, &[], "")?;
+        self.builder.build_return(None)?;
+
+        // Restore previous position
+        self.restore_builder_position(current_position);
+
+        self.builder.build_call(initialize_fn, &[], "")?;
+        // Registered first so it runs after every other exit handler
+        self.builder.build_call(
+            atexit_fn,
+            &[shutdown_fn.as_global_value().as_pointer_value().into()],
+            "",
+        )?;
+        Ok(())
+    }
+
+    /// `sys.exit(status)`, ending the program with CPython's exit status
+    ///
+    /// No status or `None` exits with 0 and an int with that status. Any other
+    /// value is printed to stderr and the status is 1. Exit handlers still run,
+    /// so buffered output is flushed.
+    fn compile_sys_exit(&mut self, call: &Call) -> Result<CompilerValue<'ctx>, CodegenError> {
+        if call.arguments.len() > 1 {
+            return Err(format!(
+                "TypeError: exit expected at most 1 argument, got {}",
+                call.arguments.len()
+            )
+            .into());
+        }
+        let i32_type = self.context.i32_type();
+        let void_type = self.context.void_type();
+        let exit_fn =
+            self.get_or_declare_function("exit", void_type.fn_type(&[i32_type.into()], false));
+
+        let value = match call.arguments.first() {
+            Some(argument) => Some(self.compile_expression(argument)?),
+            None => None,
+        };
+        let status = match value {
+            None => i32_type.const_zero(),
+            Some(value) if value.py_type == Type::None => i32_type.const_zero(),
+            Some(value) => match value.llvm_value {
+                BasicValueEnum::IntValue(int_val) if int_val.get_type().get_bit_width() == 1 => {
+                    self.builder
+                        .build_int_z_extend(int_val, i32_type, "status")?
+                }
+                BasicValueEnum::IntValue(int_val) => self
+                    .builder
+                    .build_int_truncate(int_val, i32_type, "status")?,
+                BasicValueEnum::FloatValue(_) | BasicValueEnum::PointerValue(_) => {
+                    let stderr = self.build_stream("stderr")?;
+                    self.build_print_values(stderr, &[value])?;
+                    i32_type.const_int(1, false)
+                }
+                _ => return Err("Unsupported sys.exit() argument".into()),
+            },
+        };
+        // The block stays open; code after the call is dead but well-formed
+        self.builder.build_call(exit_fn, &[status.into()], "")?;
+        Ok(self.build_none())
+    }
+
+    /// `os.getenv`, `os.environ.get`, `os.system`, `os.path.join` or `os.path.exists`
+    ///
+    /// Returns `None` for other names so they can go through CPython. Without
+    /// a default, unset variables read as `""` since strings cannot be None.
+    fn compile_os_call(
+        &mut self,
+        call: &crate::ast::Call,
+    ) -> Result<Option<CompilerValue<'ctx>>, CodegenError> {
+        let name = call.callee.as_str();
+        let arity = match name {
+            "os.getenv" | "os.environ.get" => 1..=2,
+            "os.path.join" => 1..=usize::MAX,
+            "os.system" | "os.path.exists" => 1..=1,
+            _ => return Ok(None),
+        };
+        if !arity.contains(&call.arguments.len()) {
+            return Err(format!(
+                "TypeError: {name}() got {} argument(s)",
+                call.arguments.len()
+            )
+            .into());
+        }
+        let mut arguments = Vec::new();
+        for argument in &call.arguments {
+            match self.compile_expression(argument)?.llvm_value {
+                BasicValueEnum::PointerValue(text) => arguments.push(text),
+                _ => return Err(format!("TypeError: {name}() arguments must be str").into()),
+            }
+        }
+
+        let i32_type = self.context.i32_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let value = match name {
+            "os.system" => {
+                let fflush_fn = self
+                    .get_or_declare_function("fflush", i32_type.fn_type(&[ptr_type.into()], false));
+                let system_fn = self
+                    .get_or_declare_function("system", i32_type.fn_type(&[ptr_type.into()], false));
+                // Output printed so far must precede the command's
+                self.builder
+                    .build_call(fflush_fn, &[ptr_type.const_null().into()], "")?;
+                let status = self
+                    .builder
+                    .build_call(system_fn, &[arguments[0].into()], "system")?
+                    .try_as_basic_value()
+                    .unwrap_basic()
+                    .into_int_value();
+                // Like CPython, the raw wait status: the exit code is `status >> 8`
+                let status =
+                    self.builder
+                        .build_int_s_extend(status, self.context.i64_type(), "status")?;
+                CompilerValue::new(status.into(), Type::Int)
+            }
+            "os.path.exists" => {
+                let access_fn = self.get_or_declare_function(
+                    "access",
+                    i32_type.fn_type(&[ptr_type.into(), i32_type.into()], false),
+                );
+                let status = self
+                    .builder
+                    .build_call(
+                        access_fn,
+                        &[arguments[0].into(), i32_type.const_int(F_OK, false).into()],
+                        "access",
+                    )?
+                    .try_as_basic_value()
+                    .unwrap_basic()
+                    .into_int_value();
+                let exists = self.builder.build_int_compare(
+                    inkwell::IntPredicate::EQ,
+                    status,
+                    i32_type.const_zero(),
+                    "exists",
+                )?;
+                CompilerValue::new(exists.into(), Type::Bool)
+            }
+            "os.path.join" => {
+                let join_fn = self.get_or_build_path_join_function()?;
+                let mut path = arguments[0];
+                for component in &arguments[1..] {
+                    path = self
+                        .builder
+                        .build_call(join_fn, &[path.into(), (*component).into()], "path")?
+                        .try_as_basic_value()
+                        .unwrap_basic()
+                        .into_pointer_value();
+                }
+                CompilerValue::new(path.into(), Type::Str)
+            }
+            _ => {
+                let getenv_fn = self
+                    .get_or_declare_function("getenv", ptr_type.fn_type(&[ptr_type.into()], false));
+                let value = self
+                    .builder
+                    .build_call(getenv_fn, &[arguments[0].into()], "env_value")?
+                    .try_as_basic_value()
+                    .unwrap_basic()
+                    .into_pointer_value();
+                let default = match arguments.get(1) {
+                    Some(default) => *default,
+                    None => self
+                        .builder
+                        .build_global_string_ptr("", "env_unset")?
+                        .as_pointer_value(),
+                };
+                let is_set = self.builder.build_is_not_null(value, "env_is_set")?;
+                let value = self.builder.build_select(is_set, value, default, "env")?;
+                CompilerValue::new(value, Type::Str)
+            }
+        };
+        Ok(Some(value))
+    }
+
+    /// `os.environ[name]`, raising KeyError when the variable is unset
+    fn compile_environ_item(&mut self, index: &Node) -> Result<CompilerValue<'ctx>, CodegenError> {
+        let BasicValueEnum::PointerValue(name) = self.compile_expression(index)?.llvm_value else {
+            return Err("TypeError: os.environ keys must be str".into());
+        };
+        let item_fn = self.get_or_build_environ_item_function()?;
+        let value = self
+            .builder
+            .build_call(item_fn, &[name.into()], "env_value")?
+            .try_as_basic_value()
+            .unwrap_basic();
+        Ok(CompilerValue::new(value, Type::Str))
+    }
+
+    /// Compile an expression whose value is dropped
+    ///
+    /// Temporary strings are stack buffers that would otherwise live until the
+    /// function returns, which for the module body is the end of the program.
+    /// Restoring the stack pointer after the statement releases them at once.
+    fn compile_discarded(&mut self, expression: &Node) -> Result<(), CodegenError> {
+        let stack_save = Intrinsic::find("llvm.stacksave")
+            .and_then(|intrinsic| intrinsic.get_declaration(&self.module, &[]))
+            .ok_or_else(|| "Intrinsic llvm.stacksave is not available".to_string())?;
+        let stack_restore = Intrinsic::find("llvm.stackrestore")
+            .and_then(|intrinsic| intrinsic.get_declaration(&self.module, &[]))
+            .ok_or_else(|| "Intrinsic llvm.stackrestore is not available".to_string())?;
+
+        let stack = self
+            .builder
+            .build_call(stack_save, &[], "stack")?
+            .try_as_basic_value()
+            .unwrap_basic();
+        self.compile_expression(expression)?;
+        self.builder
+            .build_call(stack_restore, &[stack.into()], "")?;
+        Ok(())
+    }
+
+    /// Call of a builtin compiled natively, with its arity already checked
+    fn compile_builtin_call(
+        &mut self,
+        lowering: Lowering,
+        call: &Call,
+    ) -> Result<CompilerValue<'ctx>, CodegenError> {
+        match lowering {
+            Lowering::Print => self.compile_print(call),
+            Lowering::Input => self.compile_input_call(call),
+            Lowering::Repr => {
+                let value = self.compile_expression(&call.arguments[0])?;
+                Ok(CompilerValue::new(
+                    self.build_repr(value)?.into(),
+                    Type::Str,
+                ))
+            }
+            Lowering::Hash => {
+                let value = self.compile_expression(&call.arguments[0])?.llvm_value;
+                Ok(CompilerValue::new(
+                    self.build_hash(value)?.into(),
+                    Type::Int,
+                ))
+            }
+            Lowering::Id => {
+                let value = self.compile_expression(&call.arguments[0])?.llvm_value;
+                Ok(CompilerValue::new(self.build_id(value)?.into(), Type::Int))
+            }
+            Lowering::Int => {
+                let value = self.compile_expression(&call.arguments[0])?.llvm_value;
+                let int_val = match self.coerce_bool_to_int(value)? {
+                    BasicValueEnum::IntValue(int_val) => int_val,
+                    // int() truncates towards zero
+                    BasicValueEnum::FloatValue(float_val) => {
+                        self.builder.build_float_to_signed_int(
+                            float_val,
+                            self.context.i64_type(),
+                            "float_to_int",
+                        )?
+                    }
+                    _ => return Err("int() argument must be a number or a bool".into()),
+                };
+                Ok(CompilerValue::new(int_val.into(), Type::Int))
+            }
+        }
+    }
+
+    /// `print(*args, file=..., flush=...)`, with the arguments separated by spaces
+    fn compile_print(&mut self, call: &Call) -> Result<CompilerValue<'ctx>, CodegenError> {
+        let mut values = Vec::new();
+        for argument in &call.arguments {
+            values.push(self.compile_expression(argument)?);
+        }
+        let mut stream = None;
+        let mut flush = None;
+        for (keyword, argument) in &call.keywords {
+            let keyword = keyword.as_deref().unwrap_or_default();
+            match keyword {
+                "file" => stream = Some(self.compile_stream(argument)?),
+                "flush" => {
+                    let value = self.compile_expression(argument)?.llvm_value;
+                    flush = Some(self.build_truthiness(value)?);
+                }
+                _ => {
+                    return Err(format!(
+                        "Unsupported keyword argument: print() does not accept '{keyword}' yet"
+                    )
+                    .into());
+                }
+            }
+        }
+
+        let stream = match stream {
+            Some(stream) => stream,
+            None => self.build_stream("stdout")?,
+        };
+        self.build_print_values(stream, &values)?;
+        if let Some(flush) = flush {
+            self.build_flush_if(flush, stream)?;
+        }
+        // Print function returns None
+        Ok(self.build_none())
+    }
+
+    /// LLVM type of a boxed value, a `{ tag, payload }` pair (see `TypeTag`)
+    fn boxed_value_type(&self) -> inkwell::types::StructType<'ctx> {
+        let i64_type = self.context.i64_type();
+        self.context
+            .struct_type(&[i64_type.into(), i64_type.into()], false)
+    }
+
+    /// Tag and payload of `value` as a boxed value
+    fn build_boxed_value(
+        &self,
+        value: CompilerValue<'ctx>,
+    ) -> Result<(TypeTag, IntValue<'ctx>), CodegenError> {
+        let i64_type = self.context.i64_type();
+        let tag = TypeTag::of(&value)
+            .ok_or_else(|| "Unsupported value: it has no runtime representation".to_string())?;
+        let payload =
+            match (tag, value.llvm_value) {
+                (TypeTag::None, _) => i64_type.const_zero(),
+                (TypeTag::Bool, BasicValueEnum::IntValue(int_val)) => self
+                    .builder
+                    .build_int_z_extend(int_val, i64_type, "boxed_bool")?,
+                (_, BasicValueEnum::IntValue(int_val)) => self
+                    .builder
+                    .build_int_s_extend_or_bit_cast(int_val, i64_type, "boxed_int")?,
+                (_, BasicValueEnum::FloatValue(float_val)) => self
+                    .builder
+                    .build_bit_cast(float_val, i64_type, "boxed_float")?
+                    .into_int_value(),
+                (_, BasicValueEnum::PointerValue(ptr_val)) => {
+                    self.builder
+                        .build_ptr_to_int(ptr_val, i64_type, "boxed_str")?
+                }
+                _ => return Err("Unsupported value: it has no runtime representation".into()),
+            };
+        Ok((tag, payload))
+    }
+
+    /// Write `values` to `stream` like `print(*values)`, through `pycc_print`
+    fn build_print_values(
+        &mut self,
+        stream: PointerValue<'ctx>,
+        values: &[CompilerValue<'ctx>],
+    ) -> Result<(), CodegenError> {
+        let i64_type = self.context.i64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let boxed_type = self.boxed_value_type();
+
+        let array = if values.is_empty() {
+            ptr_type.const_null()
+        } else {
+            self.create_entry_block_alloca(
+                boxed_type.array_type(values.len() as u32).into(),
+                "print_values",
+            )?
+        };
+        for (i, value) in values.iter().enumerate() {
+            let (tag, payload) = self.build_boxed_value(*value)?;
+            let slot = unsafe {
+                self.builder.build_gep(
+                    boxed_type,
+                    array,
+                    &[i64_type.const_int(i as u64, false)],
+                    "print_value",
+                )?
+            };
+            let tag_ptr = self
+                .builder
+                .build_struct_gep(boxed_type, slot, 0, "print_tag")?;
+            self.builder
+                .build_store(tag_ptr, i64_type.const_int(tag as u64, false))?;
+            let payload_ptr =
+                self.builder
+                    .build_struct_gep(boxed_type, slot, 1, "print_payload")?;
+            self.builder.build_store(payload_ptr, payload)?;
+        }
+
+        let print_fn = self.get_or_build_print_function()?;
+        self.builder.build_call(
+            print_fn,
+            &[
+                array.into(),
+                i64_type.const_int(values.len() as u64, false).into(),
+                stream.into(),
+            ],
+            "",
+        )?;
+        Ok(())
+    }
+
+    /// Runtime function `void pycc_print(ptr values, i64 count, ptr stream)`
+    ///
+    /// Writes `count` boxed values separated by spaces and a newline, each
+    /// formatted by switching on its tag, so every print formats alike.
+    fn get_or_build_print_function(&mut self) -> Result<FunctionValue<'ctx>, CodegenError> {
+        if let Some(function) = self.module.get_function("pycc_print") {
+            return Ok(function);
+        }
+
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let f64_type = self.context.f64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let boxed_type = self.boxed_value_type();
+
+        let fputs_fn = self.get_or_declare_function(
+            "fputs",
+            i32_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+        );
+        let fprintf_fn = self.get_or_declare_function(
+            "fprintf",
+            i32_type.fn_type(&[ptr_type.into(), ptr_type.into()], true),
+        );
+        let repr_float_fn = self.get_or_build_repr_float_function()?;
+
+        let function = self.module.add_function(
+            "pycc_print",
+            self.context
+                .void_type()
+                .fn_type(&[ptr_type.into(), i64_type.into(), ptr_type.into()], false),
+            Some(inkwell::module::Linkage::Private),
+        );
+
+        let current_position = self.save_builder_position();
+
+        let entry_block = self.context.append_basic_block(function, "entry");
+        let loop_block = self.context.append_basic_block(function, "loop");
+        let check_block = self.context.append_basic_block(function, "check_first");
+        let separator_block = self.context.append_basic_block(function, "separator");
+        let value_block = self.context.append_basic_block(function, "value");
+        let next_block = self.context.append_basic_block(function, "next");
+        let done_block = self.context.append_basic_block(function, "done");
+
+        self.builder.position_at_end(entry_block);
+        let values = nth_param(function, 0)?.into_pointer_value();
+        let count = nth_param(function, 1)?.into_int_value();
+        let stream = nth_param(function, 2)?.into_pointer_value();
+        let index_ptr = self.builder.build_alloca(i64_type, "index")?;
+        self.builder.build_store(index_ptr, i64_type.const_zero())?;
+        let separator = self.constant_string(" ", "print_separator")?;
+        let newline = self.constant_string("\n", "print_newline")?;
+        let int_format = self.constant_string("%ld", "print_int_fmt")?;
+        let none_str = self.constant_string("None", "none_str")?;
+        self.builder.build_unconditional_branch(loop_block)?;
+
+        // while index < count
+        self.builder.position_at_end(loop_block);
+        let index = self
+            .builder
+            .build_load(i64_type, index_ptr, "index")?
+            .into_int_value();
+        let finished =
+            self.builder
+                .build_int_compare(inkwell::IntPredicate::UGE, index, count, "finished")?;
+        self.builder
+            .build_conditional_branch(finished, done_block, check_block)?;
+
+        // Arguments after the first are preceded by a space
+        self.builder.position_at_end(check_block);
+        let is_first = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            index,
+            i64_type.const_zero(),
+            "is_first",
+        )?;
+        self.builder
+            .build_conditional_branch(is_first, value_block, separator_block)?;
+        self.builder.position_at_end(separator_block);
+        self.builder
+            .build_call(fputs_fn, &[separator.into(), stream.into()], "")?;
+        self.builder.build_unconditional_branch(value_block)?;
+
+        self.builder.position_at_end(value_block);
+        let slot = unsafe {
+            self.builder
+                .build_gep(boxed_type, values, &[index], "slot")?
+        };
+        let tag_ptr = self
+            .builder
+            .build_struct_gep(boxed_type, slot, 0, "tag_ptr")?;
+        let tag = self
+            .builder
+            .build_load(i64_type, tag_ptr, "tag")?
+            .into_int_value();
+        let payload_ptr = self
+            .builder
+            .build_struct_gep(boxed_type, slot, 1, "payload_ptr")?;
+        let payload = self
+            .builder
+            .build_load(i64_type, payload_ptr, "payload")?
+            .into_int_value();
+
+        let mut cases = Vec::new();
+        for type_tag in TypeTag::ALL {
+            let block = self
+                .context
+                .append_basic_block(function, &format!("print_{type_tag:?}").to_lowercase());
+            self.builder.position_at_end(block);
+            // Ints are formatted by fprintf, everything else is written as text
+            let text = match type_tag {
+                TypeTag::None => Some(none_str),
+                TypeTag::Bool => {
+                    let is_true = self.builder.build_int_compare(
+                        inkwell::IntPredicate::NE,
+                        payload,
+                        i64_type.const_zero(),
+                        "is_true",
+                    )?;
+                    Some(self.build_bool_string(is_true)?)
+                }
+                TypeTag::Int => {
+                    self.builder.build_call(
+                        fprintf_fn,
+                        &[stream.into(), int_format.into(), payload.into()],
+                        "",
+                    )?;
+                    None
+                }
+                TypeTag::Float => {
+                    let float_val = self.builder.build_bit_cast(payload, f64_type, "float")?;
+                    Some(
+                        self.builder
+                            .build_call(repr_float_fn, &[float_val.into()], "float_repr")?
+                            .try_as_basic_value()
+                            .unwrap_basic()
+                            .into_pointer_value(),
+                    )
+                }
+                TypeTag::Str => Some(self.builder.build_int_to_ptr(payload, ptr_type, "str")?),
+            };
+            if let Some(text) = text {
+                self.builder
+                    .build_call(fputs_fn, &[text.into(), stream.into()], "")?;
+            }
+            self.builder.build_unconditional_branch(next_block)?;
+            cases.push((i64_type.const_int(type_tag as u64, false), block));
+        }
+        self.builder.position_at_end(value_block);
+        self.builder.build_switch(tag, next_block, &cases)?;
+
+        self.builder.position_at_end(next_block);
+        let next_index =
+            self.builder
+                .build_int_add(index, i64_type.const_int(1, false), "next_index")?;
+        self.builder.build_store(index_ptr, next_index)?;
+        self.builder.build_unconditional_branch(loop_block)?;
+
+        self.builder.position_at_end(done_block);
+        self.builder
+            .build_call(fputs_fn, &[newline.into(), stream.into()], "")?;
+        self.builder.build_return(None)?;
+
+        self.restore_builder_position(current_position);
+        Ok(function)
+    }
+
+    /// The C `FILE *` behind `sys.stdout` or `sys.stderr`
+    fn compile_stream(&mut self, file: &Node) -> Result<PointerValue<'ctx>, CodegenError> {
+        let name = match file {
+            Node::Identifier(identifier) if self.native_modules.contains("sys") => {
+                match identifier.name.as_str() {
+                    "sys.stdout" => "stdout",
+                    "sys.stderr" => "stderr",
+                    _ => "",
+                }
+            }
+            _ => "",
+        };
+        if name.is_empty() {
+            return Err(
+                "Unsupported print file: only sys.stdout and sys.stderr can be written to".into(),
+            );
+        }
+
+        self.build_stream(name)
+    }
+
+    /// Load the C library's `stdout` or `stderr` stream
+    fn build_stream(&mut self, name: &str) -> Result<PointerValue<'ctx>, CodegenError> {
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let symbol = Platform::host().stream_symbol(name);
+        let global = match self.module.get_global(&symbol) {
+            Some(global) => global,
+            None => self.module.add_global(ptr_type, None, &symbol),
+        };
+        Ok(self
+            .builder
+            .build_load(ptr_type, global.as_pointer_value(), name)?
+            .into_pointer_value())
+    }
+
+    /// `fflush(stream)` when `condition` holds, for `print(..., flush=...)`
+    fn build_flush_if(
+        &mut self,
+        condition: IntValue<'ctx>,
+        stream: PointerValue<'ctx>,
+    ) -> Result<(), CodegenError> {
+        let i32_type = self.context.i32_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let fflush_fn =
+            self.get_or_declare_function("fflush", i32_type.fn_type(&[ptr_type.into()], false));
+        let function = self.current_function()?;
+        let flush_block = self.context.append_basic_block(function, "flush");
+        let done_block = self.context.append_basic_block(function, "flushed");
+        self.builder
+            .build_conditional_branch(condition, flush_block, done_block)?;
+
+        self.builder.position_at_end(flush_block);
+        self.builder.build_call(fflush_fn, &[stream.into()], "")?;
+        self.builder.build_unconditional_branch(done_block)?;
+        self.builder.position_at_end(done_block);
+        Ok(())
+    }
+
+    /// Store an assigned value into one target of an assignment
+    fn store_target(
+        &mut self,
+        target: &Node,
+        value: CompilerValue<'ctx>,
+    ) -> Result<(), CodegenError> {
+        match target {
+            Node::Identifier(identifier) if !identifier.name.contains('.') => {
+                self.assign_variable(&identifier.name, value)?;
+                Ok(())
+            }
+            Node::Subscript(subscript) => match subscript.value.as_ref() {
+                Node::Identifier(identifier)
+                    if identifier.name == "os.environ" && self.native_modules.contains("os") =>
+                {
+                    self.store_environ_item(&subscript.index, value)
+                }
+                _ => Err(
+                    "Unsupported assignment target: only os.environ items can be assigned by subscript"
+                        .into(),
+                ),
+            },
+            _ => Err(
+                "Unsupported assignment target: attributes cannot be assigned yet (use --fallback interp to run the program)"
+                    .into(),
+            ),
+        }
+    }
+
+    /// `os.environ[index] = value`, which sets the variable for this process and its children
+    fn store_environ_item(
+        &mut self,
+        index: &Node,
+        value: CompilerValue<'ctx>,
+    ) -> Result<(), CodegenError> {
+        let BasicValueEnum::PointerValue(value) = value.llvm_value else {
+            return Err(format!("TypeError: str expected, not {}", value.py_type).into());
+        };
+        let BasicValueEnum::PointerValue(name) = self.compile_expression(index)?.llvm_value else {
+            return Err("TypeError: os.environ keys must be str".into());
+        };
+        let i32_type = self.context.i32_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let setenv_fn = self.get_or_declare_function(
+            "setenv",
+            i32_type.fn_type(&[ptr_type.into(), ptr_type.into(), i32_type.into()], false),
+        );
+        self.builder.build_call(
+            setenv_fn,
+            &[
+                name.into(),
+                value.into(),
+                i32_type.const_int(1, false).into(),
+            ],
+            "setenv",
+        )?;
+        Ok(())
+    }
+
+    /// Get or define `ptr pycc_environ_item(ptr name)`, exiting with KeyError if unset
+    fn get_or_build_environ_item_function(&mut self) -> Result<FunctionValue<'ctx>, CodegenError> {
+        if let Some(func) = self.module.get_function("pycc_environ_item") {
+            return Ok(func);
+        }
+
+        let i32_type = self.context.i32_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let void_type = self.context.void_type();
+
+        let getenv_fn =
+            self.get_or_declare_function("getenv", ptr_type.fn_type(&[ptr_type.into()], false));
+        let fflush_fn =
+            self.get_or_declare_function("fflush", i32_type.fn_type(&[ptr_type.into()], false));
+        let dprintf_fn = self.get_or_declare_function(
+            "dprintf",
+            i32_type.fn_type(&[i32_type.into(), ptr_type.into()], true),
+        );
+        let exit_fn =
+            self.get_or_declare_function("exit", void_type.fn_type(&[i32_type.into()], false));
+
+        let function = self.module.add_function(
+            "pycc_environ_item",
+            ptr_type.fn_type(&[ptr_type.into()], false),
+            Some(inkwell::module::Linkage::Private),
+        );
+
+        // Save current position
+        let current_position = self.save_builder_position();
+
+        let entry_block = self.context.append_basic_block(function, "entry");
+        let missing_block = self.context.append_basic_block(function, "missing");
+        let found_block = self.context.append_basic_block(function, "found");
+
+        self.builder.position_at_end(entry_block);
+        let name = nth_param(function, 0)?.into_pointer_value();
+        let value = self
+            .builder
+            .build_call(getenv_fn, &[name.into()], "value")?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value();
+        let missing = self.builder.build_is_null(value, "missing")?;
+        self.builder
+            .build_conditional_branch(missing, missing_block, found_block)?;
+
+        self.builder.position_at_end(missing_block);
+        let format = self
+            .builder
+            .build_global_string_ptr("KeyError: '%s'\n", "key_error_format")?
+            .as_pointer_value();
+        // Output printed so far must precede the error
+        self.builder
+            .build_call(fflush_fn, &[ptr_type.const_null().into()], "")?;
+        self.builder.build_call(
+            dprintf_fn,
+            &[
+                i32_type.const_int(2, false).into(),
+                format.into(),
+                name.into(),
+            ],
+            "",
+        )?;
+        self.builder
+            .build_call(exit_fn, &[i32_type.const_int(1, false).into()], "")?;
+        self.builder.build_unreachable()?;
+
+        self.builder.position_at_end(found_block);
+        self.builder.build_return(Some(&value))?;
+
+        // Restore previous position
+        self.restore_builder_position(current_position);
+
+        Ok(function)
+    }
+
+    /// `input()` or `input(prompt)`
+    fn compile_input_call(
+        &mut self,
+        call: &crate::ast::Call,
+    ) -> Result<CompilerValue<'ctx>, CodegenError> {
+        let prompt = match call.arguments.as_slice() {
+            [] => self
+                .builder
+                .build_global_string_ptr("", "empty_prompt")?
+                .as_pointer_value(),
+            [prompt] => match self.compile_expression(prompt)?.llvm_value {
+                BasicValueEnum::PointerValue(prompt) => prompt,
+                _ => return Err("TypeError: input() prompt must be str".into()),
+            },
+            arguments => {
+                return Err(format!(
+                    "TypeError: input expected at most 1 argument, got {}",
+                    arguments.len()
+                )
+                .into());
+            }
+        };
+        let input_fn = self.get_or_build_input_function()?;
+        let line = self
+            .builder
+            .build_call(input_fn, &[prompt.into()], "line")?
+            .try_as_basic_value()
+            .unwrap_basic();
+        Ok(CompilerValue::new(line, Type::Str))
+    }
+
+    /// Get or define `ptr pycc_input(ptr prompt)`
+    ///
+    /// Prints the prompt, reads one line from stdin without its newline and
+    /// exits with EOFError at end of input.
+    fn get_or_build_input_function(&mut self) -> Result<FunctionValue<'ctx>, CodegenError> {
+        if let Some(func) = self.module.get_function("pycc_input") {
+            return Ok(func);
+        }
+
+        let i8_type = self.context.i8_type();
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let void_type = self.context.void_type();
+
+        let printf_fn =
+            self.get_or_declare_function("printf", i32_type.fn_type(&[ptr_type.into()], true));
+        let fflush_fn =
+            self.get_or_declare_function("fflush", i32_type.fn_type(&[ptr_type.into()], false));
+        let getline_fn = self.get_or_declare_function(
+            "getline",
+            i64_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into()], false),
+        );
+        let memcpy_fn = self.get_or_declare_function(
+            "memcpy",
+            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into(), i64_type.into()], false),
+        );
+        let free_fn =
+            self.get_or_declare_function("free", void_type.fn_type(&[ptr_type.into()], false));
+        let alloc_fn = self.get_or_build_alloc_function()?;
+        let stdin_symbol = Platform::host().stream_symbol("stdin");
+        let stdin_global = match self.module.get_global(&stdin_symbol) {
+            Some(global) => global,
+            None => self.module.add_global(ptr_type, None, &stdin_symbol),
+        };
+
+        let function = self.module.add_function(
+            "pycc_input",
+            ptr_type.fn_type(&[ptr_type.into()], false),
+            Some(inkwell::module::Linkage::Private),
+        );
+
+        // Save current position
+        let current_position = self.save_builder_position();
+
+        let entry_block = self.context.append_basic_block(function, "entry");
+        let eof_block = self.context.append_basic_block(function, "eof");
+        let read_block = self.context.append_basic_block(function, "read");
+        let strip_block = self.context.append_basic_block(function, "strip_newline");
+        let copy_block = self.context.append_basic_block(function, "copy");
+
+        self.builder.position_at_end(entry_block);
+        let prompt = nth_param(function, 0)?.into_pointer_value();
+        let format = self
+            .builder
+            .build_global_string_ptr("%s", "input_prompt_format")?
+            .as_pointer_value();
+        self.builder
+            .build_call(printf_fn, &[format.into(), prompt.into()], "")?;
+        // The prompt has no newline, so it must be flushed before blocking
+        self.builder
+            .build_call(fflush_fn, &[ptr_type.const_null().into()], "")?;
+        let line_ptr = self.builder.build_alloca(ptr_type, "line_ptr")?;
+        self.builder.build_store(line_ptr, ptr_type.const_null())?;
+        let capacity_ptr = self.builder.build_alloca(i64_type, "capacity")?;
+        self.builder
+            .build_store(capacity_ptr, i64_type.const_zero())?;
+        let stream =
+            self.builder
+                .build_load(ptr_type, stdin_global.as_pointer_value(), "stream")?;
+        let length = self
+            .builder
+            .build_call(
+                getline_fn,
+                &[line_ptr.into(), capacity_ptr.into(), stream.into()],
+                "length",
+            )?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_int_value();
+        let line = self
+            .builder
+            .build_load(ptr_type, line_ptr, "line")?
+            .into_pointer_value();
+        let at_eof = self.builder.build_int_compare(
+            inkwell::IntPredicate::SLT,
+            length,
+            i64_type.const_zero(),
+            "at_eof",
+        )?;
+        self.builder
+            .build_conditional_branch(at_eof, eof_block, read_block)?;
+
+        self.builder.position_at_end(eof_block);
+        self.builder.build_call(free_fn, &[line.into()], "")?;
+        self.build_fatal_error("EOFError: EOF when reading a line\n", "eof_error_message")?;
+
+        // Drop the trailing newline, if the last line has one
+        self.builder.position_at_end(read_block);
+        let last_index =
+            self.builder
+                .build_int_sub(length, i64_type.const_int(1, false), "last_index")?;
+        let last_ptr = unsafe {
+            self.builder
+                .build_gep(i8_type, line, &[last_index], "last_ptr")?
+        };
+        let last = self
+            .builder
+            .build_load(i8_type, last_ptr, "last")?
+            .into_int_value();
+        let ends_with_newline = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            last,
+            i8_type.const_int(b'\n' as u64, false),
+            "ends_with_newline",
+        )?;
+        self.builder
+            .build_conditional_branch(ends_with_newline, strip_block, copy_block)?;
+
+        self.builder.position_at_end(strip_block);
+        self.builder.build_unconditional_branch(copy_block)?;
+
+        // Copy into a runtime-owned string so getline's buffer can be freed
+        self.builder.position_at_end(copy_block);
+        let content_length = self.builder.build_phi(i64_type, "content_length")?;
+        content_length.add_incoming(&[(&length, read_block), (&last_index, strip_block)]);
+        let content_length = content_length.as_basic_value().into_int_value();
+        let size =
+            self.builder
+                .build_int_add(content_length, i64_type.const_int(1, false), "size")?;
+        let result = self
+            .builder
+            .build_call(alloc_fn, &[size.into()], "result")?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value();
+        self.builder.build_call(
+            memcpy_fn,
+            &[result.into(), line.into(), content_length.into()],
+            "",
+        )?;
+        let terminator_ptr = unsafe {
+            self.builder
+                .build_gep(i8_type, result, &[content_length], "terminator_ptr")?
+        };
+        self.builder
+            .build_store(terminator_ptr, i8_type.const_zero())?;
+        self.builder.build_call(free_fn, &[line.into()], "")?;
+        self.builder.build_return(Some(&result))?;
+
+        // Restore previous position
+        self.restore_builder_position(current_position);
+
+        Ok(function)
+    }
+
+    /// Get or define `ptr pycc_path_join(ptr path, ptr component)`
+    ///
+    /// Follows `os.path.join`: an absolute component replaces the path, and a
+    /// separator is added unless the path is empty or already ends with one.
+    fn get_or_build_path_join_function(&mut self) -> Result<FunctionValue<'ctx>, CodegenError> {
+        if let Some(func) = self.module.get_function("pycc_path_join") {
+            return Ok(func);
+        }
+
+        let i8_type = self.context.i8_type();
+        let i64_type = self.context.i64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+
+        let alloc_fn = self.get_or_build_alloc_function()?;
+
+        let function = self.module.add_function(
+            "pycc_path_join",
+            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+            Some(inkwell::module::Linkage::Private),
+        );
+
+        // Save current position
+        let current_position = self.save_builder_position();
+
+        let entry_block = self.context.append_basic_block(function, "entry");
+        let absolute_block = self.context.append_basic_block(function, "absolute");
+        let relative_block = self.context.append_basic_block(function, "relative");
+        let check_end_block = self.context.append_basic_block(function, "check_end");
+        let separator_block = self.context.append_basic_block(function, "separator");
+        let append_block = self.context.append_basic_block(function, "append");
+
+        self.builder.position_at_end(entry_block);
+        let path = nth_param(function, 0)?.into_pointer_value();
+        let component = nth_param(function, 1)?.into_pointer_value();
+        let separator = i8_type.const_int(b'/' as u64, false);
+        let first_char = self
+            .builder
+            .build_load(i8_type, component, "first_char")?
+            .into_int_value();
+        let absolute = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            first_char,
+            separator,
+            "absolute",
+        )?;
+        self.builder
+            .build_conditional_branch(absolute, absolute_block, relative_block)?;
+
+        self.builder.position_at_end(absolute_block);
+        self.builder.build_return(Some(&component))?;
+
+        self.builder.position_at_end(relative_block);
+        let path_len = self.build_string_length(path, "path_len")?;
+        let component_len = self.build_string_length(component, "component_len")?;
+        // Room for a separator and the terminator
+        let size = self
+            .builder
+            .build_int_add(path_len, component_len, "size")?;
+        let size = self
+            .builder
+            .build_int_add(size, i64_type.const_int(2, false), "size")?;
+        let joined = self
+            .builder
+            .build_call(alloc_fn, &[size.into()], "joined")?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value();
+        self.build_copy_at(joined, i64_type.const_zero(), path, path_len)?;
+        let empty = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            path_len,
+            i64_type.const_zero(),
+            "empty",
+        )?;
+        self.builder
+            .build_conditional_branch(empty, append_block, check_end_block)?;
+
+        self.builder.position_at_end(check_end_block);
+        let last_index =
+            self.builder
+                .build_int_sub(path_len, i64_type.const_int(1, false), "last_index")?;
+        let last_ptr = unsafe {
+            self.builder
+                .build_gep(i8_type, path, &[last_index], "last_ptr")?
+        };
+        let last_char = self
+            .builder
+            .build_load(i8_type, last_ptr, "last_char")?
+            .into_int_value();
+        let has_separator = self.builder.build_int_compare(
+            inkwell::IntPredicate::EQ,
+            last_char,
+            separator,
+            "has_separator",
+        )?;
+        self.builder
+            .build_conditional_branch(has_separator, append_block, separator_block)?;
+
+        self.builder.position_at_end(separator_block);
+        let separator_ptr = unsafe {
+            self.builder
+                .build_gep(i8_type, joined, &[path_len], "separator_ptr")?
+        };
+        self.builder.build_store(separator_ptr, separator)?;
+        let after_separator = self.builder.build_int_add(
+            path_len,
+            i64_type.const_int(1, false),
+            "after_separator",
+        )?;
+        self.builder.build_unconditional_branch(append_block)?;
+
+        // The component is copied with its terminator
+        self.builder.position_at_end(append_block);
+        let offset = self.builder.build_phi(i64_type, "offset")?;
+        offset.add_incoming(&[
+            (&path_len, relative_block),
+            (&path_len, check_end_block),
+            (&after_separator, separator_block),
+        ]);
+        let component_size = self.builder.build_int_add(
+            component_len,
+            i64_type.const_int(1, false),
+            "component_size",
+        )?;
+        self.build_copy_at(
+            joined,
+            offset.as_basic_value().into_int_value(),
+            component,
+            component_size,
+        )?;
+        self.builder.build_return(Some(&joined))?;
+
+        // Restore previous position
+        self.restore_builder_position(current_position);
+
+        Ok(function)
+    }
+
+    /// `import module` through CPython, raising its ImportError at run time
+    fn compile_python_import(&mut self, import: &crate::ast::Import) -> Result<(), CodegenError> {
+        if self.local_modules.contains(&import.module) {
+            return Ok(());
+        }
+        if NATIVE_MODULES.contains(&import.module.as_str()) {
+            // `import os.path` binds `os` like `import os`
+            let root = import.module.split('.').next().unwrap_or_default();
+            self.native_modules.insert(root.to_string());
+            return Ok(());
+        }
+        #[cfg(feature = "python")]
+        if self.options.python_interop {
+            return self.build_python_import(&import.module);
+        }
+        Err(format!(
+            "Unsupported statement: import {} (compile with --python-interop)",
+            import.module
+        )
+        .into())
+    }
+
+    /// Import `module` with `PyImport_ImportModule`, dropping the reference
+    #[cfg(feature = "python")]
+    fn build_python_import(&mut self, module: &str) -> Result<(), CodegenError> {
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let import_fn = self.get_or_declare_function(
+            "PyImport_ImportModule",
+            ptr_type.fn_type(&[ptr_type.into()], false),
+        );
+        let module_name = self.python_name_ptr(module)?;
+        let module = self
+            .builder
+            .build_call(import_fn, &[module_name.into()], "module")?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value();
+        self.build_python_release(module)?;
+        Ok(())
+    }
+
+    /// Call `callee` (a builtin, or `module.attr`) through CPython
+    ///
+    /// Arguments are converted by their static types. The result is converted to
+    /// `python_result_type`, which comes from the enclosing annotation or return
+    /// type and defaults to `str()` of the result.
+    #[cfg(feature = "python")]
+    fn compile_python_call(
+        &mut self,
+        call: &crate::ast::Call,
+    ) -> Result<CompilerValue<'ctx>, CodegenError> {
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+
+        let (module, attribute) = call
+            .callee
+            .rsplit_once('.')
+            .unwrap_or(("builtins", call.callee.as_str()));
+        let result_type = std::mem::replace(&mut self.python_result_type, Type::Str);
+
+        let tuple_new_fn = self
+            .get_or_declare_function("PyTuple_New", ptr_type.fn_type(&[i64_type.into()], false));
+        let tuple_set_fn = self.get_or_declare_function(
+            "PyTuple_SetItem",
+            i32_type.fn_type(&[ptr_type.into(), i64_type.into(), ptr_type.into()], false),
+        );
+        let arguments = self
+            .builder
+            .build_call(
+                tuple_new_fn,
+                &[i64_type
+                    .const_int(call.arguments.len() as u64, false)
+                    .into()],
+                "arguments",
+            )?
+            .try_as_basic_value()
+            .unwrap_basic();
+        for (i, argument) in call.arguments.iter().enumerate() {
+            let value = self.compile_expression(argument)?;
+            let object = self.build_to_python(value)?;
+            self.builder.build_call(
+                tuple_set_fn,
+                &[
+                    arguments.into(),
+                    i64_type.const_int(i as u64, false).into(),
+                    object.into(),
+                ],
+                "",
+            )?;
+        }
+        self.python_result_type = result_type;
+
+        let call_fn = self.get_or_build_python_call_function()?;
+        let module_name = self.python_name_ptr(module)?;
+        let attribute_name = self.python_name_ptr(attribute)?;
+        let result = self
+            .builder
+            .build_call(
+                call_fn,
+                &[module_name.into(), attribute_name.into(), arguments.into()],
+                "py_result",
+            )?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value();
+
+        let value = self.build_from_python(result, result_type)?;
+        self.build_python_release(result)?;
+        Ok(value)
+    }
+
+    /// Global C string holding a module or attribute name
+    #[cfg(feature = "python")]
+    fn python_name_ptr(&mut self, name: &str) -> Result<PointerValue<'ctx>, CodegenError> {
+        self.constant_string(name, "py_name")
+    }
+
+    /// Global constant holding `text`, emitted once and shared by every use
+    fn constant_string(
+        &mut self,
+        text: &str,
+        prefix: &str,
+    ) -> Result<PointerValue<'ctx>, CodegenError> {
+        if let Some(global) = self.string_constants.get(text) {
+            return Ok(*global);
+        }
+        let name = format!("{prefix}_{}", self.string_counter);
+        self.string_counter += 1;
+        let global = self
+            .builder
+            .build_global_string_ptr(text, &name)?
+            .as_pointer_value();
+        self.string_constants.insert(text.to_string(), global);
+        Ok(global)
+    }
+
+    /// New CPython reference to a compiled value, converted by its static type
+    #[cfg(feature = "python")]
+    fn build_to_python(
+        &mut self,
+        value: CompilerValue<'ctx>,
+    ) -> Result<PointerValue<'ctx>, CodegenError> {
+        let i64_type = self.context.i64_type();
+        let f64_type = self.context.f64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+
+        let object = match value.llvm_value {
+            _ if value.py_type == Type::None => {
+                // Py_BuildValue("") is a new reference to None
+                let build_fn = self.get_or_declare_function(
+                    "Py_BuildValue",
+                    ptr_type.fn_type(&[ptr_type.into()], true),
+                );
+                let empty = self.python_name_ptr("")?;
+                self.builder
+                    .build_call(build_fn, &[empty.into()], "py_none")
+            }
+            BasicValueEnum::IntValue(int_val) if int_val.get_type().get_bit_width() == 1 => {
+                let bool_fn = self.get_or_declare_function(
+                    "PyBool_FromLong",
+                    ptr_type.fn_type(&[i64_type.into()], false),
+                );
+                let extended = self
+                    .builder
+                    .build_int_z_extend(int_val, i64_type, "bool_long")?;
+                self.builder
+                    .build_call(bool_fn, &[extended.into()], "py_bool")
+            }
+            BasicValueEnum::IntValue(int_val) => {
+                let long_fn = self.get_or_declare_function(
+                    "PyLong_FromLongLong",
+                    ptr_type.fn_type(&[i64_type.into()], false),
+                );
+                self.builder
+                    .build_call(long_fn, &[int_val.into()], "py_int")
+            }
+            BasicValueEnum::FloatValue(float_val) => {
+                let float_fn = self.get_or_declare_function(
+                    "PyFloat_FromDouble",
+                    ptr_type.fn_type(&[f64_type.into()], false),
+                );
+                self.builder
+                    .build_call(float_fn, &[float_val.into()], "py_float")
+            }
+            other => {
+                let str_fn = self.get_or_declare_function(
+                    "PyUnicode_FromString",
+                    ptr_type.fn_type(&[ptr_type.into()], false),
+                );
+                self.builder.build_call(str_fn, &[other.into()], "py_str")
+            }
+        };
+        let object = object?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value();
+        self.build_python_error_check()?;
+        Ok(object)
+    }
+
+    /// Convert a CPython object to a compiled value of `result_type`
+    ///
+    /// Anything but int, float, bool and None becomes the text of `str(object)`.
+    #[cfg(feature = "python")]
+    fn build_from_python(
+        &mut self,
+        object: PointerValue<'ctx>,
+        result_type: Type,
+    ) -> Result<CompilerValue<'ctx>, CodegenError> {
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let f64_type = self.context.f64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+
+        let value = match result_type {
+            Type::Int => {
+                let as_long_fn = self.get_or_declare_function(
+                    "PyLong_AsLongLong",
+                    i64_type.fn_type(&[ptr_type.into()], false),
+                );
+                self.builder
+                    .build_call(as_long_fn, &[object.into()], "int_result")?
+                    .try_as_basic_value()
+                    .unwrap_basic()
+            }
+            Type::Float => {
+                let as_double_fn = self.get_or_declare_function(
+                    "PyFloat_AsDouble",
+                    f64_type.fn_type(&[ptr_type.into()], false),
+                );
+                self.builder
+                    .build_call(as_double_fn, &[object.into()], "float_result")?
+                    .try_as_basic_value()
+                    .unwrap_basic()
+            }
+            Type::Bool => {
+                let is_true_fn = self.get_or_declare_function(
+                    "PyObject_IsTrue",
+                    i32_type.fn_type(&[ptr_type.into()], false),
+                );
+                let truth = self
+                    .builder
+                    .build_call(is_true_fn, &[object.into()], "truth")?
+                    .try_as_basic_value()
+                    .unwrap_basic()
+                    .into_int_value();
+                self.builder
+                    .build_int_compare(
+                        inkwell::IntPredicate::SGT,
+                        truth,
+                        i32_type.const_zero(),
+                        "bool_result",
+                    )?
+                    .into()
+            }
+            Type::None => return Ok(self.build_none()),
+            Type::Str | Type::Any => {
+                let str_fn = self.get_or_declare_function(
+                    "PyObject_Str",
+                    ptr_type.fn_type(&[ptr_type.into()], false),
+                );
+                let as_utf8_fn = self.get_or_declare_function(
+                    "PyUnicode_AsUTF8",
+                    ptr_type.fn_type(&[ptr_type.into()], false),
+                );
+                let text_object = self
+                    .builder
+                    .build_call(str_fn, &[object.into()], "text_object")?
+                    .try_as_basic_value()
+                    .unwrap_basic()
+                    .into_pointer_value();
+                self.build_python_error_check()?;
+                let text = self
+                    .builder
+                    .build_call(as_utf8_fn, &[text_object.into()], "text")?
+                    .try_as_basic_value()
+                    .unwrap_basic()
+                    .into_pointer_value();
+                self.build_python_error_check()?;
+
+                // The UTF-8 buffer belongs to the str object, so copy it out
+                let length = self.build_string_length(text, "length")?;
+                let size =
+                    self.builder
+                        .build_int_add(length, i64_type.const_int(1, false), "size")?;
+                let alloc_fn = self.get_or_build_alloc_function()?;
+                let copy = self
+                    .builder
+                    .build_call(alloc_fn, &[size.into()], "str_result")?
+                    .try_as_basic_value()
+                    .unwrap_basic()
+                    .into_pointer_value();
+                self.build_copy_at(copy, i64_type.const_zero(), text, size)?;
+                self.build_python_release(text_object)?;
+                copy.into()
+            }
+        };
+        self.build_python_error_check()?;
+        Ok(CompilerValue::new(value, result_type))
+    }
+
+    /// Drop a CPython reference
+    #[cfg(feature = "python")]
+    fn build_python_release(&mut self, object: PointerValue<'ctx>) -> Result<(), CodegenError> {
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let decref_fn = self.get_or_declare_function(
+            "Py_DecRef",
+            self.context.void_type().fn_type(&[ptr_type.into()], false),
+        );
+        self.builder.build_call(decref_fn, &[object.into()], "")?;
+        Ok(())
+    }
+
+    /// Report a pending CPython exception like an uncaught one
+    #[cfg(feature = "python")]
+    fn build_python_error_check(&mut self) -> Result<(), CodegenError> {
+        let check_fn = self.get_or_build_python_check_function()?;
+        self.builder.build_call(check_fn, &[], "")?;
+        Ok(())
+    }
+
+    /// Get or define `void pycc_python_check()`
+    ///
+    /// Prints the traceback of a pending exception and exits with status 1.
+    #[cfg(feature = "python")]
+    fn get_or_build_python_check_function(&mut self) -> Result<FunctionValue<'ctx>, CodegenError> {
+        if let Some(func) = self.module.get_function("pycc_python_check") {
+            return Ok(func);
+        }
+
+        let i32_type = self.context.i32_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let void_type = self.context.void_type();
+
+        let occurred_fn =
+            self.get_or_declare_function("PyErr_Occurred", ptr_type.fn_type(&[], false));
+        let print_fn = self.get_or_declare_function("PyErr_Print", void_type.fn_type(&[], false));
+        let fflush_fn =
+            self.get_or_declare_function("fflush", i32_type.fn_type(&[ptr_type.into()], false));
+        let exit_fn =
+            self.get_or_declare_function("exit", void_type.fn_type(&[i32_type.into()], false));
+
+        let function = self.module.add_function(
+            "pycc_python_check",
+            void_type.fn_type(&[], false),
+            Some(inkwell::module::Linkage::Private),
+        );
+
+        // Save current position
+        let current_position = self.save_builder_position();
+
+        let entry_block = self.context.append_basic_block(function, "entry");
+        let raise_block = self.context.append_basic_block(function, "raise");
+        let done_block = self.context.append_basic_block(function, "done");
+
+        self.builder.position_at_end(entry_block);
+        let exception = self
+            .builder
+            .build_call(occurred_fn, &[], "exception")?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value();
+        let raised = self.builder.build_is_not_null(exception, "raised")?;
+        self.builder
+            .build_conditional_branch(raised, raise_block, done_block)?;
+
+        self.builder.position_at_end(raise_block);
+        // Output printed so far must precede the traceback
+        self.builder
+            .build_call(fflush_fn, &[ptr_type.const_null().into()], "")?;
+        self.builder.build_call(print_fn, &[], "")?;
+        self.builder
+            .build_call(exit_fn, &[i32_type.const_int(1, false).into()], "")?;
+        self.builder.build_unreachable()?;
+
+        self.builder.position_at_end(done_block);
+        self.builder.build_return(None)?;
+
+        // Restore previous position
+        self.restore_builder_position(current_position);
+
+        Ok(function)
+    }
+
+    /// Get or define `ptr pycc_python_call(ptr module, ptr name, ptr arguments)`
+    ///
+    /// Looks `name` up in `module`, calls it with the `arguments` tuple (whose
+    /// reference it consumes) and returns a new reference to the result. C and
+    /// Python stdout are flushed around the call so output stays in order.
+    #[cfg(feature = "python")]
+    fn get_or_build_python_call_function(&mut self) -> Result<FunctionValue<'ctx>, CodegenError> {
+        if let Some(func) = self.module.get_function("pycc_python_call") {
+            return Ok(func);
+        }
+
+        let i32_type = self.context.i32_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+
+        let fflush_fn =
+            self.get_or_declare_function("fflush", i32_type.fn_type(&[ptr_type.into()], false));
+        let import_fn = self.get_or_declare_function(
+            "PyImport_ImportModule",
+            ptr_type.fn_type(&[ptr_type.into()], false),
+        );
+        let get_attr_fn = self.get_or_declare_function(
+            "PyObject_GetAttrString",
+            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+        );
+        let call_object_fn = self.get_or_declare_function(
+            "PyObject_CallObject",
+            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+        );
+        let sys_get_fn = self.get_or_declare_function(
+            "PySys_GetObject",
+            ptr_type.fn_type(&[ptr_type.into()], false),
+        );
+        let call_method_fn = self.get_or_declare_function(
+            "PyObject_CallMethod",
+            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into()], true),
+        );
+        let clear_fn = self
+            .get_or_declare_function("PyErr_Clear", self.context.void_type().fn_type(&[], false));
+        let check_fn = self.get_or_build_python_check_function()?;
+
+        let function = self.module.add_function(
+            "pycc_python_call",
+            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into()], false),
+            Some(inkwell::module::Linkage::Private),
+        );
+
+        // Save current position
+        let current_position = self.save_builder_position();
+
+        let entry_block = self.context.append_basic_block(function, "entry");
+        let flush_block = self.context.append_basic_block(function, "flush_stdout");
+        let done_block = self.context.append_basic_block(function, "done");
+
+        self.builder.position_at_end(entry_block);
+        let module_name = nth_param(function, 0)?;
+        let name = nth_param(function, 1)?;
+        let arguments = nth_param(function, 2)?.into_pointer_value();
+        self.builder
+            .build_call(fflush_fn, &[ptr_type.const_null().into()], "")?;
+
+        let call_pointer = |builder: &Builder<'ctx>,
+                            callee: FunctionValue<'ctx>,
+                            args: &[inkwell::values::BasicMetadataValueEnum<'ctx>],
+                            name: &str| {
+            builder
+                .build_call(callee, args, name)?
+                .try_as_basic_value()
+                .unwrap_basic()
+                .into_pointer_value()
+        };
+        let module = call_pointer(&self.builder, import_fn, &[module_name.into()], "module");
+        self.builder.build_call(check_fn, &[], "")?;
+        let callable = call_pointer(
+            &self.builder,
+            get_attr_fn,
+            &[module.into(), name.into()],
+            "callable",
+        );
+        self.build_python_release(module)?;
+        self.builder.build_call(check_fn, &[], "")?;
+        let result = call_pointer(
+            &self.builder,
+            call_object_fn,
+            &[callable.into(), arguments.into()],
+            "result",
+        );
+        self.build_python_release(callable)?;
+        self.build_python_release(arguments)?;
+        self.builder.build_call(check_fn, &[], "")?;
+
+        let stdout_name = self.python_name_ptr("stdout")?;
+        let flush_name = self.python_name_ptr("flush")?;
+        let stdout = call_pointer(&self.builder, sys_get_fn, &[stdout_name.into()], "stdout");
+        let has_stdout = self.builder.build_is_not_null(stdout, "has_stdout")?;
+        self.builder
+            .build_conditional_branch(has_stdout, flush_block, done_block)?;
+
+        self.builder.position_at_end(flush_block);
+        let flushed = call_pointer(
+            &self.builder,
+            call_method_fn,
+            &[
+                stdout.into(),
+                flush_name.into(),
+                ptr_type.const_null().into(),
+            ],
+            "flushed",
+        );
+        // A failed flush of sys.stdout is not the callee's exception
+        self.builder.build_call(clear_fn, &[], "")?;
+        let flushed_ok = self.builder.build_is_not_null(flushed, "flushed_ok")?;
+        let release_block = self.context.append_basic_block(function, "release_flush");
+        self.builder
+            .build_conditional_branch(flushed_ok, release_block, done_block)?;
+
+        self.builder.position_at_end(release_block);
+        self.build_python_release(flushed)?;
+        self.builder.build_unconditional_branch(done_block)?;
+
+        self.builder.position_at_end(done_block);
+        self.builder.build_return(Some(&result))?;
+
+        // Restore previous position
+        self.restore_builder_position(current_position);
+
+        Ok(function)
+    }
+
+    /// Buffer the standard streams like CPython at program start
+    ///
+    /// stdout stays line-buffered on a terminal and fully buffered otherwise, or
+    /// becomes unbuffered when `PYTHONUNBUFFERED` is set. stderr is
+    /// line-buffered. Registered last, the exit flush runs before the coverage
+    /// and profile reports are written.
+    fn build_stdio_install(&mut self) -> Result<(), CodegenError> {
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let void_type = self.context.void_type();
+        let setvbuf_fn = self.get_or_declare_function(
+            "setvbuf",
+            i32_type.fn_type(
+                &[
+                    ptr_type.into(),
+                    ptr_type.into(),
+                    i32_type.into(),
+                    i64_type.into(),
+                ],
+                false,
+            ),
+        );
+        let getenv_fn =
+            self.get_or_declare_function("getenv", ptr_type.fn_type(&[ptr_type.into()], false));
+        let fflush_fn =
+            self.get_or_declare_function("fflush", i32_type.fn_type(&[ptr_type.into()], false));
+        let atexit_fn =
+            self.get_or_declare_function("atexit", i32_type.fn_type(&[ptr_type.into()], false));
+
+        let stderr = self.build_stream("stderr")?;
+        self.builder.build_call(
+            setvbuf_fn,
+            &[
+                stderr.into(),
+                ptr_type.const_null().into(),
+                i32_type.const_int(IO_LINE_BUFFERED, false).into(),
+                i64_type.const_zero().into(),
+            ],
+            "",
+        )?;
+
+        // Like CPython, any non-empty value of PYTHONUNBUFFERED counts
+        let function = self.current_function()?;
+        let check_block = self
+            .context
+            .append_basic_block(function, "check_unbuffered");
+        let unbuffered_block = self
+            .context
+            .append_basic_block(function, "stdout_unbuffered");
+        let done_block = self.context.append_basic_block(function, "stdio_ready");
+        let variable = self
+            .builder
+            .build_global_string_ptr("PYTHONUNBUFFERED", "unbuffered_variable")?;
+        let value = self
+            .builder
+            .build_call(
+                getenv_fn,
+                &[variable.as_pointer_value().into()],
+                "unbuffered",
+            )?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_pointer_value();
+        let is_set = self.builder.build_is_not_null(value, "is_set")?;
+        self.builder
+            .build_conditional_branch(is_set, check_block, done_block)?;
+
+        self.builder.position_at_end(check_block);
+        let first_byte = self
+            .builder
+            .build_load(self.context.i8_type(), value, "first_byte")?
+            .into_int_value();
+        let non_empty = self.builder.build_int_compare(
+            inkwell::IntPredicate::NE,
+            first_byte,
+            self.context.i8_type().const_zero(),
+            "non_empty",
+        )?;
+        self.builder
+            .build_conditional_branch(non_empty, unbuffered_block, done_block)?;
+
+        self.builder.position_at_end(unbuffered_block);
+        let stdout = self.build_stream("stdout")?;
+        self.builder.build_call(
+            setvbuf_fn,
+            &[
+                stdout.into(),
+                ptr_type.const_null().into(),
+                i32_type.const_int(IO_UNBUFFERED, false).into(),
+                i64_type.const_zero().into(),
+            ],
+            "",
+        )?;
+        self.builder.build_unconditional_branch(done_block)?;
+        self.builder.position_at_end(done_block);
+
+        let flush_fn = self.module.add_function(
+            "pycc_flush_output",
+            void_type.fn_type(&[], false),
+            Some(inkwell::module::Linkage::Private),
+        );
+        let current_position = self.save_builder_position();
+        let entry_block = self.context.append_basic_block(flush_fn, "entry");
+        self.builder.position_at_end(entry_block);
+        self.builder
+            .build_call(fflush_fn, &[ptr_type.const_null().into()], "")?;
+        self.builder.build_return(None)?;
+        self.restore_builder_position(current_position);
+
+        self.builder.build_call(
+            atexit_fn,
+            &[flush_fn.as_global_value().as_pointer_value().into()],
+            "",
+        )?;
+        Ok(())
+    }
+
+    /// Install `pycc_sigint_handler` for SIGINT at program start
+    ///
+    /// The handler only sets a flag that Python function entries check, so
+    /// programs without functions keep the default disposition: they have
+    /// nowhere to notice the flag, and SIGINT ends them with status 130 anyway.
+    fn build_sigint_handler_install(&mut self) -> Result<(), CodegenError> {
+        let i32_type = self.context.i32_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+        let handler = self.get_or_build_sigint_handler()?;
+        let signal_fn = self.get_or_declare_function(
+            "signal",
+            ptr_type.fn_type(&[i32_type.into(), ptr_type.into()], false),
+        );
+        self.builder.build_call(
+            signal_fn,
+            &[
+                i32_type.const_int(SIGINT as u64, false).into(),
+                handler.as_global_value().as_pointer_value().into(),
+            ],
+            "",
+        )?;
+        Ok(())
+    }
+
+    /// Get or define `void pycc_sigint_handler(i32)`
+    ///
+    /// A handler may only make async-signal-safe calls, and stdio is not safe,
+    /// so the first SIGINT just sets `pycc_interrupted` for the next function
+    /// entry to raise KeyboardInterrupt (see `build_interrupt_check`). A second
+    /// one, e.g. while blocked reading input, writes the traceback and leaves
+    /// with `_exit(130)`, dropping output still buffered.
+    fn get_or_build_sigint_handler(&mut self) -> Result<FunctionValue<'ctx>, CodegenError> {
+        if let Some(func) = self.module.get_function("pycc_sigint_handler") {
+            return Ok(func);
+        }
+
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
+
+        let write_fn = self.get_or_declare_function(
+            "write",
+            i64_type.fn_type(&[i32_type.into(), ptr_type.into(), i64_type.into()], false),
+        );
+        let exit_fn = self.get_or_declare_function(
+            "_exit",
+            self.context.void_type().fn_type(&[i32_type.into()], false),
+        );
+
+        let function = self.module.add_function(
+            "pycc_sigint_handler",
+            self.context.void_type().fn_type(&[i32_type.into()], false),
+            Some(inkwell::module::Linkage::Private),
+        );
+
+        // Save current position
+        let current_position = self.save_builder_position();
+
+        let entry_block = self.context.append_basic_block(function, "entry");
+        let record_block = self.context.append_basic_block(function, "record");
+        let exit_block = self.context.append_basic_block(function, "exit");
+        self.builder.position_at_end(entry_block);
+        let pending = self.build_interrupted_load("pending")?;
+        let repeated = self.builder.build_int_compare(
+            inkwell::IntPredicate::NE,
+            pending,
+            i32_type.const_zero(),
+            "repeated",
+        )?;
+        self.builder
+            .build_conditional_branch(repeated, exit_block, record_block)?;
+
+        self.builder.position_at_end(record_block);
+        let store = self
+            .builder
+            .build_store(self.interrupted_global(), i32_type.const_int(1, false))?;
+        store.set_volatile(true)?;
+        self.builder.build_return(None)?;
+
+        self.builder.position_at_end(exit_block);
+        let message_ptr = match self.module.get_global("keyboard_interrupt_message") {
+            Some(global) => global.as_pointer_value(),
+            None => self
+                .builder
+                .build_global_string_ptr(KEYBOARD_INTERRUPT, "keyboard_interrupt_message")?
+                .as_pointer_value(),
+        };
+        self.builder.build_call(
+            write_fn,
+            &[
+                i32_type.const_int(2, false).into(),
+                message_ptr.into(),
+                i64_type
+                    .const_int(KEYBOARD_INTERRUPT.len() as u64, false)
+                    .into(),
+            ],
+            "",
+        )?;
+        self.builder
+            .build_call(exit_fn, &[i32_type.const_int(130, false).into()], "")?;
+        self.builder.build_unreachable()?;
+
+        // Restore previous position
+        self.restore_builder_position(current_position);
+
+        Ok(function)
+    }
+
+    /// Raise KeyboardInterrupt if SIGINT arrived since the last check
+    ///
+    /// Outside the handler stdio is safe to use, so pending output is flushed
+    /// before the traceback, and the exit status is 130 like a SIGINT-killed
+    /// process.
+    fn build_interrupt_check(
+        &mut self,
+        function_value: FunctionValue<'ctx>,
+    ) -> Result<(), CodegenError> {
+        if self.module.get_function("pycc_sigint_handler").is_none() {
+            return Ok(());
+        }
+        let i32_type = self.context.i32_type();
+        let pending = self.build_interrupted_load("interrupted")?;
+        let interrupted = self.builder.build_int_compare(
+            inkwell::IntPredicate::NE,
+            pending,
+            i32_type.const_zero(),
+            "is_interrupted",
+        )?;
+        let interrupt_block = self
+            .context
+            .append_basic_block(function_value, "keyboard_interrupt");
+        let continue_block = self
+            .context
+            .append_basic_block(function_value, "not_interrupted");
+        self.builder
+            .build_conditional_branch(interrupted, interrupt_block, continue_block)?;
 
-                // Allocate space for the variable on the stack
-                let ptr = if is_division {
-                    // For division, always allocate as float
-                    let float_type = self.context.f64_type();
-                    self.builder
-                        .build_alloca(float_type, &assignment.name)
-                        .unwrap()
-                } else {
-                    self.builder
-                        .build_alloca(value.get_type(), &assignment.name)
-                        .unwrap()
-                };
+        self.builder.position_at_end(interrupt_block);
+        self.build_fatal_error_with_status(KEYBOARD_INTERRUPT, "keyboard_interrupt_message", 130)?;
+        self.builder.position_at_end(continue_block);
+        Ok(())
+    }
 
-                // Convert value to the allocation type if needed
-                let stored_value = if is_division {
-                    // For division, ensure the result is stored as float
-                    match value {
-                        BasicValueEnum::FloatValue(_) => value,
-                        BasicValueEnum::IntValue(int_val) => {
-                            let float_type = self.context.f64_type();
-                            self.builder
-                                .build_signed_int_to_float(int_val, float_type, "int_to_float")
-                                .unwrap()
-                                .into()
-                        }
-                        _ => value,
-                    }
-                } else {
-                    value
-                };
+    /// Set by the SIGINT handler; a `volatile sig_atomic_t` in C terms
+    fn interrupted_global(&self) -> PointerValue<'ctx> {
+        let i32_type = self.context.i32_type();
+        self.runtime_global("pycc_interrupted", i32_type.as_basic_type_enum())
+    }
 
-                self.builder.build_store(ptr, stored_value).unwrap();
-                self.variables
-                    .insert(assignment.name.clone(), (ptr, stored_value));
-                Ok(())
-            }
-            Node::ExpressionStatement(expr_stmt) => {
-                self.compile_expression(&expr_stmt.expression)?;
-                Ok(())
-            }
-            Node::Function(function) => {
-                self.compile_function(function)?;
-                Ok(())
-            }
-            Node::Return(return_stmt) => {
-                // Handle return statement
-                if let Some(value) = &return_stmt.value {
-                    let return_value = self.compile_expression(value)?;
-                    self.builder.build_return(Some(&return_value)).unwrap();
-                    Ok(())
-                } else {
-                    // Return void
-                    self.builder.build_return(None).unwrap();
-                    Ok(())
-                }
-            }
-            _ => Ok(()), // Ignore unsupported statements for now
+    /// Volatile load of `pycc_interrupted`, so it is reread after a signal
+    fn build_interrupted_load(&self, name: &str) -> Result<IntValue<'ctx>, CodegenError> {
+        let i32_type = self.context.i32_type();
+        let value = self
+            .builder
+            .build_load(i32_type, self.interrupted_global(), name)?
+            .into_int_value();
+        if let Some(load) = value.as_instruction() {
+            load.set_volatile(true)?;
         }
+        Ok(value)
     }
 
-    fn compile_function(&mut self, function: &crate::ast::Function) -> Result<(), String> {
-        // Save current position
-        let current_position = self.builder.get_insert_block();
+    /// Count the call towards the recursion limit, raising RecursionError past it
+    ///
+    /// Native frames are small, so CPython's default limit keeps well clear of
+    /// the real stack size while failing where CPython would.
+    fn build_recursion_check(
+        &mut self,
+        function_value: FunctionValue<'ctx>,
+    ) -> Result<(), CodegenError> {
+        let i32_type = self.context.i32_type();
+        let depth_global = self.recursion_depth_global();
+        let depth = self
+            .builder
+            .build_load(i32_type, depth_global, "depth")?
+            .into_int_value();
+        let depth = self
+            .builder
+            .build_int_add(depth, i32_type.const_int(1, false), "depth")?;
+        self.builder.build_store(depth_global, depth)?;
+
+        let exceeded = self.builder.build_int_compare(
+            inkwell::IntPredicate::UGT,
+            depth,
+            i32_type.const_int(RECURSION_LIMIT, false),
+            "recursion_exceeded",
+        )?;
+        let error_block = self
+            .context
+            .append_basic_block(function_value, "recursion_error");
+        let body_block = self.context.append_basic_block(function_value, "body");
+        self.builder
+            .build_conditional_branch(exceeded, error_block, body_block)?;
+
+        self.builder.position_at_end(error_block);
+        self.build_fatal_error(
+            "RecursionError: maximum recursion depth exceeded\n",
+            "recursion_message",
+        )?;
+        self.builder.position_at_end(body_block);
+        Ok(())
+    }
 
-        // For now, we'll use i64 as the return type for all functions
-        // The f-string issue needs a different approach
-        let return_type = self.context.i64_type();
-        let param_types: Vec<_> = function
-            .parameters
-            .iter()
-            .map(|_| return_type.into())
-            .collect();
-        let fn_type = return_type.fn_type(&param_types, false);
+    /// Leave the current Python function, releasing its recursion depth slot
+    fn build_function_return(&mut self, value: BasicValueEnum<'ctx>) -> Result<(), CodegenError> {
+        if !self.module_level {
+            let i32_type = self.context.i32_type();
+            let depth_global = self.recursion_depth_global();
+            let depth = self
+                .builder
+                .build_load(i32_type, depth_global, "depth")?
+                .into_int_value();
+            let depth = self
+                .builder
+                .build_int_sub(depth, i32_type.const_int(1, false), "depth")?;
+            self.builder.build_store(depth_global, depth)?;
+            self.build_profile_exit()?;
+        }
+        self.builder.build_return(Some(&value))?;
+        Ok(())
+    }
 
-        // Create function
-        let function_value = self.module.add_function(&function.name, fn_type, None);
+    /// Number of Python function calls currently active
+    fn recursion_depth_global(&self) -> PointerValue<'ctx> {
+        let i32_type = self.context.i32_type();
+        self.runtime_global("pycc_recursion_depth", i32_type.as_basic_type_enum())
+    }
 
-        // Create basic block
-        let basic_block = self.context.append_basic_block(function_value, "entry");
-        self.builder.position_at_end(basic_block);
+    /// Give a Python function its own debug scope, returning the enclosing one
+    fn enter_debug_scope(
+        &mut self,
+        function_value: FunctionValue<'ctx>,
+        function: &crate::ast::Function,
+    ) -> Option<inkwell::debug_info::DIScope<'ctx>> {
+        let debug_info = self.debug_info.as_mut()?;
+        let optimized = self.options.optimization > 0;
+        let scope = debug_info.create_function_scope(
+            function_value,
+            &function.name,
+            function.line,
+            optimized,
+        );
+        let outer_scope = std::mem::replace(&mut debug_info.scope, scope);
+        self.builder
+            .set_current_debug_location(debug_info.location(self.context, function.line));
+        Some(outer_scope)
+    }
 
-        // Create allocations for parameters
-        for (i, param_name) in function.parameters.iter().enumerate() {
-            let param = function_value.get_nth_param(i as u32).unwrap();
-            let ptr = self.builder.build_alloca(return_type, param_name).unwrap();
-            self.builder.build_store(ptr, param).unwrap();
-            self.variables.insert(param_name.clone(), (ptr, param));
-        }
+    /// Block the builder is positioned in
+    fn current_block(&self) -> Result<inkwell::basic_block::BasicBlock<'ctx>, CodegenError> {
+        self.builder
+            .get_insert_block()
+            .ok_or_else(|| CodegenError::new("internal compiler error: builder is not positioned"))
+    }
 
-        // Compile function body
-        self.compile_statement(&function.body)?;
+    /// Function the builder is positioned in
+    fn current_function(&self) -> Result<FunctionValue<'ctx>, CodegenError> {
+        self.current_block()?
+            .get_parent()
+            .ok_or_else(|| CodegenError::new("internal compiler error: block is not in a function"))
+    }
 
-        // Add return instruction if not already present
-        if !basic_block
-            .get_last_instruction()
-            .is_some_and(|inst| inst.is_terminator())
-        {
-            self.builder
-                .build_return(Some(&return_type.const_int(0, false)))
-                .unwrap();
+    /// Insertion point and debug location, saved while another function is emitted
+    ///
+    /// The debug location is cleared so helpers without debug info stay valid.
+    fn save_builder_position(
+        &self,
+    ) -> (
+        Option<inkwell::basic_block::BasicBlock<'ctx>>,
+        Option<inkwell::debug_info::DILocation<'ctx>>,
+    ) {
+        // Only query the location when one was set, which requires debug info
+        let location = self
+            .debug_info
+            .as_ref()
+            .and_then(|_| self.builder.get_current_debug_location());
+        if location.is_some() {
+            self.builder.unset_current_debug_location();
         }
+        (self.builder.get_insert_block(), location)
+    }
 
-        // Restore previous position
-        if let Some(block) = current_position {
+    fn restore_builder_position(
+        &self,
+        (block, location): (
+            Option<inkwell::basic_block::BasicBlock<'ctx>>,
+            Option<inkwell::debug_info::DILocation<'ctx>>,
+        ),
+    ) {
+        if let Some(block) = block {
             self.builder.position_at_end(block);
         }
+        if let Some(location) = location {
+            self.builder.set_current_debug_location(location);
+        }
+    }
 
-        Ok(())
+    /// Apply a binary operator to two compiled operands
+    fn build_binary(
+        &mut self,
+        operator: &BinaryOperator,
+        left: BasicValueEnum<'ctx>,
+        right: BasicValueEnum<'ctx>,
+        escapes: bool,
+    ) -> Result<BasicValueEnum<'ctx>, CodegenError> {
+        // Bools take part in arithmetic as the ints 0 and 1
+        let left = self.coerce_bool_to_int(left)?;
+        let right = self.coerce_bool_to_int(right)?;
+
+        // Mixed int/float arithmetic is done in floating point
+        let (left, right) = match (left, right) {
+            (BasicValueEnum::IntValue(_), BasicValueEnum::FloatValue(r)) => {
+                (self.coerce_to(left, r.get_type().into())?, right)
+            }
+            (BasicValueEnum::FloatValue(l), BasicValueEnum::IntValue(_)) => {
+                (left, self.coerce_to(right, l.get_type().into())?)
+            }
+            _ => (left, right),
+        };
+
+        match operator {
+            BinaryOperator::Add => match (left, right) {
+                (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => {
+                    let result = self.build_int_arithmetic(IntArithmetic::Add, l, r, "addtmp")?;
+                    Ok(result.into())
+                }
+                (BasicValueEnum::FloatValue(l), BasicValueEnum::FloatValue(r)) => {
+                    let result = self.builder.build_float_add(l, r, "faddtmp")?;
+                    Ok(result.into())
+                }
+                (BasicValueEnum::PointerValue(l), BasicValueEnum::PointerValue(r)) => {
+                    // String concatenation
+                    self.concatenate_strings(l, r, escapes)
+                }
+                _ => Err("Unsupported operation".into()),
+            },
+            BinaryOperator::Subtract => match (left, right) {
+                (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => {
+                    let result =
+                        self.build_int_arithmetic(IntArithmetic::Subtract, l, r, "subtmp")?;
+                    Ok(result.into())
+                }
+                (BasicValueEnum::FloatValue(l), BasicValueEnum::FloatValue(r)) => {
+                    let result = self.builder.build_float_sub(l, r, "fsubtmp")?;
+                    Ok(result.into())
+                }
+                _ => Err("Unsupported operation".into()),
+            },
+            BinaryOperator::Multiply => match (left, right) {
+                (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => {
+                    let result =
+                        self.build_int_arithmetic(IntArithmetic::Multiply, l, r, "multmp")?;
+                    Ok(result.into())
+                }
+                (BasicValueEnum::FloatValue(l), BasicValueEnum::FloatValue(r)) => {
+                    let result = self.builder.build_float_mul(l, r, "fmultmp")?;
+                    Ok(result.into())
+                }
+                (BasicValueEnum::PointerValue(l), BasicValueEnum::IntValue(r)) => {
+                    // String multiplication: string * int
+                    self.multiply_string(l, r, escapes)
+                }
+                _ => Err("Unsupported operation".into()),
+            },
+            BinaryOperator::Divide => match (left, right) {
+                (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => {
+                    if r.get_zero_extended_constant() == Some(0) {
+                        Err("Division by zero".into())
+                    } else {
+                        self.build_zero_division_check(
+                            r,
+                            "ZeroDivisionError: division by zero\n",
+                            "div_zero_message",
+                        )?;
+                        // Convert integers to float for true division
+                        let float_type = self.context.f64_type();
+                        let l_float = self
+                            .builder
+                            .build_signed_int_to_float(l, float_type, "l_float")?;
+                        let r_float = self
+                            .builder
+                            .build_signed_int_to_float(r, float_type, "r_float")?;
+                        let result = self.builder.build_float_div(l_float, r_float, "fdivtmp")?;
+                        Ok(result.into())
+                    }
+                }
+                (BasicValueEnum::FloatValue(l), BasicValueEnum::FloatValue(r)) => {
+                    if r.is_null() {
+                        Err("Division by zero".into())
+                    } else {
+                        let result = self.builder.build_float_div(l, r, "fdivtmp")?;
+                        Ok(result.into())
+                    }
+                }
+                _ => Err("Unsupported operation".into()),
+            },
+            BinaryOperator::FloorDivide => match (left, right) {
+                (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => {
+                    if r.get_zero_extended_constant() == Some(0) {
+                        Err("Division by zero".into())
+                    } else {
+                        Ok(self.build_int_floor_div(l, r)?.into())
+                    }
+                }
+                (BasicValueEnum::FloatValue(l), BasicValueEnum::FloatValue(r)) => {
+                    if r.is_null() {
+                        Err("Division by zero".into())
+                    } else {
+                        Ok(self.build_float_floor_div(l, r)?.into())
+                    }
+                }
+                _ => Err("Unsupported operation".into()),
+            },
+            BinaryOperator::Modulo => match (left, right) {
+                (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => {
+                    if r.get_zero_extended_constant() == Some(0) {
+                        Err("Division by zero".into())
+                    } else {
+                        Ok(self.build_int_mod(l, r)?.into())
+                    }
+                }
+                (BasicValueEnum::FloatValue(l), BasicValueEnum::FloatValue(r)) => {
+                    if r.is_null() {
+                        Err("Division by zero".into())
+                    } else {
+                        Ok(self.build_float_mod(l, r)?.into())
+                    }
+                }
+                _ => Err("Unsupported operation".into()),
+            },
+            BinaryOperator::Power => match (left, right) {
+                (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => {
+                    self.build_int_power(l, r)
+                }
+                (BasicValueEnum::FloatValue(l), BasicValueEnum::FloatValue(r)) => {
+                    Ok(self.build_float_power(l, r)?.into())
+                }
+                _ => Err("Unsupported operation".into()),
+            },
+            _ => Err("Unsupported binary operator".into()),
+        }
     }
 
-    fn compile_expression(&mut self, expression: &Node) -> Result<BasicValueEnum<'ctx>, String> {
+    fn compile_expression(
+        &mut self,
+        expression: &Node,
+    ) -> Result<CompilerValue<'ctx>, CodegenError> {
+        // Only the outermost string result escapes, operands are copied into it
+        let escapes = std::mem::take(&mut self.string_escapes);
         match expression {
             Node::Literal(literal) => {
-                match &literal.value {
+                let value = match &literal.value {
                     LiteralValue::Integer(value) => {
                         let int_type = self.context.i64_type();
                         Ok(int_type.const_int(*value as u64, false).into())
@@ -189,202 +5844,196 @@ impl<'ctx> CodeGenerator<'ctx> {
                     }
                     LiteralValue::String(value) => {
                         // Create a global string constant with a unique name
-                        let name = format!("str_{}", self.string_counter);
-                        self.string_counter += 1;
-                        let str_ptr = self.builder.build_global_string_ptr(value, &name).unwrap();
+                        let str_ptr = self.constant_string(value, "str")?;
                         // Return the pointer to the string
-                        Ok(str_ptr.as_pointer_value().into())
+                        Ok(str_ptr.into())
                     }
                     LiteralValue::FString(fstring) => {
-                        // Handle f-string by parsing and evaluating expressions
-                        let evaluated_string = self.evaluate_fstring_codegen(fstring)?;
-                        Ok(evaluated_string)
+                        if let Some(text) = self
+                            .with_fold_scope(|scope| constants::fold_fstring(fstring, scope))
+                        {
+                            Ok(self.constant_string(&text, "str")?.into())
+                        } else {
+                            // Handle f-string by parsing and evaluating expressions
+                            let evaluated_string =
+                                self.evaluate_fstring_codegen(fstring, escapes)?;
+                            Ok(evaluated_string)
+                        }
                     }
                     LiteralValue::Boolean(value) => {
-                        // For boolean literals, we'll use i64 but with a special marker
-                        // We'll use -2 for True and -3 for False to distinguish from regular integers
-                        let int_type = self.context.i64_type();
-                        let bool_val = if *value { -2i64 } else { -3i64 };
-                        Ok(int_type.const_int(bool_val as u64, true).into())
-                    }
-                    LiteralValue::None => {
-                        // Represent None as 0
-                        let int_type = self.context.i64_type();
-                        Ok(int_type.const_int(0, false).into())
+                        // Booleans are i1 and widen to i64 when used as integers
+                        let bool_type = self.context.bool_type();
+                        Ok(bool_type.const_int(u64::from(*value), false).into())
                     }
-                }
+                    LiteralValue::None => return Ok(self.build_none()),
+                };
+                value.map(CompilerValue::from_llvm)
             }
             Node::Identifier(identifier) => {
-                if let Some((ptr, stored_value)) = self.variables.get(&identifier.name) {
+                if let Some(variable) = self.variables.get(&identifier.name) {
                     let value = self
                         .builder
-                        .build_load(stored_value.get_type(), *ptr, "loadtmp")
-                        .unwrap();
-                    Ok(value)
+                        .build_load(variable.llvm_type, variable.slot, "loadtmp")?;
+                    Ok(CompilerValue::new(value, variable.py_type))
+                } else if !self.module_level
+                    && !self.local_names.contains(&identifier.name)
+                    && let Some(value) = self.module_constants.get(&identifier.name).cloned()
+                {
+                    // Functions cannot reach module slots, but constants fold into them
+                    self.compile_expression(&Node::Literal(Literal { value }))
+                } else if let Some(value) = self.module_dunders.get(&identifier.name).cloned() {
+                    self.compile_expression(&Node::Literal(Literal { value }))
                 } else {
-                    Err(format!("Undefined variable: {}", identifier.name))
+                    Err(format!("Undefined variable: {}", identifier.name).into())
                 }
             }
             Node::Unary(unary) => {
                 let operand = self.compile_expression(&unary.operand)?;
-                match unary.operator {
+                let py_type = match (&unary.operator, operand.py_type) {
+                    (crate::ast::UnaryOperator::Not, _) => Type::Bool,
+                    (_, Type::Bool) => Type::Int,
+                    (_, py_type) => py_type,
+                };
+                let operand = operand.llvm_value;
+                // Unary arithmetic on a bool works on its integer value
+                let operand = match unary.operator {
+                    crate::ast::UnaryOperator::Not => operand,
+                    _ => self.coerce_bool_to_int(operand)?,
+                };
+                let result = match unary.operator {
                     crate::ast::UnaryOperator::Plus => Ok(operand),
                     crate::ast::UnaryOperator::Minus => match operand {
                         BasicValueEnum::IntValue(int_val) => {
                             let zero = int_val.get_type().const_int(0, false);
-                            let result =
-                                self.builder.build_int_sub(zero, int_val, "negtmp").unwrap();
+                            let result = self.build_int_arithmetic(
+                                IntArithmetic::Subtract,
+                                zero,
+                                int_val,
+                                "negtmp",
+                            )?;
                             Ok(result.into())
                         }
                         BasicValueEnum::FloatValue(float_val) => {
-                            let zero = float_val.get_type().const_float(0.0);
-                            let result = self
-                                .builder
-                                .build_float_sub(zero, float_val, "fnegtmp")
-                                .unwrap();
+                            let result =
+                                self.builder.build_float_neg(float_val, "fnegtmp")?;
                             Ok(result.into())
                         }
-                        _ => Err("Unsupported unary minus operation".to_string()),
+                        _ => Err("Unsupported unary minus operation".into()),
                     },
                     crate::ast::UnaryOperator::Not => {
-                        Err("Unsupported unary not operation".to_string())
+                        let truth = self.build_truthiness(operand)?;
+                        Ok(self.builder.build_not(truth, "nottmp")?.into())
                     }
-                }
+                };
+                result.map(|value| CompilerValue::new(value, py_type))
             }
             Node::Binary(binary) => {
+                // Comparisons are not lowered yet, but constant ones such as
+                // `__name__ == "__main__"` fold
+                if matches!(
+                    binary.operator,
+                    BinaryOperator::Equal
+                        | BinaryOperator::NotEqual
+                        | BinaryOperator::Less
+                        | BinaryOperator::Greater
+                        | BinaryOperator::LessEqual
+                        | BinaryOperator::GreaterEqual
+                ) && let Some(value) =
+                    self.with_fold_scope(|scope| constants::fold(expression, scope))
+                {
+                    return self.compile_expression(&Node::Literal(Literal { value }));
+                }
                 let left = self.compile_expression(&binary.left)?;
                 let right = self.compile_expression(&binary.right)?;
 
-                match binary.operator {
-                    BinaryOperator::Add => match (left, right) {
-                        (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => {
-                            let result = self.builder.build_int_add(l, r, "addtmp").unwrap();
-                            Ok(result.into())
-                        }
-                        (BasicValueEnum::FloatValue(l), BasicValueEnum::FloatValue(r)) => {
-                            let result = self.builder.build_float_add(l, r, "faddtmp").unwrap();
-                            Ok(result.into())
-                        }
-                        (BasicValueEnum::PointerValue(l), BasicValueEnum::PointerValue(r)) => {
-                            // String concatenation
-                            self.concatenate_strings(l, r)
-                        }
-                        _ => Err("Unsupported operation".to_string()),
-                    },
-                    BinaryOperator::Subtract => match (left, right) {
-                        (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => {
-                            let result = self.builder.build_int_sub(l, r, "subtmp").unwrap();
-                            Ok(result.into())
-                        }
-                        (BasicValueEnum::FloatValue(l), BasicValueEnum::FloatValue(r)) => {
-                            let result = self.builder.build_float_sub(l, r, "fsubtmp").unwrap();
-                            Ok(result.into())
-                        }
-                        _ => Err("Unsupported operation".to_string()),
-                    },
-                    BinaryOperator::Multiply => match (left, right) {
-                        (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => {
-                            let result = self.builder.build_int_mul(l, r, "multmp").unwrap();
-                            Ok(result.into())
-                        }
-                        (BasicValueEnum::FloatValue(l), BasicValueEnum::FloatValue(r)) => {
-                            let result = self.builder.build_float_mul(l, r, "fmultmp").unwrap();
-                            Ok(result.into())
-                        }
-                        (BasicValueEnum::PointerValue(l), BasicValueEnum::IntValue(r)) => {
-                            // String multiplication: string * int
-                            self.multiply_string(l, r)
-                        }
-                        _ => Err("Unsupported operation".to_string()),
-                    },
-                    BinaryOperator::Divide => match (left, right) {
-                        (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => {
-                            if r.get_zero_extended_constant() == Some(0) {
-                                Err("Division by zero".to_string())
-                            } else {
-                                // Convert integers to float for true division
-                                let float_type = self.context.f64_type();
-                                let l_float = self
-                                    .builder
-                                    .build_signed_int_to_float(l, float_type, "l_float")
-                                    .unwrap();
-                                let r_float = self
-                                    .builder
-                                    .build_signed_int_to_float(r, float_type, "r_float")
-                                    .unwrap();
-                                let result = self
-                                    .builder
-                                    .build_float_div(l_float, r_float, "fdivtmp")
-                                    .unwrap();
-                                Ok(result.into())
-                            }
-                        }
-                        (BasicValueEnum::FloatValue(l), BasicValueEnum::FloatValue(r)) => {
-                            if r.is_null() {
-                                Err("Division by zero".to_string())
-                            } else {
-                                let result = self.builder.build_float_div(l, r, "fdivtmp").unwrap();
-                                Ok(result.into())
-                            }
-                        }
-                        _ => Err("Unsupported operation".to_string()),
-                    },
-                    BinaryOperator::FloorDivide => match (left, right) {
-                        (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => {
-                            if r.get_zero_extended_constant() == Some(0) {
-                                Err("Division by zero".to_string())
-                            } else {
-                                Ok(BasicValueEnum::IntValue(l))
-                            }
-                        }
-                        (BasicValueEnum::FloatValue(l), BasicValueEnum::FloatValue(r)) => {
-                            if r.is_null() {
-                                Err("Division by zero".to_string())
-                            } else {
-                                Ok(BasicValueEnum::FloatValue(l))
-                            }
-                        }
-                        _ => Err("Unsupported operation".to_string()),
-                    },
-                    BinaryOperator::Modulo => match (left, right) {
-                        (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(r)) => {
-                            if r.get_zero_extended_constant() == Some(0) {
-                                Err("Division by zero".to_string())
-                            } else {
-                                let result =
-                                    self.builder.build_int_signed_rem(l, r, "modtmp").unwrap();
-                                Ok(result.into())
-                            }
-                        }
-                        (BasicValueEnum::FloatValue(l), BasicValueEnum::FloatValue(r)) => {
-                            if r.is_null() {
-                                Err("Division by zero".to_string())
-                            } else {
-                                let result = self.builder.build_float_rem(l, r, "fmodtmp").unwrap();
-                                Ok(result.into())
-                            }
-                        }
-                        _ => Err("Unsupported operation".to_string()),
-                    },
-                    BinaryOperator::Power => match (left, right) {
-                        (BasicValueEnum::IntValue(l), BasicValueEnum::IntValue(_r)) => {
-                            Ok(BasicValueEnum::IntValue(l))
-                        }
-                        (BasicValueEnum::FloatValue(l), BasicValueEnum::FloatValue(_r)) => {
-                            Ok(BasicValueEnum::FloatValue(l))
-                        }
-                        _ => Err("Unsupported operation".to_string()),
-                    },
-                    _ => Err("Unsupported binary operator".to_string()),
-                }
+                // Integer arithmetic on an untyped operand can only be typed at run time
+                let dynamic = left.py_type == Type::Any || right.py_type == Type::Any;
+                let result = self.build_binary(
+                    &binary.operator,
+                    left.llvm_value,
+                    right.llvm_value,
+                    escapes,
+                )?;
+                Ok(if dynamic && result.is_int_value() {
+                    CompilerValue::new(result, Type::Any)
+                } else {
+                    CompilerValue::from_llvm(result)
+                })
             }
             Node::Call(call) => {
-                // Look up the function in the module
-                if let Some(function_value) = self.module.get_function(&call.callee) {
+                if call
+                    .arguments
+                    .iter()
+                    .any(|argument| matches!(argument, Node::Starred(_)))
+                    || call.keywords.iter().any(|(name, _)| name.is_none())
+                {
+                    return self.compile_expression(&Node::Call(expand_unpacking(call)?));
+                }
+                if call.callee != "print"
+                    && let Some((Some(keyword), _)) = call.keywords.first()
+                {
+                    return Err(format!(
+                        "Unsupported keyword argument '{keyword}' in call to {}: keyword arguments cannot be compiled yet (use --fallback interp to run the program)",
+                        call.callee
+                    ).into());
+                }
+                // `module.function` of a module compiled into the program is a direct call
+                if let Some((module, function)) = call.callee.split_once('.')
+                    && self.local_modules.contains(module)
+                {
+                    if self.module_names.get(function).map(String::as_str) != Some(module) {
+                        return Err(format!(
+                            "AttributeError: module '{module}' has no attribute '{function}'"
+                        )
+                        .into());
+                    }
+                    let mut local_call = call.clone();
+                    local_call.callee = function.to_string();
+                    return self.compile_expression(&Node::Call(local_call));
+                }
+                if call.callee.starts_with("os.")
+                    && self.native_modules.contains("os")
+                    && let Some(value) = self.compile_os_call(call)?
+                {
+                    return Ok(value);
+                }
+                if call.callee == "sys.exit" && self.native_modules.contains("sys") {
+                    return self.compile_sys_exit(call);
+                }
+                if let Some(value) =
+                    self.with_fold_scope(|scope| constants::fold(expression, scope))
+                {
+                    return self.compile_expression(&Node::Literal(Literal { value }));
+                }
+                // Only functions defined in the program are callable, not runtime helpers
+                if let Some(binding) = self.functions.get(&call.callee).cloned() {
+                    // Module code runs top to bottom, so the `def` must already have executed
+                    if self.module_level && !self.defined_functions.contains(&call.callee) {
+                        return Err(
+                            format!("NameError: name '{}' is not defined", call.callee).into()
+                        );
+                    }
+                    if let Some(message) =
+                        arity_error(&call.callee, &binding.parameters, call.arguments.len())
+                    {
+                        return Err(format!("TypeError: {message}").into());
+                    }
+                    let function_value = binding.value;
+                    let return_type = binding.return_type;
+
                     // Compile arguments
+                    let param_types = function_value.get_type().get_param_types();
                     let mut args = Vec::new();
-                    for arg in &call.arguments {
-                        let value = self.compile_expression(arg)?;
+                    for (i, arg) in call.arguments.iter().enumerate() {
+                        // The callee may hand an argument back as its result
+                        self.string_escapes = escapes;
+                        let mut value = self.compile_expression(arg)?.llvm_value;
+                        if let Some(ty) = param_types
+                            .get(i)
+                            .and_then(|ty| BasicTypeEnum::try_from(*ty).ok())
+                        {
+                            value = self.coerce_to(value, ty)?;
+                        }
                         args.push(value.into());
                     }
 
@@ -392,296 +6041,75 @@ impl<'ctx> CodeGenerator<'ctx> {
                     let call_result = self
                         .builder
                         .build_call(function_value, &args, "calltmp")
-                        .unwrap();
-                    // For now, we'll assume the function returns a value
-                    // In a real implementation, we'd need to handle void returns
-                    Ok(call_result.try_as_basic_value().unwrap_basic())
-                } else if call.callee == "print" {
-                    // Special handling for print function
-                    // Get or declare printf function
-                    let printf_fn = if let Some(func) = self.module.get_function("printf") {
-                        func
-                    } else {
-                        let i32_type = self.context.i32_type();
-                        let str_type = self.context.ptr_type(inkwell::AddressSpace::default());
-                        let printf_fn_type = i32_type.fn_type(&[str_type.into()], true);
-                        self.module.add_function("printf", printf_fn_type, None)
-                    };
-
-                    if let Some(arg) = call.arguments.first() {
-                        let value = self.compile_expression(arg)?;
-
-                        // Handle different types of values
-                        match value {
-                            BasicValueEnum::IntValue(int_val) => {
-                                // Check if this is a boolean value (we use -2 for True, -3 for False)
-                                let name = format!("fmt_{}", self.string_counter);
-                                self.string_counter += 1;
-
-                                let true_val = int_val.get_type().const_int((-2i64) as u64, true);
-                                let false_val = int_val.get_type().const_int((-3i64) as u64, true);
-
-                                let is_true = self
-                                    .builder
-                                    .build_int_compare(
-                                        inkwell::IntPredicate::EQ,
-                                        int_val,
-                                        true_val,
-                                        "is_true",
-                                    )
-                                    .unwrap();
-                                let is_false = self
-                                    .builder
-                                    .build_int_compare(
-                                        inkwell::IntPredicate::EQ,
-                                        int_val,
-                                        false_val,
-                                        "is_false",
-                                    )
-                                    .unwrap();
-                                let is_boolean = self
-                                    .builder
-                                    .build_or(is_true, is_false, "is_boolean")
-                                    .unwrap();
-
-                                // Create basic blocks for conditional branching
-                                let function = self
-                                    .builder
-                                    .get_insert_block()
-                                    .unwrap()
-                                    .get_parent()
-                                    .unwrap();
-                                let boolean_block =
-                                    self.context.append_basic_block(function, "boolean_check");
-                                let numeric_block =
-                                    self.context.append_basic_block(function, "print_numeric");
-                                let true_print_block =
-                                    self.context.append_basic_block(function, "print_true");
-                                let false_print_block =
-                                    self.context.append_basic_block(function, "print_false");
-                                let merge_block =
-                                    self.context.append_basic_block(function, "merge");
-
-                                // Branch based on whether it's a boolean
-                                self.builder
-                                    .build_conditional_branch(
-                                        is_boolean,
-                                        boolean_block,
-                                        numeric_block,
-                                    )
-                                    .unwrap();
-
-                                // Block for boolean values - check if true or false
-                                self.builder.position_at_end(boolean_block);
-                                let is_true_val = self
-                                    .builder
-                                    .build_int_compare(
-                                        inkwell::IntPredicate::EQ,
-                                        int_val,
-                                        true_val,
-                                        "is_true_val",
-                                    )
-                                    .unwrap();
-                                self.builder
-                                    .build_conditional_branch(
-                                        is_true_val,
-                                        true_print_block,
-                                        false_print_block,
-                                    )
-                                    .unwrap();
-
-                                // Block for printing "True"
-                                self.builder.position_at_end(true_print_block);
-                                let true_format = self
-                                    .builder
-                                    .build_global_string_ptr("True\n", &format!("{}_true", name))
-                                    .unwrap();
-                                let _ = self
-                                    .builder
-                                    .build_call(
-                                        printf_fn,
-                                        &[true_format.as_pointer_value().into()],
-                                        "printf_true",
-                                    )
-                                    .unwrap();
-                                self.builder
-                                    .build_unconditional_branch(merge_block)
-                                    .unwrap();
-
-                                // Block for printing "False"
-                                self.builder.position_at_end(false_print_block);
-                                let false_format = self
-                                    .builder
-                                    .build_global_string_ptr("False\n", &format!("{}_false", name))
-                                    .unwrap();
-                                let _ = self
-                                    .builder
-                                    .build_call(
-                                        printf_fn,
-                                        &[false_format.as_pointer_value().into()],
-                                        "printf_false",
-                                    )
-                                    .unwrap();
-                                self.builder
-                                    .build_unconditional_branch(merge_block)
-                                    .unwrap();
-
-                                // Block for printing numeric values
-                                self.builder.position_at_end(numeric_block);
-                                // Print integers as integers, not as floats
-                                let format_str = self
-                                    .builder
-                                    .build_global_string_ptr("%ld\n", &name)
-                                    .unwrap();
-                                let _ = self
-                                    .builder
-                                    .build_call(
-                                        printf_fn,
-                                        &[format_str.as_pointer_value().into(), int_val.into()],
-                                        "printf",
-                                    )
-                                    .unwrap();
-                                self.builder
-                                    .build_unconditional_branch(merge_block)
-                                    .unwrap();
-
-                                // Merge block
-                                self.builder.position_at_end(merge_block);
-                            }
-                            BasicValueEnum::FloatValue(float_val) => {
-                                // Create format string for float with proper formatting
-                                let name = format!("fmt_{}", self.string_counter);
-                                self.string_counter += 1;
-
-                                // Check if it's zero and print as 0.0 instead of 0
-                                let zero_val = float_val.get_type().const_float(0.0);
-                                let is_zero = self
-                                    .builder
-                                    .build_float_compare(
-                                        inkwell::FloatPredicate::OEQ,
-                                        float_val,
-                                        zero_val,
-                                        "is_zero_float",
-                                    )
-                                    .unwrap();
-
-                                let function = self
-                                    .builder
-                                    .get_insert_block()
-                                    .unwrap()
-                                    .get_parent()
-                                    .unwrap();
-                                let zero_block = self
-                                    .context
-                                    .append_basic_block(function, "print_zero_float");
-                                let regular_block = self
-                                    .context
-                                    .append_basic_block(function, "print_regular_float");
-                                let merge_block =
-                                    self.context.append_basic_block(function, "merge_float");
-
-                                self.builder
-                                    .build_conditional_branch(is_zero, zero_block, regular_block)
-                                    .unwrap();
-
-                                // Block for printing 0.0
-                                self.builder.position_at_end(zero_block);
-                                let zero_format = self
-                                    .builder
-                                    .build_global_string_ptr("0.0\n", &format!("{}_zero", name))
-                                    .unwrap();
-                                let _ = self
-                                    .builder
-                                    .build_call(
-                                        printf_fn,
-                                        &[zero_format.as_pointer_value().into()],
-                                        "printf_zero",
-                                    )
-                                    .unwrap();
-                                self.builder
-                                    .build_unconditional_branch(merge_block)
-                                    .unwrap();
-
-                                // Block for printing regular float
-                                self.builder.position_at_end(regular_block);
-                                let format_str =
-                                    self.builder.build_global_string_ptr("%g\n", &name).unwrap();
-                                let _ = self
-                                    .builder
-                                    .build_call(
-                                        printf_fn,
-                                        &[format_str.as_pointer_value().into(), float_val.into()],
-                                        "printf",
-                                    )
-                                    .unwrap();
-                                self.builder
-                                    .build_unconditional_branch(merge_block)
-                                    .unwrap();
-
-                                // Merge block
-                                self.builder.position_at_end(merge_block);
-                            }
-                            BasicValueEnum::PointerValue(ptr_val) => {
-                                // For string literals in print, we need to handle them specially
-                                // Let's check if this is a string literal and handle it correctly
-                                let name = format!("fmt_{}", self.string_counter);
-                                self.string_counter += 1;
-                                let format_str =
-                                    self.builder.build_global_string_ptr("%s\n", &name).unwrap();
-                                let _ = self
-                                    .builder
-                                    .build_call(
-                                        printf_fn,
-                                        &[format_str.as_pointer_value().into(), ptr_val.into()],
-                                        "printf",
-                                    )
-                                    .unwrap();
-                            }
-                            _ => {
-                                // For other types, just print a placeholder
-                                let name = format!("fmt_{}", self.string_counter);
-                                self.string_counter += 1;
-                                let format_str = self
-                                    .builder
-                                    .build_global_string_ptr("Value\n", &name)
-                                    .unwrap();
-                                let _ = self
-                                    .builder
-                                    .build_call(
-                                        printf_fn,
-                                        &[format_str.as_pointer_value().into()],
-                                        "printf",
-                                    )
-                                    .unwrap();
-                            }
-                        }
-                    } else {
-                        // Print just a newline
-                        let name = format!("fmt_{}", self.string_counter);
-                        self.string_counter += 1;
-                        let format_str = self.builder.build_global_string_ptr("\n", &name).unwrap();
-                        let _ = self
-                            .builder
-                            .build_call(
-                                printf_fn,
-                                &[format_str.as_pointer_value().into()],
-                                "printf",
-                            )
-                            .unwrap();
+                        .map_err(|e| format!("Failed to call {}: {e}", call.callee))?;
+                    Ok(match call_result.try_as_basic_value().basic() {
+                        Some(value) => CompilerValue::new(value, return_type),
+                        None => self.build_none(),
+                    })
+                } else if let Some(builtin) = builtins::lookup(&call.callee)
+                    && let Some(lowering) = builtin.lowering
+                {
+                    if let Some(message) = builtin.arity_error(call.arguments.len()) {
+                        return Err(format!("TypeError: {message}").into());
                     }
-                    // Print function returns None (represented as 0)
-                    let int_type = self.context.i64_type();
-                    Ok(int_type.const_int(0, false).into())
+                    self.compile_builtin_call(lowering, call)
                 } else {
-                    Err(format!("Undefined function: {}", call.callee))
+                    #[cfg(feature = "python")]
+                    if self.options.python_interop {
+                        return self.compile_python_call(call);
+                    }
+                    Err(format!("Undefined function: {}", call.callee).into())
+                }
+            }
+            Node::Subscript(subscript) => match subscript.value.as_ref() {
+                Node::Identifier(identifier)
+                    if identifier.name == "os.environ" && self.native_modules.contains("os") =>
+                {
+                    self.compile_environ_item(&subscript.index)
                 }
+                _ => Err("Unsupported subscript".into()),
+            },
+            Node::Await(_) => Err(
+                "Unsupported 'await': coroutines cannot be compiled yet (use --fallback interp to run the program)"
+                    .into(),
+            ),
+            Node::Tuple(_) | Node::List(_) | Node::Dict(_) | Node::Set(_) => Err(
+                "Unsupported container literal: tuples, lists, dicts and sets cannot be compiled yet (use --fallback interp to run the program)"
+                    .into(),
+            ),
+            _ => Err("Unsupported expression type".into()),
+        }
+    }
+
+    /// The LLVM module the program is generated into
+    pub fn module(&self) -> &Module<'ctx> {
+        &self.module
+    }
+
+    /// Run the LLVM verifier over the module, `stage` naming when in the build
+    ///
+    /// Malformed IR is a bug in pycc, and the object writer would turn it into
+    /// a broken executable or crash, so it is reported as an internal error.
+    /// With `keep_invalid_ir` set, the rejected module is written there first.
+    pub fn verify(&self, stage: &str) -> Result<(), CodegenError> {
+        let Err(report) = self.module.verify() else {
+            return Ok(());
+        };
+        let mut message = format!(
+            "internal compiler error: invalid LLVM IR {stage}: {}",
+            report.to_string().trim_end()
+        );
+        if let Some(path) = &self.options.keep_invalid_ir {
+            match self.module.print_to_file(path) {
+                Ok(()) => message.push_str(&format!("\n(IR written to {path})")),
+                Err(e) => message.push_str(&format!("\n(Failed to write IR to {path}: {e})")),
             }
-            _ => Err("Unsupported expression type".to_string()),
         }
+        Err(message.into())
     }
 
     pub fn print_ir(&self) {
-        self.module.print_to_stderr();
+        self.module().print_to_stderr();
     }
 
     pub fn write_ir_to_file(&self, filename: &str) -> Result<(), String> {
@@ -696,32 +6124,86 @@ impl<'ctx> CodeGenerator<'ctx> {
         Ok(())
     }
 
-    pub fn write_object_to_file(&self, filename: &str) -> Result<(), String> {
-        use inkwell::targets::FileType;
-        use inkwell::targets::{InitializationConfig, Target, TargetMachine};
-        use std::fs::File;
-        use std::io::Write;
+    /// Target machine for the host triple, configured from the codegen options
+    fn create_target_machine(&self) -> Result<inkwell::targets::TargetMachine, String> {
+        use inkwell::targets::{Target, TargetMachine};
 
-        // Initialize LLVM targets
-        let config = InitializationConfig::default();
-        Target::initialize_all(&config);
+        initialize_targets();
 
         // Get the target triple for the current machine
         let target_triple = TargetMachine::get_default_triple();
         let target = Target::from_triple(&target_triple)
             .map_err(|e| format!("Failed to get target: {}", e.to_string()))?;
 
-        // Create target machine
-        let target_machine = target
+        // "native" is resolved to the host CPU and its feature set
+        let (cpu, features) = if self.options.target_cpu == "native" {
+            let mut features = TargetMachine::get_host_cpu_features().to_string();
+            if !self.options.target_features.is_empty() {
+                features.push(',');
+                features.push_str(&self.options.target_features);
+            }
+            (TargetMachine::get_host_cpu_name().to_string(), features)
+        } else {
+            (
+                self.options.target_cpu.clone(),
+                self.options.target_features.clone(),
+            )
+        };
+
+        target
             .create_target_machine(
                 &target_triple,
-                "generic",
-                "",
-                inkwell::OptimizationLevel::Default,
-                inkwell::targets::RelocMode::Default,
-                inkwell::targets::CodeModel::Default,
+                &cpu,
+                &features,
+                self.options.optimization_level(),
+                self.options.reloc_model.to_llvm(),
+                self.options.code_model.to_llvm(),
             )
-            .ok_or("Failed to create target machine")?;
+            .ok_or_else(|| "Failed to create target machine".to_string())
+    }
+
+    /// Run the IR pass pipeline for the configured optimization level
+    ///
+    /// Even at -O0 this drops dead stores and unused globals and declarations,
+    /// so the emitted IR only contains what the program uses.
+    pub fn optimize(&self) -> Result<(), String> {
+        use inkwell::attributes::{Attribute, AttributeLoc};
+
+        let target_machine = self.create_target_machine()?;
+        // Like clang's -Os/-Oz, so code generation favours size as well as the passes
+        let size_attributes: &[&str] = match self.options.size_level {
+            0 => &[],
+            1 => &["optsize"],
+            _ => &["optsize", "minsize"],
+        };
+        for function in self.module.get_functions() {
+            if function.count_basic_blocks() == 0 {
+                continue;
+            }
+            for name in size_attributes {
+                let kind = Attribute::get_named_enum_kind_id(name);
+                function.add_attribute(
+                    AttributeLoc::Function,
+                    self.context.create_enum_attribute(kind, 0),
+                );
+            }
+        }
+        self.module
+            .run_passes(
+                &self.options.pass_pipeline(),
+                &target_machine,
+                inkwell::passes::PassBuilderOptions::create(),
+            )
+            .map_err(|e| format!("Failed to optimize module: {}", e.to_string()))?;
+        self.verify("after optimization").map_err(|e| e.to_string())
+    }
+
+    pub fn write_object_to_file(&self, filename: &str) -> Result<(), String> {
+        use inkwell::targets::FileType;
+        use std::fs::File;
+        use std::io::Write;
+
+        let target_machine = self.create_target_machine()?;
 
         // Generate object code
         let object_data = target_machine
@@ -738,16 +6220,79 @@ impl<'ctx> CodeGenerator<'ctx> {
         Ok(())
     }
 
+    /// Write the module as LLVM bitcode, used as linker input for LTO builds
+    pub fn write_bitcode_to_file(&self, filename: &str) -> Result<(), String> {
+        if self.module.write_bitcode_to_path(filename) {
+            Ok(())
+        } else {
+            Err(format!("Failed to write bitcode to file {filename}"))
+        }
+    }
+
+    /// Write the module and link it into the executable `filename`
+    ///
+    /// The intermediate object is written next to it and removed afterwards.
+    pub fn write_executable(&self, filename: &str) -> Result<(), String> {
+        // A fresh temporary file, so nothing next to the output is clobbered
+        let object_path = tempfile::Builder::new()
+            .prefix("pycc-")
+            .suffix(".o")
+            .tempfile()
+            .map_err(|e| format!("Failed to create a temporary object file: {e}"))?
+            .into_temp_path();
+        let object_file = object_path.to_string_lossy().into_owned();
+        let written = if self.options.lto.is_enabled() {
+            self.write_bitcode_to_file(&object_file)
+        } else {
+            self.write_object_to_file(&object_file)
+        };
+        let linked = written.and_then(|_| {
+            let output = self
+                .options
+                .linker_command(&object_file, filename)?
+                .output()
+                .map_err(|e| format!("Failed to execute linker: {e}"))?;
+            if output.status.success() {
+                Ok(())
+            } else {
+                Err(format!(
+                    "Linking failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ))
+            }
+        });
+        let _ = object_path.close();
+        linked
+    }
+
+    /// Run constant folding with what is known at the current position
+    ///
+    /// Module-level code may read a constant before it is assigned, so only
+    /// functions substitute module constants; the dunders are defined from the start.
+    fn with_fold_scope<T>(&self, fold: impl FnOnce(&constants::Scope) -> T) -> T {
+        fold(&constants::Scope {
+            constants: if self.module_level {
+                &self.module_dunders
+            } else {
+                &self.module_constants
+            },
+            shadows: &|name: &str| {
+                self.functions.contains_key(name)
+                    || self.variables.contains_key(name)
+                    || self.local_names.contains(name)
+            },
+        })
+    }
+
     fn evaluate_fstring_codegen(
         &mut self,
         fstring: &crate::ast::FString,
-    ) -> Result<BasicValueEnum<'ctx>, String> {
+        escapes: bool,
+    ) -> Result<BasicValueEnum<'ctx>, CodegenError> {
         // If there are no expressions, just return the string as is
         if fstring.parts.is_empty() {
-            let name = format!("str_{}", self.string_counter);
-            self.string_counter += 1;
-            let str_ptr = self.builder.build_global_string_ptr("", &name).unwrap();
-            return Ok(str_ptr.as_pointer_value().into());
+            let str_ptr = self.constant_string("", "str")?;
+            return Ok(str_ptr.into());
         }
 
         // For f-strings, we need to build a proper string instead of printing directly
@@ -766,73 +6311,33 @@ impl<'ctx> CodeGenerator<'ctx> {
                     // Evaluate the expression and add appropriate format specifier
                     let expr_value = self.evaluate_fstring_expression(expr)?;
                     match expr_value {
+                        BasicValueEnum::IntValue(int_val)
+                            if int_val.get_type().get_bit_width() == 1 =>
+                        {
+                            format_string.push_str("%s");
+                            sprintf_args.push(self.build_bool_string(int_val)?.into());
+                        }
                         BasicValueEnum::IntValue(int_val) => {
                             format_string.push_str("%ld");
                             sprintf_args.push(int_val.into());
-                        }
-                        BasicValueEnum::FloatValue(float_val) => {
-                            format_string.push_str("%.6g");
-                            sprintf_args.push(float_val.into());
-                        }
-                        BasicValueEnum::PointerValue(ptr_val) => {
-                            format_string.push_str("%s");
-                            sprintf_args.push(ptr_val.into());
-                        }
-                        _ => {
-                            format_string.push_str("%s");
-                            let name = format!("unknown_{}", self.string_counter);
-                            self.string_counter += 1;
-                            let str_ptr = self.builder.build_global_string_ptr("?", &name).unwrap();
-                            sprintf_args.push(str_ptr.as_pointer_value().into());
-                        }
-                    }
-                }
-            }
-        }
-
-        // Allocate buffer for the result string on stack
-        let result_size = format_string.len() + 256; // Extra space for formatted values
-        let i8_type = self.context.i8_type();
-        let result_type = i8_type.array_type(result_size as u32);
-        let result_alloc = self
-            .builder
-            .build_alloca(result_type, "fstring_result")
-            .unwrap();
-        let result_ptr = self
-            .builder
-            .build_pointer_cast(
-                result_alloc,
-                self.context.ptr_type(inkwell::AddressSpace::default()),
-                "result_ptr",
-            )
-            .unwrap();
-
-        // Initialize the buffer to zero to prevent garbage data
-        let zero = i8_type.const_int(0, false);
-        let memset_fn = if let Some(func) = self.module.get_function("memset") {
-            func
-        } else {
-            let i8_ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
-            let memset_fn_type = self.context.i64_type().fn_type(
-                &[
-                    i8_ptr_type.into(),
-                    i8_type.into(),
-                    self.context.i64_type().into(),
-                ],
-                false,
-            );
-            self.module.add_function("memset", memset_fn_type, None)
-        };
-
-        let size_val = self.context.i64_type().const_int(result_size as u64, false);
-        let _ = self
-            .builder
-            .build_call(
-                memset_fn,
-                &[result_ptr.into(), zero.into(), size_val.into()],
-                "memset_call",
-            )
-            .unwrap();
+                        }
+                        BasicValueEnum::FloatValue(float_val) => {
+                            format_string.push_str("%.6g");
+                            sprintf_args.push(float_val.into());
+                        }
+                        BasicValueEnum::PointerValue(ptr_val) => {
+                            format_string.push_str("%s");
+                            sprintf_args.push(ptr_val.into());
+                        }
+                        _ => {
+                            format_string.push_str("%s");
+                            let str_ptr = self.constant_string("?", "unknown")?;
+                            sprintf_args.push(str_ptr.into());
+                        }
+                    }
+                }
+            }
+        }
 
         // Get or declare snprintf function for safe string formatting
         let snprintf_fn = if let Some(func) = self.module.get_function("snprintf") {
@@ -846,29 +6351,47 @@ impl<'ctx> CodeGenerator<'ctx> {
         };
 
         // Create format string global
-        let format_name = format!("fmt_{}", self.string_counter);
-        self.string_counter += 1;
-        let format_ptr = self
-            .builder
-            .build_global_string_ptr(&format_string, &format_name)
-            .unwrap();
+        let format_ptr = self.constant_string(&format_string, "fmt")?;
 
-        // Build snprintf call with buffer size limit
-        let buffer_size = self
+        // Measure the formatted length first so the buffer is never truncated
+        let i32_type = self.context.i32_type();
+        let null_ptr = self
             .context
-            .i32_type()
-            .const_int((result_size - 1) as u64, false); // Leave space for null terminator
-        let mut all_args: Vec<inkwell::values::BasicMetadataValueEnum<'ctx>> = vec![
-            result_ptr.into(),
-            buffer_size.into(),
-            format_ptr.as_pointer_value().into(),
+            .ptr_type(inkwell::AddressSpace::default())
+            .const_null();
+        let mut measure_args: Vec<inkwell::values::BasicMetadataValueEnum<'ctx>> = vec![
+            null_ptr.into(),
+            i32_type.const_zero().into(),
+            format_ptr.into(),
         ];
+        measure_args.extend(sprintf_args.iter().copied());
+        let formatted_len = self
+            .builder
+            .build_call(snprintf_fn, &measure_args, "fstring_len")?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_int_value();
+        let buffer_size = self.builder.build_int_add(
+            formatted_len,
+            i32_type.const_int(1, false),
+            "fstring_size",
+        )?;
+
+        // Returned f-strings outlive the frame and go through the allocator
+        let result_size = self.builder.build_int_s_extend(
+            buffer_size,
+            self.context.i64_type(),
+            "fstring_size64",
+        )?;
+        let result_ptr = self.allocate_string_buffer(result_size, escapes, "fstring_result")?;
+
+        let mut all_args: Vec<inkwell::values::BasicMetadataValueEnum<'ctx>> =
+            vec![result_ptr.into(), buffer_size.into(), format_ptr.into()];
         all_args.extend(sprintf_args);
 
         let _ = self
             .builder
-            .build_call(snprintf_fn, &all_args, "snprintf_call")
-            .unwrap();
+            .build_call(snprintf_fn, &all_args, "snprintf_call")?;
 
         // Return the result pointer
         Ok(result_ptr.into())
@@ -878,15 +6401,13 @@ impl<'ctx> CodeGenerator<'ctx> {
     fn concatenate_string_parts(
         &mut self,
         parts: &[BasicValueEnum<'ctx>],
-    ) -> Result<BasicValueEnum<'ctx>, String> {
+    ) -> Result<BasicValueEnum<'ctx>, CodegenError> {
         // For f-strings, we need to build a format string and use printf to output the result
         // This is a simplified approach that prints directly instead of returning a string
 
         if parts.is_empty() {
-            let name = format!("empty_{}", self.string_counter);
-            self.string_counter += 1;
-            let str_ptr = self.builder.build_global_string_ptr("", &name).unwrap();
-            Ok(str_ptr.as_pointer_value().into())
+            let str_ptr = self.constant_string("", "empty")?;
+            Ok(str_ptr.into())
         } else if parts.len() == 1 {
             Ok(parts[0])
         } else {
@@ -899,7 +6420,7 @@ impl<'ctx> CodeGenerator<'ctx> {
     fn build_printf_concatenation(
         &mut self,
         parts: &[BasicValueEnum<'ctx>],
-    ) -> Result<BasicValueEnum<'ctx>, String> {
+    ) -> Result<BasicValueEnum<'ctx>, CodegenError> {
         // Get or declare printf function
         let printf_fn = if let Some(func) = self.module.get_function("printf") {
             func
@@ -921,6 +6442,10 @@ impl<'ctx> CodeGenerator<'ctx> {
                     format_string.push_str("%s");
                     printf_args.push((*ptr_val).into());
                 }
+                BasicValueEnum::IntValue(int_val) if int_val.get_type().get_bit_width() == 1 => {
+                    format_string.push_str("%s");
+                    printf_args.push(self.build_bool_string(*int_val)?.into());
+                }
                 BasicValueEnum::IntValue(int_val) => {
                     format_string.push_str("%ld");
                     printf_args.push((*int_val).into());
@@ -931,10 +6456,8 @@ impl<'ctx> CodeGenerator<'ctx> {
                 }
                 _ => {
                     format_string.push_str("%s");
-                    let name = format!("unknown_{}", self.string_counter);
-                    self.string_counter += 1;
-                    let str_ptr = self.builder.build_global_string_ptr("?", &name).unwrap();
-                    printf_args.push(str_ptr.as_pointer_value().into());
+                    let str_ptr = self.constant_string("?", "unknown")?;
+                    printf_args.push(str_ptr.into());
                 }
             }
         }
@@ -943,29 +6466,21 @@ impl<'ctx> CodeGenerator<'ctx> {
         format_string.push('\n');
 
         // Create the format string global
-        let format_name = format!("fmt_{}", self.string_counter);
-        self.string_counter += 1;
-        let format_ptr = self
-            .builder
-            .build_global_string_ptr(&format_string, &format_name)
-            .unwrap();
+        let format_ptr = self.constant_string(&format_string, "fmt")?;
 
         // Build printf call with format string as first argument
         let mut all_args: Vec<inkwell::values::BasicMetadataValueEnum<'ctx>> =
-            vec![format_ptr.as_pointer_value().into()];
+            vec![format_ptr.into()];
         all_args.extend(printf_args);
 
         // Call printf to output the concatenated string
         let _ = self
             .builder
-            .build_call(printf_fn, &all_args, "printf_concat")
-            .unwrap();
+            .build_call(printf_fn, &all_args, "printf_concat")?;
 
         // Return an empty string as the result (since we already printed it)
-        let name = format!("empty_{}", self.string_counter);
-        self.string_counter += 1;
-        let str_ptr = self.builder.build_global_string_ptr("", &name).unwrap();
-        Ok(str_ptr.as_pointer_value().into())
+        let str_ptr = self.constant_string("", "empty")?;
+        Ok(str_ptr.into())
     }
 
     #[allow(dead_code)]
@@ -976,26 +6491,27 @@ impl<'ctx> CodeGenerator<'ctx> {
         None
     }
 
-    fn evaluate_fstring_expression(&mut self, expr: &str) -> Result<BasicValueEnum<'ctx>, String> {
+    fn evaluate_fstring_expression(
+        &mut self,
+        expr: &str,
+    ) -> Result<BasicValueEnum<'ctx>, CodegenError> {
         // Try to parse and evaluate the expression using the existing parser
         let expr = expr.trim();
 
         // First, try to handle simple variable names
-        if let Some((ptr, stored_value)) = self.variables.get(expr) {
+        if let Some(variable) = self.variables.get(expr).copied() {
             // Load the current value from the variable's memory location
-            let loaded_value = self
-                .builder
-                .build_load(stored_value.get_type(), *ptr, &format!("load_{}", expr))
-                .unwrap();
-
-            // For string variables, we need to handle them specially
-            // Check if the stored value was a string pointer
-            if matches!(stored_value, BasicValueEnum::PointerValue(_)) {
-                // This is a string variable, return the loaded value directly
+            let loaded_value = self.builder.build_load(
+                variable.llvm_type,
+                variable.slot,
+                &format!("load_{}", expr),
+            )?;
+
+            // String variables are used directly, everything else is converted
+            if variable.py_type == Type::Str {
                 return Ok(loaded_value);
             } else {
-                // For other types, convert to string
-                return self.value_to_string(loaded_value);
+                return self.value_to_string(CompilerValue::new(loaded_value, variable.py_type));
             }
         }
 
@@ -1008,17 +6524,23 @@ impl<'ctx> CodeGenerator<'ctx> {
         }
 
         // If all else fails, return the expression as a string literal
-        let name = format!("expr_{}", self.string_counter);
-        self.string_counter += 1;
-        let str_ptr = self.builder.build_global_string_ptr(expr, &name).unwrap();
-        Ok(str_ptr.as_pointer_value().into())
+        let str_ptr = self.constant_string(expr, "expr")?;
+        Ok(str_ptr.into())
     }
 
     fn value_to_string(
         &mut self,
-        value: BasicValueEnum<'ctx>,
-    ) -> Result<BasicValueEnum<'ctx>, String> {
-        match value {
+        value: CompilerValue<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>, CodegenError> {
+        if value.py_type == Type::None {
+            let none_str = self.constant_string("None", "none_str")?;
+            return Ok(none_str.into());
+        }
+
+        match value.llvm_value {
+            BasicValueEnum::IntValue(int_val) if int_val.get_type().get_bit_width() == 1 => {
+                Ok(self.build_bool_string(int_val)?.into())
+            }
             BasicValueEnum::IntValue(int_val) => {
                 // For runtime integer values, we need to convert them to strings using snprintf
                 let name = format!("int_str_{}", self.string_counter);
@@ -1027,15 +6549,12 @@ impl<'ctx> CodeGenerator<'ctx> {
                 // Allocate buffer for the string representation
                 let i8_type = self.context.i8_type();
                 let buffer_type = i8_type.array_type(32); // Enough space for 64-bit integer
-                let buffer_alloc = self.builder.build_alloca(buffer_type, &name).unwrap();
-                let buffer_ptr = self
-                    .builder
-                    .build_pointer_cast(
-                        buffer_alloc,
-                        self.context.ptr_type(inkwell::AddressSpace::default()),
-                        "buffer_ptr",
-                    )
-                    .unwrap();
+                let buffer_alloc = self.builder.build_alloca(buffer_type, &name)?;
+                let buffer_ptr = self.builder.build_pointer_cast(
+                    buffer_alloc,
+                    self.context.ptr_type(inkwell::AddressSpace::default()),
+                    "buffer_ptr",
+                )?;
 
                 // Initialize buffer to zero
                 let zero = i8_type.const_int(0, false);
@@ -1055,14 +6574,11 @@ impl<'ctx> CodeGenerator<'ctx> {
                 };
 
                 let size_val = self.context.i64_type().const_int(32, false);
-                let _ = self
-                    .builder
-                    .build_call(
-                        memset_fn,
-                        &[buffer_ptr.into(), zero.into(), size_val.into()],
-                        "memset_int",
-                    )
-                    .unwrap();
+                let _ = self.builder.build_call(
+                    memset_fn,
+                    &[buffer_ptr.into(), zero.into(), size_val.into()],
+                    "memset_int",
+                )?;
 
                 // Get or declare snprintf function
                 let snprintf_fn = if let Some(func) = self.module.get_function("snprintf") {
@@ -1076,125 +6592,31 @@ impl<'ctx> CodeGenerator<'ctx> {
                 };
 
                 // Create format string for integer
-                let format_name = format!("int_fmt_{}", self.string_counter);
-                self.string_counter += 1;
-                let format_ptr = self
-                    .builder
-                    .build_global_string_ptr("%ld", &format_name)
-                    .unwrap();
+                let format_ptr = self.constant_string("%ld", "int_fmt")?;
 
                 // Call snprintf to convert integer to string
                 let buffer_size = self.context.i32_type().const_int(32, false);
-                let _ = self
-                    .builder
-                    .build_call(
-                        snprintf_fn,
-                        &[
-                            buffer_ptr.into(),
-                            buffer_size.into(),
-                            format_ptr.as_pointer_value().into(),
-                            int_val.into(),
-                        ],
-                        "snprintf_call",
-                    )
-                    .unwrap();
-
-                Ok(buffer_ptr.into())
-            }
-            BasicValueEnum::FloatValue(float_val) => {
-                // For runtime float values, we need to convert them to strings using snprintf
-                let name = format!("float_str_{}", self.string_counter);
-                self.string_counter += 1;
-
-                // Allocate buffer for the string representation
-                let i8_type = self.context.i8_type();
-                let buffer_type = i8_type.array_type(64); // Enough space for float
-                let buffer_alloc = self.builder.build_alloca(buffer_type, &name).unwrap();
-                let buffer_ptr = self
-                    .builder
-                    .build_pointer_cast(
-                        buffer_alloc,
-                        self.context.ptr_type(inkwell::AddressSpace::default()),
-                        "buffer_ptr",
-                    )
-                    .unwrap();
-
-                // Initialize buffer to zero
-                let zero = i8_type.const_int(0, false);
-                let memset_fn = if let Some(func) = self.module.get_function("memset") {
-                    func
-                } else {
-                    let i8_ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
-                    let memset_fn_type = self.context.i64_type().fn_type(
-                        &[
-                            i8_ptr_type.into(),
-                            i8_type.into(),
-                            self.context.i64_type().into(),
-                        ],
-                        false,
-                    );
-                    self.module.add_function("memset", memset_fn_type, None)
-                };
-
-                let size_val = self.context.i64_type().const_int(64, false);
-                let _ = self
-                    .builder
-                    .build_call(
-                        memset_fn,
-                        &[buffer_ptr.into(), zero.into(), size_val.into()],
-                        "memset_float",
-                    )
-                    .unwrap();
-
-                // Get or declare snprintf function
-                let snprintf_fn = if let Some(func) = self.module.get_function("snprintf") {
-                    func
-                } else {
-                    let i32_type = self.context.i32_type();
-                    let str_type = self.context.ptr_type(inkwell::AddressSpace::default());
-                    let snprintf_fn_type = i32_type
-                        .fn_type(&[str_type.into(), i32_type.into(), str_type.into()], true);
-                    self.module.add_function("snprintf", snprintf_fn_type, None)
-                };
-
-                // Create format string for float
-                let format_name = format!("float_fmt_{}", self.string_counter);
-                self.string_counter += 1;
-                let format_ptr = self
-                    .builder
-                    .build_global_string_ptr("%.6g", &format_name)
-                    .unwrap();
-
-                // Call snprintf to convert float to string
-                let buffer_size = self.context.i32_type().const_int(64, false);
-                let _ = self
-                    .builder
-                    .build_call(
-                        snprintf_fn,
-                        &[
-                            buffer_ptr.into(),
-                            buffer_size.into(),
-                            format_ptr.as_pointer_value().into(),
-                            float_val.into(),
-                        ],
-                        "snprintf_call",
-                    )
-                    .unwrap();
+                let _ = self.builder.build_call(
+                    snprintf_fn,
+                    &[
+                        buffer_ptr.into(),
+                        buffer_size.into(),
+                        format_ptr.into(),
+                        int_val.into(),
+                    ],
+                    "snprintf_call",
+                )?;
 
                 Ok(buffer_ptr.into())
             }
+            BasicValueEnum::FloatValue(float_val) => Ok(self.build_float_repr(float_val)?.into()),
             BasicValueEnum::PointerValue(ptr_val) => {
                 // Assume this is already a string pointer
                 Ok(BasicValueEnum::PointerValue(ptr_val))
             }
             _ => {
-                let name = format!("unknown_{}", self.string_counter);
-                self.string_counter += 1;
-                let str_ptr = self
-                    .builder
-                    .build_global_string_ptr("unknown", &name)
-                    .unwrap();
-                Ok(str_ptr.as_pointer_value().into())
+                let str_ptr = self.constant_string("unknown", "unknown")?;
+                Ok(str_ptr.into())
             }
         }
     }
@@ -1244,7 +6666,8 @@ impl<'ctx> CodeGenerator<'ctx> {
         }
 
         // Try to parse as identifier
-        if expr.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        let mut chars = expr.chars();
+        if chars.next().is_some_and(is_identifier_start) && chars.all(is_identifier_continue) {
             return Some(Node::Identifier(Identifier {
                 name: expr.to_string(),
             }));
@@ -1316,68 +6739,25 @@ impl<'ctx> CodeGenerator<'ctx> {
         &mut self,
         string_ptr: inkwell::values::PointerValue<'ctx>,
         count: inkwell::values::IntValue<'ctx>,
-    ) -> Result<BasicValueEnum<'ctx>, String> {
-        // Get or declare strlen function to get string length
-        let strlen_fn = if let Some(func) = self.module.get_function("strlen") {
-            func
-        } else {
-            let i32_type = self.context.i32_type();
-            let str_type = self.context.ptr_type(inkwell::AddressSpace::default());
-            let strlen_fn_type = i32_type.fn_type(&[str_type.into()], false);
-            self.module.add_function("strlen", strlen_fn_type, None)
-        };
-
-        // Get or declare malloc function for memory allocation
-        let malloc_fn = if let Some(func) = self.module.get_function("malloc") {
-            func
-        } else {
-            let i8_ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
-            let malloc_fn_type = i8_ptr_type.fn_type(&[self.context.i64_type().into()], false);
-            self.module.add_function("malloc", malloc_fn_type, None)
-        };
-
-        // Get or declare strcpy function for string copying
-        let strcpy_fn = if let Some(func) = self.module.get_function("strcpy") {
-            func
-        } else {
-            let i8_ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
-            let strcpy_fn_type =
-                i8_ptr_type.fn_type(&[i8_ptr_type.into(), i8_ptr_type.into()], false);
-            self.module.add_function("strcpy", strcpy_fn_type, None)
-        };
-
-        // Get or declare strcat function for string concatenation
-        let strcat_fn = if let Some(func) = self.module.get_function("strcat") {
-            func
-        } else {
-            let i8_ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
-            let strcat_fn_type =
-                i8_ptr_type.fn_type(&[i8_ptr_type.into(), i8_ptr_type.into()], false);
-            self.module.add_function("strcat", strcat_fn_type, None)
-        };
-
+        escapes: bool,
+    ) -> Result<BasicValueEnum<'ctx>, CodegenError> {
         // Get the current function for basic block operations
-        let current_function = self
-            .builder
-            .get_insert_block()
-            .unwrap()
-            .get_parent()
-            .unwrap();
+        let current_function = self.current_function()?;
 
         // Check if count is negative or zero
         let zero = self.context.i64_type().const_int(0, false);
-        let is_negative = self
-            .builder
-            .build_int_compare(inkwell::IntPredicate::SLT, count, zero, "is_negative")
-            .unwrap();
-        let is_zero = self
-            .builder
-            .build_int_compare(inkwell::IntPredicate::EQ, count, zero, "is_zero")
-            .unwrap();
+        let is_negative = self.builder.build_int_compare(
+            inkwell::IntPredicate::SLT,
+            count,
+            zero,
+            "is_negative",
+        )?;
+        let is_zero =
+            self.builder
+                .build_int_compare(inkwell::IntPredicate::EQ, count, zero, "is_zero")?;
         let is_non_positive = self
             .builder
-            .build_or(is_negative, is_zero, "is_non_positive")
-            .unwrap();
+            .build_or(is_negative, is_zero, "is_non_positive")?;
 
         // Create basic blocks for conditional branching
         let empty_block = self
@@ -1392,81 +6772,29 @@ impl<'ctx> CodeGenerator<'ctx> {
 
         // Branch based on count value
         self.builder
-            .build_conditional_branch(is_non_positive, empty_block, multiply_block)
-            .unwrap();
+            .build_conditional_branch(is_non_positive, empty_block, multiply_block)?;
 
         // Block for empty result (count <= 0)
         self.builder.position_at_end(empty_block);
-        let empty_name = format!("empty_{}", self.string_counter);
-        self.string_counter += 1;
-        let empty_str = self
-            .builder
-            .build_global_string_ptr("", &empty_name)
-            .unwrap();
-        self.builder
-            .build_unconditional_branch(merge_block)
-            .unwrap();
+        let empty_str = self.constant_string("", "empty")?;
+        self.builder.build_unconditional_branch(merge_block)?;
 
         // Block for actual multiplication
         self.builder.position_at_end(multiply_block);
 
         // Calculate length of the original string
-        let str_len = self
-            .builder
-            .build_call(strlen_fn, &[string_ptr.into()], "str_len")
-            .unwrap()
-            .try_as_basic_value()
-            .unwrap_basic()
-            .into_int_value();
-
-        // Convert count to i32 for calculations
-        let count_i32 = self
-            .builder
-            .build_int_cast(count, self.context.i32_type(), "count_i32")
-            .unwrap();
+        let str_len = self.build_string_length(string_ptr, "str_len")?;
 
         // Calculate total length: str_len * count + 1 for null terminator
-        let total_len = self
-            .builder
-            .build_int_mul(str_len, count_i32, "total_len")
-            .unwrap();
-        let total_len_with_null = self
-            .builder
-            .build_int_add(
-                total_len,
-                self.context.i32_type().const_int(1, false),
-                "total_len_with_null",
-            )
-            .unwrap();
-
-        // Convert to i64 for malloc
-        let malloc_size = self
-            .builder
-            .build_int_cast(total_len_with_null, self.context.i64_type(), "malloc_size")
-            .unwrap();
+        let total_len = self.builder.build_int_mul(str_len, count, "total_len")?;
+        let malloc_size = self.builder.build_int_add(
+            total_len,
+            self.context.i64_type().const_int(1, false),
+            "malloc_size",
+        )?;
 
         // Allocate memory for the result string
-        let result_ptr = self
-            .builder
-            .build_call(malloc_fn, &[malloc_size.into()], "result_ptr")
-            .unwrap()
-            .try_as_basic_value()
-            .unwrap_basic()
-            .into_pointer_value();
-
-        // Initialize result as empty string
-        let empty_for_init = self
-            .builder
-            .build_global_string_ptr("", "empty_init")
-            .unwrap();
-        let _ = self
-            .builder
-            .build_call(
-                strcpy_fn,
-                &[result_ptr.into(), empty_for_init.as_pointer_value().into()],
-                "init_empty",
-            )
-            .unwrap();
+        let result_ptr = self.allocate_string_buffer(malloc_size, escapes, "result_ptr")?;
 
         // Create loop to concatenate string count times
         let loop_block = self.context.append_basic_block(current_function, "loop");
@@ -1480,76 +6808,66 @@ impl<'ctx> CodeGenerator<'ctx> {
         // Initialize loop counter
         let loop_counter = self
             .builder
-            .build_alloca(self.context.i64_type(), "loop_counter")
-            .unwrap();
-        self.builder.build_store(loop_counter, zero).unwrap();
+            .build_alloca(self.context.i64_type(), "loop_counter")?;
+        self.builder.build_store(loop_counter, zero)?;
 
         // Jump to loop condition
-        self.builder.build_unconditional_branch(loop_block).unwrap();
+        self.builder.build_unconditional_branch(loop_block)?;
 
         // Loop condition block
         self.builder.position_at_end(loop_block);
         let current_counter = self
             .builder
-            .build_load(self.context.i64_type(), loop_counter, "current_counter")
-            .unwrap()
+            .build_load(self.context.i64_type(), loop_counter, "current_counter")?
             .into_int_value();
-        let loop_condition = self
-            .builder
-            .build_int_compare(
-                inkwell::IntPredicate::SLT,
-                current_counter,
-                count,
-                "loop_condition",
-            )
-            .unwrap();
+        let loop_condition = self.builder.build_int_compare(
+            inkwell::IntPredicate::SLT,
+            current_counter,
+            count,
+            "loop_condition",
+        )?;
         self.builder
-            .build_conditional_branch(loop_condition, loop_body, loop_end)
-            .unwrap();
+            .build_conditional_branch(loop_condition, loop_body, loop_end)?;
 
         // Loop body block
         self.builder.position_at_end(loop_body);
-        // Concatenate the string to result
-        let _ = self
+        // Copy the string into the next slot of the result
+        let offset = self
             .builder
-            .build_call(
-                strcat_fn,
-                &[result_ptr.into(), string_ptr.into()],
-                "strcat_iter",
-            )
-            .unwrap();
+            .build_int_mul(current_counter, str_len, "offset")?;
+        self.build_copy_at(result_ptr, offset, string_ptr, str_len)?;
 
         // Increment counter
-        let next_counter = self
-            .builder
-            .build_int_add(
-                current_counter,
-                self.context.i64_type().const_int(1, false),
-                "next_counter",
-            )
-            .unwrap();
-        self.builder
-            .build_store(loop_counter, next_counter)
-            .unwrap();
+        let next_counter = self.builder.build_int_add(
+            current_counter,
+            self.context.i64_type().const_int(1, false),
+            "next_counter",
+        )?;
+        self.builder.build_store(loop_counter, next_counter)?;
 
         // Jump back to loop condition
-        self.builder.build_unconditional_branch(loop_block).unwrap();
+        self.builder.build_unconditional_branch(loop_block)?;
 
-        // Loop end block
+        // Loop end block: terminate the result
         self.builder.position_at_end(loop_end);
+        let terminator_ptr = unsafe {
+            self.builder.build_gep(
+                self.context.i8_type(),
+                result_ptr,
+                &[total_len],
+                "terminator_ptr",
+            )?
+        };
         self.builder
-            .build_unconditional_branch(merge_block)
-            .unwrap();
+            .build_store(terminator_ptr, self.context.i8_type().const_zero())?;
+        self.builder.build_unconditional_branch(merge_block)?;
 
         // Merge block
         self.builder.position_at_end(merge_block);
 
         // Create phi node for the result
         let result_type = self.context.ptr_type(inkwell::AddressSpace::default());
-        let phi = self
-            .builder
-            .build_phi(result_type, "multiply_result")
-            .unwrap();
+        let phi = self.builder.build_phi(result_type, "multiply_result")?;
         phi.add_incoming(&[(&empty_str, empty_block), (&result_ptr, loop_end)]);
 
         Ok(phi.as_basic_value())
@@ -1559,108 +6877,281 @@ impl<'ctx> CodeGenerator<'ctx> {
         &mut self,
         left: inkwell::values::PointerValue<'ctx>,
         right: inkwell::values::PointerValue<'ctx>,
-    ) -> Result<BasicValueEnum<'ctx>, String> {
-        // Get or declare strlen function to get string lengths
-        let strlen_fn = if let Some(func) = self.module.get_function("strlen") {
-            func
-        } else {
-            let i32_type = self.context.i32_type();
-            let str_type = self.context.ptr_type(inkwell::AddressSpace::default());
-            let strlen_fn_type = i32_type.fn_type(&[str_type.into()], false);
-            self.module.add_function("strlen", strlen_fn_type, None)
-        };
+        escapes: bool,
+    ) -> Result<BasicValueEnum<'ctx>, CodegenError> {
+        let i8_type = self.context.i8_type();
+        let i64_type = self.context.i64_type();
 
-        // Get or declare malloc function for memory allocation
-        let malloc_fn = if let Some(func) = self.module.get_function("malloc") {
-            func
-        } else {
-            let i8_ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
-            let malloc_fn_type = i8_ptr_type.fn_type(&[self.context.i64_type().into()], false);
-            self.module.add_function("malloc", malloc_fn_type, None)
-        };
+        // Calculate lengths of both strings
+        let left_len = self.build_string_length(left, "left_len")?;
+        let right_len = self.build_string_length(right, "right_len")?;
 
-        // Get or declare strcpy function for string copying
-        let strcpy_fn = if let Some(func) = self.module.get_function("strcpy") {
-            func
-        } else {
-            let i8_ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
-            let strcpy_fn_type =
-                i8_ptr_type.fn_type(&[i8_ptr_type.into(), i8_ptr_type.into()], false);
-            self.module.add_function("strcpy", strcpy_fn_type, None)
+        // Allocate the total length plus the null terminator
+        let total_len = self
+            .builder
+            .build_int_add(left_len, right_len, "total_len")?;
+        let size = self
+            .builder
+            .build_int_add(total_len, i64_type.const_int(1, false), "size")?;
+        let result_ptr = self.allocate_string_buffer(size, escapes, "result_ptr")?;
+
+        // Copy both strings, then terminate the result
+        self.build_copy_at(result_ptr, i64_type.const_zero(), left, left_len)?;
+        self.build_copy_at(result_ptr, left_len, right, right_len)?;
+        let terminator_ptr = unsafe {
+            self.builder
+                .build_gep(i8_type, result_ptr, &[total_len], "terminator_ptr")?
         };
+        self.builder
+            .build_store(terminator_ptr, i8_type.const_zero())?;
 
-        // Get or declare strcat function for string concatenation
-        let strcat_fn = if let Some(func) = self.module.get_function("strcat") {
-            func
-        } else {
-            let i8_ptr_type = self.context.ptr_type(inkwell::AddressSpace::default());
-            let strcat_fn_type =
-                i8_ptr_type.fn_type(&[i8_ptr_type.into(), i8_ptr_type.into()], false);
-            self.module.add_function("strcat", strcat_fn_type, None)
+        Ok(result_ptr.into())
+    }
+}
+
+/// CPython's complaint about calling `name` with `given` positional arguments, if any
+fn arity_error(name: &str, parameters: &[String], given: usize) -> Option<String> {
+    let expected = parameters.len();
+    if given < expected {
+        let missing: Vec<String> = parameters[given..]
+            .iter()
+            .map(|parameter| format!("'{parameter}'"))
+            .collect();
+        let listed = match missing.as_slice() {
+            [only] => only.clone(),
+            [first, second] => format!("{first} and {second}"),
+            [rest @ .., last] => format!("{}, and {last}", rest.join(", ")),
+            [] => unreachable!(),
         };
+        let plural = if missing.len() == 1 { "" } else { "s" };
+        Some(format!(
+            "{name}() missing {} required positional argument{plural}: {listed}",
+            missing.len()
+        ))
+    } else if given > expected {
+        let plural = if expected == 1 { "" } else { "s" };
+        let verb = if given == 1 { "was" } else { "were" };
+        Some(format!(
+            "{name}() takes {expected} positional argument{plural} but {given} {verb} given"
+        ))
+    } else {
+        None
+    }
+}
 
-        // Calculate lengths of both strings
-        let left_len = self
-            .builder
-            .build_call(strlen_fn, &[left.into()], "left_len")
-            .unwrap()
-            .try_as_basic_value()
-            .unwrap_basic()
-            .into_int_value();
+/// `access` mode that only checks a path exists
+const F_OK: u64 = 0;
 
-        let right_len = self
-            .builder
-            .build_call(strlen_fn, &[right.into()], "right_len")
-            .unwrap()
-            .try_as_basic_value()
-            .unwrap_basic()
-            .into_int_value();
+/// Standard library modules compiled natively instead of through CPython
+const NATIVE_MODULES: [&str; 3] = ["os", "os.path", "sys"];
 
-        // Calculate total length (left + right + 1 for null terminator)
-        let total_len = self
-            .builder
-            .build_int_add(left_len, right_len, "total_len")
-            .unwrap();
-        let total_len_with_null = self
-            .builder
-            .build_int_add(
-                total_len,
-                self.context.i32_type().const_int(1, false),
-                "total_len_with_null",
-            )
-            .unwrap();
+/// `setvbuf` modes, which glibc and the BSDs number alike
+const IO_LINE_BUFFERED: u64 = 1;
+const IO_UNBUFFERED: u64 = 2;
 
-        // Convert to i64 for malloc
-        let malloc_size = self
-            .builder
-            .build_int_cast(total_len_with_null, self.context.i64_type(), "malloc_size")
-            .unwrap();
+/// Signal number of SIGINT on all supported platforms
+const SIGINT: u32 = 2;
 
-        // Allocate memory for the concatenated string
-        let result_ptr = self
-            .builder
-            .build_call(malloc_fn, &[malloc_size.into()], "result_ptr")
-            .unwrap()
-            .try_as_basic_value()
-            .unwrap_basic()
-            .into_pointer_value();
+/// What an uncaught KeyboardInterrupt prints, as in CPython
+const KEYBOARD_INTERRUPT: &str = "Traceback (most recent call last):\nKeyboardInterrupt\n";
 
-        // Copy left string to result
-        let _ = self
-            .builder
-            .build_call(strcpy_fn, &[result_ptr.into(), left.into()], "strcpy_left")
-            .unwrap();
+/// Nesting depth of Python calls at which RecursionError is raised, as in CPython
+const RECURSION_LIMIT: u64 = 1000;
 
-        // Concatenate right string to result
-        let _ = self
-            .builder
-            .build_call(
-                strcat_fn,
-                &[result_ptr.into(), right.into()],
-                "strcat_right",
-            )
-            .unwrap();
+/// Slots in the `pycc_alloc` table after the first heap string
+const HEAP_TABLE_INITIAL_CAPACITY: u64 = 16;
 
-        Ok(result_ptr.into())
+/// Register every LLVM target, once per process
+///
+/// Registration is not safe to run concurrently, and programs may be compiled
+/// on several threads at once.
+pub(crate) fn initialize_targets() {
+    use inkwell::targets::{InitializationConfig, Target};
+    static TARGETS: std::sync::Once = std::sync::Once::new();
+    TARGETS.call_once(|| Target::initialize_all(&InitializationConfig::default()));
+}
+
+/// Prefix of the symbols Python functions are emitted as
+///
+/// C identifiers cannot contain a dot, so `def malloc()` or `def exit()` never
+/// collides with the libc functions the runtime declares on demand.
+const PYTHON_SYMBOL_PREFIX: &str = "py.";
+
+/// Functions with at most this many statements are hinted for inlining
+const SMALL_FUNCTION_STATEMENTS: usize = 5;
+
+/// Number of statements in a body, counting those nested in compound statements
+fn statement_count(statement: &Node) -> usize {
+    match statement {
+        Node::Block(block) => block.statements.iter().map(statement_count).sum(),
+        Node::If(if_stmt) => {
+            1 + statement_count(&if_stmt.then_branch)
+                + if_stmt.else_branch.as_deref().map_or(0, statement_count)
+        }
+        Node::While(while_stmt) => 1 + statement_count(&while_stmt.body),
+        _ => 1,
+    }
+}
+
+/// `call` with the items of its `*` and `**` arguments passed separately
+///
+/// Only displays have items known at compile time: `*` takes a tuple or list
+/// display and `**` a dict display with string literal keys.
+fn expand_unpacking(call: &Call) -> Result<Call, String> {
+    let unknown = |argument: &str| {
+        format!(
+            "Unsupported argument unpacking in call to {}: the items of a '{argument}' argument other than a display are not known at compile time (use --fallback interp to run the program)",
+            call.callee
+        )
+    };
+    let mut arguments = Vec::new();
+    for argument in &call.arguments {
+        match argument {
+            Node::Starred(starred) => match starred.value.as_ref() {
+                Node::Tuple(Tuple { elements }) | Node::List(List { elements }) => {
+                    arguments.extend(elements.iter().cloned());
+                }
+                _ => return Err(unknown("*")),
+            },
+            _ => arguments.push(argument.clone()),
+        }
+    }
+
+    let mut keywords: Vec<(Option<String>, Node)> = Vec::new();
+    for (name, value) in &call.keywords {
+        let entries = match (name, value) {
+            (Some(name), _) => vec![(name.clone(), value.clone())],
+            (None, Node::Dict(dict)) => dict
+                .entries
+                .iter()
+                .map(|(key, value)| match key {
+                    Node::Literal(Literal {
+                        value: LiteralValue::String(key),
+                    }) => Ok((key.clone(), value.clone())),
+                    _ => Err(format!("{}() keywords must be strings", call.callee)),
+                })
+                .collect::<Result<_, _>>()?,
+            (None, _) => return Err(unknown("**")),
+        };
+        for (name, value) in entries {
+            if keywords
+                .iter()
+                .any(|(keyword, _)| keyword.as_ref() == Some(&name))
+            {
+                return Err(format!(
+                    "{}() got multiple values for keyword argument '{name}'",
+                    call.callee
+                ));
+            }
+            keywords.push((Some(name), value));
+        }
+    }
+
+    Ok(Call {
+        callee: call.callee.clone(),
+        arguments,
+        keywords,
+    })
+}
+
+/// Whether `node` contains a call to `name`
+fn calls_function(node: &Node, name: &str) -> bool {
+    match node {
+        Node::Call(call) => {
+            call.callee == name
+                || call.arguments.iter().any(|arg| calls_function(arg, name))
+                || call
+                    .keywords
+                    .iter()
+                    .any(|(_, value)| calls_function(value, name))
+        }
+        Node::Block(block) => block.statements.iter().any(|s| calls_function(s, name)),
+        Node::If(if_stmt) => {
+            calls_function(&if_stmt.condition, name)
+                || calls_function(&if_stmt.then_branch, name)
+                || if_stmt
+                    .else_branch
+                    .as_deref()
+                    .is_some_and(|branch| calls_function(branch, name))
+        }
+        Node::While(while_stmt) => {
+            calls_function(&while_stmt.condition, name) || calls_function(&while_stmt.body, name)
+        }
+        Node::Return(return_stmt) => return_stmt
+            .value
+            .as_deref()
+            .is_some_and(|value| calls_function(value, name)),
+        Node::Assignment(assignment) => {
+            calls_function(&assignment.value, name)
+                || assignment
+                    .targets
+                    .iter()
+                    .any(|target| calls_function(target, name))
+        }
+        Node::ExpressionStatement(expr_stmt) => calls_function(&expr_stmt.expression, name),
+        Node::Binary(binary) => {
+            calls_function(&binary.left, name) || calls_function(&binary.right, name)
+        }
+        Node::Unary(unary) => calls_function(&unary.operand, name),
+        _ => false,
     }
 }
+
+/// Error for an option that needs CPython in a build without the `python` feature
+#[cfg(not(feature = "python"))]
+fn python_feature_disabled(option: &str) -> CodegenError {
+    format!("{option} needs pycc built with the `python` feature").into()
+}
+
+/// Whether a function body contains a `return` with a value
+fn returns_value(statement: &Node) -> bool {
+    match statement {
+        Node::Return(return_stmt) => return_stmt.value.is_some(),
+        Node::Block(block) => block.statements.iter().any(returns_value),
+        Node::If(if_stmt) => {
+            returns_value(&if_stmt.then_branch)
+                || if_stmt.else_branch.as_deref().is_some_and(returns_value)
+        }
+        Node::While(while_stmt) => returns_value(&while_stmt.body),
+        _ => false,
+    }
+}
+
+/// Whether `statement` is or contains a `def`
+fn defines_function(statement: &Node) -> bool {
+    match statement {
+        Node::Function(_) => true,
+        Node::Block(block) => block.statements.iter().any(defines_function),
+        Node::If(if_stmt) => {
+            defines_function(&if_stmt.then_branch)
+                || if_stmt.else_branch.as_deref().is_some_and(defines_function)
+        }
+        Node::While(while_stmt) => defines_function(&while_stmt.body),
+        _ => false,
+    }
+}
+
+/// Whether values of the two types can share a stack slot
+///
+/// All strings are pointers, whatever they point to.
+fn same_representation(a: BasicTypeEnum<'_>, b: BasicTypeEnum<'_>) -> bool {
+    a == b || (a.is_pointer_type() && b.is_pointer_type())
+}
+
+/// Parameter `index` of a function pycc declared itself
+fn nth_param(function: FunctionValue<'_>, index: u32) -> Result<BasicValueEnum<'_>, CodegenError> {
+    function.get_nth_param(index).ok_or_else(|| {
+        CodegenError::new(format!(
+            "internal compiler error: {} has no parameter {index}",
+            function.get_name().to_string_lossy()
+        ))
+    })
+}
+
+/// Integer operations that can overflow an i64
+#[derive(Clone, Copy)]
+enum IntArithmetic {
+    Add,
+    Subtract,
+    Multiply,
+}
@@ -0,0 +1,425 @@
+use crate::ast::{
+    BinaryOperator, Call, FString, FStringPart, Function, LiteralValue, Node, UnaryOperator,
+};
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use std::collections::{HashMap, HashSet};
+
+/// What constant folding can rely on at some point in a program
+pub struct Scope<'a> {
+    /// Module constants the code there can read
+    pub constants: &'a HashMap<String, LiteralValue>,
+    /// Whether the code binds `name` itself, hiding the builtin or constant of that name
+    pub shadows: &'a dyn Fn(&str) -> bool,
+}
+
+/// Module-level names that hold the same literal for the whole program
+///
+/// A name qualifies when its only binding in the module body is a top-level
+/// assignment of a constant expression, so every function that reads it sees
+/// that value. Functions get their own scope and do not count. A function
+/// called before the assignment runs would see the name unbound instead, so
+/// names such a function may read do not qualify either.
+pub fn module_constants(statements: &[Node]) -> HashMap<String, LiteralValue> {
+    let mut bindings = HashMap::new();
+    for statement in statements {
+        count_bindings(statement, &mut bindings);
+    }
+    // Constants may be assigned in any order, so they cannot refer to each other
+    let scope = Scope {
+        constants: &HashMap::new(),
+        shadows: &|name: &str| bindings.contains_key(name),
+    };
+    let functions: HashMap<&str, &Function> = statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Node::Function(function) => Some((function.name.as_str(), function)),
+            _ => None,
+        })
+        .collect();
+
+    let mut constants = HashMap::new();
+    // Names read by the functions called so far
+    let mut read_early = HashSet::new();
+    for statement in statements {
+        // A `def` only binds its name; the body runs when it is called
+        if !matches!(statement, Node::Function(_)) {
+            read_early.extend(names_read_by_calls(statement, &functions));
+        }
+        let Node::Assignment(assignment) = statement else {
+            continue;
+        };
+        let Some(value) = fold(&assignment.value, &scope) else {
+            continue;
+        };
+        for name in assignment.names() {
+            if bindings.get(name) == Some(&1) && !read_early.contains(name) {
+                constants.insert(name.to_string(), value.clone());
+            }
+        }
+    }
+    constants
+}
+
+/// Module names the functions `statement` calls may read, also through the
+/// functions those call in turn
+fn names_read_by_calls(statement: &Node, functions: &HashMap<&str, &Function>) -> HashSet<String> {
+    let mut pending = Vec::new();
+    walk(statement, &mut |node| {
+        if let Node::Call(call) = node {
+            pending.push(call.callee.clone());
+        }
+    });
+
+    let mut called = HashSet::new();
+    let mut read = HashSet::new();
+    while let Some(callee) = pending.pop() {
+        let Some(function) = functions.get(callee.as_str()) else {
+            continue;
+        };
+        if !called.insert(callee) {
+            continue;
+        }
+        let locals = local_names(function);
+        let mut read_name = |name: &str| {
+            // `math.pi` and `text.upper()` read `math` and `text`
+            let base = name.split('.').next().unwrap_or(name);
+            if !locals.contains(base) {
+                read.insert(base.to_string());
+            }
+        };
+        walk(&function.body, &mut |node| match node {
+            Node::Identifier(identifier) => read_name(&identifier.name),
+            Node::Call(call) => {
+                if call.callee.contains('.') {
+                    read_name(&call.callee);
+                }
+                pending.push(call.callee.clone());
+            }
+            _ => {}
+        });
+    }
+    read
+}
+
+/// Visit `node` and every node below it, including f-string replacement fields
+fn walk(node: &Node, visit: &mut dyn FnMut(&Node)) {
+    fn walk_all(nodes: &[Node], visit: &mut dyn FnMut(&Node)) {
+        for node in nodes {
+            walk(node, visit);
+        }
+    }
+
+    visit(node);
+    match node {
+        Node::Program(program) => walk_all(&program.statements, visit),
+        Node::Block(block) => walk_all(&block.statements, visit),
+        Node::Function(function) => walk(&function.body, visit),
+        Node::Assignment(assignment) => {
+            walk_all(&assignment.targets, visit);
+            walk(&assignment.value, visit);
+        }
+        Node::If(if_stmt) => {
+            walk(&if_stmt.condition, visit);
+            walk(&if_stmt.then_branch, visit);
+            if let Some(else_branch) = &if_stmt.else_branch {
+                walk(else_branch, visit);
+            }
+        }
+        Node::While(while_stmt) => {
+            walk(&while_stmt.condition, visit);
+            walk(&while_stmt.body, visit);
+        }
+        Node::Return(return_stmt) => {
+            if let Some(value) = &return_stmt.value {
+                walk(value, visit);
+            }
+        }
+        Node::ExpressionStatement(expr_stmt) => walk(&expr_stmt.expression, visit),
+        Node::Binary(binary) => {
+            walk(&binary.left, visit);
+            walk(&binary.right, visit);
+        }
+        Node::Unary(unary) => walk(&unary.operand, visit),
+        Node::Call(call) => {
+            walk_all(&call.arguments, visit);
+            for (_, value) in &call.keywords {
+                walk(value, visit);
+            }
+        }
+        Node::Subscript(subscript) => {
+            walk(&subscript.value, visit);
+            walk(&subscript.index, visit);
+        }
+        Node::Await(await_expr) => walk(&await_expr.value, visit),
+        Node::Starred(starred) => walk(&starred.value, visit),
+        Node::Tuple(tuple) => walk_all(&tuple.elements, visit),
+        Node::List(list) => walk_all(&list.elements, visit),
+        Node::Set(set) => walk_all(&set.elements, visit),
+        Node::Dict(dict) => {
+            for (key, value) in &dict.entries {
+                walk(key, visit);
+                walk(value, visit);
+            }
+        }
+        Node::Literal(literal) => {
+            if let LiteralValue::FString(fstring) = &literal.value {
+                for part in &fstring.parts {
+                    if let FStringPart::Expression(expression) = part {
+                        let program = Parser::new(Lexer::new(expression.trim())).parse_program();
+                        walk(&program, visit);
+                    }
+                }
+            }
+        }
+        Node::Declaration(_) | Node::Import(_) | Node::Identifier(_) => {}
+    }
+}
+
+/// Module attributes Python defines before the program runs, unless the module rebinds them
+///
+/// The program always runs as the main module, also in code from other input
+/// files.
+pub fn module_dunders(statements: &[Node], file_name: &str) -> HashMap<String, LiteralValue> {
+    let mut bindings = HashMap::new();
+    for statement in statements {
+        count_bindings(statement, &mut bindings);
+    }
+    [("__name__", "__main__"), ("__file__", file_name)]
+        .into_iter()
+        .filter(|(name, _)| !bindings.contains_key(*name))
+        .map(|(name, value)| (name.to_string(), LiteralValue::String(value.to_string())))
+        .collect()
+}
+
+/// Names bound by top-level assignments and `def`s among `statements`
+pub fn bound_names(statements: &[Node]) -> HashSet<String> {
+    let mut bindings = HashMap::new();
+    for statement in statements {
+        count_bindings(statement, &mut bindings);
+    }
+    bindings.into_keys().collect()
+}
+
+/// Names local to `function`: its parameters and every name its body binds
+pub fn local_names(function: &Function) -> HashSet<String> {
+    let mut bindings = HashMap::new();
+    count_bindings(&function.body, &mut bindings);
+    function
+        .parameters
+        .iter()
+        .cloned()
+        .chain(bindings.into_keys())
+        .collect()
+}
+
+fn count_bindings(statement: &Node, bindings: &mut HashMap<String, usize>) {
+    match statement {
+        Node::Assignment(assignment) => {
+            for name in assignment.names() {
+                *bindings.entry(name.to_string()).or_default() += 1;
+            }
+        }
+        Node::Function(function) => *bindings.entry(function.name.clone()).or_default() += 1,
+        Node::Block(block) => {
+            for statement in &block.statements {
+                count_bindings(statement, bindings);
+            }
+        }
+        Node::If(if_stmt) => {
+            count_bindings(&if_stmt.then_branch, bindings);
+            if let Some(else_branch) = &if_stmt.else_branch {
+                count_bindings(else_branch, bindings);
+            }
+        }
+        Node::While(while_stmt) => count_bindings(&while_stmt.body, bindings),
+        _ => {}
+    }
+}
+
+/// Text of an f-string whose replacement fields are all compile-time constants
+///
+/// Fields may use literals, the constants in `scope`, integer or string
+/// arithmetic and pure builtins on them. Float fields are left to the
+/// runtime, which formats them differently from Python's `repr`. A field
+/// naming a local that shadows a module constant is also left to the runtime.
+pub fn fold_fstring(fstring: &FString, scope: &Scope) -> Option<String> {
+    let mut text = String::new();
+    for part in &fstring.parts {
+        match part {
+            FStringPart::Literal(literal) => text.push_str(literal),
+            FStringPart::Expression(expression) => {
+                let mut parser = Parser::new(Lexer::new(expression.trim()));
+                let Node::Program(program) = parser.parse_program() else {
+                    return None;
+                };
+                let [Node::ExpressionStatement(statement)] = program.statements.as_slice() else {
+                    return None;
+                };
+                if !parser.errors().is_empty() {
+                    return None;
+                }
+                match fold(&statement.expression, scope)? {
+                    LiteralValue::Integer(value) => text.push_str(&value.to_string()),
+                    LiteralValue::String(value) => text.push_str(&value),
+                    LiteralValue::Boolean(true) => text.push_str("True"),
+                    LiteralValue::Boolean(false) => text.push_str("False"),
+                    LiteralValue::None => text.push_str("None"),
+                    LiteralValue::Float(_) | LiteralValue::FString(_) => return None,
+                }
+            }
+        }
+    }
+    Some(text)
+}
+
+/// Value of a constant expression, `None` when it needs the runtime or would raise
+pub fn fold(expression: &Node, scope: &Scope) -> Option<LiteralValue> {
+    match expression {
+        Node::Identifier(identifier) if !(scope.shadows)(&identifier.name) => {
+            scope.constants.get(&identifier.name).cloned()
+        }
+        Node::Call(call) => fold_builtin_call(call, scope),
+        Node::Literal(literal) => match &literal.value {
+            LiteralValue::FString(_) => None,
+            value => Some(value.clone()),
+        },
+        Node::Unary(unary) if unary.operator == UnaryOperator::Not => {
+            truthiness(&fold(&unary.operand, scope)?).map(|value| LiteralValue::Boolean(!value))
+        }
+        Node::Unary(unary) => match (&unary.operator, fold(&unary.operand, scope)?) {
+            (UnaryOperator::Minus, LiteralValue::Integer(value)) => {
+                value.checked_neg().map(LiteralValue::Integer)
+            }
+            (UnaryOperator::Minus, LiteralValue::Float(value)) => Some(LiteralValue::Float(-value)),
+            (UnaryOperator::Plus, LiteralValue::Integer(value)) => {
+                Some(LiteralValue::Integer(value))
+            }
+            _ => None,
+        },
+        Node::Binary(binary) => {
+            let left = fold(&binary.left, scope)?;
+            let right = fold(&binary.right, scope)?;
+            match (left, right) {
+                (LiteralValue::Integer(left), LiteralValue::Integer(right)) => {
+                    fold_integers(&binary.operator, left, right)
+                }
+                (LiteralValue::String(left), LiteralValue::String(right)) => {
+                    match binary.operator {
+                        BinaryOperator::Add => Some(LiteralValue::String(left + &right)),
+                        BinaryOperator::Equal => Some(LiteralValue::Boolean(left == right)),
+                        BinaryOperator::NotEqual => Some(LiteralValue::Boolean(left != right)),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Integer arithmetic with Python's flooring division, or a comparison
+///
+/// `None` on overflow or division by zero.
+fn fold_integers(operator: &BinaryOperator, left: i64, right: i64) -> Option<LiteralValue> {
+    let value = match operator {
+        BinaryOperator::Add => left.checked_add(right),
+        BinaryOperator::Subtract => left.checked_sub(right),
+        BinaryOperator::Multiply => left.checked_mul(right),
+        BinaryOperator::FloorDivide => {
+            let quotient = left.checked_div(right)?;
+            let remainder = left.checked_rem(right)?;
+            if remainder != 0 && (remainder < 0) != (right < 0) {
+                Some(quotient - 1)
+            } else {
+                Some(quotient)
+            }
+        }
+        BinaryOperator::Modulo => {
+            let remainder = left.checked_rem(right)?;
+            if remainder != 0 && (remainder < 0) != (right < 0) {
+                Some(remainder + right)
+            } else {
+                Some(remainder)
+            }
+        }
+        BinaryOperator::Power => left.checked_pow(u32::try_from(right).ok()?),
+        BinaryOperator::Equal => return Some(LiteralValue::Boolean(left == right)),
+        BinaryOperator::NotEqual => return Some(LiteralValue::Boolean(left != right)),
+        BinaryOperator::Less => return Some(LiteralValue::Boolean(left < right)),
+        BinaryOperator::Greater => return Some(LiteralValue::Boolean(left > right)),
+        BinaryOperator::LessEqual => return Some(LiteralValue::Boolean(left <= right)),
+        BinaryOperator::GreaterEqual => return Some(LiteralValue::Boolean(left >= right)),
+        _ => None,
+    };
+    value.map(LiteralValue::Integer)
+}
+
+/// Result of a call to a pure builtin with constant arguments
+///
+/// Conversions that would raise, such as `int("x")`, are left to the runtime
+/// so the program still fails where CPython would.
+fn fold_builtin_call(call: &Call, scope: &Scope) -> Option<LiteralValue> {
+    if !call.keywords.is_empty() || (scope.shadows)(&call.callee) {
+        return None;
+    }
+    let arguments = call
+        .arguments
+        .iter()
+        .map(|argument| fold(argument, scope))
+        .collect::<Option<Vec<_>>>()?;
+    match (call.callee.as_str(), arguments.as_slice()) {
+        ("len", [LiteralValue::String(value)]) => i64::try_from(value.chars().count())
+            .ok()
+            .map(LiteralValue::Integer),
+        ("abs", [LiteralValue::Integer(value)]) => value.checked_abs().map(LiteralValue::Integer),
+        ("abs", [LiteralValue::Boolean(value)]) => Some(LiteralValue::Integer(i64::from(*value))),
+        ("abs", [LiteralValue::Float(value)]) => Some(LiteralValue::Float(value.abs())),
+        ("int", [LiteralValue::Integer(value)]) => Some(LiteralValue::Integer(*value)),
+        ("int", [LiteralValue::Boolean(value)]) => Some(LiteralValue::Integer(i64::from(*value))),
+        ("int", [LiteralValue::String(value)]) => parse_int(value).map(LiteralValue::Integer),
+        ("int", [LiteralValue::Float(value)])
+            if value.is_finite() && (-9.2e18..9.2e18).contains(value) =>
+        {
+            Some(LiteralValue::Integer(value.trunc() as i64))
+        }
+        ("str", []) => Some(LiteralValue::String(String::new())),
+        ("str", [LiteralValue::String(value)]) => Some(LiteralValue::String(value.clone())),
+        ("str", [LiteralValue::Integer(value)]) => Some(LiteralValue::String(value.to_string())),
+        ("str", [LiteralValue::Boolean(value)]) => Some(LiteralValue::String(
+            if *value { "True" } else { "False" }.to_string(),
+        )),
+        ("str", [LiteralValue::None]) => Some(LiteralValue::String("None".to_string())),
+        ("bool", []) => Some(LiteralValue::Boolean(false)),
+        ("bool", [value]) => truthiness(value).map(LiteralValue::Boolean),
+        _ => None,
+    }
+}
+
+/// `int(text)` as CPython parses it: surrounding whitespace, a sign and
+/// single underscores between digits are allowed
+fn parse_int(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let digits = text.strip_prefix(['+', '-']).unwrap_or(text);
+    if digits.is_empty()
+        || digits.starts_with('_')
+        || digits.ends_with('_')
+        || digits.contains("__")
+        || !digits.chars().all(|ch| ch.is_ascii_digit() || ch == '_')
+    {
+        return None;
+    }
+    text.replace('_', "").parse().ok()
+}
+
+fn truthiness(value: &LiteralValue) -> Option<bool> {
+    match value {
+        LiteralValue::Integer(value) => Some(*value != 0),
+        LiteralValue::Float(value) => Some(*value != 0.0),
+        LiteralValue::String(value) => Some(!value.is_empty()),
+        LiteralValue::Boolean(value) => Some(*value),
+        LiteralValue::None => Some(false),
+        LiteralValue::FString(_) => None,
+    }
+}
@@ -0,0 +1,36 @@
+use crate::ast::Node;
+use std::collections::BTreeSet;
+
+/// Source lines holding a statement, the lines a coverage report accounts for
+///
+/// Function bodies are included, so a function that is never called shows
+/// its lines as not executed.
+pub fn statement_lines(statements: &[Node]) -> BTreeSet<usize> {
+    let mut lines = BTreeSet::new();
+    for statement in statements {
+        collect_lines(statement, &mut lines);
+    }
+    lines
+}
+
+fn collect_lines(statement: &Node, lines: &mut BTreeSet<usize>) {
+    if let Some(line) = statement.line() {
+        lines.insert(line);
+    }
+    match statement {
+        Node::Block(block) => {
+            for statement in &block.statements {
+                collect_lines(statement, lines);
+            }
+        }
+        Node::If(if_stmt) => {
+            collect_lines(&if_stmt.then_branch, lines);
+            if let Some(else_branch) = &if_stmt.else_branch {
+                collect_lines(else_branch, lines);
+            }
+        }
+        Node::While(while_stmt) => collect_lines(&while_stmt.body, lines),
+        Node::Function(function) => collect_lines(&function.body, lines),
+        _ => {}
+    }
+}
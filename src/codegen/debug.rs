@@ -0,0 +1,105 @@
+use inkwell::context::Context;
+use inkwell::debug_info::{
+    AsDIScope, DICompileUnit, DIFlags, DIFlagsConstants, DILocation, DIScope, DWARFEmissionKind,
+    DWARFSourceLanguage, DebugInfoBuilder,
+};
+use inkwell::module::{FlagBehavior, Module};
+use inkwell::values::FunctionValue;
+use std::path::Path;
+
+/// Line-table debug info mapping generated instructions to Python source lines
+///
+/// Every function gets a subprogram and every statement a location, which
+/// shows up in textual IR as `!dbg` attachments and in object files as a
+/// DWARF line table.
+pub struct DebugInfo<'ctx> {
+    builder: DebugInfoBuilder<'ctx>,
+    compile_unit: DICompileUnit<'ctx>,
+    /// Subprogram of the function currently being generated
+    pub scope: DIScope<'ctx>,
+}
+
+impl<'ctx> DebugInfo<'ctx> {
+    pub fn new(context: &'ctx Context, module: &Module<'ctx>, optimized: bool) -> Self {
+        let source = module.get_source_file_name().to_string_lossy().into_owned();
+        let path = Path::new(&source);
+        let file_name = path
+            .file_name()
+            .map_or(source.clone(), |name| name.to_string_lossy().into_owned());
+        let directory = path
+            .parent()
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        module.add_basic_value_flag(
+            "Debug Info Version",
+            FlagBehavior::Warning,
+            context.i32_type().const_int(3, false),
+        );
+        let (builder, compile_unit) = module.create_debug_info_builder(
+            true,
+            DWARFSourceLanguage::Python,
+            &file_name,
+            &directory,
+            &crate::codegen::build_info::compiler_ident(),
+            optimized,
+            "",
+            0,
+            "",
+            DWARFEmissionKind::LineTablesOnly,
+            0,
+            false,
+            false,
+            "",
+            "",
+        );
+        let scope = compile_unit.as_debug_info_scope();
+
+        DebugInfo {
+            builder,
+            compile_unit,
+            scope,
+        }
+    }
+
+    /// Attach a subprogram for `name`, defined on `line`, to `function`
+    pub fn create_function_scope(
+        &self,
+        function: FunctionValue<'ctx>,
+        name: &str,
+        line: usize,
+        optimized: bool,
+    ) -> DIScope<'ctx> {
+        let file = self.compile_unit.get_file();
+        let subroutine_type = self
+            .builder
+            .create_subroutine_type(file, None, &[], DIFlags::ZERO);
+        let linkage_name = function.get_name().to_string_lossy().into_owned();
+        let subprogram = self.builder.create_function(
+            self.compile_unit.as_debug_info_scope(),
+            name,
+            Some(&linkage_name),
+            file,
+            line as u32,
+            subroutine_type,
+            true,
+            true,
+            line as u32,
+            DIFlags::ZERO,
+            optimized,
+        );
+        function.set_subprogram(subprogram);
+        subprogram.as_debug_info_scope()
+    }
+
+    /// Location of `line` in the current function
+    pub fn location(&self, context: &'ctx Context, line: usize) -> DILocation<'ctx> {
+        self.builder
+            .create_debug_location(context, line as u32, 0, self.scope, None)
+    }
+
+    /// Resolve pending debug metadata before the module is verified or emitted
+    pub fn finalize(&self) {
+        self.builder.finalize();
+    }
+}
@@ -0,0 +1,74 @@
+use inkwell::builder::BuilderError;
+use std::fmt;
+
+/// Why code could not be generated for a program
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodegenError {
+    pub message: String,
+    /// Source line (1-based) of the statement being compiled, when known
+    pub line: Option<usize>,
+    /// Module of that statement, when several input files are compiled together
+    pub module: Option<String>,
+}
+
+impl CodegenError {
+    pub fn new(message: impl Into<String>) -> Self {
+        CodegenError {
+            message: message.into(),
+            line: None,
+            module: None,
+        }
+    }
+
+    /// Attribute the error to `line`, unless a nested statement already claimed it
+    pub fn at_line(mut self, line: usize) -> Self {
+        self.line.get_or_insert(line);
+        self
+    }
+
+    /// Attribute the error to a statement of `module`
+    pub fn in_module(mut self, module: &str) -> Self {
+        self.module.get_or_insert_with(|| module.to_string());
+        self
+    }
+}
+
+/// Code that compiles but is probably a mistake, such as a statement with no effect
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodegenWarning {
+    /// Source line (1-based) of the statement, or 0 if it is unknown
+    pub line: usize,
+    pub message: String,
+    /// Module of the statement, when several input files are compiled together
+    pub module: Option<String>,
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CodegenError {}
+
+impl From<String> for CodegenError {
+    fn from(message: String) -> Self {
+        CodegenError::new(message)
+    }
+}
+
+impl From<&str> for CodegenError {
+    fn from(message: &str) -> Self {
+        CodegenError::new(message)
+    }
+}
+
+/// The builder only fails when pycc misuses it, e.g. positioning it nowhere,
+/// so these are bugs in pycc rather than in the program
+impl From<BuilderError> for CodegenError {
+    fn from(error: BuilderError) -> Self {
+        CodegenError::new(format!(
+            "internal compiler error: LLVM builder call failed: {error}"
+        ))
+    }
+}
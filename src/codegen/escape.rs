@@ -0,0 +1,79 @@
+use crate::ast::{List, Node, Tuple};
+use std::collections::HashSet;
+
+/// Local variables of a function whose value may outlive the function's frame
+///
+/// A value escapes when it is returned, either directly or through a call that
+/// may hand an argument back. Escape propagates backwards through assignments
+/// (`y = x; return y` makes `x` escape too). Operands of `+`, `*` and f-string
+/// placeholders are copied into a fresh string, so they never escape.
+pub fn escaping_variables(body: &Node) -> HashSet<String> {
+    let mut escaping = HashSet::new();
+
+    // Iterate to a fixed point so assignment chains in any order are covered
+    loop {
+        let before = escaping.len();
+        collect_statement(body, &mut escaping);
+        if escaping.len() == before {
+            return escaping;
+        }
+    }
+}
+
+fn collect_statement(statement: &Node, escaping: &mut HashSet<String>) {
+    match statement {
+        Node::Return(return_stmt) => {
+            if let Some(value) = &return_stmt.value {
+                collect_roots(value, escaping);
+            }
+        }
+        Node::Assignment(assignment) if assignment.names().any(|name| escaping.contains(name)) => {
+            collect_roots(&assignment.value, escaping);
+        }
+        Node::Block(block) => {
+            for statement in &block.statements {
+                collect_statement(statement, escaping);
+            }
+        }
+        Node::If(if_stmt) => {
+            collect_statement(&if_stmt.then_branch, escaping);
+            if let Some(else_branch) = &if_stmt.else_branch {
+                collect_statement(else_branch, escaping);
+            }
+        }
+        Node::While(while_stmt) => collect_statement(&while_stmt.body, escaping),
+        // Nested definitions have their own frames
+        _ => {}
+    }
+}
+
+/// Variables whose storage an expression's value may share
+fn collect_roots(expression: &Node, escaping: &mut HashSet<String>) {
+    match expression {
+        Node::Identifier(identifier) => {
+            escaping.insert(identifier.name.clone());
+        }
+        Node::Call(call) => {
+            for argument in call
+                .arguments
+                .iter()
+                .chain(call.keywords.iter().map(|(_, value)| value))
+            {
+                collect_roots(argument, escaping);
+            }
+        }
+        Node::Starred(starred) => collect_roots(&starred.value, escaping),
+        // Containers share the storage of their items
+        Node::Tuple(Tuple { elements }) | Node::List(List { elements }) => {
+            for element in elements {
+                collect_roots(element, escaping);
+            }
+        }
+        Node::Dict(dict) => {
+            for (_, value) in &dict.entries {
+                collect_roots(value, escaping);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,5 @@
+/// Environment variable that makes compiled programs print heap statistics at exit
+pub const HEAP_STATS_VARIABLE: &str = "PYCC_HEAP_STATS";
+
+/// Start of each summary line the runtime writes to stderr
+pub const SUMMARY_PREFIX: &str = "pycc heap:";
@@ -0,0 +1,69 @@
+use crate::ast::{LiteralValue, Node};
+use crate::codegen::error::CodegenWarning;
+use crate::codegen::options::LocalModule;
+
+/// Warnings about suspicious but valid code, such as statements with no effect
+///
+/// This only looks at the syntax tree, so it runs before code generation and
+/// builds reusing a cached object still report them. `modules` says which
+/// top-level statements came from which file, like `set_local_modules`.
+pub fn warnings(program: &Node, modules: &[LocalModule]) -> Vec<CodegenWarning> {
+    let mut warnings = Vec::new();
+    let Node::Program(program) = program else {
+        return warnings;
+    };
+    let mut spans = modules
+        .iter()
+        .flat_map(|module| std::iter::repeat_n(&module.name, module.statements));
+    for statement in &program.statements {
+        let module = spans.next().cloned();
+        check_statement(statement, &module, &mut warnings);
+    }
+    warnings
+}
+
+fn check_statement(statement: &Node, module: &Option<String>, warnings: &mut Vec<CodegenWarning>) {
+    match statement {
+        Node::ExpressionStatement(expr_stmt) => {
+            if has_no_effect(&expr_stmt.expression) {
+                warnings.push(CodegenWarning {
+                    line: expr_stmt.line,
+                    message: "statement has no effect".to_string(),
+                    module: module.clone(),
+                });
+            }
+        }
+        Node::Block(block) => {
+            for statement in &block.statements {
+                check_statement(statement, module, warnings);
+            }
+        }
+        Node::If(if_stmt) => {
+            check_statement(&if_stmt.then_branch, module, warnings);
+            if let Some(else_branch) = &if_stmt.else_branch {
+                check_statement(else_branch, module, warnings);
+            }
+        }
+        Node::While(while_stmt) => check_statement(&while_stmt.body, module, warnings),
+        Node::Function(function) => check_statement(&function.body, module, warnings),
+        _ => {}
+    }
+}
+
+/// Whether an expression statement is pointless, such as `1 + 2` alone on a line
+///
+/// Only literals, names and operators on them qualify. Calls and f-string
+/// fields may have side effects, and a bare string is the usual way to write a
+/// block comment.
+fn has_no_effect(expression: &Node) -> bool {
+    match expression {
+        Node::Literal(literal) => !matches!(
+            literal.value,
+            LiteralValue::String(_) | LiteralValue::FString(_)
+        ),
+        Node::Identifier(_) => true,
+        Node::Binary(binary) => has_no_effect(&binary.left) && has_no_effect(&binary.right),
+        Node::Unary(unary) => has_no_effect(&unary.operand),
+        _ => false,
+    }
+}
@@ -1,4 +1,19 @@
+pub mod build;
+pub mod build_info;
 #[allow(clippy::module_inception)]
 pub mod codegen;
+pub mod constants;
+pub mod coverage;
+pub mod debug;
+pub mod error;
+pub mod escape;
+pub mod heap_stats;
+pub mod lint;
+pub mod options;
+pub mod value;
 
 pub use codegen::CodeGenerator;
+pub use error::{CodegenError, CodegenWarning};
+pub use options::{
+    CodeModel, CodegenOptions, Entry, Fallback, LocalModule, LtoMode, RelocModel, Sanitizer,
+};
@@ -0,0 +1,510 @@
+use inkwell::OptimizationLevel;
+
+/// Link-time optimization mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LtoMode {
+    #[default]
+    Off,
+    Thin,
+    Full,
+}
+
+impl LtoMode {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "off" | "no" | "false" => Ok(LtoMode::Off),
+            "thin" => Ok(LtoMode::Thin),
+            "full" | "fat" | "yes" | "true" => Ok(LtoMode::Full),
+            _ => Err(format!(
+                "Invalid LTO mode '{value}' (expected off, thin or full)"
+            )),
+        }
+    }
+
+    pub fn is_enabled(self) -> bool {
+        self != LtoMode::Off
+    }
+
+    /// Flag passed to the linker driver to run LTO over the emitted bitcode
+    pub fn linker_flag(self) -> Option<&'static str> {
+        match self {
+            LtoMode::Off => None,
+            LtoMode::Thin => Some("-flto=thin"),
+            LtoMode::Full => Some("-flto=full"),
+        }
+    }
+}
+
+/// Runtime sanitizer compiled into the program
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sanitizer {
+    #[default]
+    None,
+    Address,
+}
+
+impl Sanitizer {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "none" => Ok(Sanitizer::None),
+            "address" => Ok(Sanitizer::Address),
+            _ => Err(format!(
+                "Invalid sanitizer '{value}' (expected none or address)"
+            )),
+        }
+    }
+
+    /// Flag passed to the linker driver to pull in the sanitizer runtime
+    pub fn linker_flag(self) -> Option<&'static str> {
+        match self {
+            Sanitizer::None => None,
+            Sanitizer::Address => Some("-fsanitize=address"),
+        }
+    }
+}
+
+/// Code model: how far apart code and data may be placed in memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CodeModel {
+    /// The target's default, small on x86_64 and aarch64
+    #[default]
+    Default,
+    Small,
+    Medium,
+    Large,
+}
+
+impl CodeModel {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "default" => Ok(CodeModel::Default),
+            "small" => Ok(CodeModel::Small),
+            "medium" => Ok(CodeModel::Medium),
+            "large" => Ok(CodeModel::Large),
+            _ => Err(format!(
+                "Invalid code model '{value}' (expected small, medium or large)"
+            )),
+        }
+    }
+
+    pub fn to_llvm(self) -> inkwell::targets::CodeModel {
+        match self {
+            CodeModel::Default => inkwell::targets::CodeModel::Default,
+            CodeModel::Small => inkwell::targets::CodeModel::Small,
+            CodeModel::Medium => inkwell::targets::CodeModel::Medium,
+            CodeModel::Large => inkwell::targets::CodeModel::Large,
+        }
+    }
+}
+
+/// Relocation model of the emitted objects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RelocModel {
+    /// The target's default: static on Linux, PIC on macOS
+    #[default]
+    Default,
+    Static,
+    Pic,
+}
+
+impl RelocModel {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "default" => Ok(RelocModel::Default),
+            "static" => Ok(RelocModel::Static),
+            "pic" => Ok(RelocModel::Pic),
+            _ => Err(format!(
+                "Invalid relocation model '{value}' (expected static or pic)"
+            )),
+        }
+    }
+
+    pub fn to_llvm(self) -> inkwell::targets::RelocMode {
+        match self {
+            RelocModel::Default => inkwell::targets::RelocMode::Default,
+            RelocModel::Static => inkwell::targets::RelocMode::Static,
+            RelocModel::Pic => inkwell::targets::RelocMode::PIC,
+        }
+    }
+}
+
+/// What to do with a program the compiler cannot lower
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Fallback {
+    /// Report the error and stop
+    #[default]
+    None,
+    /// Build an executable that runs the whole source with an embedded CPython
+    ///
+    /// Nothing is compiled natively then, not even the supported statements.
+    Interp,
+}
+
+impl Fallback {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "none" => Ok(Fallback::None),
+            "interp" if cfg!(feature = "python") => Ok(Fallback::Interp),
+            "interp" => {
+                Err("--fallback interp needs pycc built with the `python` feature".to_string())
+            }
+            _ => Err(format!(
+                "Invalid fallback '{value}' (expected none or interp)"
+            )),
+        }
+    }
+}
+
+/// Operating system family programs are linked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Linux,
+    MacOs,
+}
+
+impl Platform {
+    /// The platform pycc runs on, which is also the one it links for
+    pub fn host() -> Self {
+        if cfg!(target_os = "macos") {
+            Platform::MacOs
+        } else {
+            Platform::Linux
+        }
+    }
+
+    /// Linker driver flags every executable needs
+    ///
+    /// On Linux objects use the static relocation model, so the executable
+    /// cannot be position independent, and libm is a separate library. Apple
+    /// platforms only allow PIE executables, which LLVM's default PIC objects
+    /// link into, and libSystem (linked by default) includes libm.
+    pub fn linker_flags(self) -> &'static [&'static str] {
+        match self {
+            Platform::Linux => &["-no-pie", "-lm"],
+            Platform::MacOs => &[],
+        }
+    }
+
+    /// Linker driver flag that drops unreferenced sections
+    pub fn dead_strip_flag(self) -> &'static str {
+        match self {
+            Platform::Linux => "-Wl,--gc-sections",
+            Platform::MacOs => "-Wl,-dead_strip",
+        }
+    }
+
+    /// Linker driver flag that only records shared libraries the program uses
+    pub fn as_needed_flag(self) -> &'static str {
+        match self {
+            Platform::Linux => "-Wl,--as-needed",
+            // ld64 rejects --as-needed; -dead_strip_dylibs drops unused dylibs instead
+            Platform::MacOs => "-Wl,-dead_strip_dylibs",
+        }
+    }
+
+    /// Linker driver flag that removes the symbol table from the executable
+    pub fn strip_flag(self) -> &'static str {
+        match self {
+            Platform::Linux => "-s",
+            // ld64 ignores -s; -x drops the local symbols, which is all it can remove
+            Platform::MacOs => "-Wl,-x",
+        }
+    }
+
+    /// Symbol of the C library's `FILE *` global for `stdin`, `stdout` or `stderr`
+    pub fn stream_symbol(self, stream: &str) -> String {
+        match self {
+            Platform::Linux => stream.to_string(),
+            Platform::MacOs => format!("__{stream}p"),
+        }
+    }
+
+    /// `clock_gettime` clock id of CLOCK_MONOTONIC
+    pub fn clock_monotonic(self) -> u64 {
+        match self {
+            Platform::Linux => 1,
+            Platform::MacOs => 6,
+        }
+    }
+}
+
+/// Function that becomes the process entry point, as `module:function`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// File stem of the module defining the function
+    pub module: String,
+    pub function: String,
+}
+
+impl Entry {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.split_once(':') {
+            Some((module, function)) if !module.is_empty() && !function.is_empty() => Ok(Entry {
+                module: module.to_string(),
+                function: function.to_string(),
+            }),
+            _ => Err(format!(
+                "Invalid entry '{value}' (expected module:function)"
+            )),
+        }
+    }
+}
+
+/// An input file compiled into the program, as a module named after its stem
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalModule {
+    pub name: String,
+    /// Number of top-level statements it contributes, in input order
+    pub statements: usize,
+}
+
+/// Settings that control how the LLVM module is lowered to machine code
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodegenOptions {
+    /// Optimization level (0-3)
+    pub optimization: u8,
+    /// Optimize for size instead of speed: 0 for speed, 1 for -Os, 2 for -Oz
+    pub size_level: u8,
+    /// Target CPU name, e.g. "generic" or "native"
+    pub target_cpu: String,
+    /// Comma-separated target feature list, e.g. "+avx2,-sse4a"
+    pub target_features: String,
+    pub lto: LtoMode,
+    pub code_model: CodeModel,
+    pub reloc_model: RelocModel,
+    /// Give annotated functions native LLVM signatures instead of i64 everywhere
+    pub typed: bool,
+    /// Trap on signed integer overflow instead of wrapping
+    pub overflow_checks: bool,
+    /// Inliner cost threshold applied to Python functions, LLVM's default when unset
+    pub inline_threshold: Option<u32>,
+    /// Attach the Python source line of each statement as debug info
+    pub debug_info: bool,
+    /// Count executed lines and write an lcov report at exit
+    pub coverage: bool,
+    /// Count calls and time per Python function and print a table at exit
+    pub instrument_profile: bool,
+    /// Log each executed statement and the values it assigns to stderr
+    pub trace: bool,
+    /// Count executed statements and write the total to `$PYCC_STEPS_FILE` at exit
+    pub count_steps: bool,
+    /// Instrument memory accesses and link the sanitizer runtime
+    pub sanitize: Sanitizer,
+    /// Run programs that fail to compile with an embedded interpreter instead
+    pub fallback: Fallback,
+    /// Call CPython for functions and modules pycc cannot compile natively
+    pub python_interop: bool,
+    /// Call this function with the command-line arguments after the module code runs
+    pub entry: Option<Entry>,
+    /// Value of `__file__` in the program, the module's source file name when unset
+    pub file_name: Option<String>,
+    /// Link the C library into a self-contained executable, against musl when available
+    pub static_link: bool,
+    /// Remove the symbol table from the linked executable
+    pub strip: bool,
+    /// Leave the build timestamp out of the embedded build info
+    pub deterministic: bool,
+    /// Extra arguments for the linker driver, after pycc's own
+    pub link_args: Vec<String>,
+    /// Where to write the IR of a module the LLVM verifier rejects
+    pub keep_invalid_ir: Option<String>,
+}
+
+impl Default for CodegenOptions {
+    fn default() -> Self {
+        CodegenOptions {
+            optimization: 2,
+            size_level: 0,
+            target_cpu: "generic".to_string(),
+            target_features: String::new(),
+            lto: LtoMode::Off,
+            code_model: CodeModel::Default,
+            reloc_model: RelocModel::Default,
+            typed: false,
+            overflow_checks: false,
+            inline_threshold: None,
+            debug_info: false,
+            coverage: false,
+            instrument_profile: false,
+            trace: false,
+            count_steps: false,
+            sanitize: Sanitizer::None,
+            fallback: Fallback::None,
+            python_interop: false,
+            entry: None,
+            file_name: None,
+            static_link: false,
+            strip: false,
+            deterministic: false,
+            link_args: Vec::new(),
+            keep_invalid_ir: None,
+        }
+    }
+}
+
+impl CodegenOptions {
+    /// Parse an `-O` level into `(optimization, size_level)`
+    ///
+    /// `s` and `z` optimize for size like clang: -O2 with smaller inlining
+    /// and unrolling thresholds, `z` more aggressively.
+    pub fn parse_optimization(value: &str) -> Result<(u8, u8), String> {
+        match value {
+            "s" => Ok((2, 1)),
+            "z" => Ok((2, 2)),
+            _ => match value.parse::<u8>() {
+                Ok(level) if level <= 3 => Ok((level, 0)),
+                _ => Err(format!(
+                    "Invalid optimization level '{value}' (expected 0-3, s or z)"
+                )),
+            },
+        }
+    }
+
+    /// `-O` flag naming the optimization level, as passed to the linker driver
+    pub fn optimization_flag(&self) -> String {
+        match self.size_level {
+            0 => format!("-O{}", self.optimization),
+            1 => "-Os".to_string(),
+            _ => "-Oz".to_string(),
+        }
+    }
+
+    pub fn optimization_level(&self) -> OptimizationLevel {
+        match self.optimization {
+            0 => OptimizationLevel::None,
+            1 => OptimizationLevel::Less,
+            2 => OptimizationLevel::Default,
+            _ => OptimizationLevel::Aggressive,
+        }
+    }
+
+    /// New pass manager pipeline run over the module before emission
+    pub fn pass_pipeline(&self) -> String {
+        let pipeline = match (self.optimization, self.size_level) {
+            (0, _) => "function(dse),globaldce".to_string(),
+            (_, 1) => "default<Os>".to_string(),
+            (_, 2..) => "default<Oz>".to_string(),
+            (level, _) => format!("default<O{}>", level.min(3)),
+        };
+        match self.sanitize {
+            Sanitizer::None => pipeline,
+            // Instrument after optimizing, as clang does
+            Sanitizer::Address => format!("{pipeline},asan"),
+        }
+    }
+
+    /// Flags that let the linker resolve libpython, if the program may need it
+    ///
+    /// The as-needed flag keeps programs that never call CPython free of libpython.
+    pub fn python_linker_flags(&self) -> Result<Vec<String>, String> {
+        if self.fallback == Fallback::None && !self.python_interop {
+            return Ok(Vec::new());
+        }
+        let output = std::process::Command::new("python3-config")
+            .args(["--embed", "--ldflags"])
+            .output()
+            .map_err(|e| format!("Failed to run python3-config: {e}"))?;
+        if !output.status.success() {
+            return Err("python3-config could not locate libpython".to_string());
+        }
+        let mut flags = vec![Platform::host().as_needed_flag().to_string()];
+        flags.extend(
+            String::from_utf8_lossy(&output.stdout)
+                .split_whitespace()
+                .map(str::to_string),
+        );
+        Ok(flags)
+    }
+
+    /// Command that links `object_file` into the executable `output_file`
+    pub fn linker_command(
+        &self,
+        object_file: &str,
+        output_file: &str,
+    ) -> Result<std::process::Command, String> {
+        if self.static_link {
+            self.check_static_link()?;
+        }
+        // LTO needs an LLVM-aware linker driver to consume the bitcode
+        let mut command = if let Some(lto_flag) = self.lto.linker_flag() {
+            let mut command = std::process::Command::new("clang");
+            command.args([lto_flag, &self.optimization_flag()]);
+            command
+        } else if self.static_link && !cfg!(target_env = "musl") && find_program("musl-gcc") {
+            // The wrapper swaps in musl's headers, libc.a and CRT startup files
+            std::process::Command::new("musl-gcc")
+        } else {
+            std::process::Command::new("cc")
+        };
+        if self.static_link {
+            command.arg("-static");
+        }
+        if let Some(sanitizer_flag) = self.sanitize.linker_flag() {
+            command.arg(sanitizer_flag);
+        }
+        if self.size_level > 0 {
+            command.arg(Platform::host().dead_strip_flag());
+        }
+        if self.strip {
+            command.arg(Platform::host().strip_flag());
+        }
+        command
+            .args([object_file, "-o", output_file])
+            .args(Platform::host().linker_flags())
+            .args(self.python_linker_flags()?)
+            .args(&self.link_args);
+        Ok(command)
+    }
+
+    /// Reject options that need shared libraries when linking statically
+    fn check_static_link(&self) -> Result<(), String> {
+        if Platform::host() == Platform::MacOs {
+            return Err("--static is not supported on macOS, which has no static libc".to_string());
+        }
+        if self.python_interop || self.fallback != Fallback::None {
+            return Err(
+                "--static cannot be combined with --python-interop or --fallback interp, which load libpython"
+                    .to_string(),
+            );
+        }
+        if self.sanitize != Sanitizer::None {
+            return Err("--static cannot be combined with --sanitize".to_string());
+        }
+        Ok(())
+    }
+
+    /// Stable textual summary of every option that affects the generated code
+    pub fn fingerprint(&self) -> String {
+        format!(
+            "O{};size={};cpu={};features={};lto={:?};code_model={:?};reloc_model={:?};typed={};overflow_checks={};inline_threshold={:?};debug_info={};coverage={};instrument_profile={};trace={};count_steps={};sanitize={:?};fallback={:?};python_interop={};entry={:?};file_name={:?};deterministic={}",
+            self.optimization,
+            self.size_level,
+            self.target_cpu,
+            self.target_features,
+            self.lto,
+            self.code_model,
+            self.reloc_model,
+            self.typed,
+            self.overflow_checks,
+            self.inline_threshold,
+            self.debug_info,
+            self.coverage,
+            self.instrument_profile,
+            self.trace,
+            self.count_steps,
+            self.sanitize,
+            self.fallback,
+            self.python_interop,
+            self.entry,
+            self.file_name,
+            self.deterministic
+        )
+    }
+}
+
+/// Whether an executable named `name` is on the `PATH`
+pub(crate) fn find_program(name: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|path| {
+        std::env::split_paths(&path).any(|directory| directory.join(name).is_file())
+    })
+}
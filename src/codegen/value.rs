@@ -0,0 +1,106 @@
+use crate::typecheck::Type;
+use inkwell::types::BasicTypeEnum;
+use inkwell::values::{BasicValueEnum, FunctionValue, PointerValue};
+
+/// A compiled expression: its LLVM value and the Python type it represents
+///
+/// The LLVM representation alone is ambiguous (an i64 may be an int, `None`
+/// or an untyped parameter), so the Python type travels alongside it.
+#[derive(Debug, Clone, Copy)]
+pub struct CompilerValue<'ctx> {
+    pub llvm_value: BasicValueEnum<'ctx>,
+    pub py_type: Type,
+}
+
+impl<'ctx> CompilerValue<'ctx> {
+    pub fn new(llvm_value: BasicValueEnum<'ctx>, py_type: Type) -> Self {
+        CompilerValue {
+            llvm_value,
+            py_type,
+        }
+    }
+
+    /// Wrap a value whose Python type follows from its representation
+    ///
+    /// i1 is a bool, other integers are ints, doubles are floats and pointers
+    /// are strings.
+    pub fn from_llvm(llvm_value: BasicValueEnum<'ctx>) -> Self {
+        let py_type = match llvm_value {
+            BasicValueEnum::IntValue(int_val) if int_val.get_type().get_bit_width() == 1 => {
+                Type::Bool
+            }
+            BasicValueEnum::IntValue(_) => Type::Int,
+            BasicValueEnum::FloatValue(_) => Type::Float,
+            BasicValueEnum::PointerValue(_) => Type::Str,
+            _ => Type::Any,
+        };
+        CompilerValue::new(llvm_value, py_type)
+    }
+}
+
+/// Tag telling runtime helpers such as `pycc_print` what a boxed value holds
+///
+/// A boxed value is a `{ i64 tag, i64 payload }` pair: ints are the payload
+/// itself, bools are 0 or 1, floats are stored by their bits and strings as
+/// their pointer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeTag {
+    None = 0,
+    Bool = 1,
+    Int = 2,
+    Float = 3,
+    Str = 4,
+}
+
+impl TypeTag {
+    pub const ALL: [TypeTag; 5] = [
+        TypeTag::None,
+        TypeTag::Bool,
+        TypeTag::Int,
+        TypeTag::Float,
+        TypeTag::Str,
+    ];
+
+    /// Tag of a compiled value, `None` if it has no boxed representation
+    pub fn of(value: &CompilerValue<'_>) -> Option<TypeTag> {
+        if value.py_type == Type::None {
+            return Some(TypeTag::None);
+        }
+        match value.llvm_value {
+            BasicValueEnum::IntValue(int_val) if int_val.get_type().get_bit_width() == 1 => {
+                Some(TypeTag::Bool)
+            }
+            BasicValueEnum::IntValue(_) => Some(TypeTag::Int),
+            BasicValueEnum::FloatValue(_) => Some(TypeTag::Float),
+            BasicValueEnum::PointerValue(_) => Some(TypeTag::Str),
+            _ => None,
+        }
+    }
+}
+
+/// A variable's stack slot and the type of the value it currently holds
+#[derive(Debug, Clone, Copy)]
+pub struct Variable<'ctx> {
+    pub slot: PointerValue<'ctx>,
+    pub llvm_type: BasicTypeEnum<'ctx>,
+    pub py_type: Type,
+}
+
+/// The LLVM function a Python function name is currently bound to
+#[derive(Debug, Clone)]
+pub struct FunctionBinding<'ctx> {
+    pub value: FunctionValue<'ctx>,
+    pub parameters: Vec<String>,
+    pub return_type: Type,
+}
+
+/// Call and time counters of one compiled Python function
+#[derive(Debug, Clone)]
+pub struct ProfileCounters<'ctx> {
+    pub name: String,
+    pub line: usize,
+    pub calls: PointerValue<'ctx>,
+    pub nanoseconds: PointerValue<'ctx>,
+    /// Calls currently active, so recursive calls are only timed once
+    pub depth: PointerValue<'ctx>,
+}
@@ -0,0 +1,310 @@
+use crate::ast::Node;
+use crate::codegen::CodegenOptions;
+use crate::codegen::build;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Line printed before each example's output, to tell the outputs apart
+const MARKER: &str = "--- pycc doctest example ---";
+
+/// A `>>>` example from a docstring
+#[derive(Debug, Clone, PartialEq)]
+pub struct Example {
+    /// Module, or `module.function`, whose docstring holds the example
+    pub name: String,
+    /// Source line of the `>>>` prompt (1-based), assuming a function's
+    /// docstring starts on the line after its `def`
+    pub line: usize,
+    /// Code after the `>>>` and `...` prompts, one line each
+    pub source: String,
+    /// Expected output, each line ending in a newline; empty when none is expected
+    pub want: String,
+}
+
+impl Example {
+    /// Whether `got` is the output this example expects
+    ///
+    /// For an expected traceback, as in CPython's doctest, only the exception
+    /// line at its end is compared.
+    pub fn matches(&self, got: &str) -> bool {
+        if self.want.starts_with("Traceback (most recent call last):") {
+            return got.starts_with("Traceback (most recent call last):")
+                && got.lines().next_back() == self.want.lines().next_back();
+        }
+        got == self.want
+    }
+
+    /// Failure report in the layout of CPython's doctest
+    pub fn report(&self, file: &Path, got: &str) -> String {
+        let indent =
+            |text: &str| -> String { text.lines().map(|line| format!("    {line}\n")).collect() };
+        let expected = if self.want.is_empty() {
+            "Expected nothing\n".to_string()
+        } else {
+            format!("Expected:\n{}", indent(&self.want))
+        };
+        let got = if got.is_empty() {
+            "Got nothing\n".to_string()
+        } else {
+            format!("Got:\n{}", indent(got))
+        };
+        format!(
+            "File \"{}\", line {}, in {}\nFailed example:\n{}{expected}{got}",
+            file.display(),
+            self.line,
+            self.name,
+            indent(&self.source)
+        )
+    }
+}
+
+/// The examples of the module docstring and of every function's, in source order
+pub fn extract(program: &Node, module: &str) -> Vec<Example> {
+    let mut examples = Vec::new();
+    if let Node::Program(program) = program {
+        if let Some(docstring) = &program.docstring {
+            parse_docstring(docstring, module, 1, &mut examples);
+        }
+        for statement in &program.statements {
+            collect_examples(statement, module, &mut examples);
+        }
+    }
+    examples
+}
+
+fn collect_examples(statement: &Node, module: &str, examples: &mut Vec<Example>) {
+    match statement {
+        Node::Function(function) => {
+            if let Some(docstring) = &function.docstring {
+                let name = format!("{module}.{}", function.name);
+                parse_docstring(docstring, &name, function.line + 1, examples);
+            }
+            collect_examples(&function.body, module, examples);
+        }
+        Node::Block(block) => {
+            for statement in &block.statements {
+                collect_examples(statement, module, examples);
+            }
+        }
+        Node::If(if_stmt) => {
+            collect_examples(&if_stmt.then_branch, module, examples);
+            if let Some(else_branch) = &if_stmt.else_branch {
+                collect_examples(else_branch, module, examples);
+            }
+        }
+        Node::While(while_stmt) => collect_examples(&while_stmt.body, module, examples),
+        _ => {}
+    }
+}
+
+/// Examples of a docstring whose first line is source line `first_line`
+///
+/// An example's expected output runs up to the next blank line or prompt and
+/// loses the indentation of its `>>>`; `<BLANKLINE>` stands for an empty line.
+fn parse_docstring(docstring: &str, name: &str, first_line: usize, examples: &mut Vec<Example>) {
+    let after_prompt = |text: &str| text.strip_prefix(' ').unwrap_or(text).to_string();
+    let lines: Vec<&str> = docstring.lines().collect();
+    let mut i = 0;
+    while i < lines.len() {
+        let trimmed = lines[i].trim_start();
+        let Some(first) = trimmed.strip_prefix(">>>") else {
+            i += 1;
+            continue;
+        };
+        let indent = lines[i].len() - trimmed.len();
+        let line = first_line + i;
+        let mut source = vec![after_prompt(first)];
+        i += 1;
+        while let Some(rest) = lines
+            .get(i)
+            .and_then(|line| line.trim_start().strip_prefix("..."))
+        {
+            source.push(after_prompt(rest));
+            i += 1;
+        }
+        let mut want = String::new();
+        while let Some(output) = lines.get(i)
+            && !output.trim().is_empty()
+            && !output.trim_start().starts_with(">>>")
+        {
+            let output = output.get(indent..).unwrap_or(output.trim_start());
+            if output != "<BLANKLINE>" {
+                want.push_str(output);
+            }
+            want.push('\n');
+            i += 1;
+        }
+        examples.push(Example {
+            name: name.to_string(),
+            line,
+            source: source.join("\n"),
+            want,
+        });
+    }
+}
+
+/// Output of each example when run by CPython (`python3`) after the module code
+///
+/// Examples share the module's namespace and show the `repr` of expression
+/// values other than None, like CPython's doctest. The module runs under its
+/// own name, so code guarded by `if __name__ == "__main__"` does not run.
+pub fn run_interpreted(
+    source: &str,
+    path: &Path,
+    examples: &[Example],
+) -> Result<Vec<String>, String> {
+    let module = path.file_stem().unwrap_or_default().to_string_lossy();
+    let example_sources: Vec<String> = examples
+        .iter()
+        .map(|example| python_string(&format!("{}\n", example.source)))
+        .collect();
+    let driver = format!(
+        r#"import traceback
+namespace = {{"__name__": {name}, "__file__": {file}}}
+exec(compile({source}, {file}, "exec"), namespace)
+for example in [{examples}]:
+    print({marker})
+    try:
+        exec(compile(example, "<doctest>", "single"), namespace)
+    except Exception as error:
+        print("Traceback (most recent call last):")
+        print(traceback.format_exception_only(type(error), error)[-1], end="")
+"#,
+        name = python_string(&module),
+        file = python_string(&path.to_string_lossy()),
+        source = python_string(source),
+        examples = example_sources.join(", "),
+        marker = python_string(MARKER),
+    );
+
+    // The driver holds the whole module, too long for a command-line argument
+    let run = || -> std::io::Result<std::process::Output> {
+        let mut python = Command::new("python3")
+            .arg("-")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = python.stdin.take() {
+            stdin.write_all(driver.as_bytes())?;
+        }
+        python.wait_with_output()
+    };
+    let output = run().map_err(|e| format!("Failed to run python3: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "The module failed to run:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(outputs(
+        &String::from_utf8_lossy(&output.stdout),
+        examples.len(),
+    ))
+}
+
+/// Output of each example when the module and its examples are compiled by pycc
+///
+/// Examples that are expressions print their `repr`, unless they call `print`
+/// or a function annotated to return None. An example that fails at run time
+/// stops the program, so it and the examples after it get no output.
+pub fn run_compiled_examples(
+    program: &Node,
+    source: &str,
+    path: &Path,
+    examples: &[Example],
+) -> Result<Vec<String>, String> {
+    let mut returns_none = vec!["print".to_string()];
+    if let Node::Program(program) = program {
+        returns_none.extend(
+            program
+                .statements
+                .iter()
+                .filter_map(|statement| match statement {
+                    Node::Function(function) if function.return_type.as_deref() == Some("None") => {
+                        Some(function.name.clone())
+                    }
+                    _ => None,
+                }),
+        );
+    }
+
+    let mut script = source.trim_end().to_string();
+    script.push('\n');
+    for example in examples {
+        script.push_str(&format!("print({})\n", python_string(MARKER)));
+        let mut parser = Parser::new(Lexer::new(&example.source));
+        let shows_value = match parser.parse_program() {
+            Node::Program(program) => match program.statements.as_slice() {
+                [Node::ExpressionStatement(statement)] => !matches!(
+                    statement.expression.as_ref(),
+                    Node::Call(call) if returns_none.contains(&call.callee)
+                ),
+                _ => false,
+            },
+            _ => false,
+        };
+        if shows_value {
+            script.push_str(&format!("print(repr({}))\n", example.source));
+        } else {
+            script.push_str(&example.source);
+            script.push('\n');
+        }
+    }
+
+    let mut parser = Parser::new(Lexer::new(&script));
+    parser.parse_program();
+    if let Some(error) = parser.errors().first() {
+        return Err(format!("Examples cannot be compiled: {}", error.message));
+    }
+    let options = CodegenOptions {
+        file_name: Some(path.to_string_lossy().into_owned()),
+        ..CodegenOptions::default()
+    };
+    let executable = build::compile_and_link(&script, &options)?;
+    let output = build::run_compiled(&executable, b"");
+    let _ = std::fs::remove_file(&executable);
+    let output = output.map_err(|e| format!("Failed to run the examples: {e}"))?;
+    Ok(outputs(
+        &String::from_utf8_lossy(&output.stdout),
+        examples.len(),
+    ))
+}
+
+/// Split a run's stdout into the output of each of `count` examples
+///
+/// Output before the first example is the module's own and is dropped.
+/// Examples a failed run did not reach get empty output.
+pub fn outputs(stdout: &str, count: usize) -> Vec<String> {
+    let mut outputs: Vec<String> = Vec::with_capacity(count);
+    for line in stdout.split_inclusive('\n') {
+        if line.trim_end_matches('\n') == MARKER {
+            outputs.push(String::new());
+        } else if let Some(output) = outputs.last_mut() {
+            output.push_str(line);
+        }
+    }
+    outputs.resize(count, String::new());
+    outputs
+}
+
+/// `text` as a Python string literal
+fn python_string(text: &str) -> String {
+    let mut literal = String::from("\"");
+    for ch in text.chars() {
+        match ch {
+            '\\' => literal.push_str("\\\\"),
+            '"' => literal.push_str("\\\""),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            ch if ch.is_control() => literal.push_str(&format!("\\u{:04x}", u32::from(ch))),
+            ch => literal.push(ch),
+        }
+    }
+    literal.push('"');
+    literal
+}
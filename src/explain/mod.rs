@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Interleave textual LLVM IR with the Python lines its `!dbg` locations point at
+///
+/// Each run of instructions from the same source line is preceded by a
+/// `; <line>: <source>` comment. Debug metadata is left out of the output.
+pub fn explain(ir: &str, source: &str) -> String {
+    let source_lines: Vec<&str> = source.lines().collect();
+    let locations = debug_locations(ir);
+
+    let mut output = String::new();
+    let mut current_line = None;
+    for ir_line in ir.lines() {
+        if ir_line.starts_with('!') {
+            continue;
+        }
+        if ir_line.starts_with("define ") {
+            current_line = None;
+        }
+
+        let (code, location) = split_debug_location(ir_line);
+        if let Some(line) = location.and_then(|id| locations.get(id).copied())
+            && current_line != Some(line)
+        {
+            let text = line
+                .checked_sub(1)
+                .and_then(|index| source_lines.get(index))
+                .map_or("", |text| text.trim());
+            output.push_str(&format!("; {line}: {text}\n"));
+            current_line = Some(line);
+        }
+        output.push_str(&code);
+        output.push('\n');
+    }
+
+    let trimmed = output.trim_end().len();
+    output.truncate(trimmed);
+    output.push('\n');
+    output
+}
+
+/// Path of the Python source recorded in the IR's debug info
+pub fn source_file(ir: &str) -> Option<PathBuf> {
+    let file = ir
+        .lines()
+        .find_map(|line| line.split_once("!DIFile("))
+        .map(|(_, rest)| rest)?;
+    let filename = quoted_field(file, "filename")?;
+    let directory = quoted_field(file, "directory").unwrap_or_default();
+    Some(PathBuf::from(directory).join(filename))
+}
+
+/// Source line of each `!N = !DILocation(line: L, ...)` node, by `N`
+fn debug_locations(ir: &str) -> HashMap<&str, usize> {
+    ir.lines()
+        .filter_map(|line| {
+            let (id, node) = line.split_once(" = ")?;
+            let fields = node
+                .strip_prefix("!DILocation(")
+                .or_else(|| node.strip_prefix("distinct !DILocation("))?;
+            let line = fields
+                .split_once("line: ")?
+                .1
+                .split(|c: char| !c.is_ascii_digit())
+                .next()?
+                .parse()
+                .ok()?;
+            Some((id, line))
+        })
+        .collect()
+}
+
+/// Split an IR line into its code and the id of its `!dbg` attachment, if any
+fn split_debug_location(ir_line: &str) -> (String, Option<&str>) {
+    let Some(start) = ir_line.find("!dbg !") else {
+        return (ir_line.to_string(), None);
+    };
+    let id_start = start + "!dbg ".len();
+    let id_end = ir_line[id_start + 1..]
+        .find(|c: char| !c.is_ascii_digit())
+        .map_or(ir_line.len(), |end| id_start + 1 + end);
+
+    // Instructions attach it as `, !dbg !N`, function definitions as ` !dbg !N {`
+    let code = ir_line[..start]
+        .strip_suffix(", ")
+        .unwrap_or(ir_line[..start].trim_end());
+    (
+        format!("{code}{}", &ir_line[id_end..]),
+        Some(&ir_line[id_start..id_end]),
+    )
+}
+
+fn quoted_field<'a>(fields: &'a str, name: &str) -> Option<&'a str> {
+    let value = fields.split_once(&format!("{name}: \""))?.1;
+    value.split_once('"').map(|(value, _)| value)
+}
@@ -0,0 +1,272 @@
+use crate::lexer::{Lexer, Token};
+use crate::lsp::Json;
+
+/// Syntactic category of a highlighted token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenClass {
+    Keyword,
+    /// Name of a defined or called function
+    Function,
+    Identifier,
+    /// `True`, `False` and `None`
+    Constant,
+    Number,
+    String,
+    Comment,
+    Operator,
+    Punctuation,
+    /// Characters the lexer does not accept
+    Invalid,
+}
+
+impl TokenClass {
+    /// Every class, in the order used for LSP semantic token types
+    pub const ALL: [TokenClass; 10] = [
+        TokenClass::Keyword,
+        TokenClass::Function,
+        TokenClass::Identifier,
+        TokenClass::Constant,
+        TokenClass::Number,
+        TokenClass::String,
+        TokenClass::Comment,
+        TokenClass::Operator,
+        TokenClass::Punctuation,
+        TokenClass::Invalid,
+    ];
+
+    /// Name used for CSS classes and JSON output
+    pub fn name(self) -> &'static str {
+        match self {
+            TokenClass::Keyword => "keyword",
+            TokenClass::Function => "function",
+            TokenClass::Identifier => "identifier",
+            TokenClass::Constant => "constant",
+            TokenClass::Number => "number",
+            TokenClass::String => "string",
+            TokenClass::Comment => "comment",
+            TokenClass::Operator => "operator",
+            TokenClass::Punctuation => "punctuation",
+            TokenClass::Invalid => "invalid",
+        }
+    }
+
+    /// Standard LSP semantic token type closest to the class
+    pub fn semantic_token_type(self) -> &'static str {
+        match self {
+            TokenClass::Identifier => "variable",
+            TokenClass::Constant => "enumMember",
+            TokenClass::Punctuation | TokenClass::Invalid => "operator",
+            other => other.name(),
+        }
+    }
+
+    fn ansi_color(self) -> Option<&'static str> {
+        match self {
+            TokenClass::Keyword => Some("35"),
+            TokenClass::Function => Some("34"),
+            TokenClass::Constant => Some("33"),
+            TokenClass::Number => Some("36"),
+            TokenClass::String => Some("32"),
+            TokenClass::Comment => Some("90"),
+            TokenClass::Invalid => Some("31;4"),
+            TokenClass::Identifier | TokenClass::Operator | TokenClass::Punctuation => None,
+        }
+    }
+}
+
+/// A classified token and where its text is in the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct HighlightToken {
+    pub class: TokenClass,
+    /// Character offsets into the source
+    pub start: usize,
+    pub end: usize,
+    /// Position of the first character, both 1-based
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Output format of `pycc highlight`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    #[default]
+    Ansi,
+    Html,
+    Json,
+}
+
+impl Format {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "ansi" => Ok(Format::Ansi),
+            "html" => Ok(Format::Html),
+            "json" => Ok(Format::Json),
+            _ => Err(format!(
+                "Invalid format '{value}' (expected ansi, html or json)"
+            )),
+        }
+    }
+}
+
+/// Split `source` into classified tokens, in source order
+///
+/// Whitespace and layout are not tokens; renderers copy the text between
+/// tokens unchanged.
+pub fn tokenize(source: &str) -> Vec<HighlightToken> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut lexer = Lexer::new(source);
+    let mut tokens: Vec<HighlightToken> = Vec::new();
+    let mut previous = Token::Eof;
+
+    loop {
+        let token = lexer.next_token();
+        let class = match &token {
+            Token::Eof => break,
+            Token::Newline | Token::Indent | Token::Dedent => continue,
+            Token::Def
+            | Token::If
+            | Token::Elif
+            | Token::Else
+            | Token::While
+            | Token::Return
+            | Token::Import
+            | Token::Async
+            | Token::Await
+            | Token::And
+            | Token::Or
+            | Token::Not => TokenClass::Keyword,
+            Token::Identifier(_) if previous == Token::Def => TokenClass::Function,
+            Token::Identifier(_) => TokenClass::Identifier,
+            Token::Boolean(_) | Token::None => TokenClass::Constant,
+            Token::Integer(_) | Token::Float(_) => TokenClass::Number,
+            Token::String(_) | Token::FString(_) => TokenClass::String,
+            Token::Comment(_) => TokenClass::Comment,
+            Token::LeftParen
+            | Token::RightParen
+            | Token::LeftBrace
+            | Token::RightBrace
+            | Token::LeftBracket
+            | Token::RightBracket
+            | Token::Comma
+            | Token::Colon
+            | Token::Semicolon
+            | Token::Dot => TokenClass::Punctuation,
+            Token::Error(_) => TokenClass::Invalid,
+            _ => TokenClass::Operator,
+        };
+
+        // A name followed by `(` is being called
+        if token == Token::LeftParen
+            && let Some(callee) = tokens.last_mut()
+            && callee.class == TokenClass::Identifier
+        {
+            callee.class = TokenClass::Function;
+        }
+
+        let span = lexer.span();
+        let line_start = chars[..span.start]
+            .iter()
+            .rposition(|&c| c == '\n')
+            .map_or(0, |newline| newline + 1);
+        tokens.push(HighlightToken {
+            class,
+            start: span.start,
+            end: span.end,
+            line: lexer.line(),
+            column: span.start - line_start + 1,
+        });
+        previous = token;
+    }
+    tokens
+}
+
+/// Render `source` in `format`
+pub fn render(source: &str, format: Format) -> String {
+    let tokens = tokenize(source);
+    match format {
+        Format::Ansi => render_ansi(source, &tokens),
+        Format::Html => render_html(source, &tokens),
+        Format::Json => render_json(source, &tokens),
+    }
+}
+
+/// Source with each token wrapped by `wrap` and the text between tokens by `plain`
+fn render_with(
+    source: &str,
+    tokens: &[HighlightToken],
+    wrap: impl Fn(TokenClass, &str) -> String,
+    plain: impl Fn(&str) -> String,
+) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let mut output = String::new();
+    let mut position = 0;
+    for token in tokens {
+        let gap: String = chars[position..token.start].iter().collect();
+        output.push_str(&plain(&gap));
+        let text: String = chars[token.start..token.end].iter().collect();
+        output.push_str(&wrap(token.class, &text));
+        position = token.end;
+    }
+    let rest: String = chars[position..].iter().collect();
+    output.push_str(&plain(&rest));
+    output
+}
+
+fn render_ansi(source: &str, tokens: &[HighlightToken]) -> String {
+    render_with(
+        source,
+        tokens,
+        |class, text| match class.ansi_color() {
+            Some(color) => format!("\x1b[{color}m{text}\x1b[0m"),
+            None => text.to_string(),
+        },
+        str::to_string,
+    )
+}
+
+fn render_html(source: &str, tokens: &[HighlightToken]) -> String {
+    let body = render_with(
+        source,
+        tokens,
+        |class, text| {
+            format!(
+                "<span class=\"{}\">{}</span>",
+                class.name(),
+                escape_html(text)
+            )
+        },
+        escape_html,
+    );
+    format!("<pre class=\"pycc-highlight\"><code>{body}</code></pre>\n")
+}
+
+fn render_json(source: &str, tokens: &[HighlightToken]) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let tokens = tokens
+        .iter()
+        .map(|token| {
+            Json::object([
+                ("class", token.class.name().into()),
+                (
+                    "text",
+                    chars[token.start..token.end]
+                        .iter()
+                        .collect::<String>()
+                        .into(),
+                ),
+                ("start", token.start.into()),
+                ("end", token.end.into()),
+                ("line", token.line.into()),
+                ("column", token.column.into()),
+            ])
+        })
+        .collect();
+    format!("{}\n", Json::Array(tokens))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
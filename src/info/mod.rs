@@ -0,0 +1,175 @@
+use crate::codegen::codegen::initialize_targets;
+use crate::codegen::options::find_program;
+use crate::lsp::Json;
+use inkwell::targets::{Target, TargetMachine};
+
+/// Language features pycc compiles to native code
+const SUPPORTED: &[&str] = &[
+    "int, float, str, bool and None values",
+    "arithmetic, comparison and logical operators with Python's precedence",
+    "if/elif/else and while statements",
+    "functions, with type annotations and docstrings",
+    "f-strings",
+    "print, int, repr, hash, id and input builtins",
+    "len, abs, int, str and bool of constant arguments, evaluated at compile time",
+    "sys.exit, sys.stdout and sys.stderr",
+    "os.getenv, os.environ, os.system and os.path",
+    "multiple modules compiled into one executable",
+];
+
+/// Language features pycc does not compile yet
+///
+/// Programs using the ones it parses still run with `--fallback interp`.
+const UNSUPPORTED: &[&str] = &[
+    "tuple, list, dict and set values",
+    "classes",
+    "exceptions",
+    "for loops",
+    "async functions and await",
+    "keyword arguments other than print's file= and flush=",
+];
+
+/// Optional compiler feature and whether the tools it relies on were found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Feature {
+    pub name: &'static str,
+    pub available: bool,
+    /// What enabling it requires
+    pub requires: &'static str,
+}
+
+/// What this build of pycc is and can do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompilerInfo {
+    pub version: String,
+    /// Version of the LLVM library pycc is linked against, e.g. "21.1.0"
+    pub llvm_version: String,
+    /// Triple executables are compiled for
+    pub default_target: String,
+    /// CPU `--target-cpu native` resolves to
+    pub host_cpu: String,
+    /// LLVM code generation backends, such as "x86-64" or "aarch64"
+    pub backends: Vec<String>,
+    pub features: Vec<Feature>,
+    pub supported: Vec<&'static str>,
+    pub unsupported: Vec<&'static str>,
+}
+
+/// Describe this build of pycc and the host it runs on
+///
+/// Optional features are reported available when the external programs they
+/// need are on the `PATH`.
+pub fn compiler_info() -> CompilerInfo {
+    initialize_targets();
+    let mut backends = Vec::new();
+    let mut target = Target::get_first();
+    while let Some(current) = target {
+        backends.push(current.get_name().to_string_lossy().into_owned());
+        target = current.get_next();
+    }
+    backends.sort();
+
+    let (major, minor, patch) = inkwell::support::get_llvm_version();
+    let features = vec![
+        Feature {
+            name: "lto",
+            available: find_program("clang"),
+            requires: "clang",
+        },
+        Feature {
+            name: "static-musl",
+            available: cfg!(target_env = "musl") || find_program("musl-gcc"),
+            requires: "musl-gcc",
+        },
+        Feature {
+            name: "address-sanitizer",
+            available: find_program("cc"),
+            requires: "a C compiler with libasan",
+        },
+        Feature {
+            name: "python-interop",
+            available: cfg!(feature = "python") && find_program("python3-config"),
+            requires: "the python feature, python3-config and libpython",
+        },
+    ];
+
+    CompilerInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        llvm_version: format!("{major}.{minor}.{patch}"),
+        default_target: TargetMachine::get_default_triple()
+            .as_str()
+            .to_string_lossy()
+            .into_owned(),
+        host_cpu: TargetMachine::get_host_cpu_name().to_string(),
+        backends,
+        features,
+        supported: SUPPORTED.to_vec(),
+        unsupported: UNSUPPORTED.to_vec(),
+    }
+}
+
+impl CompilerInfo {
+    /// Human-readable report printed by `pycc info`
+    pub fn render(&self) -> String {
+        let mut output = format!("pycc {}\n", self.version);
+        output.push_str(&format!("LLVM version: {}\n", self.llvm_version));
+        output.push_str(&format!("Default target: {}\n", self.default_target));
+        output.push_str(&format!("Host CPU: {}\n", self.host_cpu));
+        output.push_str(&format!("Backends: {}\n", self.backends.join(", ")));
+        output.push_str("Features:\n");
+        for feature in &self.features {
+            let status = if feature.available {
+                "available".to_string()
+            } else {
+                format!("unavailable (requires {})", feature.requires)
+            };
+            output.push_str(&format!("  {}: {status}\n", feature.name));
+        }
+        output.push_str("Compiles:\n");
+        for feature in &self.supported {
+            output.push_str(&format!("  {feature}\n"));
+        }
+        output.push_str("Not supported yet:\n");
+        for feature in &self.unsupported {
+            output.push_str(&format!("  {feature}\n"));
+        }
+        output
+    }
+
+    pub fn to_json(&self) -> Json {
+        let strings =
+            |values: &[&str]| Json::Array(values.iter().map(|&value| value.into()).collect());
+        Json::object([
+            ("version", self.version.as_str().into()),
+            ("llvm_version", self.llvm_version.as_str().into()),
+            ("default_target", self.default_target.as_str().into()),
+            ("host_cpu", self.host_cpu.as_str().into()),
+            (
+                "backends",
+                Json::Array(
+                    self.backends
+                        .iter()
+                        .map(|name| name.as_str().into())
+                        .collect(),
+                ),
+            ),
+            (
+                "features",
+                Json::Array(
+                    self.features
+                        .iter()
+                        .map(|feature| {
+                            Json::object([
+                                ("name", feature.name.into()),
+                                ("available", feature.available.into()),
+                                ("requires", feature.requires.into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            ("supported", strings(&self.supported)),
+            ("unsupported", strings(&self.unsupported)),
+        ])
+    }
+}
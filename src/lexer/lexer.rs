@@ -1,10 +1,30 @@
 use crate::lexer::token::Token;
+use std::collections::VecDeque;
 
 pub struct Lexer {
     input: Vec<char>,
     position: usize,
     read_position: usize,
     ch: char,
+    // Nesting depth of (), [] and {}; newlines inside brackets are not significant
+    bracket_depth: usize,
+    // Indentation columns of the enclosing blocks, outermost first
+    indent_stack: Vec<usize>,
+    // Depths of `indent_stack` entries opened by an unindent that matched no
+    // outer level; they were reported as errors and close without a DEDENT
+    unmatched_levels: Vec<usize>,
+    // Whether the next token starts a new line and indentation must be checked
+    at_line_start: bool,
+    // Whether the current logical line produced any non-comment token yet
+    line_has_tokens: bool,
+    // Layout tokens (extra DEDENTs) waiting to be returned
+    pending: VecDeque<Token>,
+    // Line of the current character, 1-based
+    current_line: usize,
+    // Line the most recently returned token started on
+    token_line: usize,
+    // Character offset the most recently returned token started at
+    token_start: usize,
 }
 
 impl Lexer {
@@ -14,12 +34,24 @@ impl Lexer {
             position: 0,
             read_position: 0,
             ch: '\0',
+            bracket_depth: 0,
+            indent_stack: Vec::new(),
+            unmatched_levels: Vec::new(),
+            at_line_start: true,
+            line_has_tokens: false,
+            pending: VecDeque::new(),
+            current_line: 1,
+            token_line: 1,
+            token_start: 0,
         };
         lexer.read_char();
         lexer
     }
 
     fn read_char(&mut self) {
+        if self.ch == '\n' {
+            self.current_line += 1;
+        }
         if self.read_position >= self.input.len() {
             self.ch = '\0';
         } else {
@@ -37,14 +69,87 @@ impl Lexer {
         }
     }
 
+    /// Look `offset` characters ahead of the current one (1 is the same as `peek_char`)
+    fn peek_char_at(&self, offset: usize) -> char {
+        self.input
+            .get(self.position + offset)
+            .copied()
+            .unwrap_or('\0')
+    }
+
+    /// Length of the source in characters
+    pub fn source_len(&self) -> usize {
+        self.input.len()
+    }
+
+    /// Line the token last returned by `next_token` started on
+    ///
+    /// Layout tokens report the line of the token that follows them.
+    pub fn line(&self) -> usize {
+        self.token_line
+    }
+
+    /// Character offsets of the source text of the token last returned by `next_token`
+    ///
+    /// Layout tokens have no text of their own, so their span is meaningless.
+    pub fn span(&self) -> std::ops::Range<usize> {
+        self.token_start..self.position.max(self.token_start)
+    }
+
     pub fn next_token(&mut self) -> Token {
-        self.skip_whitespace();
+        if let Some(token) = self.pending.pop_front() {
+            return token;
+        }
+
+        loop {
+            if self.at_line_start {
+                self.at_line_start = false;
+                if let Some(token) = self.read_indentation() {
+                    return token;
+                }
+            }
+
+            self.skip_whitespace();
+
+            // A newline outside brackets ends the logical line
+            if self.ch != '\n' {
+                break;
+            }
+            self.read_char();
+            self.at_line_start = true;
+            if self.line_has_tokens {
+                self.line_has_tokens = false;
+                return Token::Newline;
+            }
+            // Blank or comment-only line, keep scanning
+        }
+
+        self.token_line = self.current_line;
+        self.token_start = self.position;
 
         // Check for comments
         if self.ch == '#' {
             return self.read_comment();
         }
 
+        // Close any blocks that are still open at the end of the input
+        if self.ch == '\0' && self.indent_stack.len() > 1 {
+            while self.indent_stack.len() > 1 {
+                self.pop_indent_level();
+            }
+            if let Some(token) = self.pending.pop_front() {
+                return token;
+            }
+        }
+
+        let token = self.read_token();
+        if token != Token::Eof {
+            self.line_has_tokens = true;
+        }
+        token
+    }
+
+    fn read_token(&mut self) -> Token {
         // All tokens have already been advanced to the next character
         // except for EOF, so we don't need to do anything here
 
@@ -73,27 +178,55 @@ impl Lexer {
             }
             '(' => {
                 self.read_char();
+                self.bracket_depth += 1;
                 Token::LeftParen
             }
             ')' => {
                 self.read_char();
+                self.bracket_depth = self.bracket_depth.saturating_sub(1);
                 Token::RightParen
             }
             '{' => {
                 self.read_char();
+                self.bracket_depth += 1;
                 Token::LeftBrace
             }
             '}' => {
                 self.read_char();
+                self.bracket_depth = self.bracket_depth.saturating_sub(1);
                 Token::RightBrace
             }
+            '[' => {
+                self.read_char();
+                self.bracket_depth += 1;
+                Token::LeftBracket
+            }
+            ']' => {
+                self.read_char();
+                self.bracket_depth = self.bracket_depth.saturating_sub(1);
+                Token::RightBracket
+            }
             '+' => {
                 self.read_char();
                 Token::Plus
             }
             '-' => {
+                if self.peek_char() == '>' {
+                    self.read_char();
+                    self.read_char();
+                    Token::Arrow
+                } else {
+                    self.read_char();
+                    Token::Minus
+                }
+            }
+            '|' => {
+                self.read_char();
+                Token::Pipe
+            }
+            '.' => {
                 self.read_char();
-                Token::Minus
+                Token::Dot
             }
             '!' => {
                 if self.peek_char() == '=' {
@@ -149,25 +282,37 @@ impl Lexer {
                     Token::Greater
                 }
             }
+            '"' | '\'' if self.peek_char() == self.ch && self.peek_char_at(2) == self.ch => {
+                let quote = self.ch;
+                // skip the three opening quotes
+                self.read_char();
+                self.read_char();
+                self.read_char();
+                self.read_triple_quoted_string(quote)
+                    .map_or_else(Token::Error, Token::String)
+            }
             '"' => {
                 self.read_char(); // skip opening quote
-                Token::String(self.read_string())
+                self.read_string().map_or_else(Token::Error, Token::String)
             }
             '\'' => {
                 self.read_char(); // skip opening quote
-                Token::String(self.read_string_single())
+                self.read_string_single()
+                    .map_or_else(Token::Error, Token::String)
             }
             '0'..='9' => self.read_number(),
-            'a'..='z' | 'A'..='Z' | '_' => {
+            ch if is_identifier_start(ch) => {
                 // Check if this could be an f-string
                 if self.ch == 'f' && (self.peek_char() == '"' || self.peek_char() == '\'') {
                     self.read_char(); // consume 'f'
                     if self.ch == '"' {
                         self.read_char(); // skip opening quote
-                        Token::FString(self.read_fstring())
+                        self.read_fstring()
+                            .map_or_else(Token::Error, Token::FString)
                     } else if self.ch == '\'' {
                         self.read_char(); // skip opening quote
-                        Token::FString(self.read_fstring_single())
+                        self.read_fstring_single()
+                            .map_or_else(Token::Error, Token::FString)
                     } else {
                         // This shouldn't happen, but fallback to identifier
                         let ident = self.read_identifier();
@@ -178,9 +323,13 @@ impl Lexer {
                     match ident.as_str() {
                         "def" => Token::Def,
                         "if" => Token::If,
+                        "elif" => Token::Elif,
                         "else" => Token::Else,
                         "while" => Token::While,
                         "return" => Token::Return,
+                        "import" => Token::Import,
+                        "async" => Token::Async,
+                        "await" => Token::Await,
                         "True" => Token::Boolean(true),
                         "False" => Token::Boolean(false),
                         "None" => Token::None,
@@ -195,20 +344,106 @@ impl Lexer {
             _ => {
                 let ch = self.ch;
                 self.read_char();
-                Token::Illegal(ch.to_string())
+                Token::Error(format!(
+                    "invalid character '{ch}' (U+{:04X})",
+                    u32::from(ch)
+                ))
             }
         }
     }
 
     fn skip_whitespace(&mut self) {
-        while self.ch == ' ' || self.ch == '\t' || self.ch == '\n' || self.ch == '\r' {
+        loop {
+            match self.ch {
+                ' ' | '\t' | '\r' | '\x0c' => self.read_char(),
+                // Implicit line joining inside brackets
+                '\n' if self.bracket_depth > 0 => self.read_char(),
+                // Explicit line joining with a trailing backslash
+                '\\' if self.peek_char() == '\n' => {
+                    self.read_char();
+                    self.read_char();
+                }
+                '\\' if self.peek_char() == '\r' => {
+                    self.read_char();
+                    self.read_char();
+                    if self.ch == '\n' {
+                        self.read_char();
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Measure the indentation of a new line and emit INDENT/DEDENT tokens
+    ///
+    /// Blank and comment-only lines never change the indentation level. The
+    /// first logical line sets the base level, so snippets that are indented
+    /// as a whole still lex as top-level code.
+    fn read_indentation(&mut self) -> Option<Token> {
+        let line_start = self.position;
+        let mut column = 0;
+        loop {
+            match self.ch {
+                ' ' => column += 1,
+                '\t' => column = (column / 8 + 1) * 8,
+                '\x0c' => column = 0,
+                '\r' => {}
+                _ => break,
+            }
             self.read_char();
         }
+
+        if matches!(self.ch, '\n' | '#' | '\0') {
+            return None;
+        }
+
+        let Some(&current) = self.indent_stack.last() else {
+            self.indent_stack.push(column);
+            return None;
+        };
+
+        // Layout tokens and indentation errors are placed at the start of the line
+        self.token_line = self.current_line;
+        self.token_start = line_start;
+
+        if column > current {
+            self.indent_stack.push(column);
+            return Some(Token::Indent);
+        }
+
+        while self.indent_stack.len() > 1 && column < *self.indent_stack.last().unwrap() {
+            self.pop_indent_level();
+        }
+        if self
+            .indent_stack
+            .last()
+            .is_some_and(|&level| column > level)
+        {
+            self.pending.push_back(Token::Error(
+                "unindent does not match any outer indentation level".to_string(),
+            ));
+            // Later lines at this column belong to the same block rather than
+            // each being reported again
+            self.indent_stack.push(column);
+            self.unmatched_levels.push(self.indent_stack.len());
+        }
+        self.pending.pop_front()
+    }
+
+    /// Leave the innermost block, queueing its DEDENT
+    fn pop_indent_level(&mut self) {
+        if self.unmatched_levels.last() == Some(&self.indent_stack.len()) {
+            self.unmatched_levels.pop();
+        } else {
+            self.pending.push_back(Token::Dedent);
+        }
+        self.indent_stack.pop();
     }
 
     fn read_identifier(&mut self) -> String {
         let start = self.position;
-        while is_letter(self.ch) {
+        while is_identifier_continue(self.ch) {
             self.read_char();
         }
         self.input[start..self.position].iter().collect()
@@ -229,13 +464,17 @@ impl Lexer {
             Token::Float(float_str.parse().unwrap_or(0.0))
         } else {
             let int_str: String = self.input[start..self.position].iter().collect();
-            Token::Integer(int_str.parse().unwrap_or(0))
+            // Integers are 64-bit until bigints land
+            match int_str.parse() {
+                Ok(value) => Token::Integer(value),
+                Err(_) => Token::Error("integer literal too large".to_string()),
+            }
         }
     }
 
-    fn read_string(&mut self) -> String {
+    fn read_string(&mut self) -> Result<String, String> {
         let mut result = String::new();
-        while self.ch != '"' && self.ch != '\0' {
+        while self.ch != '"' && self.ch != '\n' && self.ch != '\0' {
             if self.ch == '\\' {
                 self.read_char(); // consume the backslash
                 match self.ch {
@@ -245,6 +484,7 @@ impl Lexer {
                     '"' => result.push('"'),
                     '\'' => result.push('\''),
                     '\\' => result.push('\\'),
+                    '\n' => {} // Escaped newline joins the lines
                     _ => {
                         // If it's not a recognized escape sequence,
                         // just add the backslash and the character as-is
@@ -257,15 +497,16 @@ impl Lexer {
             }
             self.read_char();
         }
-        if self.ch == '"' {
-            self.read_char(); // consume closing quote
+        if self.ch != '"' {
+            return Err(self.unterminated("string"));
         }
-        result
+        self.read_char(); // consume closing quote
+        Ok(result)
     }
 
-    fn read_string_single(&mut self) -> String {
+    fn read_string_single(&mut self) -> Result<String, String> {
         let mut result = String::new();
-        while self.ch != '\'' && self.ch != '\0' {
+        while self.ch != '\'' && self.ch != '\n' && self.ch != '\0' {
             if self.ch == '\\' {
                 self.read_char(); // consume the backslash
                 match self.ch {
@@ -275,6 +516,7 @@ impl Lexer {
                     '"' => result.push('"'),
                     '\'' => result.push('\''),
                     '\\' => result.push('\\'),
+                    '\n' => {} // Escaped newline joins the lines
                     _ => {
                         // If it's not a recognized escape sequence,
                         // just add the backslash and the character as-is
@@ -287,10 +529,57 @@ impl Lexer {
             }
             self.read_char();
         }
-        if self.ch == '\'' {
-            self.read_char(); // consume closing quote
+        if self.ch != '\'' {
+            return Err(self.unterminated("string"));
         }
-        result
+        self.read_char(); // consume closing quote
+        Ok(result)
+    }
+
+    /// Read a `"""..."""` or `'''...'''` string, which may span several lines
+    fn read_triple_quoted_string(&mut self, quote: char) -> Result<String, String> {
+        let mut result = String::new();
+        while self.ch != '\0'
+            && !(self.ch == quote && self.peek_char() == quote && self.peek_char_at(2) == quote)
+        {
+            if self.ch == '\\' {
+                self.read_char(); // consume the backslash
+                match self.ch {
+                    'n' => result.push('\n'),
+                    't' => result.push('\t'),
+                    'r' => result.push('\r'),
+                    '"' => result.push('"'),
+                    '\'' => result.push('\''),
+                    '\\' => result.push('\\'),
+                    '\n' => {} // Escaped newline joins the lines
+                    _ => {
+                        // If it's not a recognized escape sequence,
+                        // just add the backslash and the character as-is
+                        result.push('\\');
+                        result.push(self.ch);
+                    }
+                }
+            } else {
+                result.push(self.ch);
+            }
+            self.read_char();
+        }
+        if self.ch != quote {
+            return Err(self.unterminated("triple-quoted string"));
+        }
+        // consume the three closing quotes
+        self.read_char();
+        self.read_char();
+        self.read_char();
+        Ok(result)
+    }
+
+    /// Message for a literal that reaches the end of its line or of the input
+    fn unterminated(&self, kind: &str) -> String {
+        format!(
+            "unterminated {kind} literal (detected at line {})",
+            self.current_line
+        )
     }
 
     fn read_comment(&mut self) -> Token {
@@ -305,12 +594,12 @@ impl Lexer {
         Token::Comment(comment_text)
     }
 
-    fn read_fstring(&mut self) -> String {
+    fn read_fstring(&mut self) -> Result<String, String> {
         let mut result = String::new();
         let mut brace_depth = 0;
         let mut in_expression = false;
 
-        while self.ch != '"' && self.ch != '\0' {
+        while self.ch != '"' && self.ch != '\n' && self.ch != '\0' {
             if self.ch == '\\' {
                 // Handle escape sequences
                 self.read_char(); // consume the backslash
@@ -321,6 +610,7 @@ impl Lexer {
                     '"' => result.push('"'),
                     '\'' => result.push('\''),
                     '\\' => result.push('\\'),
+                    '\n' => {}               // Escaped newline joins the lines
                     '{' => result.push('{'), // Escaped brace
                     '}' => result.push('}'), // Escaped brace
                     _ => {
@@ -351,18 +641,19 @@ impl Lexer {
             self.read_char();
         }
 
-        if self.ch == '"' {
-            self.read_char(); // consume closing quote
+        if self.ch != '"' {
+            return Err(self.unterminated("f-string"));
         }
-        result
+        self.read_char(); // consume closing quote
+        Ok(result)
     }
 
-    fn read_fstring_single(&mut self) -> String {
+    fn read_fstring_single(&mut self) -> Result<String, String> {
         let mut result = String::new();
         let mut brace_depth = 0;
         let mut in_expression = false;
 
-        while self.ch != '\'' && self.ch != '\0' {
+        while self.ch != '\'' && self.ch != '\n' && self.ch != '\0' {
             if self.ch == '\\' {
                 // Handle escape sequences
                 self.read_char(); // consume the backslash
@@ -373,6 +664,7 @@ impl Lexer {
                     '"' => result.push('"'),
                     '\'' => result.push('\''),
                     '\\' => result.push('\\'),
+                    '\n' => {}               // Escaped newline joins the lines
                     '{' => result.push('{'), // Escaped brace
                     '}' => result.push('}'), // Escaped brace
                     _ => {
@@ -403,15 +695,25 @@ impl Lexer {
             self.read_char();
         }
 
-        if self.ch == '\'' {
-            self.read_char(); // consume closing quote
+        if self.ch != '\'' {
+            return Err(self.unterminated("f-string"));
         }
-        result
+        self.read_char(); // consume closing quote
+        Ok(result)
     }
 }
 
-fn is_letter(ch: char) -> bool {
-    ch.is_alphabetic() || ch == '_' || ch.is_numeric()
+/// Whether `ch` may start an identifier, following Python's XID_Start rule
+///
+/// Names are not NFKC-normalized as CPython does, so `ﬁ` and `fi` are
+/// distinct identifiers.
+pub fn is_identifier_start(ch: char) -> bool {
+    ch == '_' || unicode_ident::is_xid_start(ch)
+}
+
+/// Whether `ch` may follow the first character of an identifier (XID_Continue)
+pub fn is_identifier_continue(ch: char) -> bool {
+    unicode_ident::is_xid_continue(ch)
 }
 
 fn is_digit(ch: char) -> bool {
@@ -17,9 +17,13 @@ pub enum Token {
     // Keywords
     Def,
     If,
+    Elif,
     Else,
     While,
     Return,
+    Import,
+    Async,
+    Await,
     // True, False are handled as Boolean literals instead
     // True,
     // False,
@@ -42,19 +46,28 @@ pub enum Token {
     And,          // and
     Or,           // or
     Not,          // not
+    Arrow,        // ->
+    Pipe,         // |
 
     // Delimiters
-    LeftParen,  // (
-    RightParen, // )
-    LeftBrace,  // {
-    RightBrace, // }
-    // LeftBracket,  // [ - Not currently used
-    // RightBracket, // ] - Not currently used
-    Comma,     // ,
-    Colon,     // :
-    Semicolon, // ;
+    LeftParen,    // (
+    RightParen,   // )
+    LeftBrace,    // {
+    RightBrace,   // }
+    LeftBracket,  // [
+    RightBracket, // ]
+    Comma,        // ,
+    Colon,        // :
+    Semicolon,    // ;
+    Dot,          // .
+
+    // Layout
+    Newline, // End of a logical line
+    Indent,  // Indentation level increased
+    Dedent,  // Indentation level decreased
 
     // Special
     Eof,
-    Illegal(String),
+    /// Text that is not a valid token, with a message describing the problem
+    Error(String),
 }
@@ -1,8 +1,20 @@
 pub mod ast;
+pub mod bench;
+pub mod builtins;
+pub mod cache;
 pub mod cli;
 pub mod codegen;
+pub mod doctest;
+pub mod explain;
+pub mod highlight;
+pub mod info;
 pub mod lexer;
+pub mod lsp;
 pub mod parser;
+pub mod passes;
+pub mod pipeline;
+pub mod session;
+pub mod typecheck;
 
 // Re-export commonly used items
 pub use ast::*;
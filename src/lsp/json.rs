@@ -0,0 +1,315 @@
+use std::fmt;
+
+/// Minimal JSON value, enough for the Language Server Protocol messages pycc handles
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Members in insertion order
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = JsonParser {
+            chars: text.chars().collect(),
+            position: 0,
+        };
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+        if parser.position != parser.chars.len() {
+            return Err(format!(
+                "Unexpected trailing characters at offset {}",
+                parser.position
+            ));
+        }
+        Ok(value)
+    }
+
+    /// Build an object from `(key, value)` pairs
+    pub fn object<const N: usize>(members: [(&str, Json); N]) -> Json {
+        Json::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    /// Member `key` of an object
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Json::Number(value) if *value >= 0.0 && value.fract() == 0.0 => Some(*value as u64),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Json::String(value.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Self {
+        Json::String(value)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(value: usize) -> Self {
+        Json::Number(value as f64)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Json::Bool(value)
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{value}"),
+            Json::Number(value) if value.fract() == 0.0 && value.abs() < 1e15 => {
+                write!(f, "{}", *value as i64)
+            }
+            Json::Number(value) => write!(f, "{value}"),
+            Json::String(value) => write_string(f, value),
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for ch in value.chars() {
+        match ch {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    write!(f, "\"")
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl JsonParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(format!("Expected '{expected}' at offset {}", self.position))
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.parse_object(),
+            Some('[') => self.parse_array(),
+            Some('"') => self.parse_string().map(Json::String),
+            Some('t') => self.parse_keyword("true", Json::Bool(true)),
+            Some('f') => self.parse_keyword("false", Json::Bool(false)),
+            Some('n') => self.parse_keyword("null", Json::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.parse_number(),
+            _ => Err(format!("Unexpected character at offset {}", self.position)),
+        }
+    }
+
+    fn parse_keyword(&mut self, keyword: &str, value: Json) -> Result<Json, String> {
+        for expected in keyword.chars() {
+            if self.peek() != Some(expected) {
+                return Err(format!("Invalid literal at offset {}", self.position));
+            }
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    fn parse_number(&mut self) -> Result<Json, String> {
+        let start = self.position;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            self.position += 1;
+        }
+        let text: String = self.chars[start..self.position].iter().collect();
+        text.parse()
+            .map(Json::Number)
+            .map_err(|_| format!("Invalid number '{text}'"))
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            let Some(ch) = self.peek() else {
+                return Err("Unterminated string".to_string());
+            };
+            self.position += 1;
+            match ch {
+                '"' => return Ok(value),
+                '\\' => {
+                    let Some(escape) = self.peek() else {
+                        return Err("Unterminated string".to_string());
+                    };
+                    self.position += 1;
+                    match escape {
+                        'n' => value.push('\n'),
+                        'r' => value.push('\r'),
+                        't' => value.push('\t'),
+                        'b' => value.push('\u{8}'),
+                        'f' => value.push('\u{c}'),
+                        'u' => value.push(self.parse_unicode_escape()?),
+                        other => value.push(other),
+                    }
+                }
+                other => value.push(other),
+            }
+        }
+    }
+
+    /// The code point after `\u`, combining UTF-16 surrogate pairs
+    fn parse_unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.parse_hex4()?;
+        if (0xd800..0xdc00).contains(&high) && self.chars[self.position..].starts_with(&['\\', 'u'])
+        {
+            self.position += 2;
+            let low = self.parse_hex4()?;
+            let code = 0x10000 + ((high - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+            return Ok(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+        }
+        Ok(char::from_u32(high).unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, String> {
+        let end = self.position + 4;
+        let digits: String = self
+            .chars
+            .get(self.position..end)
+            .ok_or("Truncated \\u escape")?
+            .iter()
+            .collect();
+        self.position = end;
+        u32::from_str_radix(&digits, 16).map_err(|_| format!("Invalid \\u escape '{digits}'"))
+    }
+
+    fn parse_array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(']') => {
+                    self.position += 1;
+                    return Ok(Json::Array(values));
+                }
+                _ => return Err(format!("Expected ',' or ']' at offset {}", self.position)),
+            }
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.expect(':')?;
+            members.push((key, self.parse_value()?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some('}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(members));
+                }
+                _ => return Err(format!("Expected ',' or '}}' at offset {}", self.position)),
+            }
+        }
+    }
+}
@@ -0,0 +1,681 @@
+mod json;
+
+pub use json::Json;
+
+use crate::ast::{Function, Node};
+use crate::highlight::{self, TokenClass};
+use crate::lexer::Lexer;
+use crate::lexer::lexer::{is_identifier_continue, is_identifier_start};
+use crate::parser::{ParseError, Parser};
+use crate::typecheck::{self, Type};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+/// LSP `SymbolKind` values used in document symbols
+const SYMBOL_KIND_FUNCTION: usize = 12;
+const SYMBOL_KIND_VARIABLE: usize = 13;
+
+/// JSON-RPC error code for requests the server does not implement
+const METHOD_NOT_FOUND: f64 = -32601.0;
+
+/// Language server for the Python subset pycc compiles
+///
+/// Documents are synced in full on every change. Diagnostics come from the
+/// parser's syntax errors or, when there are none, the type checker; definitions, hovers and symbols from the parsed AST.
+pub struct Server {
+    documents: HashMap<String, String>,
+    shutdown_requested: bool,
+    exited: bool,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Server::new()
+    }
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Server {
+            documents: HashMap::new(),
+            shutdown_requested: false,
+            exited: false,
+        }
+    }
+
+    /// Whether the client has sent `exit`
+    pub fn exited(&self) -> bool {
+        self.exited
+    }
+
+    /// Handle one client message and return the messages to send back
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let Some(method) = message.get("method").and_then(Json::as_str) else {
+            // Responses to server requests; none are sent
+            return Vec::new();
+        };
+        let params = message.get("params").unwrap_or(&Json::Null);
+        let id = message.get("id");
+
+        let result = match method {
+            "initialize" => Some(initialize_result()),
+            "shutdown" => {
+                self.shutdown_requested = true;
+                Some(Json::Null)
+            }
+            "exit" => {
+                self.exited = true;
+                None
+            }
+            "textDocument/didOpen" => {
+                let document = params.get("textDocument");
+                let uri = document.and_then(|d| d.get("uri")).and_then(Json::as_str);
+                let text = document.and_then(|d| d.get("text")).and_then(Json::as_str);
+                if let (Some(uri), Some(text)) = (uri, text) {
+                    self.documents.insert(uri.to_string(), text.to_string());
+                    return vec![self.publish_diagnostics(uri)];
+                }
+                None
+            }
+            "textDocument/didChange" => {
+                let uri = document_uri(params);
+                // With full sync the last change holds the whole text
+                let text = params
+                    .get("contentChanges")
+                    .and_then(Json::as_array)
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(Json::as_str);
+                if let (Some(uri), Some(text)) = (uri, text) {
+                    self.documents.insert(uri.to_string(), text.to_string());
+                    return vec![self.publish_diagnostics(uri)];
+                }
+                None
+            }
+            "textDocument/didClose" => {
+                if let Some(uri) = document_uri(params) {
+                    self.documents.remove(uri);
+                    return vec![notification(
+                        "textDocument/publishDiagnostics",
+                        Json::object([("uri", uri.into()), ("diagnostics", Json::Array(vec![]))]),
+                    )];
+                }
+                None
+            }
+            "textDocument/definition" => Some(self.definition(params)),
+            "textDocument/hover" => Some(self.hover(params)),
+            "textDocument/documentSymbol" => Some(self.document_symbols(params)),
+            "textDocument/semanticTokens/full" => Some(self.semantic_tokens(params)),
+            _ => {
+                return match id {
+                    Some(id) => vec![Json::object([
+                        ("jsonrpc", "2.0".into()),
+                        ("id", id.clone()),
+                        (
+                            "error",
+                            Json::object([
+                                ("code", Json::Number(METHOD_NOT_FOUND)),
+                                ("message", format!("Unsupported method: {method}").into()),
+                            ]),
+                        ),
+                    ])],
+                    None => Vec::new(),
+                };
+            }
+        };
+
+        match (id, result) {
+            (Some(id), Some(result)) => vec![Json::object([
+                ("jsonrpc", "2.0".into()),
+                ("id", id.clone()),
+                ("result", result),
+            ])],
+            _ => Vec::new(),
+        }
+    }
+
+    fn publish_diagnostics(&self, uri: &str) -> Json {
+        let text = &self.documents[uri];
+        let mut parser = Parser::new(Lexer::new(text));
+        let program = parser.parse_program();
+        let diagnostics = if !parser.errors().is_empty() {
+            parser
+                .errors()
+                .iter()
+                .map(|error| syntax_diagnostic(text, error))
+                .collect()
+        } else {
+            typecheck::diagnose(&program)
+                .into_iter()
+                .map(|diagnostic| {
+                    let line = diagnostic.line.max(1);
+                    let source_line = text.lines().nth(line - 1).unwrap_or("");
+                    let start = source_line.len() - source_line.trim_start().len();
+                    let start = utf16_column(source_line, start);
+                    let end = utf16_column(source_line, source_line.trim_end().len());
+                    Json::object([
+                        ("range", range(line, start, line, end)),
+                        ("severity", 1.into()),
+                        ("source", "pycc".into()),
+                        ("message", diagnostic.message.into()),
+                    ])
+                })
+                .collect()
+        };
+        notification(
+            "textDocument/publishDiagnostics",
+            Json::object([
+                ("uri", uri.into()),
+                ("diagnostics", Json::Array(diagnostics)),
+            ]),
+        )
+    }
+
+    /// Document text and the identifier under the request's position
+    fn target<'a>(&'a self, params: &'a Json) -> Option<(&'a str, &'a str, String, usize)> {
+        let uri = document_uri(params)?;
+        let text = self.documents.get(uri)?;
+        let position = params.get("position")?;
+        let line = position.get("line")?.as_u64()? as usize + 1;
+        let character = position.get("character")?.as_u64()? as usize;
+        let word = word_at(text.lines().nth(line - 1)?, character)?;
+        Some((uri, text, word, line))
+    }
+
+    fn definition(&self, params: &Json) -> Json {
+        let Some((uri, text, word, line)) = self.target(params) else {
+            return Json::Null;
+        };
+        let outline = Outline::new(&parse(text));
+        match outline.resolve(&word, line) {
+            Some(symbol) => {
+                Json::object([("uri", uri.into()), ("range", name_range(text, symbol))])
+            }
+            None => Json::Null,
+        }
+    }
+
+    fn hover(&self, params: &Json) -> Json {
+        let Some((_, text, word, line)) = self.target(params) else {
+            return Json::Null;
+        };
+        let program = parse(text);
+        let outline = Outline::new(&program);
+        let Some(symbol) = outline.resolve(&word, line) else {
+            return Json::Null;
+        };
+
+        let (code, docstring) = match &symbol.function {
+            Some(function) => (
+                format!("def {}", function_signature(function)),
+                function.docstring.clone(),
+            ),
+            None => {
+                let types = typecheck::variable_types(&program);
+                let ty = types
+                    .get(&(symbol.scope.clone(), symbol.name.clone()))
+                    .copied()
+                    .unwrap_or(Type::Any);
+                let role = if symbol.is_parameter {
+                    "parameter"
+                } else {
+                    "variable"
+                };
+                (format!("({role}) {}: {ty}", symbol.name), None)
+            }
+        };
+        let mut value = format!("```python\n{code}\n```");
+        if let Some(docstring) = docstring {
+            value.push_str(&format!("\n\n{docstring}"));
+        }
+        Json::object([(
+            "contents",
+            Json::object([("kind", "markdown".into()), ("value", value.into())]),
+        )])
+    }
+
+    /// Highlighted tokens, encoded as relative positions per the LSP spec
+    ///
+    /// Tokens spanning lines, like triple-quoted strings, are split per line.
+    fn semantic_tokens(&self, params: &Json) -> Json {
+        let Some(text) = document_uri(params).and_then(|uri| self.documents.get(uri)) else {
+            return Json::Null;
+        };
+        let chars: Vec<char> = text.chars().collect();
+        let mut data = Vec::new();
+        let (mut previous_line, mut previous_column) = (0, 0);
+        for token in highlight::tokenize(text) {
+            let token_type = TokenClass::ALL
+                .iter()
+                .position(|class| *class == token.class)
+                .unwrap_or(0);
+            let text: String = chars[token.start..token.end].iter().collect();
+            for (offset, segment) in text.split('\n').enumerate() {
+                let line = token.line - 1 + offset;
+                let column = if offset == 0 { token.column - 1 } else { 0 };
+                let length = segment.chars().count();
+                if length == 0 {
+                    continue;
+                }
+                let delta_column = if line == previous_line {
+                    column - previous_column
+                } else {
+                    column
+                };
+                data.extend([line - previous_line, delta_column, length, token_type, 0]);
+                (previous_line, previous_column) = (line, column);
+            }
+        }
+        Json::object([(
+            "data",
+            Json::Array(data.into_iter().map(Json::from).collect()),
+        )])
+    }
+
+    fn document_symbols(&self, params: &Json) -> Json {
+        let Some(text) = document_uri(params).and_then(|uri| self.documents.get(uri)) else {
+            return Json::Null;
+        };
+        let outline = Outline::new(&parse(text));
+        let symbol_json = |symbol: &Symbol, children: Vec<Json>| {
+            let (kind, detail) = match &symbol.function {
+                Some(function) => (SYMBOL_KIND_FUNCTION, function_signature(function)),
+                None => (SYMBOL_KIND_VARIABLE, String::new()),
+            };
+            let selection = name_range(text, symbol);
+            let full = match symbol.function {
+                Some(_) => {
+                    let end_text = text.lines().nth(symbol.end_line - 1).unwrap_or("");
+                    range(
+                        symbol.line,
+                        0,
+                        symbol.end_line,
+                        utf16_column(end_text, end_text.len()),
+                    )
+                }
+                None => selection.clone(),
+            };
+            Json::object([
+                ("name", symbol.name.clone().into()),
+                ("detail", detail.into()),
+                ("kind", kind.into()),
+                ("range", full),
+                ("selectionRange", selection),
+                ("children", Json::Array(children)),
+            ])
+        };
+
+        let symbols = outline
+            .symbols
+            .iter()
+            .filter(|symbol| symbol.scope.is_none())
+            .map(|symbol| {
+                let children = outline
+                    .symbols
+                    .iter()
+                    .filter(|child| {
+                        symbol.function.is_some()
+                            && child.scope.as_deref() == Some(symbol.name.as_str())
+                            && !child.is_parameter
+                    })
+                    .map(|child| symbol_json(child, Vec::new()))
+                    .collect();
+                symbol_json(symbol, children)
+            })
+            .collect();
+        Json::Array(symbols)
+    }
+}
+
+/// Serve LSP messages from `input` until the client exits
+///
+/// Returns whether the client asked for a shutdown first, which decides the
+/// exit status the protocol expects.
+pub fn run(input: &mut impl BufRead, output: &mut impl Write) -> Result<bool, String> {
+    let mut server = Server::new();
+    while let Some(message) = read_message(input)? {
+        for reply in server.handle(&message) {
+            write_message(output, &reply)?;
+        }
+        if server.exited() {
+            return Ok(server.shutdown_requested);
+        }
+    }
+    Ok(server.shutdown_requested)
+}
+
+/// Read one `Content-Length`-framed message, or `None` at end of input
+pub fn read_message(input: &mut impl BufRead) -> Result<Option<Json>, String> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        let read = input
+            .read_line(&mut header)
+            .map_err(|e| format!("Failed to read message header: {e}"))?;
+        if read == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("Content-Length")
+        {
+            content_length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let length = content_length.ok_or("Message without Content-Length header")?;
+    let mut body = vec![0; length];
+    input
+        .read_exact(&mut body)
+        .map_err(|e| format!("Failed to read message body: {e}"))?;
+    let body = String::from_utf8(body).map_err(|e| format!("Message is not UTF-8: {e}"))?;
+    Json::parse(&body).map(Some)
+}
+
+pub fn write_message(output: &mut impl Write, message: &Json) -> Result<(), String> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{body}", body.len())
+        .and_then(|_| output.flush())
+        .map_err(|e| format!("Failed to write message: {e}"))
+}
+
+fn initialize_result() -> Json {
+    Json::object([
+        (
+            "capabilities",
+            Json::object([
+                // Full document sync
+                ("textDocumentSync", 1.into()),
+                ("hoverProvider", true.into()),
+                ("definitionProvider", true.into()),
+                ("documentSymbolProvider", true.into()),
+                (
+                    "semanticTokensProvider",
+                    Json::object([
+                        (
+                            "legend",
+                            Json::object([
+                                (
+                                    "tokenTypes",
+                                    Json::Array(
+                                        TokenClass::ALL
+                                            .iter()
+                                            .map(|class| class.semantic_token_type().into())
+                                            .collect(),
+                                    ),
+                                ),
+                                ("tokenModifiers", Json::Array(Vec::new())),
+                            ]),
+                        ),
+                        ("full", true.into()),
+                    ]),
+                ),
+            ]),
+        ),
+        (
+            "serverInfo",
+            Json::object([
+                ("name", "pycc".into()),
+                ("version", env!("CARGO_PKG_VERSION").into()),
+            ]),
+        ),
+    ])
+}
+
+fn notification(method: &str, params: Json) -> Json {
+    Json::object([
+        ("jsonrpc", "2.0".into()),
+        ("method", method.into()),
+        ("params", params),
+    ])
+}
+
+fn document_uri(params: &Json) -> Option<&str> {
+    params.get("textDocument")?.get("uri")?.as_str()
+}
+
+fn parse(text: &str) -> Node {
+    Parser::new(Lexer::new(text)).parse_program()
+}
+
+/// Diagnostic covering the text of a syntax error
+fn syntax_diagnostic(text: &str, error: &ParseError) -> Json {
+    let (start_line, start) = text_position(text, error.span.start);
+    let (end_line, end) = text_position(text, error.span.end.max(error.span.start + 1));
+    Json::object([
+        ("range", range(start_line, start, end_line, end)),
+        ("severity", 1.into()),
+        ("source", "pycc".into()),
+        ("message", error.message.clone().into()),
+    ])
+}
+
+/// 1-based line and UTF-16 column within it of a character offset into `text`
+fn text_position(text: &str, offset: usize) -> (usize, usize) {
+    let mut line = 1;
+    let mut character = 0;
+    for ch in text.chars().take(offset) {
+        if ch == '\n' {
+            line += 1;
+            character = 0;
+        } else {
+            character += ch.len_utf16();
+        }
+    }
+    (line, character)
+}
+
+/// Column of byte offset `byte` in `line` in UTF-16 code units, as LSP counts them
+fn utf16_column(line: &str, byte: usize) -> usize {
+    line[..byte].encode_utf16().count()
+}
+
+/// LSP range between 1-based lines and UTF-16 columns
+fn range(start_line: usize, start: usize, end_line: usize, end: usize) -> Json {
+    let position = |line: usize, character: usize| {
+        Json::object([
+            ("line", line.saturating_sub(1).into()),
+            ("character", character.into()),
+        ])
+    };
+    Json::object([
+        ("start", position(start_line, start)),
+        ("end", position(end_line, end)),
+    ])
+}
+
+/// The identifier around the UTF-16 column `character` in `line`
+fn word_at(line: &str, character: usize) -> Option<String> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut units = 0;
+    let character = chars
+        .iter()
+        .take_while(|ch| {
+            units += ch.len_utf16();
+            units <= character
+        })
+        .count();
+    let mut start = character.min(chars.len());
+    while start > 0 && is_identifier_continue(chars[start - 1]) {
+        start -= 1;
+    }
+    let mut end = character.min(chars.len());
+    while end < chars.len() && is_identifier_continue(chars[end]) {
+        end += 1;
+    }
+    (start < end && is_identifier_start(chars[start])).then(|| chars[start..end].iter().collect())
+}
+
+/// Range of the symbol's name on the line that defines it
+fn name_range(text: &str, symbol: &Symbol) -> Json {
+    let line = text.lines().nth(symbol.line - 1).unwrap_or("");
+    // A function's name follows `def`; its parameters come after the name
+    let search_from = match &symbol.function {
+        Some(_) => line.find("def").map_or(0, |index| index + 3),
+        None if symbol.is_parameter => line.find('(').unwrap_or(0),
+        None => 0,
+    };
+    let start = find_word(line, &symbol.name, search_from).unwrap_or(0);
+    let end = utf16_column(line, start) + symbol.name.encode_utf16().count();
+    range(symbol.line, utf16_column(line, start), symbol.line, end)
+}
+
+/// Byte offset of the first whole-word `word` in `line` at or after `from`
+fn find_word(line: &str, word: &str, from: usize) -> Option<usize> {
+    let mut offset = from;
+    while let Some(found) = line.get(offset..)?.find(word) {
+        let start = offset + found;
+        let end = start + word.len();
+        let before = line[..start].chars().next_back();
+        let after = line[end..].chars().next();
+        if !before.is_some_and(is_identifier_continue) && !after.is_some_and(is_identifier_continue)
+        {
+            return Some(start);
+        }
+        offset = end;
+    }
+    None
+}
+
+fn function_signature(function: &Function) -> String {
+    let parameters: Vec<String> = function
+        .parameters
+        .iter()
+        .zip(&function.parameter_types)
+        .map(|(name, annotation)| match annotation {
+            Some(annotation) => format!("{name}: {annotation}"),
+            None => name.clone(),
+        })
+        .collect();
+    let return_type = function
+        .return_type
+        .as_ref()
+        .map_or(String::new(), |annotation| format!(" -> {annotation}"));
+    format!("{}({}){return_type}", function.name, parameters.join(", "))
+}
+
+/// A name bound by a `def`, a parameter or an assignment
+struct Symbol {
+    name: String,
+    line: usize,
+    /// Last line of a function's body; the definition line otherwise
+    end_line: usize,
+    /// Enclosing function, `None` at module level
+    scope: Option<String>,
+    function: Option<Function>,
+    is_parameter: bool,
+}
+
+/// Every definition in a document, in source order
+struct Outline {
+    symbols: Vec<Symbol>,
+}
+
+impl Outline {
+    fn new(program: &Node) -> Self {
+        let mut outline = Outline {
+            symbols: Vec::new(),
+        };
+        if let Node::Program(program) = program {
+            for statement in &program.statements {
+                outline.collect(statement, None);
+            }
+        }
+        outline
+    }
+
+    fn collect(&mut self, statement: &Node, scope: Option<&str>) {
+        match statement {
+            Node::Function(function) => {
+                self.symbols.push(Symbol {
+                    name: function.name.clone(),
+                    line: function.line,
+                    end_line: last_line(&function.body).max(function.line),
+                    scope: scope.map(str::to_string),
+                    function: Some(function.clone()),
+                    is_parameter: false,
+                });
+                for parameter in &function.parameters {
+                    self.symbols.push(Symbol {
+                        name: parameter.clone(),
+                        line: function.line,
+                        end_line: function.line,
+                        scope: Some(function.name.clone()),
+                        function: None,
+                        is_parameter: true,
+                    });
+                }
+                self.collect(&function.body, Some(&function.name));
+            }
+            Node::Assignment(assignment) => {
+                for name in assignment.names() {
+                    let defined = self
+                        .symbols
+                        .iter()
+                        .any(|symbol| symbol.name == name && symbol.scope.as_deref() == scope);
+                    if !defined {
+                        self.symbols.push(Symbol {
+                            name: name.to_string(),
+                            line: assignment.line,
+                            end_line: assignment.line,
+                            scope: scope.map(str::to_string),
+                            function: None,
+                            is_parameter: false,
+                        });
+                    }
+                }
+            }
+            Node::Block(block) => {
+                for statement in &block.statements {
+                    self.collect(statement, scope);
+                }
+            }
+            Node::If(if_stmt) => {
+                self.collect(&if_stmt.then_branch, scope);
+                if let Some(else_branch) = &if_stmt.else_branch {
+                    self.collect(else_branch, scope);
+                }
+            }
+            Node::While(while_stmt) => self.collect(&while_stmt.body, scope),
+            _ => {}
+        }
+    }
+
+    /// The definition `name` refers to on `line`: the innermost function's
+    /// parameters and locals first, then module-level names
+    fn resolve(&self, name: &str, line: usize) -> Option<&Symbol> {
+        let enclosing = self
+            .symbols
+            .iter()
+            .filter(|symbol| {
+                symbol.function.is_some() && symbol.line <= line && line <= symbol.end_line
+            })
+            .max_by_key(|symbol| symbol.line)
+            .map(|symbol| symbol.name.as_str());
+
+        let in_scope = |scope: Option<&str>| {
+            self.symbols
+                .iter()
+                .find(|symbol| symbol.name == name && symbol.scope.as_deref() == scope)
+        };
+        enclosing
+            .and_then(|function| in_scope(Some(function)))
+            .or_else(|| in_scope(None))
+    }
+}
+
+/// Last source line covered by a statement
+fn last_line(statement: &Node) -> usize {
+    match statement {
+        Node::Block(block) => block.statements.iter().map(last_line).max().unwrap_or(0),
+        Node::If(if_stmt) => last_line(&if_stmt.then_branch)
+            .max(if_stmt.else_branch.as_deref().map_or(0, last_line))
+            .max(if_stmt.line),
+        Node::While(while_stmt) => last_line(&while_stmt.body).max(while_stmt.line),
+        Node::Function(function) => last_line(&function.body).max(function.line),
+        other => other.line().unwrap_or(0),
+    }
+}
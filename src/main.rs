@@ -1,110 +1,716 @@
 mod ast;
+mod builtins;
+mod cache;
 mod cli;
 mod codegen;
+mod doctest;
+mod explain;
+mod highlight;
+mod info;
 mod lexer;
+mod lsp;
 mod parser;
+mod typecheck;
 
+use cache::BuildCache;
 use clap::Parser as ClapParser;
 use cli::{Cli, Commands};
-use codegen::CodeGenerator;
+use codegen::{
+    CodeGenerator, CodegenError, CodegenOptions, CodegenWarning, Fallback, LocalModule, build,
+};
 use lexer::Lexer;
-use parser::Parser as PyParser;
+use parser::{ParseError, Parser as PyParser};
 use std::fs;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
 use std::process;
-use std::process::Command;
+use std::process::{Child, Command, ExitStatus};
+use std::time::{Duration, Instant};
 
 fn main() {
     let cli = Cli::parse();
 
     match cli.command {
         Commands::Compile {
-            input_file,
+            input_files,
             output,
             emit_llvm,
-            optimization: _,
+            optimization: (optimization, size_level),
+            lto,
+            target_cpu,
+            target_features,
+            code_model,
+            reloc_model,
+            no_cache,
+            typed,
+            overflow_checks,
+            inline_threshold,
+            debug_info,
+            coverage,
+            instrument_profile,
+            sanitize,
+            fallback,
+            python_interop,
+            entry,
+            file_name,
+            static_link,
+            strip,
+            deterministic,
+            link_args,
+            keep_invalid_ir,
+            verbose,
         } => {
-            let input = match fs::read_to_string(&input_file) {
-                Ok(content) => content,
-                Err(e) => {
-                    eprintln!("Error reading file {input_file:?}: {e}");
-                    process::exit(1);
+            let options = CodegenOptions {
+                optimization,
+                size_level,
+                target_cpu,
+                target_features,
+                lto,
+                code_model,
+                reloc_model,
+                typed,
+                overflow_checks,
+                inline_threshold,
+                debug_info,
+                coverage,
+                instrument_profile,
+                trace: false,
+                count_steps: false,
+                sanitize,
+                fallback,
+                python_interop,
+                entry,
+                file_name: file_name.or_else(|| Some(source_path(&input_files[0]))),
+                static_link,
+                strip,
+                deterministic,
+                link_args,
+                keep_invalid_ir,
+            };
+
+            // Each file is a module named after its stem
+            let modules: Vec<String> = input_files
+                .iter()
+                .map(|file| {
+                    file.file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect();
+            if let Some(entry) = &options.entry
+                && !modules.contains(&entry.module)
+            {
+                eprintln!(
+                    "Error: entry module '{}' is not one of the input files",
+                    entry.module
+                );
+                process::exit(1);
+            }
+            let sources: Vec<String> = input_files.iter().map(|file| read_source(file)).collect();
+            let input = sources.join("\n");
+            let input_file = &input_files[0];
+
+            let output_file_name = if let Some(output_file) = &output {
+                output_file.to_str().unwrap_or("a.out").to_string()
+            } else {
+                "a.out".to_string()
+            };
+
+            // With `--fallback interp`, a program pycc cannot parse runs in the
+            // embedded interpreter, just like one it cannot compile
+            let parsed = match parse_modules(&input_files, &modules, &sources) {
+                Ok(parsed) => Some(parsed),
+                Err(errors) if options.fallback == Fallback::Interp => {
+                    for (input_file, error) in &errors {
+                        eprintln!(
+                            "{}:{}: warning: syntax error: {}; running the program with the embedded interpreter",
+                            input_file.display(),
+                            error.line,
+                            error.message
+                        );
+                    }
+                    None
                 }
+                Err(errors) => exit_on_syntax_errors(&errors),
             };
 
-            let lexer = Lexer::new(&input);
-            let mut py_parser = PyParser::new(lexer);
-            let ast = py_parser.parse_program();
+            // Native signatures are only sound once the annotations have been checked
+            if typed && let Some((ast, _)) = &parsed {
+                let diagnostics = typecheck::check(ast);
+                if !diagnostics.is_empty() {
+                    report_type_errors(input_file, &diagnostics);
+                    process::exit(1);
+                }
+            }
+
+            // Diagnostics come from the sources, so cached builds report them too
+            if let Some((ast, local_modules)) = &parsed {
+                report_warnings(
+                    &input_files,
+                    &modules,
+                    &codegen::lint::warnings(ast, local_modules),
+                );
+            }
+
+            // Reuse a previously compiled object when nothing relevant changed
+            let cache = BuildCache::for_source(input_file);
+            let module_sources: Vec<(&str, &str)> = modules
+                .iter()
+                .zip(&sources)
+                .map(|(module, source)| (module.as_str(), source.as_str()))
+                .collect();
+            let cache_key = BuildCache::program_key(&module_sources, &options.fingerprint());
+            if !emit_llvm
+                && !no_cache
+                && let Some(cached_object) = cache.lookup(&modules[0], &cache_key)
+            {
+                if let Err(e) = link_executable(
+                    cached_object.to_str().unwrap_or_default(),
+                    &output_file_name,
+                    &options,
+                    verbose,
+                ) {
+                    eprintln!("Error: {e}");
+                    process::exit(1);
+                }
+                if let Err(e) = cache.record_output(Path::new(&output_file_name)) {
+                    eprintln!("Warning: {e}");
+                }
+                return;
+            }
 
             // Generate LLVM IR
             let context = inkwell::context::Context::create();
-            let mut codegen = CodeGenerator::new(&context, "pycc_module");
-
-            match codegen.compile(&ast) {
-                Ok(_) => {
-                    if emit_llvm {
-                        // Print IR to stdout or write to file
-                        if let Some(output_file) = output {
-                            match codegen
-                                .write_ir_to_file(output_file.to_str().unwrap_or("output.ll"))
-                            {
-                                Ok(_) => println!("IR written to {output_file:?}"),
-                                Err(e) => eprintln!("Error writing IR to file: {e}"),
-                            }
-                        } else {
-                            codegen.print_ir();
-                        }
-                    } else {
-                        // Compile to executable
-                        let output_file_name = if let Some(output_file) = output {
-                            output_file.to_str().unwrap_or("a.out").to_string()
-                        } else {
-                            "a.out".to_string()
-                        };
-
-                        // Generate object file
-                        let object_file_name = format!("{output_file_name}.o");
-                        match codegen.write_object_to_file(&object_file_name) {
-                            Ok(_) => {
-                                // Link object file to create executable
-                                match Command::new("cc")
-                                    .args([&object_file_name, "-o", &output_file_name, "-no-pie"])
-                                    .status()
-                                {
-                                    Ok(status) => {
-                                        if status.success() {
-                                            println!(
-                                                "Successfully compiled to executable: {output_file_name}"
-                                            );
-
-                                            // Clean up object file
-                                            if std::fs::remove_file(&object_file_name).is_err() {
-                                                eprintln!(
-                                                    "Warning: Failed to remove temporary object file: {object_file_name}"
-                                                );
-                                            }
-                                        } else {
-                                            eprintln!("Error: Linking failed");
-                                            process::exit(1);
-                                        }
-                                    }
-                                    Err(e) => {
-                                        eprintln!("Failed to execute linker: {e}");
-                                        process::exit(1);
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                eprintln!("Error generating object file: {e}");
-                                process::exit(1);
-                            }
-                        }
+            let mut warnings = Vec::new();
+            let generated = match &parsed {
+                Some((ast, local_modules)) => build::generate(
+                    &context,
+                    &modules[0],
+                    ast,
+                    &input,
+                    &options,
+                    |codegen| {
+                        codegen.set_source_file(&input_file.to_string_lossy());
+                        codegen.set_local_modules(local_modules);
+                    },
+                    &mut warnings,
+                ),
+                None => {
+                    build::generate_fallback(&context, &modules[0], &input, &options, |codegen| {
+                        codegen.set_source_file(&input_file.to_string_lossy());
+                    })
+                }
+            };
+            report_warnings(&input_files, &modules, &warnings);
+            let codegen = match generated {
+                Ok(codegen) => codegen,
+                Err(e) => {
+                    report_codegen_error(&input_files, &modules, &e);
+                    process::exit(1);
+                }
+            };
+
+            if emit_llvm {
+                // Print IR to stdout or write to file
+                if let Some(output_file) = output {
+                    match codegen.write_ir_to_file(output_file.to_str().unwrap_or("output.ll")) {
+                        Ok(_) => println!("IR written to {output_file:?}"),
+                        Err(e) => eprintln!("Error writing IR to file: {e}"),
+                    }
+                } else {
+                    codegen.print_ir();
+                }
+            } else if no_cache {
+                // Link through a temporary object file next to the output
+                if let Err(e) = codegen.write_executable(&output_file_name) {
+                    eprintln!("Error: {e}");
+                    process::exit(1);
+                }
+                report_executable(&output_file_name, verbose);
+            } else {
+                // Generate the object file into the cache, then move it into place
+                let object = match cache.prepare(&modules[0]) {
+                    Ok(object) => object,
+                    Err(e) => {
+                        eprintln!("Error preparing build cache: {e}");
+                        process::exit(1);
                     }
+                };
+                if let Err(e) = write_linker_input(&codegen, &object.to_string_lossy(), &options) {
+                    let _ = object.close();
+                    eprintln!("Error generating object file: {e}");
+                    process::exit(1);
                 }
+                let object_path = match cache.commit(&modules[0], &cache_key, object) {
+                    Ok(path) => path,
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        process::exit(1);
+                    }
+                };
+
+                if let Err(e) = link_executable(
+                    object_path.to_str().unwrap_or_default(),
+                    &output_file_name,
+                    &options,
+                    verbose,
+                ) {
+                    eprintln!("Error: {e}");
+                    process::exit(1);
+                }
+                if let Err(e) = cache.record_output(Path::new(&output_file_name)) {
+                    eprintln!("Warning: {e}");
+                }
+            }
+        }
+        Commands::Run {
+            input_file,
+            stdin,
+            trace,
+            stats,
+            args,
+        } => {
+            let source = read_source(&input_file);
+            let stdin = stdin.map(|path| {
+                fs::File::open(&path).unwrap_or_else(|e| {
+                    eprintln!("Error: Failed to open {}: {e}", path.display());
+                    process::exit(1);
+                })
+            });
+            let ast = parse_source(&input_file, &source);
+            let input_files = [input_file.clone()];
+            report_warnings(&input_files, &[], &codegen::lint::warnings(&ast, &[]));
+
+            let context = inkwell::context::Context::create();
+            let module_name = input_file.file_stem().unwrap_or_default().to_string_lossy();
+            let options = CodegenOptions {
+                file_name: Some(source_path(&input_file)),
+                trace,
+                count_steps: stats,
+                ..CodegenOptions::default()
+            };
+            let mut warnings = Vec::new();
+            let generated = build::generate(
+                &context,
+                &module_name,
+                &ast,
+                &source,
+                &options,
+                |codegen| {
+                    codegen.set_source_file(&input_file.to_string_lossy());
+                    codegen.set_source_text(&source);
+                },
+                &mut warnings,
+            );
+            report_warnings(&input_files, &[], &warnings);
+            let codegen = generated.unwrap_or_else(|e| {
+                report_codegen_error(&input_files, &[], &e);
+                process::exit(1);
+            });
+            // A fresh file that is removed on drop; `process::exit` skips
+            // destructors, so it is closed explicitly before exiting
+            let executable = tempfile::Builder::new()
+                .prefix("pycc-run-")
+                .tempfile()
+                .map(|file| file.into_temp_path())
+                .unwrap_or_else(|e| {
+                    eprintln!("Error: Failed to create a temporary executable: {e}");
+                    process::exit(1);
+                });
+            if let Err(e) = codegen.write_executable(&executable.to_string_lossy()) {
+                let _ = executable.close();
+                eprintln!("Error: {e}");
+                process::exit(1);
+            }
+
+            let mut program = Command::new(&*executable);
+            program.args(&args);
+            if let Some(stdin) = stdin {
+                program.stdin(stdin);
+            }
+            // The program writes its step count here when it exits normally
+            let steps_file = stats.then(|| {
+                tempfile::Builder::new()
+                    .prefix("pycc-steps-")
+                    .tempfile()
+                    .map(|file| file.into_temp_path())
+                    .unwrap_or_else(|e| {
+                        eprintln!("Error: Failed to create a temporary file: {e}");
+                        process::exit(1);
+                    })
+            });
+            if let Some(steps_file) = &steps_file {
+                program.env("PYCC_STEPS_FILE", steps_file);
+            }
+            // Output printed before a runtime error reaches the terminal as it happens
+            let started = Instant::now();
+            let status = program.spawn().and_then(|mut child| {
+                let Some(steps_file) = &steps_file else {
+                    return child.wait();
+                };
+                let (status, usage) = wait_with_usage(&child)?;
+                let steps = fs::read_to_string(steps_file)
+                    .ok()
+                    .and_then(|text| text.trim().parse().ok());
+                report_stats(started.elapsed(), &usage, steps);
+                Ok(status)
+            });
+            if let Some(steps_file) = steps_file {
+                let _ = steps_file.close();
+            }
+            let _ = executable.close();
+            match status {
+                Ok(status) => process::exit(exit_code(status)),
+                Err(e) => {
+                    eprintln!("Error: Failed to run program: {e}");
+                    process::exit(1);
+                }
+            }
+        }
+        Commands::Clean {
+            dir,
+            recursive,
+            dry_run,
+        } => match cache::clean(&dir, recursive, dry_run) {
+            Ok(removed) if removed.is_empty() => println!("Nothing to clean"),
+            Ok(removed) => {
+                let verb = if dry_run { "Would remove" } else { "Removed" };
+                for path in removed {
+                    println!("{verb} {}", path.display());
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {e}");
+                process::exit(1);
+            }
+        },
+        Commands::Highlight { input_file, format } => {
+            let source = read_source(&input_file);
+            print!("{}", highlight::render(&source, format));
+        }
+        Commands::Info { json } => {
+            let info = info::compiler_info();
+            if json {
+                println!("{}", info.to_json());
+            } else {
+                print!("{}", info.render());
+            }
+        }
+        Commands::Lsp => {
+            let stdin = std::io::stdin();
+            match lsp::run(&mut stdin.lock(), &mut std::io::stdout()) {
+                // The protocol expects a non-zero status when exit comes without shutdown
+                Ok(shutdown_requested) => process::exit(if shutdown_requested { 0 } else { 1 }),
                 Err(e) => {
-                    eprintln!("Error compiling to LLVM IR: {e}");
+                    eprintln!("Error: {e}");
                     process::exit(1);
                 }
             }
         }
+        Commands::Explain { ir_file, source } => {
+            let ir = read_source(&ir_file);
+            let Some(source_file) = source.or_else(|| explain::source_file(&ir)) else {
+                eprintln!(
+                    "No debug info found in {ir_file:?}; emit it with `pycc compile -g --emit-llvm`"
+                );
+                process::exit(1);
+            };
+            let source = read_source(&source_file);
+            print!("{}", explain::explain(&ir, &source));
+        }
+        Commands::Doctest {
+            input_file,
+            compiled,
+        } => {
+            let source = read_source(&input_file);
+            let ast = parse_source(&input_file, &source);
+            let module = input_file.file_stem().unwrap_or_default().to_string_lossy();
+            let examples = doctest::extract(&ast, &module);
+            let path = PathBuf::from(source_path(&input_file));
+
+            let mut runs = vec![(
+                "interpreted",
+                doctest::run_interpreted(&source, &path, &examples),
+            )];
+            if compiled {
+                runs.push((
+                    "compiled",
+                    doctest::run_compiled_examples(&ast, &source, &path, &examples),
+                ));
+            }
+            let mut failed = false;
+            for (mode, outputs) in runs {
+                let outputs = outputs.unwrap_or_else(|e| {
+                    eprintln!("Error: {e}");
+                    process::exit(1);
+                });
+                let mut failures = 0;
+                for (example, got) in examples.iter().zip(&outputs) {
+                    if !example.matches(got) {
+                        println!("{}", example.report(&input_file, got));
+                        failures += 1;
+                    }
+                }
+                println!(
+                    "{mode}: {} example(s), {failures} failure(s)",
+                    examples.len()
+                );
+                failed |= failures > 0;
+            }
+            if failed {
+                process::exit(1);
+            }
+        }
+        Commands::Typecheck { input_file } => {
+            let input = read_source(&input_file);
+            let ast = parse_source(&input_file, &input);
+
+            let diagnostics = typecheck::check(&ast);
+            if diagnostics.is_empty() {
+                println!("No type errors found in {input_file:?}");
+            } else {
+                report_type_errors(&input_file, &diagnostics);
+                process::exit(1);
+            }
+        }
+    }
+}
+
+/// Parse each module and join their statements into one program, in order
+///
+/// Fails with the syntax errors of every module that has any.
+///
+/// The docstring of the first module becomes the program's. The returned
+/// modules say which statements came from which file.
+fn parse_modules(
+    input_files: &[PathBuf],
+    modules: &[String],
+    sources: &[String],
+) -> Result<(ast::Node, Vec<LocalModule>), Vec<(PathBuf, ParseError)>> {
+    let mut statements = Vec::new();
+    let mut docstring = None;
+    let mut local_modules = Vec::new();
+    let mut errors = Vec::new();
+    for (i, (input_file, source)) in input_files.iter().zip(sources).enumerate() {
+        match parse_file(source) {
+            Ok(ast::Node::Program(program)) => {
+                local_modules.push(LocalModule {
+                    name: modules[i].clone(),
+                    statements: program.statements.len(),
+                });
+                statements.extend(program.statements);
+                if i == 0 {
+                    docstring = program.docstring;
+                }
+            }
+            Ok(_) => {}
+            Err(file_errors) => errors.extend(
+                file_errors
+                    .into_iter()
+                    .map(|error| (input_file.clone(), error)),
+            ),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let program = ast::Node::Program(ast::Program {
+        statements,
+        docstring,
+    });
+    Ok((program, local_modules))
+}
+
+/// Status to exit with for a finished child, using the shell's 128 + signal
+/// convention for programs killed by a signal
+fn exit_code(status: ExitStatus) -> i32 {
+    status
+        .code()
+        .or_else(|| status.signal().map(|signal| 128 + signal))
+        .unwrap_or(1)
+}
+
+/// Wait for `child` with `wait4`, which also returns the resources it used
+fn wait_with_usage(child: &Child) -> std::io::Result<(ExitStatus, libc::rusage)> {
+    let mut status = 0;
+    let mut usage = std::mem::MaybeUninit::<libc::rusage>::uninit();
+    loop {
+        // SAFETY: `status` and `usage` are valid out pointers for the call
+        let pid = unsafe {
+            libc::wait4(
+                child.id() as libc::pid_t,
+                &mut status,
+                0,
+                usage.as_mut_ptr(),
+            )
+        };
+        if pid != -1 {
+            break;
+        }
+        let error = std::io::Error::last_os_error();
+        if error.kind() != std::io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+    // SAFETY: `wait4` succeeded, so it filled in `usage`
+    Ok((ExitStatus::from_raw(status), unsafe { usage.assume_init() }))
+}
+
+/// Print `--stats` for a finished program: its wall-clock time, CPU time,
+/// peak resident memory and, unless it was killed, the statements it executed
+fn report_stats(elapsed: Duration, usage: &libc::rusage, steps: Option<u64>) {
+    let seconds = |time: libc::timeval| time.tv_sec as f64 + time.tv_usec as f64 / 1e6;
+    // Linux reports the peak in KiB, macOS in bytes
+    let peak_kib = if cfg!(target_os = "macos") {
+        usage.ru_maxrss / 1024
+    } else {
+        usage.ru_maxrss
+    };
+    let steps = steps.map_or_else(String::new, |steps| format!(", {steps} steps"));
+    eprintln!(
+        "pycc: elapsed {:.3}s, user {:.3}s, sys {:.3}s, peak memory {peak_kib} KiB{steps}",
+        elapsed.as_secs_f64(),
+        seconds(usage.ru_utime),
+        seconds(usage.ru_stime),
+    );
+}
+
+/// Absolute path of a source file, which CPython gives the main module as `__file__`
+fn source_path(input_file: &Path) -> String {
+    std::path::absolute(input_file)
+        .unwrap_or_else(|_| input_file.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Read a source file, exiting on failure
+fn read_source(input_file: &Path) -> String {
+    match fs::read_to_string(input_file) {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("Error reading file {input_file:?}: {e}");
+            process::exit(1);
+        }
+    }
+}
+
+/// Parse a source file, exiting on syntax errors
+fn parse_source(input_file: &Path, source: &str) -> ast::Node {
+    parse_file(source).unwrap_or_else(|errors| {
+        let errors: Vec<_> = errors
+            .into_iter()
+            .map(|error| (input_file.to_path_buf(), error))
+            .collect();
+        exit_on_syntax_errors(&errors)
+    })
+}
+
+/// Parse a source file, failing with its syntax errors if it has any
+fn parse_file(source: &str) -> Result<ast::Node, Vec<ParseError>> {
+    let mut parser = PyParser::new(Lexer::new(source));
+    let program = parser.parse_program();
+    if parser.errors().is_empty() {
+        Ok(program)
+    } else {
+        Err(parser.errors().to_vec())
+    }
+}
+
+/// Report syntax errors and exit with status 2, which tells them apart from
+/// a program that ran and failed
+fn exit_on_syntax_errors(errors: &[(PathBuf, ParseError)]) -> ! {
+    for (input_file, error) in errors {
+        eprintln!(
+            "{}:{}: syntax error: {}",
+            input_file.display(),
+            error.line,
+            error.message
+        );
+    }
+    eprintln!("Found {} syntax error(s)", errors.len());
+    process::exit(2);
+}
+
+fn report_type_errors(input_file: &Path, diagnostics: &[String]) {
+    for diagnostic in diagnostics {
+        eprintln!("{}: type error: {diagnostic}", input_file.display());
+    }
+    eprintln!("Found {} type error(s)", diagnostics.len());
+}
+
+fn report_warnings(input_files: &[PathBuf], modules: &[String], warnings: &[CodegenWarning]) {
+    for warning in warnings {
+        let input_file = module_file(input_files, modules, warning.module.as_deref());
+        match warning.line {
+            0 => eprintln!("{}: warning: {}", input_file.display(), warning.message),
+            line => eprintln!(
+                "{}:{line}: warning: {}",
+                input_file.display(),
+                warning.message
+            ),
+        }
+    }
+}
+
+/// Print why code generation failed, at the line of the statement it failed on
+fn report_codegen_error(input_files: &[PathBuf], modules: &[String], error: &CodegenError) {
+    let input_file = module_file(input_files, modules, error.module.as_deref());
+    match error.line {
+        Some(line) => eprintln!("{}:{line}: error: {error}", input_file.display()),
+        None => eprintln!("{}: error: {error}", input_file.display()),
+    }
+}
+
+/// Input file a diagnostic belongs to, the first one when its module is unknown
+fn module_file<'a>(
+    input_files: &'a [PathBuf],
+    modules: &[String],
+    module: Option<&str>,
+) -> &'a Path {
+    module
+        .and_then(|module| modules.iter().position(|name| name == module))
+        .map_or(&input_files[0], |index| &input_files[index])
+}
+
+/// Write the module in the form the linker expects: bitcode for LTO builds, an object otherwise
+fn write_linker_input(
+    codegen: &CodeGenerator,
+    file_name: &str,
+    options: &CodegenOptions,
+) -> Result<(), String> {
+    if options.lto.is_enabled() {
+        codegen.write_bitcode_to_file(file_name)
+    } else {
+        codegen.write_object_to_file(file_name)
+    }
+}
+
+/// Link an object file into an executable, reporting its size if `verbose`
+fn link_executable(
+    object_file_name: &str,
+    output_file_name: &str,
+    options: &CodegenOptions,
+    verbose: bool,
+) -> Result<(), String> {
+    let status = options
+        .linker_command(object_file_name, output_file_name)?
+        .status()
+        .map_err(|e| format!("Failed to execute linker: {e}"))?;
+    if !status.success() {
+        return Err("Linking failed".to_string());
+    }
+    report_executable(output_file_name, verbose);
+    Ok(())
+}
+
+/// Announce a linked executable, with its size if `verbose`
+fn report_executable(output_file_name: &str, verbose: bool) {
+    println!("Successfully compiled to executable: {output_file_name}");
+    if verbose {
+        match fs::metadata(output_file_name) {
+            Ok(metadata) => println!("Executable size: {} bytes", metadata.len()),
+            Err(e) => eprintln!("Warning: Failed to read {output_file_name}: {e}"),
+        }
     }
 }
@@ -1,4 +1,4 @@
 #[allow(clippy::module_inception)]
 pub mod parser;
 
-pub use parser::Parser;
+pub use parser::{ParseError, Parser};
@@ -1,12 +1,125 @@
 use crate::ast::{
-    Assignment, Binary, BinaryOperator, Identifier, Literal, LiteralValue, Node, Program,
+    Assignment, Binary, BinaryOperator, Block, Declaration, Dict, Identifier, If, List, Literal,
+    LiteralValue, Node, Program, Set, Tuple, Unary, UnaryOperator,
 };
 use crate::lexer::{Lexer, Token};
+use std::ops::Range;
+
+/// A syntax error found while parsing
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// Source line (1-based) the offending text starts on
+    pub line: usize,
+    /// Character offsets of the offending text
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+/// Nesting depth allowed by default
+///
+/// Far deeper than real code nests, while leaving room for the parser and
+/// the passes over the tree on a 2 MiB thread stack in unoptimized builds.
+pub const DEFAULT_MAX_DEPTH: usize = 100;
+
+/// Tokens one logical line may span by default
+///
+/// An unclosed bracket makes the rest of the file one statement; the budget
+/// turns that into a prompt error instead of a long parse of the whole file.
+pub const DEFAULT_MAX_STATEMENT_TOKENS: usize = 1_000_000;
+
+/// Longest program, in characters, parsed by default
+pub const DEFAULT_MAX_SOURCE_LEN: usize = 16 * 1024 * 1024;
+
+/// Python's operator precedence levels, loosest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    Lowest,
+    Or,
+    And,
+    Not,
+    /// `==`, `!=`, `<`, `>`, `<=`, `>=`
+    Comparison,
+    #[allow(dead_code)]
+    BitOr,
+    #[allow(dead_code)]
+    BitXor,
+    #[allow(dead_code)]
+    BitAnd,
+    /// `<<`, `>>`
+    #[allow(dead_code)]
+    Shift,
+    /// `+`, `-`
+    Sum,
+    /// `*`, `/`, `//`, `%`
+    Product,
+    /// Prefix `+` and `-`
+    Unary,
+    Power,
+}
+
+/// The binary operator a token stands for and its precedence
+fn infix_operator(token: &Token) -> Option<(BinaryOperator, Precedence)> {
+    let entry = match token {
+        Token::Or => (BinaryOperator::Or, Precedence::Or),
+        Token::And => (BinaryOperator::And, Precedence::And),
+        Token::Equal => (BinaryOperator::Equal, Precedence::Comparison),
+        Token::NotEqual => (BinaryOperator::NotEqual, Precedence::Comparison),
+        Token::Less => (BinaryOperator::Less, Precedence::Comparison),
+        Token::Greater => (BinaryOperator::Greater, Precedence::Comparison),
+        Token::LessEqual => (BinaryOperator::LessEqual, Precedence::Comparison),
+        Token::GreaterEqual => (BinaryOperator::GreaterEqual, Precedence::Comparison),
+        Token::Plus => (BinaryOperator::Add, Precedence::Sum),
+        Token::Minus => (BinaryOperator::Subtract, Precedence::Sum),
+        Token::Multiply => (BinaryOperator::Multiply, Precedence::Product),
+        Token::Divide => (BinaryOperator::Divide, Precedence::Product),
+        Token::FloorDivide => (BinaryOperator::FloorDivide, Precedence::Product),
+        Token::Modulo => (BinaryOperator::Modulo, Precedence::Product),
+        Token::Power => (BinaryOperator::Power, Precedence::Power),
+        _ => return None,
+    };
+    Some(entry)
+}
+
+/// What an expression is called when it cannot be assigned to, as in CPython's
+/// "cannot assign to literal"
+fn invalid_target(target: &Node) -> Option<&'static str> {
+    let kind = match target {
+        Node::Identifier(_) | Node::Subscript(_) => return None,
+        Node::Literal(_) => "literal",
+        Node::Call(_) => "function call",
+        Node::Await(_) => "await expression",
+        Node::Tuple(_) => "tuple",
+        Node::List(_) => "list",
+        Node::Dict(_) => "dict literal",
+        Node::Set(_) => "set display",
+        _ => "expression",
+    };
+    Some(kind)
+}
 
 pub struct Parser {
     lexer: Lexer,
     current_token: Token,
     peek_token: Token,
+    current_line: usize,
+    peek_line: usize,
+    current_span: Range<usize>,
+    peek_span: Range<usize>,
+    errors: Vec<ParseError>,
+    /// Brackets opened by the tokens consumed so far and not yet closed, with
+    /// where each was opened
+    open_brackets: Vec<(char, usize, Range<usize>)>,
+    /// Nested expressions and blocks currently being parsed
+    depth: usize,
+    max_depth: usize,
+    /// Tokens consumed since the last logical line ended
+    statement_tokens: usize,
+    max_statement_tokens: usize,
+    max_source_len: usize,
+    /// Whether parsing stopped early; the rest of the input reads as end of file
+    halted: bool,
+    /// Tokens consumed in total, to tell whether a failed statement moved on
+    tokens_consumed: usize,
 }
 
 impl Parser {
@@ -15,6 +128,19 @@ impl Parser {
             lexer,
             current_token: Token::Eof,
             peek_token: Token::Eof,
+            current_line: 1,
+            peek_line: 1,
+            current_span: 0..0,
+            peek_span: 0..0,
+            errors: Vec::new(),
+            open_brackets: Vec::new(),
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            statement_tokens: 0,
+            max_statement_tokens: DEFAULT_MAX_STATEMENT_TOKENS,
+            max_source_len: DEFAULT_MAX_SOURCE_LEN,
+            halted: false,
+            tokens_consumed: 0,
         };
         parser.next_token(); // Initialize current_token
         parser.next_token(); // Initialize peek_token
@@ -22,8 +148,169 @@ impl Parser {
     }
 
     fn next_token(&mut self) {
+        if self.halted {
+            return;
+        }
+        self.statement_tokens += 1;
+        if self.statement_tokens > self.max_statement_tokens {
+            self.halt_on_long_statement();
+            return;
+        }
+        self.tokens_consumed += 1;
         self.current_token = self.peek_token.clone();
+        self.current_line = self.peek_line;
+        self.current_span = self.peek_span.clone();
+        if self.current_token == Token::Newline {
+            self.statement_tokens = 0;
+        }
+        let bracket = match self.current_token {
+            Token::LeftParen => Some('('),
+            Token::LeftBracket => Some('['),
+            Token::LeftBrace => Some('{'),
+            _ => None,
+        };
+        if let Some(bracket) = bracket {
+            self.open_brackets
+                .push((bracket, self.current_line, self.current_span.clone()));
+        } else if matches!(
+            self.current_token,
+            Token::RightParen | Token::RightBracket | Token::RightBrace
+        ) {
+            self.open_brackets.pop();
+        }
+
         self.peek_token = self.lexer.next_token();
+        // Line breaks and comments inside brackets do not end the statement
+        while !self.open_brackets.is_empty()
+            && matches!(
+                self.peek_token,
+                Token::Newline | Token::Indent | Token::Dedent | Token::Comment(_)
+            )
+        {
+            self.peek_token = self.lexer.next_token();
+        }
+        self.peek_line = self.lexer.line();
+        self.peek_span = self.lexer.span();
+        if let Token::Error(message) = &self.peek_token {
+            self.errors.push(ParseError {
+                line: self.peek_line,
+                span: self.peek_span.clone(),
+                message: message.clone(),
+            });
+        }
+    }
+
+    /// Limit how deeply expressions and blocks may nest
+    ///
+    /// Parsing recurses once per level, so the limit keeps pathological input
+    /// like `((((...))))` from overflowing the stack.
+    #[allow(dead_code)]
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// Limit how many tokens a single logical line may span
+    ///
+    /// Past the limit parsing stops with one error, blaming the bracket left
+    /// open if there is one.
+    #[allow(dead_code)]
+    pub fn set_max_statement_tokens(&mut self, max_statement_tokens: usize) {
+        self.max_statement_tokens = max_statement_tokens;
+    }
+
+    /// Limit the length of the program, in characters, that will be parsed at all
+    #[allow(dead_code)]
+    pub fn set_max_source_len(&mut self, max_source_len: usize) {
+        self.max_source_len = max_source_len;
+    }
+
+    /// Stop parsing because the current statement ran past its token budget
+    fn halt_on_long_statement(&mut self) {
+        let error = match self.open_brackets.first() {
+            Some((bracket, line, span)) => ParseError {
+                line: *line,
+                span: span.clone(),
+                message: format!("'{bracket}' was never closed"),
+            },
+            None => ParseError {
+                line: self.current_line,
+                span: self.current_span.clone(),
+                message: format!(
+                    "statement too long (more than {} tokens)",
+                    self.max_statement_tokens
+                ),
+            },
+        };
+        self.errors.push(error);
+        self.halt();
+    }
+
+    /// Treat the rest of the input as the end of the file
+    fn halt(&mut self) {
+        self.halted = true;
+        self.current_token = Token::Eof;
+        self.peek_token = Token::Eof;
+    }
+
+    /// Report an error at the current token and skip the rest of the statement,
+    /// with any block it opens, so the enclosing levels fail without reporting
+    /// it again
+    fn error(&mut self, message: &str) {
+        // Whatever fails once parsing has stopped is fallout of the reported error
+        if self.halted {
+            return;
+        }
+        self.errors.push(ParseError {
+            line: self.current_line,
+            span: self.current_span.clone(),
+            message: message.to_string(),
+        });
+        self.skip_statement();
+    }
+
+    /// Report a syntax error at the current token and give up on the statement
+    ///
+    /// A lexer error token was reported when it was read, so failing on one
+    /// only skips the statement.
+    fn fail<T>(&mut self, message: &str) -> Option<T> {
+        if matches!(self.current_token, Token::Error(_)) {
+            self.skip_statement();
+        } else {
+            self.error(message);
+        }
+        None
+    }
+
+    /// Report an INDENT no block was opened for and consume it
+    ///
+    /// The indented lines still parse as statements, so errors in them are
+    /// reported too.
+    fn unexpected_indent(&mut self) {
+        self.errors.push(ParseError {
+            line: self.current_line,
+            span: self.current_span.clone(),
+            message: "unexpected indent".to_string(),
+        });
+        self.next_token(); // consume INDENT
+    }
+
+    /// Run `parse` one nesting level deeper, failing once the limit is reached
+    fn nested<T>(&mut self, what: &str, parse: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        if self.depth >= self.max_depth {
+            return self.fail(&format!("{what} too deeply nested"));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Syntax errors found so far, in source order
+    ///
+    /// The statements around an error may be missing from the parsed program or
+    /// incomplete, so a program with errors should not be compiled.
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
     }
 
     fn peek_token(&self) -> &Token {
@@ -32,101 +319,163 @@ impl Parser {
 
     pub fn parse_program(&mut self) -> Node {
         let mut program = Program::new();
+        let source_len = self.lexer.source_len();
+        if source_len > self.max_source_len {
+            self.errors.push(ParseError {
+                line: 1,
+                span: 0..0,
+                message: format!(
+                    "program too long ({source_len} characters, the limit is {})",
+                    self.max_source_len
+                ),
+            });
+            self.halt();
+        }
 
         while self.current_token != Token::Eof {
-            // Skip comment tokens
-            if matches!(self.current_token, Token::Comment(_)) {
+            // Top-level code is not indented; its DEDENT is skipped below
+            if self.current_token == Token::Indent {
+                self.unexpected_indent();
+                continue;
+            }
+            // Skip comment and layout tokens between statements
+            if matches!(
+                self.current_token,
+                Token::Comment(_) | Token::Newline | Token::Semicolon | Token::Dedent
+            ) {
                 self.next_token();
                 continue;
             }
 
+            let consumed = self.tokens_consumed;
             if let Some(statement) = self.parse_statement() {
                 program.statements.push(statement);
-            } else {
-                // If we couldn't parse a statement, advance to the next token
-                // to avoid infinite loops
+            } else if self.tokens_consumed == consumed {
+                // A failed statement skips itself; make sure the loop moves on
                 self.next_token();
             }
         }
 
+        // A leading string literal is the module docstring, not code
+        if let Some(docstring) = program.statements.first().and_then(Node::as_docstring) {
+            program.docstring = Some(docstring.to_string());
+            program.statements.remove(0);
+        }
+
         Node::Program(program)
     }
 
     fn parse_statement(&mut self) -> Option<Node> {
-        match &self.current_token {
-            Token::Def => self.parse_function_definition(),
-            Token::Identifier(_) => {
-                // Could be an assignment or a function call
-                self.parse_statement_with_identifier()
+        let statement = match &self.current_token {
+            Token::Def => return self.parse_function_definition(),
+            Token::Async => return self.parse_async_function_definition(),
+            Token::If => return self.parse_if_statement(),
+            Token::Identifier(_) if self.peek_token() == &Token::Colon => {
+                self.parse_annotated_assignment()
             }
             Token::Return => self.parse_return_statement(),
+            Token::Import => self.parse_import_statement(),
             _ => {
                 // For now, treat everything else as an expression statement
                 self.parse_expression_statement()
             }
+        }?;
+
+        // A simple statement ends the line or is followed by `;`, so
+        // `x = 1 y = 2` is an error rather than two statements
+        if !self.at_statement_end() {
+            return self.fail("invalid syntax");
         }
+        Some(statement)
     }
 
-    fn parse_statement_with_identifier(&mut self) -> Option<Node> {
-        // Look ahead to see if this is an assignment
-        if let Token::Identifier(name) = &self.current_token {
-            // Check if the next token is '=' for assignment
-            if self.peek_token() == &Token::Assign {
-                // This is an assignment
-                let name_clone = name.clone();
-                self.next_token(); // consume identifier
-                self.next_token(); // consume '='
-                if let Some(value) = self.parse_expression() {
-                    return Some(Node::Assignment(Assignment {
-                        name: name_clone,
-                        value: Box::new(value),
-                    }));
-                }
-            } else {
-                // This is a function call or other expression
-                return self.parse_expression_statement();
-            }
-        }
+    /// Parse an annotated assignment `x: int = 5` or bare annotation `x: int`
+    fn parse_annotated_assignment(&mut self) -> Option<Node> {
+        let line = self.current_line;
+        let Token::Identifier(name) = &self.current_token else {
+            return self.fail("invalid syntax");
+        };
+        let name = name.clone();
+        self.next_token(); // consume identifier
+        self.next_token(); // consume ':'
+        let annotation = self.parse_annotation()?;
 
-        None
+        if self.current_token != Token::Assign {
+            return Some(Node::Declaration(Declaration { name, annotation }));
+        }
+        self.next_token(); // consume '='
+        let value = self.parse_expression()?;
+        Some(Node::Assignment(Assignment {
+            targets: vec![Node::Identifier(Identifier { name })],
+            value: Box::new(value),
+            annotation: Some(annotation),
+            line,
+        }))
     }
 
     fn parse_return_statement(&mut self) -> Option<Node> {
+        let line = self.current_line;
         self.next_token(); // consume 'return'
 
         // Check if there's a return value
-        if self.current_token != Token::Eof
-            && self.current_token != Token::Semicolon
-            && let Some(value) = self.parse_expression()
+        let value = if self.at_statement_end() {
+            None
+        } else {
+            Some(Box::new(self.parse_expression()?))
+        };
+
+        Some(Node::Return(crate::ast::Return { value, line }))
+    }
+
+    fn parse_import_statement(&mut self) -> Option<Node> {
+        let line = self.current_line;
+        self.next_token(); // consume 'import'
+
+        let module = self.parse_dotted_name()?;
+        Some(Node::Import(crate::ast::Import { module, line }))
+    }
+
+    /// Parse `name` or `name.attr...` starting at an identifier
+    fn parse_dotted_name(&mut self) -> Option<String> {
+        let Token::Identifier(name) = &self.current_token else {
+            return self.fail("invalid syntax");
+        };
+        let mut dotted = name.clone();
+        self.next_token(); // consume identifier
+
+        while self.current_token == Token::Dot
+            && let Token::Identifier(attribute) = self.peek_token()
         {
-            return Some(Node::Return(crate::ast::Return {
-                value: Some(Box::new(value)),
-            }));
+            dotted.push('.');
+            dotted.push_str(attribute);
+            self.next_token(); // consume '.'
+            self.next_token(); // consume attribute
         }
-
-        Some(Node::Return(crate::ast::Return { value: None }))
+        Some(dotted)
     }
 
     fn parse_function_definition(&mut self) -> Option<Node> {
+        let line = self.current_line;
         self.next_token(); // consume 'def'
 
         // Parse function name
         let name = if let Token::Identifier(name) = &self.current_token {
             name.clone()
         } else {
-            return None;
+            return self.fail("invalid syntax");
         };
 
         self.next_token(); // consume function name
 
         // Parse parameters
         if self.current_token != Token::LeftParen {
-            return None;
+            return self.fail("invalid syntax");
         }
 
         self.next_token(); // consume '('
 
         let mut parameters = Vec::new();
+        let mut parameter_types = Vec::new();
 
         // Parse parameter list
         if self.current_token != Token::RightParen {
@@ -134,6 +483,13 @@ impl Parser {
                 parameters.push(param_name.clone());
                 self.next_token(); // consume parameter name
 
+                if self.current_token == Token::Colon {
+                    self.next_token(); // consume ':'
+                    parameter_types.push(Some(self.parse_annotation()?));
+                } else {
+                    parameter_types.push(None);
+                }
+
                 if self.current_token == Token::Comma {
                     self.next_token(); // consume ','
                 } else {
@@ -143,131 +499,397 @@ impl Parser {
         }
 
         if self.current_token != Token::RightParen {
-            return None;
+            return self.fail("invalid syntax");
         }
 
         self.next_token(); // consume ')'
 
+        let return_type = if self.current_token == Token::Arrow {
+            self.next_token(); // consume '->'
+            Some(self.parse_annotation()?)
+        } else {
+            None
+        };
+
         if self.current_token != Token::Colon {
-            return None;
+            return self.fail("expected ':'");
         }
 
         self.next_token(); // consume ':'
 
-        // Parse function body
-        // For now, we'll just parse the return statement
-        // In a full implementation, we'd parse a block of statements
-        let body = self.parse_return_statement()?;
+        let mut body = self.parse_suite()?;
+        let docstring = Self::take_docstring(&mut body);
 
         // Create Function node
         Some(Node::Function(crate::ast::Function {
             name,
             parameters,
+            parameter_types,
+            return_type,
             body: Box::new(body),
+            docstring,
+            is_async: false,
+            line,
         }))
     }
 
-    fn parse_expression_statement(&mut self) -> Option<Node> {
-        self.parse_expression().map(|expression| {
-            Node::ExpressionStatement(crate::ast::Expression {
-                expression: Box::new(expression),
-            })
-        })
+    fn parse_async_function_definition(&mut self) -> Option<Node> {
+        self.next_token(); // consume 'async'
+        if self.current_token != Token::Def {
+            return self.fail("invalid syntax"); // Only `async def` is supported
+        }
+        match self.parse_function_definition()? {
+            Node::Function(mut function) => {
+                function.is_async = true;
+                Some(Node::Function(function))
+            }
+            other => Some(other),
+        }
     }
 
-    fn parse_expression(&mut self) -> Option<Node> {
-        self.parse_additive()
+    /// Parse a type annotation and return its normalized source text
+    ///
+    /// Annotations have no semantics yet, so only the forms used in type hints
+    /// are accepted: dotted names, `None`, string forward references,
+    /// subscripts such as `dict[str, int]` and `|` unions.
+    fn parse_annotation(&mut self) -> Option<String> {
+        let mut annotation = self.parse_annotation_atom()?;
+
+        while self.current_token == Token::Pipe {
+            self.next_token(); // consume '|'
+            annotation.push_str(" | ");
+            annotation.push_str(&self.parse_annotation_atom()?);
+        }
+
+        Some(annotation)
     }
 
-    fn parse_additive(&mut self) -> Option<Node> {
-        let mut left = self.parse_multiplicative()?;
+    fn parse_annotation_atom(&mut self) -> Option<String> {
+        let mut annotation = match &self.current_token {
+            Token::Identifier(name) => name.clone(),
+            Token::None => "None".to_string(),
+            Token::String(text) => text.clone(),
+            _ => return self.fail("invalid syntax"),
+        };
+        self.next_token(); // consume name
 
-        while matches!(self.current_token, Token::Plus | Token::Minus) {
-            let operator = match self.current_token {
-                Token::Plus => BinaryOperator::Add,
-                Token::Minus => BinaryOperator::Subtract,
-                _ => break,
+        while self.current_token == Token::Dot {
+            self.next_token(); // consume '.'
+            let Token::Identifier(attribute) = &self.current_token else {
+                return self.fail("invalid syntax");
             };
+            annotation.push('.');
+            annotation.push_str(attribute);
+            self.next_token(); // consume attribute
+        }
 
-            self.next_token(); // consume operator
-            let right = self.parse_multiplicative()?;
+        if self.current_token == Token::LeftBracket {
+            self.next_token(); // consume '['
+            let mut arguments = vec![self.parse_annotation()?];
+            while self.current_token == Token::Comma {
+                self.next_token(); // consume ','
+                arguments.push(self.parse_annotation()?);
+            }
+            if self.current_token != Token::RightBracket {
+                return self.fail("invalid syntax");
+            }
+            self.next_token(); // consume ']'
+
+            annotation.push('[');
+            annotation.push_str(&arguments.join(", "));
+            annotation.push(']');
+        }
 
-            left = Node::Binary(Binary {
-                left: Box::new(left),
-                operator,
-                right: Box::new(right),
+        Some(annotation)
+    }
+
+    /// Remove a leading docstring from a suite and return its text
+    fn take_docstring(body: &mut Node) -> Option<String> {
+        if let Some(docstring) = body.as_docstring() {
+            let docstring = docstring.to_string();
+            *body = Node::Block(Block {
+                statements: Vec::new(),
             });
+            return Some(docstring);
+        }
+
+        if let Node::Block(block) = body
+            && let Some(docstring) = block.statements.first().and_then(Node::as_docstring)
+        {
+            let docstring = docstring.to_string();
+            block.statements.remove(0);
+            if block.statements.len() == 1 {
+                *body = block.statements.pop().unwrap();
+            }
+            return Some(docstring);
         }
 
-        Some(left)
+        None
     }
 
-    fn parse_multiplicative(&mut self) -> Option<Node> {
-        let mut left = self.parse_power()?;
+    fn parse_if_statement(&mut self) -> Option<Node> {
+        let line = self.current_line;
+        self.next_token(); // consume 'if' or 'elif'
 
-        while matches!(
-            self.current_token,
-            Token::Multiply | Token::Divide | Token::FloorDivide | Token::Modulo
-        ) {
-            let operator = match self.current_token {
-                Token::Multiply => BinaryOperator::Multiply,
-                Token::Divide => BinaryOperator::Divide,
-                Token::FloorDivide => BinaryOperator::FloorDivide,
-                Token::Modulo => BinaryOperator::Modulo,
-                _ => break,
-            };
+        let condition = self.parse_expression()?;
 
-            self.next_token(); // consume operator
-            let right = self.parse_power()?;
+        if self.current_token != Token::Colon {
+            return self.fail("expected ':'");
+        }
+        self.next_token(); // consume ':'
 
-            left = Node::Binary(Binary {
-                left: Box::new(left),
-                operator,
-                right: Box::new(right),
-            });
+        let then_branch = self.parse_suite()?;
+
+        // A one-line suite leaves its NEWLINE in front of a following elif/else
+        if self.current_token == Token::Newline
+            && matches!(self.peek_token(), Token::Elif | Token::Else)
+        {
+            self.next_token(); // consume NEWLINE
         }
 
-        Some(left)
+        let else_branch = match self.current_token {
+            Token::Elif => Some(Box::new(self.parse_if_statement()?)),
+            Token::Else => {
+                self.next_token(); // consume 'else'
+                if self.current_token != Token::Colon {
+                    return self.fail("expected ':'");
+                }
+                self.next_token(); // consume ':'
+                Some(Box::new(self.parse_suite()?))
+            }
+            _ => None,
+        };
+
+        Some(Node::If(If {
+            condition: Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch,
+            line,
+        }))
     }
 
-    fn parse_power(&mut self) -> Option<Node> {
-        let mut left = self.parse_unary()?;
+    /// Parse the body following a ':'
+    ///
+    /// Either simple statements on the same line or an indented block. A suite
+    /// with a single statement is returned as that statement, larger ones as a
+    /// Block.
+    fn parse_suite(&mut self) -> Option<Node> {
+        self.nested("block", Self::parse_suite_body)
+    }
 
-        // Right associative for power operator
-        if let Token::Power = self.current_token {
-            self.next_token(); // consume operator
-            let right = self.parse_power()?;
+    fn parse_suite_body(&mut self) -> Option<Node> {
+        while matches!(self.current_token, Token::Comment(_)) {
+            self.next_token();
+        }
 
-            left = Node::Binary(Binary {
-                left: Box::new(left),
-                operator: BinaryOperator::Power,
-                right: Box::new(right),
-            });
+        if self.current_token != Token::Newline {
+            return self.parse_simple_statements();
+        }
+
+        while matches!(self.current_token, Token::Newline | Token::Comment(_)) {
+            self.next_token();
+        }
+        if self.current_token != Token::Indent {
+            return self.fail("expected an indented block");
         }
+        self.next_token(); // consume INDENT
 
-        Some(left)
+        let mut statements = Vec::new();
+        // Blocks indented further for no reason, whose DEDENTs do not end the suite
+        let mut stray_indents = 0;
+        while self.current_token != Token::Eof {
+            match self.current_token {
+                Token::Indent => {
+                    self.unexpected_indent();
+                    stray_indents += 1;
+                    continue;
+                }
+                Token::Dedent if stray_indents > 0 => {
+                    stray_indents -= 1;
+                    self.next_token();
+                    continue;
+                }
+                Token::Dedent => break,
+                _ => {}
+            }
+            if matches!(
+                self.current_token,
+                Token::Comment(_) | Token::Newline | Token::Semicolon
+            ) {
+                self.next_token();
+                continue;
+            }
+
+            let consumed = self.tokens_consumed;
+            if let Some(statement) = self.parse_statement() {
+                statements.push(statement);
+            } else if self.tokens_consumed == consumed {
+                self.next_token();
+            }
+        }
+
+        if self.current_token == Token::Dedent {
+            self.next_token(); // consume DEDENT
+        }
+
+        if statements.len() == 1 {
+            statements.pop()
+        } else {
+            Some(Node::Block(Block { statements }))
+        }
     }
 
-    fn parse_unary(&mut self) -> Option<Node> {
-        match self.current_token {
-            Token::Plus => {
-                self.next_token(); // consume '+'
-                let operand = self.parse_unary()?;
-                Some(Node::Unary(crate::ast::Unary {
-                    operator: crate::ast::UnaryOperator::Plus,
-                    operand: Box::new(operand),
-                }))
+    /// Parse `stmt; stmt; ...` up to the end of the line
+    fn parse_simple_statements(&mut self) -> Option<Node> {
+        let mut statements = vec![self.parse_statement()?];
+
+        while self.current_token == Token::Semicolon {
+            self.next_token(); // consume ';'
+            if self.at_statement_end() {
+                break; // Trailing semicolon
             }
-            Token::Minus => {
-                self.next_token(); // consume '-'
-                let operand = self.parse_unary()?;
-                Some(Node::Unary(crate::ast::Unary {
-                    operator: crate::ast::UnaryOperator::Minus,
-                    operand: Box::new(operand),
-                }))
+            match self.parse_statement() {
+                Some(statement) => statements.push(statement),
+                None => break,
             }
-            _ => self.parse_primary(),
         }
+
+        if statements.len() == 1 {
+            statements.pop()
+        } else {
+            Some(Node::Block(Block { statements }))
+        }
+    }
+
+    /// Skip to the end of the current statement and past the block it opens
+    fn skip_statement(&mut self) {
+        while !matches!(
+            self.current_token,
+            Token::Eof | Token::Semicolon | Token::Newline | Token::Dedent
+        ) {
+            self.next_token();
+        }
+        if self.current_token != Token::Newline || self.peek_token != Token::Indent {
+            return;
+        }
+        self.next_token(); // consume NEWLINE
+        let mut indents = 0;
+        loop {
+            match self.current_token {
+                Token::Indent => indents += 1,
+                Token::Dedent => indents -= 1,
+                Token::Eof => return,
+                _ => {}
+            }
+            self.next_token();
+            if indents == 0 {
+                return;
+            }
+        }
+    }
+
+    /// Whether the current token ends a simple statement
+    fn at_statement_end(&self) -> bool {
+        matches!(
+            self.current_token,
+            Token::Eof | Token::Semicolon | Token::Newline | Token::Dedent | Token::Comment(_)
+        )
+    }
+
+    /// Parse an expression statement, or an assignment if `=` follows
+    ///
+    /// Every expression before the last `=` is a target, so `a = b = 0`
+    /// assigns `0` to `a` and then to `b`.
+    fn parse_expression_statement(&mut self) -> Option<Node> {
+        let line = self.current_line;
+        let expression = self.parse_expression()?;
+        if self.current_token != Token::Assign {
+            return Some(Node::ExpressionStatement(crate::ast::Expression {
+                expression: Box::new(expression),
+                line,
+            }));
+        }
+
+        let mut targets = vec![expression];
+        while self.current_token == Token::Assign {
+            if let Some(kind) = targets.last().and_then(invalid_target) {
+                return self.fail(&format!("cannot assign to {kind}"));
+            }
+            self.next_token(); // consume '='
+            targets.push(self.parse_expression()?);
+        }
+        let value = targets.pop()?;
+        Some(Node::Assignment(Assignment {
+            targets,
+            value: Box::new(value),
+            annotation: None,
+            line,
+        }))
+    }
+
+    fn parse_expression(&mut self) -> Option<Node> {
+        self.parse_precedence(Precedence::Lowest)
+    }
+
+    /// Parse an expression whose operators all bind tighter than `min`
+    ///
+    /// Operators of the same precedence group to the left, except `**` which
+    /// groups to the right.
+    fn parse_precedence(&mut self, min: Precedence) -> Option<Node> {
+        self.nested("expression", |parser| {
+            let mut left = parser.parse_prefix()?;
+
+            while let Some((operator, precedence)) = infix_operator(&parser.current_token) {
+                if precedence <= min {
+                    break;
+                }
+                parser.next_token(); // consume operator
+
+                // The operand of `**` may itself be a unary or power expression
+                let right = match precedence {
+                    Precedence::Power => parser.parse_precedence(Precedence::Product)?,
+                    _ => parser.parse_precedence(precedence)?,
+                };
+                left = Node::Binary(Binary {
+                    left: Box::new(left),
+                    operator,
+                    right: Box::new(right),
+                });
+
+                if precedence == Precedence::Comparison
+                    && matches!(
+                        infix_operator(&parser.current_token),
+                        Some((_, Precedence::Comparison))
+                    )
+                {
+                    return parser.fail("chained comparisons are not supported");
+                }
+            }
+
+            Some(left)
+        })
+    }
+
+    /// Parse a prefix operator and its operand, or a primary expression
+    fn parse_prefix(&mut self) -> Option<Node> {
+        let (operator, precedence) = match self.current_token {
+            Token::Plus => (UnaryOperator::Plus, Precedence::Unary),
+            Token::Minus => (UnaryOperator::Minus, Precedence::Unary),
+            Token::Not => (UnaryOperator::Not, Precedence::Not),
+            _ => return self.parse_primary(),
+        };
+        self.next_token(); // consume operator
+
+        // `-2 ** 2` is `-(2 ** 2)`, and `not a == b` is `not (a == b)`
+        let below = match precedence {
+            Precedence::Unary => Precedence::Product,
+            _ => Precedence::And,
+        };
+        let operand = self.parse_precedence(below)?;
+        Some(Node::Unary(Unary {
+            operator,
+            operand: Box::new(operand),
+        }))
     }
 
     fn parse_primary(&mut self) -> Option<Node> {
@@ -314,47 +936,186 @@ impl Parser {
                 self.next_token();
                 Some(node)
             }
-            Token::Identifier(name) => {
-                let name_clone = name.clone();
-                self.next_token();
+            Token::Await => {
+                // `await` applies to a primary, so `await f() ** 2` is `(await f()) ** 2`
+                self.next_token(); // consume 'await'
+                let value = self.nested("expression", Self::parse_primary)?;
+                Some(Node::Await(crate::ast::Await {
+                    value: Box::new(value),
+                }))
+            }
+            Token::Identifier(_) => {
+                let name_clone = self.parse_dotted_name()?;
 
                 // Check if this is a function call
                 if self.current_token == Token::LeftParen {
                     self.parse_function_call(name_clone)
+                } else if self.current_token == Token::LeftBracket {
+                    self.parse_subscript(Node::Identifier(Identifier { name: name_clone }))
                 } else {
                     Some(Node::Identifier(Identifier { name: name_clone }))
                 }
             }
             Token::LeftParen => {
                 self.next_token(); // consume '('
-                let expr = self.parse_expression();
                 if self.current_token == Token::RightParen {
                     self.next_token(); // consume ')'
-                    expr
-                } else {
-                    None // Missing closing parenthesis
+                    return Some(Node::Tuple(Tuple {
+                        elements: Vec::new(),
+                    }));
+                }
+                let first = self.parse_expression()?;
+                // A comma makes a tuple, even after a single element
+                if self.current_token == Token::Comma {
+                    self.next_token(); // consume ','
+                    let mut elements = vec![first];
+                    elements.extend(self.parse_elements(Token::RightParen)?);
+                    return Some(Node::Tuple(Tuple { elements }));
                 }
+                if self.current_token != Token::RightParen {
+                    return self.fail("invalid syntax"); // Missing closing parenthesis
+                }
+                self.next_token(); // consume ')'
+                Some(first)
             }
-            _ => None,
+            Token::LeftBracket => {
+                self.next_token(); // consume '['
+                let elements = self.parse_elements(Token::RightBracket)?;
+                Some(Node::List(List { elements }))
+            }
+            Token::LeftBrace => {
+                self.next_token(); // consume '{'
+                if self.current_token == Token::RightBrace {
+                    self.next_token(); // consume '}'
+                    return Some(Node::Dict(Dict {
+                        entries: Vec::new(),
+                    }));
+                }
+                let first = self.parse_expression()?;
+                if self.current_token != Token::Colon {
+                    let mut elements = vec![first];
+                    if self.current_token == Token::Comma {
+                        self.next_token(); // consume ','
+                        elements.extend(self.parse_elements(Token::RightBrace)?);
+                    } else if self.current_token == Token::RightBrace {
+                        self.next_token(); // consume '}'
+                    } else {
+                        return self.fail("invalid syntax"); // Missing closing brace
+                    }
+                    return Some(Node::Set(Set { elements }));
+                }
+                self.parse_dict_entries(first)
+            }
+            _ => self.fail("invalid syntax"),
         }
     }
 
+    /// Parse comma-separated expressions up to and including `close`
+    ///
+    /// A trailing comma before `close` is allowed.
+    fn parse_elements(&mut self, close: Token) -> Option<Vec<Node>> {
+        let mut elements = Vec::new();
+        while self.current_token != close {
+            elements.push(self.parse_expression()?);
+            if self.current_token != Token::Comma {
+                break;
+            }
+            self.next_token(); // consume ','
+        }
+        if self.current_token != close {
+            return self.fail("invalid syntax"); // Missing closing bracket
+        }
+        self.next_token(); // consume closing bracket
+        Some(elements)
+    }
+
+    /// Parse the entries of a dict display whose first key has been parsed
+    fn parse_dict_entries(&mut self, first_key: Node) -> Option<Node> {
+        let mut entries = Vec::new();
+        let mut key = first_key;
+        loop {
+            if self.current_token != Token::Colon {
+                return self.fail("':' expected after dictionary key");
+            }
+            self.next_token(); // consume ':'
+            entries.push((key, self.parse_expression()?));
+
+            if self.current_token != Token::Comma {
+                break;
+            }
+            self.next_token(); // consume ','
+            if self.current_token == Token::RightBrace {
+                break; // Trailing comma
+            }
+            key = self.parse_expression()?;
+        }
+        if self.current_token != Token::RightBrace {
+            return self.fail("invalid syntax"); // Missing closing brace
+        }
+        self.next_token(); // consume '}'
+        Some(Node::Dict(Dict { entries }))
+    }
+
+    fn parse_subscript(&mut self, value: Node) -> Option<Node> {
+        self.next_token(); // consume '['
+        let index = self.parse_expression()?;
+        if self.current_token != Token::RightBracket {
+            return self.fail("invalid syntax"); // Missing closing bracket
+        }
+        self.next_token(); // consume ']'
+        Some(Node::Subscript(crate::ast::Subscript {
+            value: Box::new(value),
+            index: Box::new(index),
+        }))
+    }
+
     fn parse_function_call(&mut self, name: String) -> Option<Node> {
         self.next_token(); // consume '('
 
+        // Arguments may end with a trailing comma
         let mut arguments = Vec::new();
-
-        // Parse arguments
-        if self.current_token != Token::RightParen {
-            while let Some(arg) = self.parse_expression() {
-                arguments.push(arg);
-
-                if self.current_token == Token::Comma {
-                    self.next_token(); // consume ','
-                } else {
-                    break;
+        let mut keywords: Vec<(Option<String>, Node)> = Vec::new();
+        while self.current_token != Token::RightParen {
+            // Whether a `**mapping` came before, which only keywords may follow
+            let unpacked_keywords = keywords.iter().any(|(name, _)| name.is_none());
+            if let Token::Identifier(keyword) = &self.current_token
+                && self.peek_token() == &Token::Assign
+            {
+                let keyword = keyword.clone();
+                if keywords
+                    .iter()
+                    .any(|(name, _)| name.as_ref() == Some(&keyword))
+                {
+                    return self.fail(&format!("keyword argument repeated: {keyword}"));
+                }
+                self.next_token(); // consume keyword
+                self.next_token(); // consume '='
+                keywords.push((Some(keyword), self.parse_expression()?));
+            } else if self.current_token == Token::Power {
+                self.next_token(); // consume '**'
+                keywords.push((None, self.parse_expression()?));
+            } else if self.current_token == Token::Multiply {
+                if unpacked_keywords {
+                    return self
+                        .fail("iterable argument unpacking follows keyword argument unpacking");
                 }
+                self.next_token(); // consume '*'
+                arguments.push(Node::Starred(crate::ast::Starred {
+                    value: Box::new(self.parse_expression()?),
+                }));
+            } else {
+                if unpacked_keywords {
+                    return self.fail("positional argument follows keyword argument unpacking");
+                }
+                if !keywords.is_empty() {
+                    return self.fail("positional argument follows keyword argument");
+                }
+                arguments.push(self.parse_expression()?);
+            }
+            if self.current_token != Token::Comma {
+                break;
             }
+            self.next_token(); // consume ','
         }
 
         if self.current_token == Token::RightParen {
@@ -362,9 +1123,10 @@ impl Parser {
             Some(Node::Call(crate::ast::Call {
                 callee: name,
                 arguments,
+                keywords,
             }))
         } else {
-            None // Missing closing parenthesis
+            self.fail("invalid syntax") // Missing closing parenthesis
         }
     }
 }
@@ -0,0 +1,123 @@
+use crate::ast::Node;
+use crate::typecheck::{self, Diagnostic};
+
+/// Analysis or transformation run over the whole program before code generation
+///
+/// Passes may rewrite the AST in place and report findings with their
+/// source line. Any error stops compilation once every pass has run.
+pub trait Pass {
+    /// Short identifier shown next to the pass's findings, such as "typecheck"
+    fn name(&self) -> &str;
+    fn run(&mut self, program: &mut Node, diagnostics: &mut Diagnostics);
+}
+
+/// A finding reported by a pass
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    /// Name of the pass that reported it
+    pub pass: String,
+    pub diagnostic: Diagnostic,
+}
+
+/// Errors and warnings collected while running passes
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Diagnostics {
+    pub errors: Vec<Finding>,
+    pub warnings: Vec<Finding>,
+    /// Pass currently running, recorded on each finding
+    pass: String,
+}
+
+impl Diagnostics {
+    /// Report a problem that must stop compilation
+    pub fn error(&mut self, line: usize, message: impl Into<String>) {
+        let finding = self.finding(line, message.into());
+        self.errors.push(finding);
+    }
+
+    /// Report a problem compilation can continue past
+    pub fn warning(&mut self, line: usize, message: impl Into<String>) {
+        let finding = self.finding(line, message.into());
+        self.warnings.push(finding);
+    }
+
+    pub fn has_errors(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    fn finding(&self, line: usize, message: String) -> Finding {
+        Finding {
+            pass: self.pass.clone(),
+            diagnostic: Diagnostic { line, message },
+        }
+    }
+}
+
+/// Ordered list of passes run over a program
+///
+/// Embedders register their own passes next to the built-in ones, for
+/// example lint rules to run before the type checker:
+///
+/// ```ignore
+/// let mut passes = PassManager::new();
+/// passes.register(TypeCheck);
+/// passes.register_before("typecheck", MyLint::default());
+/// let diagnostics = passes.run(&mut program);
+/// ```
+#[derive(Default)]
+pub struct PassManager {
+    passes: Vec<Box<dyn Pass>>,
+}
+
+impl PassManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `pass` after the registered ones
+    pub fn register(&mut self, pass: impl Pass + 'static) -> &mut Self {
+        self.passes.push(Box::new(pass));
+        self
+    }
+
+    /// Add `pass` before the one called `name`, or last if there is none
+    pub fn register_before(&mut self, name: &str, pass: impl Pass + 'static) -> &mut Self {
+        let index = self
+            .passes
+            .iter()
+            .position(|registered| registered.name() == name)
+            .unwrap_or(self.passes.len());
+        self.passes.insert(index, Box::new(pass));
+        self
+    }
+
+    /// Names of the registered passes, in the order they run
+    pub fn names(&self) -> Vec<&str> {
+        self.passes.iter().map(|pass| pass.name()).collect()
+    }
+
+    /// Run every pass over `program` in order
+    pub fn run(&mut self, program: &mut Node) -> Diagnostics {
+        let mut diagnostics = Diagnostics::default();
+        for pass in &mut self.passes {
+            diagnostics.pass = pass.name().to_string();
+            pass.run(program, &mut diagnostics);
+        }
+        diagnostics
+    }
+}
+
+/// The gradual type checker, reporting every inconsistency as an error
+pub struct TypeCheck;
+
+impl Pass for TypeCheck {
+    fn name(&self) -> &str {
+        "typecheck"
+    }
+
+    fn run(&mut self, program: &mut Node, diagnostics: &mut Diagnostics) {
+        for diagnostic in typecheck::diagnose(program) {
+            diagnostics.error(diagnostic.line, diagnostic.message);
+        }
+    }
+}
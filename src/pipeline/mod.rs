@@ -0,0 +1,159 @@
+use crate::ast::Node;
+use crate::codegen::{CodeGenerator, CodegenOptions};
+use crate::lexer::{Lexer, Token};
+use crate::parser::Parser;
+use crate::typecheck::{self, Diagnostic};
+use inkwell::module::Module;
+
+pub use crate::codegen::build::{compile_and_link, run_compiled};
+
+type TokensHook<'a> = Box<dyn FnMut(&[Token]) + 'a>;
+type AstHook<'a> = Box<dyn FnMut(&mut Node) + 'a>;
+type AnalysisHook<'a> = Box<dyn FnMut(&Node, &[Diagnostic]) + 'a>;
+type ModuleHook<'a> = Box<dyn FnMut(&Module<'_>) + 'a>;
+
+/// Compilation of a program to LLVM IR, calling back after each phase
+///
+/// Tools built on pycc register callbacks to log, cache or rewrite what each
+/// phase produces: the tokens after lexing, the AST after parsing (which they
+/// may modify before it is checked and compiled), the type checker's findings
+/// and the generated module. Callbacks of a phase run in registration order.
+#[derive(Default)]
+pub struct Pipeline<'a> {
+    options: CodegenOptions,
+    on_tokens: Vec<TokensHook<'a>>,
+    on_ast: Vec<AstHook<'a>>,
+    on_analysis: Vec<AnalysisHook<'a>>,
+    on_module: Vec<ModuleHook<'a>>,
+}
+
+impl<'a> Pipeline<'a> {
+    pub fn new(options: CodegenOptions) -> Self {
+        Pipeline {
+            options,
+            ..Pipeline::default()
+        }
+    }
+
+    /// Call `hook` with every token of the source, up to but excluding the end of input
+    pub fn on_tokens(&mut self, hook: impl FnMut(&[Token]) + 'a) -> &mut Self {
+        self.on_tokens.push(Box::new(hook));
+        self
+    }
+
+    /// Call `hook` with the parsed program, before it is checked or compiled
+    pub fn on_ast(&mut self, hook: impl FnMut(&mut Node) + 'a) -> &mut Self {
+        self.on_ast.push(Box::new(hook));
+        self
+    }
+
+    /// Call `hook` with the program and the type checker's findings
+    pub fn on_analysis(&mut self, hook: impl FnMut(&Node, &[Diagnostic]) + 'a) -> &mut Self {
+        self.on_analysis.push(Box::new(hook));
+        self
+    }
+
+    /// Call `hook` with the generated module, before it is optimized
+    pub fn on_module(&mut self, hook: impl FnMut(&Module<'_>) + 'a) -> &mut Self {
+        self.on_module.push(Box::new(hook));
+        self
+    }
+
+    /// Compile `source` and return the optimized module as LLVM IR text
+    ///
+    /// Fails with the syntax errors, the type errors when compiling with
+    /// `typed`, or the code generation error. Later phases do not run, and
+    /// their callbacks are not called, once one fails.
+    pub fn compile(&mut self, source: &str) -> Result<String, Vec<Diagnostic>> {
+        self.run(source, |codegen| {
+            Ok(codegen.module().print_to_string().to_string())
+        })
+    }
+
+    /// Compile `source` and link it into an executable at `path`
+    pub fn build_executable(&mut self, source: &str, path: &str) -> Result<(), Vec<Diagnostic>> {
+        self.run(source, |codegen| codegen.write_executable(path))
+    }
+
+    /// Run every phase over `source`, then `finish` with the optimized module
+    ///
+    /// Each call gets its own LLVM context and module, so nothing is shared
+    /// between compilations.
+    fn run<T>(
+        &mut self,
+        source: &str,
+        finish: impl FnOnce(&CodeGenerator<'_>) -> Result<T, String>,
+    ) -> Result<T, Vec<Diagnostic>> {
+        if !self.on_tokens.is_empty() {
+            let mut lexer = Lexer::new(source);
+            let mut tokens = Vec::new();
+            loop {
+                match lexer.next_token() {
+                    Token::Eof => break,
+                    token => tokens.push(token),
+                }
+            }
+            for hook in &mut self.on_tokens {
+                hook(&tokens);
+            }
+        }
+
+        let mut parser = Parser::new(Lexer::new(source));
+        let mut program = parser.parse_program();
+        if !parser.errors().is_empty() {
+            return Err(parser
+                .errors()
+                .iter()
+                .map(|error| Diagnostic {
+                    line: error.line,
+                    message: error.message.clone(),
+                })
+                .collect());
+        }
+        for hook in &mut self.on_ast {
+            hook(&mut program);
+        }
+
+        let diagnostics = typecheck::diagnose(&program);
+        for hook in &mut self.on_analysis {
+            hook(&program, &diagnostics);
+        }
+        // Without native signatures, type errors only surface at run time
+        if self.options.typed && !diagnostics.is_empty() {
+            return Err(diagnostics);
+        }
+
+        let failure = |message: String| vec![Diagnostic { line: 0, message }];
+        let context = inkwell::context::Context::create();
+        let mut codegen = CodeGenerator::new(&context, "pycc_module");
+        codegen.set_options(self.options.clone());
+        codegen.compile(&program).map_err(|e| {
+            vec![Diagnostic {
+                line: e.line.unwrap_or(0),
+                message: e.message,
+            }]
+        })?;
+        for hook in &mut self.on_module {
+            hook(codegen.module());
+        }
+        codegen.optimize().map_err(failure)?;
+        finish(&codegen).map_err(failure)
+    }
+}
+
+/// Compile `source` to optimized LLVM IR text
+///
+/// Nothing is shared between calls, so compilations can run on any number of
+/// threads at once, as parallel test harnesses do.
+pub fn compile_to_ir(source: &str, options: &CodegenOptions) -> Result<String, Vec<Diagnostic>> {
+    Pipeline::new(options.clone()).compile(source)
+}
+
+/// Compile `source` into an executable at `path`; safe to call from several threads
+pub fn build_executable(
+    source: &str,
+    options: &CodegenOptions,
+    path: &str,
+) -> Result<(), Vec<Diagnostic>> {
+    Pipeline::new(options.clone()).build_executable(source, path)
+}
@@ -0,0 +1,204 @@
+use crate::codegen::{CodegenOptions, build};
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::typecheck::{self, Diagnostic};
+use std::time::Duration;
+
+/// How long one evaluation may run before it is stopped
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What evaluating one chunk produced
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkResult {
+    /// Standard output written by the chunk's statements
+    pub output: String,
+    /// Syntax, type, compile and runtime errors; lines are relative to the chunk
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ChunkResult {
+    pub fn is_ok(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+}
+
+/// A program built up chunk by chunk, like the cells of a notebook
+///
+/// Compiled programs cannot be paused and resumed, so each evaluation compiles
+/// and runs the accepted chunks again followed by the new one, and reports
+/// only the output past what the accepted chunks printed. This has costs:
+///
+/// - side effects of earlier chunks, such as `os.system` calls or reading
+///   `input()`, happen again on every evaluation
+/// - evaluation time grows with the number of accepted chunks
+/// - earlier chunks must print the same output every time; when they do not,
+///   the evaluation fails rather than guess which output is new
+///
+/// The program gets no input, and an evaluation that runs longer than the
+/// timeout is stopped and fails. A chunk that fails is not kept. Clone a
+/// session to fork its state.
+#[derive(Debug, Clone)]
+pub struct Session {
+    options: CodegenOptions,
+    timeout: Duration,
+    chunks: Vec<String>,
+    /// Output of the accepted chunks
+    output: String,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            options: CodegenOptions::default(),
+            timeout: DEFAULT_TIMEOUT,
+            chunks: Vec::new(),
+            output: String::new(),
+        }
+    }
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session::default()
+    }
+
+    pub fn with_options(options: CodegenOptions) -> Self {
+        Session {
+            options,
+            ..Session::default()
+        }
+    }
+
+    /// Stop evaluations that run longer than `timeout`, instead of `DEFAULT_TIMEOUT`
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Run `chunk` after the accepted chunks, keeping it if it succeeds
+    pub fn eval(&mut self, chunk: &str) -> ChunkResult {
+        let mut source = self.source();
+        let chunk_start_line = source.lines().count();
+        source.push_str(chunk);
+        if !source.ends_with('\n') {
+            source.push('\n');
+        }
+
+        let chunk_diagnostic = |line: usize, message: String| Diagnostic {
+            line: line.saturating_sub(chunk_start_line),
+            message,
+        };
+        let failure = |diagnostics| ChunkResult {
+            output: String::new(),
+            diagnostics,
+        };
+
+        let mut parser = Parser::new(Lexer::new(&source));
+        let program = parser.parse_program();
+        if !parser.errors().is_empty() {
+            return failure(
+                parser
+                    .errors()
+                    .iter()
+                    .map(|error| chunk_diagnostic(error.line, error.message.clone()))
+                    .collect(),
+            );
+        }
+        if self.options.typed {
+            let diagnostics: Vec<Diagnostic> = typecheck::diagnose(&program)
+                .into_iter()
+                .map(|diagnostic| chunk_diagnostic(diagnostic.line, diagnostic.message))
+                .collect();
+            if !diagnostics.is_empty() {
+                return failure(diagnostics);
+            }
+        }
+
+        let run = match self.build_and_run(&program, &source) {
+            Ok(run) => run,
+            Err(e) => return failure(vec![chunk_diagnostic(0, e)]),
+        };
+        let Some(output) = run.stdout.strip_prefix(self.output.as_str()) else {
+            return failure(vec![chunk_diagnostic(
+                0,
+                "the accepted chunks printed different output when run again, so the output of this chunk cannot be told apart; reset the session".to_string(),
+            )]);
+        };
+        let output = output.to_string();
+        if !run.success {
+            let message = match run.stderr.trim() {
+                "" => "program exited with an error".to_string(),
+                stderr => stderr.to_string(),
+            };
+            return ChunkResult {
+                output,
+                diagnostics: vec![chunk_diagnostic(0, message)],
+            };
+        }
+
+        self.chunks.push(chunk.to_string());
+        self.output = run.stdout;
+        ChunkResult {
+            output,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Forget every accepted chunk
+    pub fn reset(&mut self) {
+        self.chunks.clear();
+        self.output.clear();
+    }
+
+    /// Source of the accepted chunks, in order
+    pub fn source(&self) -> String {
+        let mut source = String::new();
+        for chunk in &self.chunks {
+            source.push_str(chunk);
+            if !source.ends_with('\n') {
+                source.push('\n');
+            }
+        }
+        source
+    }
+
+    /// Everything the accepted chunks printed
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    fn build_and_run(&self, program: &crate::ast::Node, source: &str) -> Result<Run, String> {
+        let context = inkwell::context::Context::create();
+        let codegen = build::generate(
+            &context,
+            "pycc_session",
+            program,
+            source,
+            &self.options,
+            |_| {},
+            &mut Vec::new(),
+        )
+        .map_err(|e| e.to_string())?;
+
+        let executable = tempfile::Builder::new()
+            .prefix("pycc-session-")
+            .tempfile()
+            .map_err(|e| format!("Failed to create a temporary executable: {e}"))?
+            .into_temp_path();
+        codegen.write_executable(&executable.to_string_lossy())?;
+
+        let run = build::run_compiled_with_timeout(&executable, b"", self.timeout)
+            .map_err(|e| format!("Failed to run program: {e}"))?
+            .ok_or_else(|| format!("program timed out after {} ms", self.timeout.as_millis()))?;
+        Ok(Run {
+            stdout: String::from_utf8_lossy(&run.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&run.stderr).into_owned(),
+            success: run.status.success(),
+        })
+    }
+}
+
+struct Run {
+    stdout: String,
+    stderr: String,
+    success: bool,
+}
@@ -0,0 +1,500 @@
+use crate::ast::{BinaryOperator, Function, List, LiteralValue, Node, Set, Tuple, UnaryOperator};
+use crate::builtins;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Static type of a value in the supported subset
+///
+/// `Any` is the gradual type: it is produced by unannotated code and by
+/// annotations the checker does not understand, and is compatible with
+/// everything in both directions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    Int,
+    Float,
+    Str,
+    Bool,
+    None,
+    Any,
+}
+
+impl Type {
+    /// Interpret the text of an annotation, falling back to `Any`
+    pub fn from_annotation(annotation: &str) -> Type {
+        match annotation {
+            "int" => Type::Int,
+            "float" => Type::Float,
+            "str" => Type::Str,
+            "bool" => Type::Bool,
+            "None" => Type::None,
+            _ => Type::Any,
+        }
+    }
+
+    /// Whether a value of type `other` may be used where `self` is expected
+    ///
+    /// Follows the numeric tower: bool is accepted as int, and int as float.
+    pub fn accepts(self, other: Type) -> bool {
+        match (self, other) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Int, Type::Bool) => true,
+            (Type::Float, Type::Int | Type::Bool) => true,
+            _ => self == other,
+        }
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(self, Type::Int | Type::Float | Type::Bool)
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Type::Int => "int",
+            Type::Float => "float",
+            Type::Str => "str",
+            Type::Bool => "bool",
+            Type::None => "None",
+            Type::Any => "Any",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Parameter and return types of a function, taken from its annotations
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionSignature {
+    pub parameters: Vec<Type>,
+    pub return_type: Type,
+}
+
+impl FunctionSignature {
+    pub fn from_function(function: &Function) -> Self {
+        let parameters = function
+            .parameters
+            .iter()
+            .enumerate()
+            .map(|(i, _)| match function.parameter_types.get(i) {
+                Some(Some(annotation)) => Type::from_annotation(annotation),
+                _ => Type::Any,
+            })
+            .collect();
+        let return_type = function
+            .return_type
+            .as_deref()
+            .map_or(Type::Any, Type::from_annotation);
+
+        FunctionSignature {
+            parameters,
+            return_type,
+        }
+    }
+}
+
+/// A type error and the line of the statement it was found in
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// Source line (1-based), or 0 if the statement's line is unknown
+    pub line: usize,
+    pub message: String,
+}
+
+/// Check a program and return one diagnostic per inconsistency found
+pub fn check(program: &Node) -> Vec<String> {
+    diagnose(program)
+        .into_iter()
+        .map(|diagnostic| diagnostic.message)
+        .collect()
+}
+
+/// Like `check`, keeping the line each diagnostic was reported on
+pub fn diagnose(program: &Node) -> Vec<Diagnostic> {
+    let mut checker = TypeChecker::new();
+    checker.check_program(program);
+    checker.diagnostics
+}
+
+/// Type of every variable and parameter, keyed by enclosing function (`None` for
+/// module level) and name
+pub fn variable_types(program: &Node) -> HashMap<(Option<String>, String), Type> {
+    let mut checker = TypeChecker::new();
+    checker.check_program(program);
+    for (name, (ty, _)) in checker.variables {
+        checker.scopes.insert((None, name), ty);
+    }
+    checker.scopes
+}
+
+/// Gradual type checker over the AST
+///
+/// Only annotated names are checked against their declarations; everything
+/// else is inferred where possible and otherwise treated as `Any`.
+struct TypeChecker {
+    functions: HashMap<String, FunctionSignature>,
+    /// Variable types in the current scope, with whether they were annotated
+    variables: HashMap<String, (Type, bool)>,
+    current_function: Option<(String, Type)>,
+    /// Line of the statement being checked
+    current_line: usize,
+    diagnostics: Vec<Diagnostic>,
+    /// Variables of functions already checked, by function and name
+    scopes: HashMap<(Option<String>, String), Type>,
+}
+
+impl TypeChecker {
+    fn new() -> Self {
+        TypeChecker {
+            functions: HashMap::new(),
+            variables: HashMap::new(),
+            current_function: None,
+            current_line: 0,
+            diagnostics: Vec::new(),
+            scopes: HashMap::new(),
+        }
+    }
+
+    fn report(&mut self, message: String) {
+        let message = match &self.current_function {
+            Some((name, _)) => format!("in function '{name}': {message}"),
+            None => message,
+        };
+        self.diagnostics.push(Diagnostic {
+            line: self.current_line,
+            message,
+        });
+    }
+
+    fn check_program(&mut self, program: &Node) {
+        let Node::Program(program) = program else {
+            self.report("Expected a program node".to_string());
+            return;
+        };
+
+        for statement in &program.statements {
+            if let Node::Function(function) = statement {
+                self.functions.insert(
+                    function.name.clone(),
+                    FunctionSignature::from_function(function),
+                );
+            }
+        }
+
+        for statement in &program.statements {
+            self.check_statement(statement);
+        }
+    }
+
+    fn check_statement(&mut self, statement: &Node) {
+        if let Some(line) = statement.line() {
+            self.current_line = line;
+        }
+        match statement {
+            Node::Assignment(assignment) => {
+                let value_type = self.infer(&assignment.value);
+                for target in &assignment.targets {
+                    if let Node::Subscript(subscript) = target {
+                        self.infer(&subscript.index);
+                        if matches!(subscript.value.as_ref(), Node::Identifier(identifier) if identifier.name == "os.environ")
+                            && !Type::Str.accepts(value_type)
+                        {
+                            self.report(format!("os.environ values must be str, not {value_type}"));
+                        }
+                    }
+                }
+                for name in assignment.names() {
+                    let declared = match &assignment.annotation {
+                        Some(annotation) => Some(self.declare(name, annotation)),
+                        None => match self.variables.get(name) {
+                            Some((ty, true)) => Some(*ty),
+                            _ => None,
+                        },
+                    };
+
+                    match declared {
+                        Some(declared) => {
+                            if !declared.accepts(value_type) {
+                                self.report(format!(
+                                    "cannot assign {value_type} to '{name}' declared as {declared}"
+                                ));
+                            }
+                        }
+                        None => {
+                            self.variables.insert(name.to_string(), (value_type, false));
+                        }
+                    }
+                }
+            }
+            Node::Declaration(declaration) => {
+                self.declare(&declaration.name, &declaration.annotation);
+            }
+            Node::Function(function) => self.check_function(function),
+            Node::Return(return_stmt) => {
+                let value_type = match &return_stmt.value {
+                    Some(value) => self.infer(value),
+                    None => Type::None,
+                };
+                if let Some((_, expected)) = self.current_function
+                    && !expected.accepts(value_type)
+                {
+                    self.report(format!("returns {value_type}, expected {expected}"));
+                }
+            }
+            Node::If(if_stmt) => {
+                self.infer(&if_stmt.condition);
+                self.check_statement(&if_stmt.then_branch);
+                if let Some(else_branch) = &if_stmt.else_branch {
+                    self.check_statement(else_branch);
+                }
+            }
+            Node::While(while_stmt) => {
+                self.infer(&while_stmt.condition);
+                self.check_statement(&while_stmt.body);
+            }
+            Node::Block(block) => {
+                for statement in &block.statements {
+                    self.check_statement(statement);
+                }
+            }
+            Node::ExpressionStatement(expr_stmt) => {
+                self.infer(&expr_stmt.expression);
+            }
+            _ => {}
+        }
+    }
+
+    /// Record an annotation for `name`, reporting conflicting redeclarations
+    fn declare(&mut self, name: &str, annotation: &str) -> Type {
+        let declared = Type::from_annotation(annotation);
+        if let Some((previous, true)) = self.variables.get(name)
+            && *previous != declared
+        {
+            self.report(format!(
+                "'{name}' redeclared as {declared}, previously {previous}"
+            ));
+        }
+        self.variables.insert(name.to_string(), (declared, true));
+        declared
+    }
+
+    fn check_function(&mut self, function: &Function) {
+        let signature = FunctionSignature::from_function(function);
+
+        let outer_variables = std::mem::take(&mut self.variables);
+        for (name, ty) in function.parameters.iter().zip(&signature.parameters) {
+            self.variables.insert(name.clone(), (*ty, *ty != Type::Any));
+        }
+        let outer_function = self
+            .current_function
+            .replace((function.name.clone(), signature.return_type));
+
+        self.check_statement(&function.body);
+
+        self.current_function = outer_function;
+        let locals = std::mem::replace(&mut self.variables, outer_variables);
+        for (name, (ty, _)) in locals {
+            self.scopes.insert((Some(function.name.clone()), name), ty);
+        }
+    }
+
+    fn infer(&mut self, expression: &Node) -> Type {
+        match expression {
+            Node::Literal(literal) => match &literal.value {
+                LiteralValue::Integer(_) => Type::Int,
+                LiteralValue::Float(_) => Type::Float,
+                LiteralValue::String(_) | LiteralValue::FString(_) => Type::Str,
+                LiteralValue::Boolean(_) => Type::Bool,
+                LiteralValue::None => Type::None,
+            },
+            Node::Identifier(identifier) => match self.variables.get(&identifier.name) {
+                Some((ty, _)) => *ty,
+                // Module attributes defined before the program runs
+                None if matches!(identifier.name.as_str(), "__name__" | "__file__") => Type::Str,
+                None => Type::Any,
+            },
+            Node::Unary(unary) => {
+                let operand = self.infer(&unary.operand);
+                match unary.operator {
+                    UnaryOperator::Not => Type::Bool,
+                    UnaryOperator::Plus | UnaryOperator::Minus => match operand {
+                        Type::Bool => Type::Int,
+                        Type::Int | Type::Float | Type::Any => operand,
+                        _ => {
+                            self.report(format!("bad operand type for unary operator: {operand}"));
+                            Type::Any
+                        }
+                    },
+                }
+            }
+            Node::Binary(binary) => {
+                let left = self.infer(&binary.left);
+                let right = self.infer(&binary.right);
+                self.infer_binary(&binary.operator, left, right)
+            }
+            Node::Call(call) => {
+                let arguments: Vec<Type> = call.arguments.iter().map(|a| self.infer(a)).collect();
+                for (_, value) in &call.keywords {
+                    self.infer(value);
+                }
+                // The number of arguments `*` and `**` pass is only known at run time
+                if call
+                    .arguments
+                    .iter()
+                    .any(|argument| matches!(argument, Node::Starred(_)))
+                    || call.keywords.iter().any(|(name, _)| name.is_none())
+                {
+                    return self
+                        .functions
+                        .get(&call.callee)
+                        .map(|signature| signature.return_type)
+                        .or_else(|| {
+                            builtins::lookup(&call.callee).map(|builtin| builtin.return_type)
+                        })
+                        .unwrap_or(Type::Any);
+                }
+
+                if let Some(signature) = self.functions.get(&call.callee).cloned() {
+                    if arguments.len() != signature.parameters.len() {
+                        self.report(format!(
+                            "'{}' expects {} argument(s), got {}",
+                            call.callee,
+                            signature.parameters.len(),
+                            arguments.len()
+                        ));
+                    }
+                    for (i, (expected, actual)) in
+                        signature.parameters.iter().zip(&arguments).enumerate()
+                    {
+                        if !expected.accepts(*actual) {
+                            self.report(format!(
+                                "argument {} of '{}' expects {expected}, got {actual}",
+                                i + 1,
+                                call.callee
+                            ));
+                        }
+                    }
+                    return signature.return_type;
+                }
+
+                if let Some(builtin) = builtins::lookup(&call.callee) {
+                    if let Some(message) = builtin.arity_error(arguments.len()) {
+                        self.report(message);
+                    }
+                    return builtin.return_type;
+                }
+                match call.callee.as_str() {
+                    "os.system" => Type::Int,
+                    "os.getenv" | "os.environ.get" | "os.path.join" => Type::Str,
+                    "os.path.exists" => Type::Bool,
+                    _ => Type::Any,
+                }
+            }
+            Node::Await(await_expr) => {
+                self.infer(&await_expr.value);
+                Type::Any
+            }
+            Node::Starred(starred) => {
+                self.infer(&starred.value);
+                Type::Any
+            }
+            Node::Tuple(Tuple { elements })
+            | Node::List(List { elements })
+            | Node::Set(Set { elements }) => {
+                for element in elements {
+                    self.infer(element);
+                }
+                Type::Any
+            }
+            Node::Dict(dict) => {
+                for (key, value) in &dict.entries {
+                    self.infer(key);
+                    self.infer(value);
+                }
+                Type::Any
+            }
+            Node::Subscript(subscript) => {
+                self.infer(&subscript.index);
+                match subscript.value.as_ref() {
+                    Node::Identifier(identifier) if identifier.name == "os.environ" => Type::Str,
+                    _ => Type::Any,
+                }
+            }
+            _ => Type::Any,
+        }
+    }
+
+    fn infer_binary(&mut self, operator: &BinaryOperator, left: Type, right: Type) -> Type {
+        let numeric_result = || {
+            if left == Type::Float || right == Type::Float {
+                Type::Float
+            } else {
+                Type::Int
+            }
+        };
+
+        let result = match operator {
+            BinaryOperator::Equal
+            | BinaryOperator::NotEqual
+            | BinaryOperator::Less
+            | BinaryOperator::Greater
+            | BinaryOperator::LessEqual
+            | BinaryOperator::GreaterEqual => return Type::Bool,
+            BinaryOperator::And | BinaryOperator::Or => {
+                return if left == right { left } else { Type::Any };
+            }
+            _ if left == Type::Any || right == Type::Any => return Type::Any,
+            BinaryOperator::Add => match (left, right) {
+                (Type::Str, Type::Str) => Some(Type::Str),
+                _ if left.is_numeric() && right.is_numeric() => Some(numeric_result()),
+                _ => None,
+            },
+            BinaryOperator::Multiply => match (left, right) {
+                (Type::Str, Type::Int | Type::Bool) | (Type::Int | Type::Bool, Type::Str) => {
+                    Some(Type::Str)
+                }
+                _ if left.is_numeric() && right.is_numeric() => Some(numeric_result()),
+                _ => None,
+            },
+            BinaryOperator::Divide if left.is_numeric() && right.is_numeric() => Some(Type::Float),
+            BinaryOperator::Modulo if left == Type::Str => Some(Type::Str),
+            // int ** int is a float for negative exponents
+            BinaryOperator::Power if left.is_numeric() && right.is_numeric() => {
+                Some(if numeric_result() == Type::Float {
+                    Type::Float
+                } else {
+                    Type::Any
+                })
+            }
+            _ if left.is_numeric() && right.is_numeric() => Some(numeric_result()),
+            _ => None,
+        };
+
+        result.unwrap_or_else(|| {
+            self.report(format!(
+                "unsupported operand types for {}: {left} and {right}",
+                operator_symbol(operator)
+            ));
+            Type::Any
+        })
+    }
+}
+
+fn operator_symbol(operator: &BinaryOperator) -> &'static str {
+    match operator {
+        BinaryOperator::Add => "+",
+        BinaryOperator::Subtract => "-",
+        BinaryOperator::Multiply => "*",
+        BinaryOperator::Divide => "/",
+        BinaryOperator::FloorDivide => "//",
+        BinaryOperator::Modulo => "%",
+        BinaryOperator::Power => "**",
+        BinaryOperator::Equal => "==",
+        BinaryOperator::NotEqual => "!=",
+        BinaryOperator::Less => "<",
+        BinaryOperator::Greater => ">",
+        BinaryOperator::LessEqual => "<=",
+        BinaryOperator::GreaterEqual => ">=",
+        BinaryOperator::And => "and",
+        BinaryOperator::Or => "or",
+    }
+}
@@ -0,0 +1,779 @@
+//! Compare pycc's AST with CPython's `ast` module
+//!
+//! Both trees are rendered in a common notation named after CPython's node
+//! types, such as `Assign(x, BinOp(Add, Name(y), Constant(1)))`, and matched
+//! statement by statement using their line numbers. Statements pycc dropped
+//! or parsed differently are reported.
+
+use pycc::ast::*;
+use pycc::lexer::Lexer;
+use pycc::lsp::Json;
+use pycc::parser::Parser;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+/// Prints `ast.parse` of the file in `sys.argv[1]` as JSON
+///
+/// Nodes become objects with their fields plus `_type` and, for statements,
+/// `_line`. Floats become `{"_type": "float"}` objects so that `1.0` stays
+/// apart from `1`. Annotations are unparsed to source text, which is how pycc keeps
+/// them. A `SyntaxError` is printed as `{"_error": ...}`.
+const DUMP_SCRIPT: &str = r#"
+import ast, json, sys
+
+def convert(node):
+    if isinstance(node, ast.AST):
+        fields = {"_type": type(node).__name__}
+        if isinstance(node, ast.stmt):
+            fields["_line"] = node.lineno
+        for name, value in ast.iter_fields(node):
+            if name in ("annotation", "returns") and value is not None:
+                fields[name] = ast.unparse(value)
+            else:
+                fields[name] = convert(value)
+        return fields
+    if isinstance(node, list):
+        return [convert(item) for item in node]
+    if isinstance(node, float):
+        return {"_type": "float", "repr": repr(node)}
+    if node is None or isinstance(node, (bool, int, str)):
+        return node
+    return repr(node)
+
+try:
+    tree = ast.parse(open(sys.argv[1], encoding="utf-8").read())
+except SyntaxError as error:
+    print(json.dumps({"_error": f"{error.msg} (line {error.lineno})"}))
+else:
+    print(json.dumps(convert(tree)))
+"#;
+
+/// A statement in the common notation
+#[derive(Debug, Clone, PartialEq)]
+struct Statement {
+    line: usize,
+    shape: String,
+}
+
+/// How pycc's parse of a file differs from CPython's
+#[derive(Debug, Default, PartialEq)]
+struct AstReport {
+    /// CPython's syntax error, for a file pycc accepted anyway
+    rejected_by_cpython: Option<String>,
+    /// Statements CPython parsed and pycc did not, as `(line, CPython shape)`
+    dropped: Vec<Statement>,
+    /// Statements both parsed on a line, but differently, as `(CPython, pycc)`
+    different: Vec<(Statement, Statement)>,
+    /// Statements pycc produced with no CPython counterpart
+    extra: Vec<Statement>,
+}
+
+impl AstReport {
+    fn is_empty(&self) -> bool {
+        *self == AstReport::default()
+    }
+
+    fn render(&self, path: &Path) -> String {
+        let mut out = String::new();
+        if let Some(error) = &self.rejected_by_cpython {
+            out.push_str(&format!(
+                "{}: CPython rejects the file ({}) but pycc accepted it\n",
+                path.display(),
+                error
+            ));
+        }
+        for statement in &self.dropped {
+            out.push_str(&format!(
+                "{}:{}: dropped by pycc: {}\n",
+                path.display(),
+                statement.line,
+                statement.shape
+            ));
+        }
+        for (cpython, pycc) in &self.different {
+            out.push_str(&format!(
+                "{}:{}: parsed differently\n  CPython: {}\n  pycc:    {}\n",
+                path.display(),
+                cpython.line,
+                cpython.shape,
+                pycc.shape
+            ));
+        }
+        for statement in &self.extra {
+            out.push_str(&format!(
+                "{}:{}: only in pycc: {}\n",
+                path.display(),
+                statement.line,
+                statement.shape
+            ));
+        }
+        out
+    }
+}
+
+// ---- pycc side ----
+
+fn pycc_statements(source: &str) -> Vec<Statement> {
+    let Node::Program(program) = Parser::new(Lexer::new(source)).parse_program() else {
+        unreachable!("parse_program returns a Program");
+    };
+    let mut statements = Vec::new();
+    if let Some(docstring) = &program.docstring {
+        // The docstring was the first statement; its line is not kept
+        let line = program
+            .statements
+            .first()
+            .and_then(Node::line)
+            .map_or(1, |next| next.saturating_sub(1).max(1));
+        statements.push(Statement {
+            line: first_string_line(source).unwrap_or(line),
+            shape: format!("Expr(Constant({:?}))", docstring),
+        });
+    }
+    for statement in &program.statements {
+        statements.push(Statement {
+            line: statement.line().unwrap_or(0),
+            shape: pycc_statement(statement),
+        });
+    }
+    statements
+}
+
+/// Line of the first non-blank, non-comment line, where a module docstring starts
+fn first_string_line(source: &str) -> Option<usize> {
+    source
+        .lines()
+        .position(|line| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#')
+        })
+        .map(|index| index + 1)
+}
+
+/// Statements of a suite, which pycc stores as a `Block` or a single statement
+fn pycc_body(node: &Node) -> Vec<String> {
+    match node {
+        Node::Block(block) => block.statements.iter().map(pycc_statement).collect(),
+        statement => vec![pycc_statement(statement)],
+    }
+}
+
+fn pycc_statement(node: &Node) -> String {
+    match node {
+        Node::Assignment(assignment) => {
+            let targets: Vec<String> = assignment.targets.iter().map(pycc_expression).collect();
+            match (&assignment.annotation, targets.as_slice()) {
+                (Some(annotation), [target]) => format!(
+                    "AnnAssign({}, {}, {})",
+                    bare_name(target),
+                    annotation,
+                    pycc_expression(&assignment.value)
+                ),
+                (None, [target]) => format!(
+                    "Assign({}, {})",
+                    bare_name(target),
+                    pycc_expression(&assignment.value)
+                ),
+                _ => format!(
+                    "Assign([{}], {})",
+                    targets.join(", "),
+                    pycc_expression(&assignment.value)
+                ),
+            }
+        }
+        Node::Declaration(declaration) => {
+            format!(
+                "AnnAssign({}, {})",
+                declaration.name, declaration.annotation
+            )
+        }
+        Node::Function(function) => {
+            let parameters: Vec<String> = function
+                .parameters
+                .iter()
+                .zip(&function.parameter_types)
+                .map(|(name, annotation)| match annotation {
+                    Some(annotation) => format!("{}: {}", name, annotation),
+                    None => name.clone(),
+                })
+                .collect();
+            let mut body = Vec::new();
+            if let Some(docstring) = &function.docstring {
+                body.push(format!("Expr(Constant({:?}))", docstring));
+            }
+            body.extend(pycc_body(&function.body));
+            format!(
+                "{}({}, [{}], {}, [{}])",
+                if function.is_async {
+                    "AsyncFunctionDef"
+                } else {
+                    "FunctionDef"
+                },
+                function.name,
+                parameters.join(", "),
+                function.return_type.as_deref().unwrap_or("None"),
+                body.join(", ")
+            )
+        }
+        Node::If(if_stmt) => format!(
+            "If({}, [{}], [{}])",
+            pycc_expression(&if_stmt.condition),
+            pycc_body(&if_stmt.then_branch).join(", "),
+            if_stmt
+                .else_branch
+                .as_deref()
+                .map(|branch| pycc_body(branch).join(", "))
+                .unwrap_or_default()
+        ),
+        Node::While(while_stmt) => format!(
+            "While({}, [{}])",
+            pycc_expression(&while_stmt.condition),
+            pycc_body(&while_stmt.body).join(", ")
+        ),
+        Node::Return(return_stmt) => match &return_stmt.value {
+            Some(value) => format!("Return({})", pycc_expression(value)),
+            None => "Return()".to_string(),
+        },
+        Node::Import(import) => format!("Import({})", import.module),
+        Node::ExpressionStatement(expression) => {
+            format!("Expr({})", pycc_expression(&expression.expression))
+        }
+        Node::Block(block) => block
+            .statements
+            .iter()
+            .map(pycc_statement)
+            .collect::<Vec<_>>()
+            .join(", "),
+        other => format!("Expr({})", pycc_expression(other)),
+    }
+}
+
+fn pycc_expression(node: &Node) -> String {
+    match node {
+        Node::Binary(binary) => {
+            let left = pycc_expression(&binary.left);
+            let right = pycc_expression(&binary.right);
+            match &binary.operator {
+                BinaryOperator::Add => format!("BinOp(Add, {}, {})", left, right),
+                BinaryOperator::Subtract => format!("BinOp(Sub, {}, {})", left, right),
+                BinaryOperator::Multiply => format!("BinOp(Mult, {}, {})", left, right),
+                BinaryOperator::Divide => format!("BinOp(Div, {}, {})", left, right),
+                BinaryOperator::FloorDivide => format!("BinOp(FloorDiv, {}, {})", left, right),
+                BinaryOperator::Modulo => format!("BinOp(Mod, {}, {})", left, right),
+                BinaryOperator::Power => format!("BinOp(Pow, {}, {})", left, right),
+                BinaryOperator::Equal => format!("Compare(Eq, {}, {})", left, right),
+                BinaryOperator::NotEqual => format!("Compare(NotEq, {}, {})", left, right),
+                BinaryOperator::Less => format!("Compare(Lt, {}, {})", left, right),
+                BinaryOperator::Greater => format!("Compare(Gt, {}, {})", left, right),
+                BinaryOperator::LessEqual => format!("Compare(LtE, {}, {})", left, right),
+                BinaryOperator::GreaterEqual => format!("Compare(GtE, {}, {})", left, right),
+                BinaryOperator::And | BinaryOperator::Or => {
+                    let name = if binary.operator == BinaryOperator::And {
+                        "And"
+                    } else {
+                        "Or"
+                    };
+                    // CPython keeps `a and b and c` as one operation
+                    let mut values = Vec::new();
+                    flatten_bool_op(node, &binary.operator, &mut values);
+                    format!("BoolOp({}, {})", name, values.join(", "))
+                }
+            }
+        }
+        Node::Unary(unary) => {
+            let operator = match unary.operator {
+                UnaryOperator::Plus => "UAdd",
+                UnaryOperator::Minus => "USub",
+                UnaryOperator::Not => "Not",
+            };
+            format!("UnaryOp({}, {})", operator, pycc_expression(&unary.operand))
+        }
+        Node::Literal(literal) => match &literal.value {
+            LiteralValue::Integer(value) => format!("Constant({})", value),
+            LiteralValue::Float(value) => format!("Constant({:?})", value),
+            LiteralValue::String(value) => format!("Constant({:?})", value),
+            LiteralValue::Boolean(true) => "Constant(True)".to_string(),
+            LiteralValue::Boolean(false) => "Constant(False)".to_string(),
+            LiteralValue::None => "Constant(None)".to_string(),
+            LiteralValue::FString(fstring) => {
+                let parts: Vec<String> = fstring
+                    .parts
+                    .iter()
+                    .map(|part| match part {
+                        FStringPart::Literal(text) => format!("Constant({:?})", text),
+                        FStringPart::Expression(source) => {
+                            format!("FormattedValue({})", pycc_source_expression(source))
+                        }
+                    })
+                    .collect();
+                format!("JoinedStr({})", parts.join(", "))
+            }
+        },
+        Node::Identifier(identifier) => format!("Name({})", identifier.name),
+        Node::Call(call) => {
+            let mut parts = vec![format!("Name({})", call.callee)];
+            parts.extend(call.arguments.iter().map(pycc_expression));
+            parts.extend(call.keywords.iter().map(|(name, value)| {
+                format!(
+                    "keyword({}, {})",
+                    name.as_deref().unwrap_or("**"),
+                    pycc_expression(value)
+                )
+            }));
+            format!("Call({})", parts.join(", "))
+        }
+        Node::Subscript(subscript) => format!(
+            "Subscript({}, {})",
+            pycc_expression(&subscript.value),
+            pycc_expression(&subscript.index)
+        ),
+        Node::Await(await_expr) => format!("Await({})", pycc_expression(&await_expr.value)),
+        Node::Starred(starred) => format!("Starred({})", pycc_expression(&starred.value)),
+        Node::Tuple(Tuple { elements }) => format!("Tuple({})", pycc_expressions(elements)),
+        Node::List(List { elements }) => format!("List({})", pycc_expressions(elements)),
+        Node::Set(Set { elements }) => format!("Set({})", pycc_expressions(elements)),
+        Node::Dict(dict) => {
+            let entries: Vec<String> = dict
+                .entries
+                .iter()
+                .map(|(key, value)| format!("{}: {}", pycc_expression(key), pycc_expression(value)))
+                .collect();
+            format!("Dict({})", entries.join(", "))
+        }
+        other => format!("<{}>", pycc_statement(other)),
+    }
+}
+
+fn pycc_expressions(nodes: &[Node]) -> String {
+    nodes
+        .iter()
+        .map(pycc_expression)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn flatten_bool_op(node: &Node, operator: &BinaryOperator, values: &mut Vec<String>) {
+    match node {
+        Node::Binary(binary) if &binary.operator == operator => {
+            flatten_bool_op(&binary.left, operator, values);
+            flatten_bool_op(&binary.right, operator, values);
+        }
+        other => values.push(pycc_expression(other)),
+    }
+}
+
+/// An f-string replacement field, which pycc keeps as source text
+fn pycc_source_expression(source: &str) -> String {
+    let Node::Program(program) = Parser::new(Lexer::new(source)).parse_program() else {
+        unreachable!("parse_program returns a Program");
+    };
+    match program.statements.as_slice() {
+        [Node::ExpressionStatement(expression)] => pycc_expression(&expression.expression),
+        _ => format!("<unparsed {:?}>", source),
+    }
+}
+
+// ---- CPython side ----
+
+/// CPython's statements for the file at `path`, or its syntax error
+fn cpython_statements(path: &Path) -> Result<Result<Vec<Statement>, String>, String> {
+    let python = std::env::var_os("PYCC_TEST_PYTHON")
+        .filter(|python| !python.is_empty())
+        .map_or_else(|| PathBuf::from("python3"), PathBuf::from);
+    let output = Command::new(&python)
+        .args(["-c", DUMP_SCRIPT])
+        .arg(path)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", python.display(), e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Dumping the AST of {} failed: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    let tree = Json::parse(String::from_utf8_lossy(&output.stdout).trim())?;
+    if let Some(error) = tree.get("_error").and_then(Json::as_str) {
+        return Ok(Err(error.to_string()));
+    }
+    let body = tree
+        .get("body")
+        .and_then(Json::as_array)
+        .ok_or("Module without a body")?;
+    Ok(Ok(body
+        .iter()
+        .map(|statement| Statement {
+            line: statement
+                .get("_line")
+                .and_then(Json::as_u64)
+                .unwrap_or_default() as usize,
+            shape: cpython_statement(statement),
+        })
+        .collect()))
+}
+
+fn node_type(node: &Json) -> &str {
+    node.get("_type").and_then(Json::as_str).unwrap_or("?")
+}
+
+fn field<'a>(node: &'a Json, name: &str) -> &'a Json {
+    static NULL: Json = Json::Null;
+    node.get(name).unwrap_or(&NULL)
+}
+
+fn items(node: &Json, name: &str) -> Vec<String> {
+    field(node, name)
+        .as_array()
+        .unwrap_or_default()
+        .iter()
+        .map(cpython_expression)
+        .collect()
+}
+
+fn cpython_body(node: &Json, name: &str) -> String {
+    field(node, name)
+        .as_array()
+        .unwrap_or_default()
+        .iter()
+        .map(cpython_statement)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn cpython_statement(node: &Json) -> String {
+    match node_type(node) {
+        "Assign" => {
+            let targets = items(node, "targets");
+            match targets.as_slice() {
+                [target] => format!(
+                    "Assign({}, {})",
+                    bare_name(target),
+                    cpython_expression(field(node, "value"))
+                ),
+                _ => format!(
+                    "Assign([{}], {})",
+                    targets.join(", "),
+                    cpython_expression(field(node, "value"))
+                ),
+            }
+        }
+        "AnnAssign" => {
+            let target = bare_name(&cpython_expression(field(node, "target")));
+            let annotation = field(node, "annotation").as_str().unwrap_or("?");
+            match field(node, "value") {
+                Json::Null => format!("AnnAssign({}, {})", target, annotation),
+                value => format!(
+                    "AnnAssign({}, {}, {})",
+                    target,
+                    annotation,
+                    cpython_expression(value)
+                ),
+            }
+        }
+        kind @ ("FunctionDef" | "AsyncFunctionDef") => {
+            let arguments = field(node, "args");
+            let parameters: Vec<String> = field(arguments, "args")
+                .as_array()
+                .unwrap_or_default()
+                .iter()
+                .map(|argument| {
+                    let name = field(argument, "arg").as_str().unwrap_or("?");
+                    match field(argument, "annotation").as_str() {
+                        Some(annotation) => format!("{}: {}", name, annotation),
+                        None => name.to_string(),
+                    }
+                })
+                .collect();
+            format!(
+                "{}({}, [{}], {}, [{}])",
+                kind,
+                field(node, "name").as_str().unwrap_or("?"),
+                parameters.join(", "),
+                field(node, "returns").as_str().unwrap_or("None"),
+                cpython_body(node, "body")
+            )
+        }
+        "If" => format!(
+            "If({}, [{}], [{}])",
+            cpython_expression(field(node, "test")),
+            cpython_body(node, "body"),
+            cpython_body(node, "orelse")
+        ),
+        "While" => format!(
+            "While({}, [{}])",
+            cpython_expression(field(node, "test")),
+            cpython_body(node, "body")
+        ),
+        "Return" => match field(node, "value") {
+            Json::Null => "Return()".to_string(),
+            value => format!("Return({})", cpython_expression(value)),
+        },
+        "Import" => field(node, "names")
+            .as_array()
+            .unwrap_or_default()
+            .iter()
+            .map(|alias| format!("Import({})", field(alias, "name").as_str().unwrap_or("?")))
+            .collect::<Vec<_>>()
+            .join(", "),
+        "Expr" => format!("Expr({})", cpython_expression(field(node, "value"))),
+        _ => generic(node),
+    }
+}
+
+/// `x` for `Name(x)`, the usual single assignment target
+fn bare_name(shape: &str) -> String {
+    shape
+        .strip_prefix("Name(")
+        .and_then(|rest| rest.strip_suffix(')'))
+        .unwrap_or(shape)
+        .to_string()
+}
+
+fn cpython_expression(node: &Json) -> String {
+    match node_type(node) {
+        "BinOp" => format!(
+            "BinOp({}, {}, {})",
+            node_type(field(node, "op")),
+            cpython_expression(field(node, "left")),
+            cpython_expression(field(node, "right"))
+        ),
+        "Compare" => {
+            let operators: Vec<&str> = field(node, "ops")
+                .as_array()
+                .unwrap_or_default()
+                .iter()
+                .map(node_type)
+                .collect();
+            format!(
+                "Compare({}, {}, {})",
+                operators.join(", "),
+                cpython_expression(field(node, "left")),
+                items(node, "comparators").join(", ")
+            )
+        }
+        "BoolOp" => format!(
+            "BoolOp({}, {})",
+            node_type(field(node, "op")),
+            items(node, "values").join(", ")
+        ),
+        "UnaryOp" => format!(
+            "UnaryOp({}, {})",
+            node_type(field(node, "op")),
+            cpython_expression(field(node, "operand"))
+        ),
+        "Constant" => match field(node, "value") {
+            Json::Null => "Constant(None)".to_string(),
+            Json::Bool(true) => "Constant(True)".to_string(),
+            Json::Bool(false) => "Constant(False)".to_string(),
+            Json::String(value) => format!("Constant({:?})", value),
+            Json::Number(value) => format!("Constant({})", *value as i64),
+            float if node_type(float) == "float" => {
+                format!("Constant({})", field(float, "repr").as_str().unwrap_or("?"))
+            }
+            other => format!("Constant({})", other),
+        },
+        "JoinedStr" => format!("JoinedStr({})", items(node, "values").join(", ")),
+        "FormattedValue" => format!(
+            "FormattedValue({})",
+            cpython_expression(field(node, "value"))
+        ),
+        "Name" => format!("Name({})", field(node, "id").as_str().unwrap_or("?")),
+        "Attribute" => {
+            // pycc names dotted paths like `os.path` as a whole
+            let value = cpython_expression(field(node, "value"));
+            let attribute = field(node, "attr").as_str().unwrap_or("?");
+            match value
+                .strip_prefix("Name(")
+                .and_then(|rest| rest.strip_suffix(')'))
+            {
+                Some(base) => format!("Name({}.{})", base, attribute),
+                None => format!("Attribute({}, {})", value, attribute),
+            }
+        }
+        // `**kwargs` is a keyword without a name
+        "Call" => {
+            let mut parts = vec![cpython_expression(field(node, "func"))];
+            parts.extend(items(node, "args"));
+            parts.extend(
+                field(node, "keywords")
+                    .as_array()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|keyword| {
+                        format!(
+                            "keyword({}, {})",
+                            field(keyword, "arg").as_str().unwrap_or("**"),
+                            cpython_expression(field(keyword, "value"))
+                        )
+                    }),
+            );
+            format!("Call({})", parts.join(", "))
+        }
+        "Subscript" => format!(
+            "Subscript({}, {})",
+            cpython_expression(field(node, "value")),
+            cpython_expression(field(node, "slice"))
+        ),
+        "Await" => format!("Await({})", cpython_expression(field(node, "value"))),
+        "Starred" => format!("Starred({})", cpython_expression(field(node, "value"))),
+        kind @ ("Tuple" | "List" | "Set") => {
+            format!("{}({})", kind, items(node, "elts").join(", "))
+        }
+        "Dict" => {
+            let entries: Vec<String> = items(node, "keys")
+                .into_iter()
+                .zip(items(node, "values"))
+                .map(|(key, value)| format!("{}: {}", key, value))
+                .collect();
+            format!("Dict({})", entries.join(", "))
+        }
+        _ => generic(node),
+    }
+}
+
+/// Any other node, with every field spelled out so it never matches pycc
+fn generic(node: &Json) -> String {
+    match node {
+        Json::Object(members) => {
+            let fields: Vec<String> = members
+                .iter()
+                .filter(|(name, value)| {
+                    !name.starts_with('_') && name != "ctx" && *value != Json::Null
+                })
+                .map(|(_, value)| generic(value))
+                .collect();
+            if fields.is_empty() {
+                node_type(node).to_string()
+            } else {
+                format!("{}({})", node_type(node), fields.join(", "))
+            }
+        }
+        Json::String(value) => value.clone(),
+        Json::Array(values) => format!(
+            "[{}]",
+            values.iter().map(generic).collect::<Vec<_>>().join(", ")
+        ),
+        other => other.to_string(),
+    }
+}
+
+// ---- comparison ----
+
+/// Compare pycc's parse of the file at `path` with CPython's
+fn compare_file(path: &Path) -> Result<AstReport, String> {
+    let source = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let pycc = pycc_statements(&source);
+    let mut report = AstReport::default();
+    let cpython = match cpython_statements(path)? {
+        Ok(statements) => statements,
+        Err(error) => {
+            report.rejected_by_cpython = Some(error);
+            return Ok(report);
+        }
+    };
+
+    let mut unmatched: Vec<Option<&Statement>> = pycc.iter().map(Some).collect();
+    for statement in &cpython {
+        // Declarations such as `x: int` carry no line, so they match by shape
+        let on_line = unmatched
+            .iter()
+            .position(|candidate| candidate.is_some_and(|c| c.line == statement.line))
+            .or_else(|| {
+                unmatched.iter().position(|candidate| {
+                    candidate.is_some_and(|c| c.line == 0 && c.shape == statement.shape)
+                })
+            })
+            .map(|index| &mut unmatched[index]);
+        match on_line {
+            Some(candidate) => {
+                let pycc_statement = candidate.take().unwrap();
+                if pycc_statement.shape != statement.shape {
+                    report
+                        .different
+                        .push((statement.clone(), pycc_statement.clone()));
+                }
+            }
+            None => report.dropped.push(statement.clone()),
+        }
+    }
+    report.extra = unmatched.into_iter().flatten().cloned().collect();
+    Ok(report)
+}
+
+fn compare_source(source: &str) -> AstReport {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("snippet.py");
+    fs::write(&path, source).unwrap();
+    compare_file(&path).unwrap()
+}
+
+/// Python files in `tests/python_files` and in the directories listed in
+/// `PYCC_AST_CORPUS`
+fn corpus_files() -> Vec<PathBuf> {
+    let mut directories = vec![PathBuf::from("tests/python_files")];
+    if let Some(extra) = std::env::var_os("PYCC_AST_CORPUS") {
+        directories.extend(std::env::split_paths(&extra));
+    }
+    let mut paths = Vec::new();
+    for directory in directories {
+        let entries = fs::read_dir(&directory)
+            .unwrap_or_else(|e| panic!("Failed to read {}: {}", directory.display(), e));
+        paths.extend(
+            entries
+                .map(|entry| entry.unwrap().path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "py")),
+        );
+    }
+    paths.sort();
+    paths
+}
+
+#[test]
+fn test_corpus_matches_cpython_ast() {
+    let mut failures = String::new();
+    for path in corpus_files() {
+        let report = compare_file(&path).unwrap();
+        failures.push_str(&report.render(&path));
+    }
+    assert!(failures.is_empty(), "{}", failures);
+}
+
+#[test]
+fn test_matching_source_has_empty_report() {
+    let report = compare_source(
+        "\"\"\"Module\"\"\"\nimport math\nx: int = 1 + 2 * 3\ny: float\n\ndef f(a: int, b) -> int:\n    if a:\n        return -a + b\n    else:\n        return a ** 2 // b\n\nprint(f\"{x} and {f(x, 1)}\", 1.0, math.pi)\npairs = [(1,), (), {}, {1, 2,}, {\"a\": [x,], 2: y,}]\n",
+    );
+    assert!(report.is_empty(), "{:?}", report);
+}
+
+#[test]
+fn test_reports_source_cpython_rejects() {
+    let report = compare_source("x = ;\nprint(1)\n");
+    let error = report.rejected_by_cpython.expect("CPython rejects `x = ;`");
+    assert!(error.contains("line 1"), "{}", error);
+}
+
+#[test]
+fn test_reports_dropped_statement() {
+    let report = compare_source("x = 1\nwhile x:\n    x = 0\nprint(x)\n");
+    assert_eq!(report.dropped.len(), 1, "{:?}", report);
+    assert_eq!(report.dropped[0].line, 2);
+    assert!(report.dropped[0].shape.starts_with("While("));
+    // The syntax error skips the loop body along with the loop
+    assert!(report.extra.is_empty(), "{:?}", report);
+}
+
+#[test]
+fn test_reports_differently_parsed_statement() {
+    let report = compare_source("x = 1\ny: \"int\" = 2\nprint(x)\n");
+    assert!(report.dropped.is_empty(), "{:?}", report);
+    assert_eq!(report.different.len(), 1, "{:?}", report);
+    let (cpython, pycc) = &report.different[0];
+    assert_eq!(cpython.line, 2);
+    assert_eq!(cpython.shape, "AnnAssign(y, 'int', Constant(2))");
+    // pycc keeps a forward reference without its quotes
+    assert_eq!(pycc.shape, "AnnAssign(y, int, Constant(2))");
+}
@@ -108,10 +108,14 @@ fn test_identifier_and_assignment() {
     });
 
     let assignment = Node::Assignment(Assignment {
-        name: "x".to_string(),
+        targets: vec![Node::Identifier(Identifier {
+            name: "x".to_string(),
+        })],
         value: Box::new(Node::Literal(Literal {
             value: LiteralValue::Integer(42),
         })),
+        annotation: None,
+        line: 1,
     });
 
     // Verify identifier
@@ -123,7 +127,7 @@ fn test_identifier_and_assignment() {
     // Verify assignment
     match assignment {
         Node::Assignment(assign) => {
-            assert_eq!(assign.name, "x");
+            assert_eq!(assign.names().collect::<Vec<_>>(), ["x"]);
             match *assign.value {
                 Node::Literal(lit) => match lit.value {
                     LiteralValue::Integer(val) => assert_eq!(val, 42),
@@ -141,11 +145,17 @@ fn test_function_node() {
     let function = Node::Function(Function {
         name: "test_func".to_string(),
         parameters: vec!["a".to_string(), "b".to_string()],
+        parameter_types: vec![None, None],
+        return_type: None,
         body: Box::new(Node::Return(Return {
             value: Some(Box::new(Node::Literal(Literal {
                 value: LiteralValue::Integer(42),
             }))),
+            line: 2,
         })),
+        docstring: None,
+        is_async: false,
+        line: 1,
     });
 
     match function {
@@ -166,6 +176,7 @@ fn test_call_node() {
         arguments: vec![Node::Literal(Literal {
             value: LiteralValue::String("Hello, World!".to_string()),
         })],
+        keywords: vec![],
     });
 
     match call {
@@ -0,0 +1,164 @@
+use pycc::cache::{self, BuildCache};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_cache_key_is_stable() {
+    let first = BuildCache::key("x = 1", "O0");
+    let second = BuildCache::key("x = 1", "O0");
+    assert_eq!(first, second);
+    assert_eq!(first.len(), 16);
+}
+
+#[test]
+fn test_cache_key_depends_on_source_and_options() {
+    let base = BuildCache::key("x = 1", "O0");
+    assert_ne!(base, BuildCache::key("x = 2", "O0"));
+    assert_ne!(base, BuildCache::key("x = 1", "O2"));
+    assert_ne!(
+        BuildCache::key("ab", "c"),
+        BuildCache::key("a", "bc"),
+        "source/options boundary must be part of the key"
+    );
+}
+
+#[test]
+fn test_program_key_depends_on_module_names_and_order() {
+    let base = BuildCache::program_key(&[("a", "x = 1"), ("b", "y = 2")], "O0");
+    assert_eq!(
+        base,
+        BuildCache::program_key(&[("a", "x = 1"), ("b", "y = 2")], "O0")
+    );
+    assert_ne!(
+        base,
+        BuildCache::program_key(&[("b", "y = 2"), ("a", "x = 1")], "O0")
+    );
+    assert_ne!(
+        base,
+        BuildCache::program_key(&[("a", "x = 1"), ("c", "y = 2")], "O0")
+    );
+    assert_ne!(
+        base,
+        BuildCache::program_key(&[("a", "x = 1\ny = 2")], "O0"),
+        "module boundaries must be part of the key"
+    );
+}
+
+#[test]
+fn test_cache_lookup_and_prepare() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = BuildCache::new(temp_dir.path().join("cache"));
+    let key = BuildCache::key("print(1)", "O0");
+
+    assert!(cache.lookup("main", &key).is_none());
+
+    let object = cache.prepare("main").unwrap();
+    fs::write(&object, b"object").unwrap();
+    // Not visible until the object is committed
+    assert!(cache.lookup("main", &key).is_none());
+
+    let object_path = cache.commit("main", &key, object).unwrap();
+    assert_eq!(object_path, cache.object_path("main", &key));
+    assert_eq!(cache.lookup("main", &key), Some(object_path.clone()));
+    assert_eq!(fs::read(&object_path).unwrap(), b"object");
+    assert!(cache.lookup("other", &key).is_none());
+}
+
+#[test]
+fn test_interrupted_builds_leave_no_entry() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = BuildCache::new(temp_dir.path());
+    let key = BuildCache::key("print(1)", "O0");
+
+    let object = cache.prepare("main").unwrap();
+    fs::write(&object, b"partial").unwrap();
+    drop(object);
+    assert!(cache.lookup("main", &key).is_none());
+    assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_stale_entries_are_replaced() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache = BuildCache::new(temp_dir.path());
+    let old_key = BuildCache::key("print(1)", "O0");
+    let new_key = BuildCache::key("print(2)", "O0");
+
+    let object = cache.prepare("main").unwrap();
+    fs::write(&object, b"old").unwrap();
+    let old_path = cache.commit("main", &old_key, object).unwrap();
+    assert!(cache.lookup("main", &new_key).is_none());
+
+    // Another module's entry is left alone
+    let object = cache.prepare("main_helpers").unwrap();
+    fs::write(&object, b"helpers").unwrap();
+    let helpers_path = cache.commit("main_helpers", &old_key, object).unwrap();
+
+    let object = cache.prepare("main").unwrap();
+    fs::write(&object, b"new").unwrap();
+    let new_path = cache.commit("main", &new_key, object).unwrap();
+    assert_ne!(new_path, old_path);
+    assert_eq!(cache.lookup("main", &new_key), Some(new_path));
+    assert!(cache.lookup("main", &old_key).is_none());
+    assert!(!old_path.exists());
+    assert!(helpers_path.exists());
+    assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 2);
+}
+
+#[test]
+fn test_cache_for_source_uses_sibling_directory() {
+    let temp_dir = TempDir::new().unwrap();
+    let source = temp_dir.path().join("main.py");
+    let cache = BuildCache::for_source(&source);
+    let key = BuildCache::key("print(1)", "O0");
+
+    assert_eq!(
+        cache.object_path("main", &key),
+        temp_dir
+            .path()
+            .join("__pycc_cache__")
+            .join(format!("main.pycc-{}.{key}.o", env!("CARGO_PKG_VERSION")))
+    );
+}
+
+#[test]
+fn test_clean_removes_only_build_artifacts() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().canonicalize().unwrap();
+    let nested = root.join("tools");
+    fs::create_dir_all(root.join(".pycc-cache")).unwrap();
+    fs::create_dir_all(&nested).unwrap();
+    // User-owned files that merely look like pycc output
+    for file in ["a.out", "a.out.o", "foo.o", "main.py"] {
+        fs::write(root.join(file), b"").unwrap();
+    }
+    fs::write(root.join("main"), b"").unwrap();
+    fs::write(nested.join("tool"), b"").unwrap();
+    BuildCache::for_source(root.join("main.py"))
+        .record_output(&root.join("main"))
+        .unwrap();
+    BuildCache::for_source(nested.join("tool.py"))
+        .record_output(&nested.join("tool"))
+        .unwrap();
+
+    let expected = vec![
+        root.join("main"),
+        root.join("__pycc_cache__"),
+        root.join(".pycc-cache"),
+    ];
+    assert_eq!(cache::clean(&root, false, true).unwrap(), expected);
+    assert!(root.join("main").exists());
+
+    assert_eq!(cache::clean(&root, false, false).unwrap(), expected);
+    assert!(expected.iter().all(|path| !path.exists()));
+    for file in ["a.out", "a.out.o", "foo.o", "main.py"] {
+        assert!(root.join(file).exists(), "{file} was removed");
+    }
+    // Subdirectories are only cleaned on request
+    assert!(nested.join("tool").exists());
+    assert_eq!(
+        cache::clean(&root, true, false).unwrap(),
+        vec![nested.join("tool"), nested.join("__pycc_cache__")]
+    );
+    assert!(cache::clean(&root, true, false).unwrap().is_empty());
+}
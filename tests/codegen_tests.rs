@@ -1,8 +1,42 @@
 use inkwell::context::Context;
-use pycc::codegen::CodeGenerator;
+use pycc::ast::LiteralValue;
+use pycc::builtins;
+use pycc::codegen::constants::module_constants;
+use pycc::codegen::escape::escaping_variables;
+use pycc::codegen::options::Platform;
+use pycc::codegen::{
+    CodeGenerator, CodeModel, CodegenOptions, Entry, Fallback, LocalModule, LtoMode, RelocModel,
+    Sanitizer, lint,
+};
 use pycc::lexer::Lexer;
 use pycc::parser::Parser;
 
+/// LLVM IR of `source` compiled with the default options
+fn compile_ir(source: &str) -> String {
+    compile_ir_with(source, CodegenOptions::default())
+}
+
+/// LLVM IR of `source` compiled with `options`, before optimization
+fn compile_ir_with(source: &str, options: CodegenOptions) -> String {
+    let program = Parser::new(Lexer::new(source)).parse_program();
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    codegen.set_options(options);
+    codegen.compile(&program).unwrap();
+    codegen.module().print_to_string().to_string()
+}
+
+/// LLVM IR of `source` compiled with `options` and optimized
+fn optimized_ir(source: &str, options: CodegenOptions) -> String {
+    let program = Parser::new(Lexer::new(source)).parse_program();
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    codegen.set_options(options);
+    codegen.compile(&program).unwrap();
+    codegen.optimize().unwrap();
+    codegen.module().print_to_string().to_string()
+}
+
 #[test]
 fn test_codegen_integer_literal() {
     let input = "42;";
@@ -162,3 +196,1093 @@ fn test_codegen_print_ir() {
     // Test that we can print the IR without panicking
     codegen.print_ir();
 }
+
+#[test]
+fn test_lto_mode_parsing() {
+    assert_eq!(LtoMode::parse("off"), Ok(LtoMode::Off));
+    assert_eq!(LtoMode::parse("thin"), Ok(LtoMode::Thin));
+    assert_eq!(LtoMode::parse("full"), Ok(LtoMode::Full));
+    assert!(LtoMode::parse("medium").is_err());
+
+    assert_eq!(LtoMode::Off.linker_flag(), None);
+    assert_eq!(LtoMode::Thin.linker_flag(), Some("-flto=thin"));
+}
+
+#[test]
+fn test_platform_linking_conventions() {
+    assert_eq!(Platform::Linux.linker_flags(), ["-no-pie", "-lm"]);
+    assert!(!Platform::MacOs.linker_flags().contains(&"-no-pie"));
+    assert_eq!(Platform::Linux.stream_symbol("stderr"), "stderr");
+    assert_eq!(Platform::MacOs.stream_symbol("stderr"), "__stderrp");
+    assert_eq!(Platform::MacOs.stream_symbol("stdin"), "__stdinp");
+    assert_eq!(Platform::Linux.as_needed_flag(), "-Wl,--as-needed");
+    assert!(!Platform::MacOs.as_needed_flag().contains("--as-needed"));
+    assert_ne!(
+        Platform::Linux.clock_monotonic(),
+        Platform::MacOs.clock_monotonic()
+    );
+}
+
+#[test]
+fn test_code_and_relocation_model_parsing() {
+    assert_eq!(CodeModel::parse("small"), Ok(CodeModel::Small));
+    assert_eq!(CodeModel::parse("medium"), Ok(CodeModel::Medium));
+    assert_eq!(CodeModel::parse("large"), Ok(CodeModel::Large));
+    assert!(CodeModel::parse("tiny").is_err());
+    assert_eq!(RelocModel::parse("static"), Ok(RelocModel::Static));
+    assert_eq!(RelocModel::parse("pic"), Ok(RelocModel::Pic));
+    assert!(RelocModel::parse("ropi").is_err());
+
+    let large = CodegenOptions {
+        code_model: CodeModel::Large,
+        ..CodegenOptions::default()
+    };
+    let pic = CodegenOptions {
+        reloc_model: RelocModel::Pic,
+        ..CodegenOptions::default()
+    };
+    let defaults = CodegenOptions::default().fingerprint();
+    assert_ne!(large.fingerprint(), defaults);
+    assert_ne!(pic.fingerprint(), defaults);
+}
+
+#[test]
+fn test_codegen_options_fingerprint() {
+    let defaults = CodegenOptions::default();
+    let tuned = CodegenOptions {
+        target_cpu: "native".to_string(),
+        lto: LtoMode::Thin,
+        ..CodegenOptions::default()
+    };
+
+    assert_ne!(defaults.fingerprint(), tuned.fingerprint());
+    let inlined = CodegenOptions {
+        inline_threshold: Some(500),
+        ..CodegenOptions::default()
+    };
+    assert_ne!(defaults.fingerprint(), inlined.fingerprint());
+    assert_eq!(
+        defaults.fingerprint(),
+        CodegenOptions::default().fingerprint()
+    );
+}
+
+#[test]
+fn test_build_info_is_embedded() {
+    let lexer = Lexer::new("print(1)\n");
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    let build_ir = |deterministic| {
+        let context = Context::create();
+        let mut codegen = CodeGenerator::new(&context, "main");
+        codegen.set_options(CodegenOptions {
+            deterministic,
+            ..CodegenOptions::default()
+        });
+        codegen.set_source_file("main.py");
+        codegen.compile(&program).unwrap();
+        codegen.optimize().unwrap();
+
+        codegen.module().print_to_string().to_string()
+    };
+
+    let ir = build_ir(false);
+    let version = env!("CARGO_PKG_VERSION");
+    assert!(ir.contains("; ModuleID = 'main'"), "{ir}");
+    assert!(ir.contains("@__pycc_build_info = constant"), "{ir}");
+    assert!(
+        ir.contains(&format!("c\"pycc {version} source=main.py options=O2;")),
+        "{ir}"
+    );
+    assert!(ir.contains("!pycc.build_info"), "{ir}");
+    assert!(ir.contains(&format!("!\"pycc version {version}\"")), "{ir}");
+    assert!(ir.contains("!\"built\""), "{ir}");
+
+    let deterministic = build_ir(true);
+    assert!(!deterministic.contains("!\"built\""), "{deterministic}");
+    assert!(!deterministic.contains(" built="), "{deterministic}");
+    assert_eq!(deterministic, build_ir(true));
+}
+
+#[test]
+fn test_codegen_writes_bitcode() {
+    let lexer = Lexer::new("x = 1; print(x);");
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    codegen.compile(&program).unwrap();
+
+    let temp_file = tempfile::NamedTempFile::new().unwrap();
+    let path = temp_file.path().to_str().unwrap();
+    assert!(codegen.write_bitcode_to_file(path).is_ok());
+
+    // LLVM bitcode starts with the 'BC' magic
+    let bytes = std::fs::read(path).unwrap();
+    assert_eq!(&bytes[..2], b"BC");
+}
+
+#[test]
+fn test_typed_codegen_uses_native_signatures() {
+    let input = "def half(x: float) -> float:\n    return x / 2.0\n\nprint(half(3))\n";
+    let ir = compile_ir_with(
+        input,
+        CodegenOptions {
+            typed: true,
+            ..CodegenOptions::default()
+        },
+    );
+    assert!(ir.contains("define double @py.half(double"), "{ir}");
+}
+
+#[test]
+fn test_overflow_checks_use_checked_intrinsics() {
+    let ir = compile_ir_with(
+        "x = 2; y = x * x + 1 - x; print(y);",
+        CodegenOptions {
+            overflow_checks: true,
+            ..CodegenOptions::default()
+        },
+    );
+    assert!(ir.contains("@llvm.smul.with.overflow.i64"), "{ir}");
+    assert!(ir.contains("@llvm.sadd.with.overflow.i64"), "{ir}");
+    assert!(ir.contains("@llvm.ssub.with.overflow.i64"), "{ir}");
+    assert!(ir.contains("integer overflow (pycc limitation)"), "{ir}");
+}
+
+#[test]
+fn test_escape_analysis_follows_returns_and_assignments() {
+    let source =
+        "def f(a, b):\n    s = a + b\n    t = s\n    u = f\"{a}\"\n    print(u)\n    return t\n";
+    let lexer = Lexer::new(source);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    let pycc::ast::Node::Program(program) = program else {
+        panic!("expected a program");
+    };
+    let pycc::ast::Node::Function(function) = &program.statements[0] else {
+        panic!("expected a function");
+    };
+
+    let escaping = escaping_variables(&function.body);
+    assert!(escaping.contains("t"));
+    assert!(escaping.contains("s"));
+    assert!(!escaping.contains("u"));
+    // Operands are copied into the concatenation, so they stay local
+    assert!(!escaping.contains("a"));
+}
+
+#[test]
+fn test_only_escaping_strings_are_heap_allocated() {
+    let source = "def greet(name: str) -> str:\n    return f\"Hello, {name}!\"\n\nn = 1\nprint(f\"{n} and {2}\");";
+    let ir = compile_ir_with(
+        source,
+        CodegenOptions {
+            typed: true,
+            ..CodegenOptions::default()
+        },
+    );
+    let (main, greet) = ir
+        .split_once("define ")
+        .and_then(|(_, defs)| defs.split_once("@py.greet("))
+        .unwrap();
+    assert!(main.contains("%fstring_result = alloca i8"), "{ir}");
+    assert!(!main.contains("@malloc("), "{ir}");
+    assert!(greet.contains("@malloc(i64 "), "{ir}");
+}
+
+#[test]
+fn test_runtime_divisors_are_checked() {
+    let ir = compile_ir("def f(a, b):\n    return a // b + a % b\n\nprint(f(7, 2))\n");
+    assert!(
+        ir.contains("ZeroDivisionError: integer division or modulo by zero"),
+        "{ir}"
+    );
+    assert!(
+        ir.contains("ZeroDivisionError: integer modulo by zero"),
+        "{ir}"
+    );
+    assert!(ir.contains("%is_minus_one = icmp eq i64 %"), "{ir}");
+
+    // Constant divisors need no check
+    let ir = compile_ir("x = 7\nprint(x // 2, x % 3)\n");
+    assert!(!ir.contains("ZeroDivisionError"), "{ir}");
+}
+
+#[test]
+fn test_branches_binding_incompatible_types_are_rejected() {
+    let lexer = Lexer::new("x = 1\nif x:\n    x = \"one\"\nprint(x)\n");
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    let error = codegen.compile(&program).unwrap_err().message;
+    assert!(
+        error.contains("'x' holds values of different types"),
+        "{error}"
+    );
+}
+
+#[test]
+fn test_runtime_helpers_are_not_callable() {
+    let lexer = Lexer::new("x = 2 ** 3\nprint(pycc_pow(2, 3))\n");
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    let error = codegen.compile(&program).unwrap_err().message;
+    assert_eq!(error, "Undefined function: pycc_pow");
+}
+
+#[test]
+fn test_call_arity_is_validated() {
+    let cases = [
+        (
+            "add(1)",
+            "TypeError: add() missing 1 required positional argument: 'b'",
+        ),
+        (
+            "add()",
+            "TypeError: add() missing 2 required positional arguments: 'a' and 'b'",
+        ),
+        (
+            "add(1, 2, 3)",
+            "TypeError: add() takes 2 positional arguments but 3 were given",
+        ),
+    ];
+
+    for (call, expected) in cases {
+        let source = format!("def add(a, b):\n    return a + b\n\nprint({call})\n");
+        let lexer = Lexer::new(&source);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        let context = Context::create();
+        let mut codegen = CodeGenerator::new(&context, "test_module");
+        assert_eq!(codegen.compile(&program).unwrap_err().message, expected);
+    }
+}
+
+#[test]
+fn test_builtin_arity_comes_from_the_registry() {
+    let cases = [
+        (
+            "repr(1, 2)",
+            "TypeError: repr() takes exactly one argument (2 given)",
+        ),
+        (
+            "hash()",
+            "TypeError: hash() takes exactly one argument (0 given)",
+        ),
+        (
+            "input(\"a\", \"b\")",
+            "TypeError: input expected at most 1 argument, got 2",
+        ),
+    ];
+
+    for (call, expected) in cases {
+        let source = format!("print({call})\n");
+        let program = Parser::new(Lexer::new(&source)).parse_program();
+
+        let context = Context::create();
+        let mut codegen = CodeGenerator::new(&context, "test_module");
+        assert_eq!(codegen.compile(&program).unwrap_err().message, expected);
+    }
+
+    let print = builtins::lookup("print").unwrap();
+    assert_eq!(print.arity_error(3), None);
+    assert_eq!(builtins::lookup("len").unwrap().lowering, None);
+    assert!(builtins::lookup("pycc_pow").is_none());
+}
+
+#[test]
+fn test_pure_builtin_calls_are_folded() {
+    // int() of a string and str() are only compiled when their result is known
+    let source = "print(str(int(\" 4_2 \")) + str(len(\"abc\") > 2))\nprint(str(abs(-3)))\n";
+    let ir = compile_ir(source);
+    assert!(ir.contains("c\"42True\\00\""), "{ir}");
+    assert!(ir.contains("c\"3\\00\""), "{ir}");
+
+    let source = "def len(text):\n    return 0\n\nprint(len(\"abc\"))\n";
+    let ir = compile_ir(source);
+    assert!(ir.contains("call i64 @py.len("), "{ir}");
+}
+
+#[test]
+fn test_calling_function_before_its_def_is_a_name_error() {
+    let lexer = Lexer::new("print(late())\n\ndef late():\n    return 1\n");
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    assert_eq!(
+        codegen.compile(&program).unwrap_err().message,
+        "NameError: name 'late' is not defined"
+    );
+}
+
+#[test]
+fn test_defining_a_function_in_both_branches_is_rejected() {
+    let source = "if 1:\n    def f():\n        return 1\nelse:\n    def f():\n        return 2\nprint(f())\n";
+    let lexer = Lexer::new(source);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    let error = codegen.compile(&program).unwrap_err().message;
+    assert!(error.contains("'f' is defined in both branches"), "{error}");
+}
+
+#[test]
+fn test_module_constants_are_bound_once_to_literals() {
+    let source = "N = 1000\nOFFSET = -3\nname = \"a\"\nname = \"b\"\nif N:\n    FLAG = True\ncount = N + 1\n";
+    let lexer = Lexer::new(source);
+    let mut parser = Parser::new(lexer);
+    let pycc::ast::Node::Program(program) = parser.parse_program() else {
+        panic!("expected a program");
+    };
+
+    let constants = module_constants(&program.statements);
+    assert_eq!(constants.get("N"), Some(&LiteralValue::Integer(1000)));
+    assert_eq!(constants.get("OFFSET"), Some(&LiteralValue::Integer(-3)));
+    // Rebound, conditional or computed names are not constants
+    assert!(!constants.contains_key("name"));
+    assert!(!constants.contains_key("FLAG"));
+    assert!(!constants.contains_key("count"));
+}
+
+#[test]
+fn test_module_constants_exclude_names_read_before_their_assignment() {
+    let source = "def show():\n    return LIMIT\n\ndef helper():\n    return show()\n\ndef greet():\n    return f\"{NAME}!\"\n\nprint(helper(), greet())\nLIMIT = 10\nNAME = \"pycc\"\nSIZE = 3\n\ndef area():\n    return SIZE * SIZE\n\nprint(area())\n";
+    let lexer = Lexer::new(source);
+    let mut parser = Parser::new(lexer);
+    let pycc::ast::Node::Program(program) = parser.parse_program() else {
+        panic!("expected a program");
+    };
+
+    let constants = module_constants(&program.statements);
+    // `helper()` runs `show()`, and `greet()` formats NAME, before either is assigned
+    assert!(!constants.contains_key("LIMIT"));
+    assert!(!constants.contains_key("NAME"));
+    assert_eq!(constants.get("SIZE"), Some(&LiteralValue::Integer(3)));
+}
+
+#[test]
+fn test_pass_pipeline_per_optimization_level() {
+    let options = |optimization| CodegenOptions {
+        optimization,
+        ..CodegenOptions::default()
+    };
+    assert_eq!(options(0).pass_pipeline(), "function(dse),globaldce");
+    assert_eq!(options(2).pass_pipeline(), "default<O2>");
+    assert_eq!(options(9).pass_pipeline(), "default<O3>");
+}
+
+#[test]
+fn test_size_optimization_levels() {
+    assert_eq!(CodegenOptions::parse_optimization("3"), Ok((3, 0)));
+    assert_eq!(CodegenOptions::parse_optimization("s"), Ok((2, 1)));
+    assert_eq!(CodegenOptions::parse_optimization("z"), Ok((2, 2)));
+    assert!(CodegenOptions::parse_optimization("4").is_err());
+    assert!(CodegenOptions::parse_optimization("fast").is_err());
+
+    let options = |(optimization, size_level)| CodegenOptions {
+        optimization,
+        size_level,
+        strip: true,
+        ..CodegenOptions::default()
+    };
+    let small = options(CodegenOptions::parse_optimization("z").unwrap());
+    assert_eq!(small.pass_pipeline(), "default<Oz>");
+    assert_eq!(small.optimization_flag(), "-Oz");
+    assert_eq!(options((2, 1)).pass_pipeline(), "default<Os>");
+    assert_ne!(small.fingerprint(), CodegenOptions::default().fingerprint());
+
+    let command = small.linker_command("a.o", "a").unwrap();
+    let args: Vec<_> = command.get_args().collect();
+    assert!(args.contains(&Platform::host().strip_flag().as_ref()));
+    assert!(args.contains(&Platform::host().dead_strip_flag().as_ref()));
+}
+
+#[test]
+fn test_size_optimization_marks_functions() {
+    let ir = optimized_ir(
+        "def f(x):\n    return x + 1\n\nprint(f(1))\n",
+        CodegenOptions {
+            size_level: 2,
+            inline_threshold: Some(0),
+            ..CodegenOptions::default()
+        },
+    );
+    assert!(ir.contains("minsize"), "{ir}");
+    assert!(ir.contains("optsize"), "{ir}");
+}
+
+#[test]
+fn test_string_constants_are_emitted_once() {
+    let ir = compile_ir_with(
+        "print(\"hello\")\nprint(\"hello\")\nprint(f\"{1}\")\n",
+        CodegenOptions {
+            optimization: 0,
+            ..CodegenOptions::default()
+        },
+    );
+    assert_eq!(ir.matches("c\"hello\\00\"").count(), 1, "{ir}");
+    assert_eq!(ir.matches("c\"None\\00\"").count(), 1, "{ir}");
+}
+
+#[test]
+fn test_constant_fstrings_are_folded() {
+    let source = "LIMIT = 10\n\ndef describe() -> str:\n    return f\"limit={LIMIT * 2}\"\n\nprint(describe())\nprint(f\"{'py' + 'cc'} {-7 // 2} {not True}\")\n";
+    let ir = compile_ir_with(
+        source,
+        CodegenOptions {
+            typed: true,
+            ..CodegenOptions::default()
+        },
+    );
+    assert!(ir.contains("c\"limit=20\\00\""), "{ir}");
+    assert!(ir.contains("c\"pycc -4 False\\00\""), "{ir}");
+    assert!(!ir.contains("@snprintf("), "{ir}");
+}
+
+#[test]
+fn test_fstring_fields_see_shadowing_locals() {
+    let source = "N = 10\n\ndef show(N):\n    print(f\"n={N}\")\n    return 0\n\nshow(3)\n";
+    let ir = compile_ir(source);
+    assert!(!ir.contains("c\"n=10\\00\""), "{ir}");
+    assert!(ir.contains("@snprintf("), "{ir}");
+}
+
+#[test]
+fn test_address_sanitizer_pass_runs_after_optimization() {
+    let options = |optimization| CodegenOptions {
+        optimization,
+        sanitize: Sanitizer::Address,
+        ..CodegenOptions::default()
+    };
+    assert_eq!(options(0).pass_pipeline(), "function(dse),globaldce,asan");
+    assert_eq!(options(2).pass_pipeline(), "default<O2>,asan");
+    assert_eq!(Sanitizer::parse("address"), Ok(Sanitizer::Address));
+    assert_eq!(Sanitizer::Address.linker_flag(), Some("-fsanitize=address"));
+    assert_eq!(Sanitizer::None.linker_flag(), None);
+    assert!(Sanitizer::parse("thread").is_err());
+}
+
+#[test]
+fn test_address_sanitizer_marks_generated_functions() {
+    let ir_with = |sanitize| {
+        compile_ir_with(
+            "def double(x):\n    return x * 2\n\nname = \"py\" + \"cc\"\nprint(name, double(2))\n",
+            CodegenOptions {
+                sanitize,
+                ..CodegenOptions::default()
+            },
+        )
+    };
+
+    assert!(!ir_with(Sanitizer::None).contains("sanitize_address"));
+    let ir = ir_with(Sanitizer::Address);
+    let attribute_groups = ir
+        .lines()
+        .filter(|line| line.starts_with("attributes #") && line.contains("sanitize_address"))
+        .count();
+    assert!(attribute_groups > 0, "{ir}");
+    // Library declarations are left alone
+    assert!(
+        ir.lines()
+            .filter(|line| line.starts_with("declare "))
+            .all(|line| !line.contains("sanitize_address")),
+        "{ir}"
+    );
+}
+
+#[cfg(not(feature = "python"))]
+#[test]
+fn test_interpreter_fallback_needs_the_python_feature() {
+    assert!(
+        Fallback::parse("interp")
+            .unwrap_err()
+            .contains("`python` feature")
+    );
+
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    let error = codegen
+        .compile_interpreter_fallback("print('hi')\n")
+        .unwrap_err();
+    assert!(error.message.contains("`python` feature"), "{error}");
+}
+
+#[cfg(feature = "python")]
+#[test]
+fn test_interpreter_fallback_embeds_the_source() {
+    assert_eq!(Fallback::parse("interp"), Ok(Fallback::Interp));
+    assert!(Fallback::parse("libpython").is_err());
+    assert_eq!(
+        CodegenOptions::default().python_linker_flags(),
+        Ok(Vec::new())
+    );
+
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    codegen
+        .compile_interpreter_fallback("print('hi')\n")
+        .unwrap();
+
+    let ir = codegen.module().print_to_string().to_string();
+    assert!(ir.contains("print('hi')\\0A"), "{ir}");
+    assert!(ir.contains("@PyRun_SimpleStringFlags"), "{ir}");
+    assert!(ir.contains("@Py_FinalizeEx"), "{ir}");
+}
+
+#[test]
+fn test_non_ascii_names_are_kept_in_symbols() {
+    let source = "def 面积(宽, 高):\n    return 宽 * 高\n\ncafé = 面积(3, 4)\nprint(café)\n";
+    let ir = compile_ir(source);
+    // LLVM quotes names that are not plain identifiers and escapes their UTF-8 bytes
+    assert!(ir.contains("@\"py.\\E9\\9D\\A2\\E7\\A7\\AF\"("), "{ir}");
+
+    #[cfg(feature = "python")]
+    {
+        let context = Context::create();
+        let mut codegen = CodeGenerator::new(&context, "fallback");
+        codegen
+            .compile_interpreter_fallback("café = 1\nprint(café)\n")
+            .unwrap();
+        let ir = codegen.module().print_to_string().to_string();
+        assert!(ir.contains("caf\\C3\\A9 = 1\\0A"), "{ir}");
+    }
+}
+
+#[test]
+fn test_module_dunders_are_baked_in() {
+    let source = "def where():\n    return __file__\n\nif __name__ == \"__main__\":\n    print(__name__, where())\n";
+    let program = Parser::new(Lexer::new(source)).parse_program();
+    let options = CodegenOptions {
+        file_name: Some("/srv/app/main.py".to_string()),
+        ..CodegenOptions::default()
+    };
+
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    codegen.set_options(options.clone());
+    codegen.set_source_file("main.py");
+    codegen.compile(&program).unwrap();
+    let ir = codegen.module().print_to_string().to_string();
+    assert!(ir.contains("c\"__main__\\00\""), "{ir}");
+    assert!(ir.contains("c\"/srv/app/main.py\\00\""), "{ir}");
+
+    // Without the option, `__file__` is the source file name
+    let program = Parser::new(Lexer::new("print(__file__)\n")).parse_program();
+    let mut codegen = CodeGenerator::new(&context, "default_file");
+    codegen.set_source_file("scripts/tool.py");
+    codegen.compile(&program).unwrap();
+    let ir = codegen.module().print_to_string().to_string();
+    assert!(ir.contains("c\"scripts/tool.py\\00\""), "{ir}");
+
+    #[cfg(feature = "python")]
+    {
+        let mut codegen = CodeGenerator::new(&context, "fallback");
+        codegen.set_options(options);
+        codegen
+            .compile_interpreter_fallback("print(__file__)\n")
+            .unwrap();
+        let ir = codegen.module().print_to_string().to_string();
+        assert!(ir.contains("@PyObject_SetAttrString"), "{ir}");
+        assert!(ir.contains("c\"/srv/app/main.py\\00\""), "{ir}");
+    }
+}
+
+#[test]
+fn test_invalid_ir_is_an_internal_error() {
+    let program = Parser::new(Lexer::new("print(1)\n")).parse_program();
+    let dump = tempfile::NamedTempFile::new().unwrap();
+    let dump_path = dump.path().to_string_lossy().into_owned();
+
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    codegen.set_options(CodegenOptions {
+        keep_invalid_ir: Some(dump_path.clone()),
+        ..CodegenOptions::default()
+    });
+    codegen.compile(&program).unwrap();
+    codegen.verify("after code generation").unwrap();
+
+    // A block without a terminator, as a codegen bug would leave it
+    let broken =
+        codegen
+            .module()
+            .add_function("broken", context.void_type().fn_type(&[], false), None);
+    context.append_basic_block(broken, "entry");
+    let error = codegen.verify("after code generation").unwrap_err();
+    assert!(
+        error
+            .message
+            .starts_with("internal compiler error: invalid LLVM IR after code generation"),
+        "{error}"
+    );
+    assert!(error.message.contains(&dump_path), "{error}");
+    let ir = std::fs::read_to_string(&dump_path).unwrap();
+    assert!(ir.contains("define void @broken()"), "{ir}");
+}
+
+#[test]
+fn test_python_functions_do_not_clash_with_libc() {
+    let source = "def malloc(n: int) -> int:\n    return n * 2\n\ndef greet(name: str) -> str:\n    return f\"Hello, {name}!\"\n\nprint(malloc(21), greet(\"py\"))\n";
+    let ir = compile_ir_with(
+        source,
+        CodegenOptions {
+            typed: true,
+            ..CodegenOptions::default()
+        },
+    );
+    assert!(ir.contains("define i64 @py.malloc(i64"), "{ir}");
+    assert!(ir.contains("call i64 @py.malloc(i64 21)"), "{ir}");
+    // The runtime still allocates through libc
+    assert!(ir.contains(" @malloc(i64"), "{ir}");
+}
+
+#[test]
+fn test_imports_need_python_interop() {
+    let lexer = Lexer::new("import math\nprint(math.floor(2.5))\n");
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    let error = codegen.compile(&program).unwrap_err().message;
+    assert!(error.contains("--python-interop"), "{error}");
+
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    codegen.set_options(CodegenOptions {
+        python_interop: true,
+        ..CodegenOptions::default()
+    });
+    let compiled = codegen.compile(&program);
+    #[cfg(feature = "python")]
+    compiled.unwrap();
+    #[cfg(not(feature = "python"))]
+    assert!(compiled.unwrap_err().message.contains("`python` feature"));
+}
+
+#[test]
+fn test_async_code_is_rejected_with_a_targeted_error() {
+    for (source, expected) in [
+        (
+            "async def f():\n    return 1\n",
+            "Unsupported async function 'f'",
+        ),
+        ("x = await f()\n", "Unsupported 'await'"),
+    ] {
+        let lexer = Lexer::new(source);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        let context = Context::create();
+        let mut codegen = CodeGenerator::new(&context, "test_module");
+        let error = codegen.compile(&program).unwrap_err().message;
+        assert!(error.starts_with(expected), "{error}");
+        assert!(error.contains("--fallback interp"), "{error}");
+    }
+}
+
+#[test]
+fn test_errors_point_at_the_innermost_statement() {
+    for source in [
+        "x = 1\nprint(x)\nx = []\n",
+        "def f():\n    y = 1\n    y = []\n\nf()\n",
+    ] {
+        let program = Parser::new(Lexer::new(source)).parse_program();
+
+        let context = Context::create();
+        let mut codegen = CodeGenerator::new(&context, "test_module");
+        let error = codegen.compile(&program).unwrap_err();
+        assert!(
+            error.message.starts_with("Unsupported container literal"),
+            "{source}: {error}"
+        );
+        assert_eq!(error.line, Some(3), "{source}");
+    }
+}
+
+#[test]
+fn test_container_literals_are_rejected_with_a_targeted_error() {
+    for source in ["x = (1,)\n", "x = []\n", "print({})\n", "x = {1, 2}\n"] {
+        let lexer = Lexer::new(source);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        let context = Context::create();
+        let mut codegen = CodeGenerator::new(&context, "test_module");
+        let error = codegen.compile(&program).unwrap_err().message;
+        assert!(
+            error.starts_with("Unsupported container literal"),
+            "{source}: {error}"
+        );
+    }
+}
+
+#[test]
+fn test_unsupported_assignment_targets_are_rejected() {
+    for source in ["obj.x = 1\n", "x = 1\nx[0] = 2\n"] {
+        let lexer = Lexer::new(source);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        let context = Context::create();
+        let mut codegen = CodeGenerator::new(&context, "test_module");
+        let error = codegen.compile(&program).unwrap_err().message;
+        assert!(
+            error.starts_with("Unsupported assignment target"),
+            "{source}: {error}"
+        );
+    }
+}
+
+#[test]
+fn test_statements_with_no_effect_are_warned_about() {
+    let source = "x = 1\n1 + 2\nprint(x)\n\"a comment\"\n-x\nf\"{x}\"\n";
+    let program = Parser::new(Lexer::new(source)).parse_program();
+
+    let warnings = lint::warnings(&program, &[]);
+    let lines: Vec<usize> = warnings.iter().map(|w| w.line).collect();
+    assert_eq!(lines, [2, 5]);
+    assert_eq!(warnings[0].message, "statement has no effect");
+}
+
+#[test]
+fn test_warnings_name_the_module_of_the_statement() {
+    let source = "x = 1\nx\ndef f():\n    x\n    return 1\n";
+    let program = Parser::new(Lexer::new(source)).parse_program();
+    let modules = [
+        LocalModule {
+            name: "first".to_string(),
+            statements: 2,
+        },
+        LocalModule {
+            name: "second".to_string(),
+            statements: 1,
+        },
+    ];
+
+    let warnings = lint::warnings(&program, &modules);
+    let found: Vec<(usize, Option<&str>)> = warnings
+        .iter()
+        .map(|w| (w.line, w.module.as_deref()))
+        .collect();
+    assert_eq!(found, [(2, Some("first")), (4, Some("second"))]);
+}
+
+#[test]
+fn test_discarded_expressions_release_their_stack_buffers() {
+    let ir = compile_ir("name = \"py\"\nprint(name + \"cc\")\n");
+    assert!(ir.contains("call i8* @llvm.stacksave()"), "{ir}");
+    assert!(ir.contains("call void @llvm.stackrestore("), "{ir}");
+}
+
+#[test]
+fn test_unsupported_keyword_arguments_are_rejected() {
+    for (source, expected) in [
+        (
+            "print(1, end=\"\")\n",
+            "Unsupported keyword argument: print()",
+        ),
+        ("print(1, file=out)\n", "Unsupported print file"),
+        (
+            "def f(a):\n    return a\nprint(f(a=1))\n",
+            "Unsupported keyword argument 'a' in call to f",
+        ),
+    ] {
+        let program = Parser::new(Lexer::new(source)).parse_program();
+
+        let context = Context::create();
+        let mut codegen = CodeGenerator::new(&context, "test_module");
+        let error = codegen.compile(&program).unwrap_err().message;
+        assert!(error.starts_with(expected), "{source}: {error}");
+    }
+}
+
+#[test]
+fn test_unpacked_arguments_need_displays() {
+    let source =
+        "def add(a, b):\n    return a + b\n\nprint(add(*(1, 2)), *[3], **{\"flush\": True})\n";
+    let program = Parser::new(Lexer::new(source)).parse_program();
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    codegen.compile(&program).unwrap();
+
+    for (source, expected) in [
+        (
+            "def f(a):\n    return a\nxs = 1\nprint(f(*xs))\n",
+            "Unsupported argument unpacking in call to f",
+        ),
+        (
+            "print(1, **options)\n",
+            "Unsupported argument unpacking in call to print",
+        ),
+        (
+            "print(1, flush=True, **{\"flush\": False})\n",
+            "print() got multiple values for keyword argument 'flush'",
+        ),
+        ("print(1, **{1: 2})\n", "print() keywords must be strings"),
+    ] {
+        let program = Parser::new(Lexer::new(source)).parse_program();
+        let mut codegen = CodeGenerator::new(&context, "test_module");
+        let error = codegen.compile(&program).unwrap_err().message;
+        assert!(error.starts_with(expected), "{source}: {error}");
+    }
+}
+
+#[test]
+fn test_prints_call_the_runtime_print() {
+    let ir = compile_ir("print(1)\nprint(2)\nprint(\"a\")\nprint(\"b\", flush=True)\n");
+    assert_eq!(
+        ir.matches("define private void @pycc_print(").count(),
+        1,
+        "{ir}"
+    );
+    assert_eq!(ir.matches("call void @pycc_print(").count(), 4, "{ir}");
+    assert!(ir.contains("call i32 @fflush("), "{ir}");
+}
+
+#[test]
+fn test_static_link_rejects_shared_runtimes() {
+    let interop = CodegenOptions {
+        static_link: true,
+        python_interop: true,
+        ..CodegenOptions::default()
+    };
+    let error = interop.linker_command("a.o", "a").unwrap_err();
+    assert!(error.contains("--static"), "{error}");
+
+    let sanitized = CodegenOptions {
+        static_link: true,
+        sanitize: Sanitizer::Address,
+        ..CodegenOptions::default()
+    };
+    assert!(sanitized.linker_command("a.o", "a").is_err());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_static_link_has_no_interpreter() {
+    let lexer = Lexer::new("print(\"portable\")\n");
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    codegen.set_options(CodegenOptions {
+        static_link: true,
+        ..CodegenOptions::default()
+    });
+    codegen.compile(&program).unwrap();
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let executable = temp_dir.path().join("portable");
+    codegen
+        .write_executable(executable.to_str().unwrap())
+        .unwrap();
+    let output = std::process::Command::new(&executable).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "portable\n");
+
+    // A static ELF executable has no PT_INTERP program header
+    let bytes = std::fs::read(&executable).unwrap();
+    let field = |offset: usize, size: usize| {
+        bytes[offset..offset + size]
+            .iter()
+            .rev()
+            .fold(0usize, |value, &byte| value << 8 | byte as usize)
+    };
+    let (phoff, phentsize, phnum) = (field(0x20, 8), field(0x36, 2), field(0x38, 2));
+    assert!((0..phnum).all(|i| field(phoff + i * phentsize, 4) != 3));
+}
+
+#[test]
+fn test_os_module_is_native() {
+    let ir = compile_ir("import os\nprint(os.getenv(\"HOME\", \"/\"))\n");
+    assert!(ir.contains("@getenv"), "{ir}");
+    assert!(!ir.contains("Py_Initialize"));
+}
+
+#[test]
+fn test_local_modules_are_called_directly() {
+    let lexer =
+        Lexer::new("def double(n):\n    return n * 2\nimport shapes\nprint(shapes.double(4))\n");
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+    let modules = |main: &str| {
+        [
+            LocalModule {
+                name: "shapes".to_string(),
+                statements: 1,
+            },
+            LocalModule {
+                name: main.to_string(),
+                statements: 2,
+            },
+        ]
+    };
+
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    codegen.set_local_modules(&modules("main"));
+    codegen.compile(&program).unwrap();
+    let ir = codegen.module().print_to_string().to_string();
+    assert!(ir.contains("call i64 @py.double(i64 4)"), "{ir}");
+    assert!(!ir.contains("PyImport_ImportModule"));
+
+    // A function is only an attribute of the module that defines it
+    let lexer =
+        Lexer::new("def double(n):\n    return n * 2\nimport main\nprint(main.double(4))\n");
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    codegen.set_local_modules(&modules("main"));
+    let error = codegen.compile(&program).unwrap_err();
+    assert_eq!(
+        error.message,
+        "AttributeError: module 'main' has no attribute 'double'"
+    );
+    assert_eq!(
+        (error.line, error.module.as_deref()),
+        (Some(4), Some("main"))
+    );
+}
+
+#[test]
+fn test_local_modules_cannot_bind_the_same_name() {
+    let lexer = Lexer::new("def area(r):\n    return r\ndef area(w):\n    return w\n");
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    codegen.set_local_modules(&[
+        LocalModule {
+            name: "circle".to_string(),
+            statements: 1,
+        },
+        LocalModule {
+            name: "square".to_string(),
+            statements: 1,
+        },
+    ]);
+    let error = codegen.compile(&program).unwrap_err();
+    assert!(
+        error
+            .message
+            .starts_with("'area' is defined in both module 'circle' and module 'square'"),
+        "{error}"
+    );
+    assert_eq!(error.module.as_deref(), Some("square"));
+}
+
+#[test]
+fn test_entry_function_receives_argv() {
+    let lexer = Lexer::new("def main(count):\n    return count\n");
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+    let options = |function: &str| CodegenOptions {
+        entry: Some(Entry::parse(&format!("tool:{function}")).unwrap()),
+        ..CodegenOptions::default()
+    };
+
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    codegen.set_options(options("main"));
+    codegen.compile(&program).unwrap();
+    let ir = codegen.module().print_to_string().to_string();
+    assert!(ir.contains("define i32 @main(i32 %0, "), "{ir}");
+    assert!(ir.contains("@strtoll"));
+    assert!(ir.contains("usage: %s count"), "{ir}");
+
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    codegen.set_options(options("missing"));
+    assert_eq!(
+        codegen.compile(&program).unwrap_err().message,
+        "Entry function 'missing' is not defined"
+    );
+
+    // The entry function must come from the entry module
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    codegen.set_options(options("main"));
+    codegen.set_local_modules(&[LocalModule {
+        name: "helpers".to_string(),
+        statements: 1,
+    }]);
+    assert_eq!(
+        codegen.compile(&program).unwrap_err().message,
+        "Entry function 'main' is not defined in module 'tool'"
+    );
+    assert!(Entry::parse("tool").is_err());
+}
+
+#[test]
+fn test_optimize_prunes_dead_stores_and_unused_declarations() {
+    let ir = optimized_ir(
+        "x = 3\nprint(x)\n",
+        CodegenOptions {
+            optimization: 0,
+            ..CodegenOptions::default()
+        },
+    );
+    // __doc__ is stored but never read
+    assert!(!ir.contains("__doc__"), "{ir}");
+    assert!(ir.contains("@pycc_print"), "{ir}");
+}
+
+#[test]
+fn test_small_functions_marked_for_inlining() {
+    let source = "def double(x):\n    return x * 2\n\ndef fact(n):\n    return fact(n)\n\nprint(double(fact(3)))\n";
+    let ir_at = |optimization, inline_threshold| {
+        compile_ir_with(
+            source,
+            CodegenOptions {
+                optimization,
+                inline_threshold,
+                ..CodegenOptions::default()
+            },
+        )
+    };
+
+    let ir = ir_at(0, None);
+    assert!(
+        !ir.contains("alwaysinline") && !ir.contains("inlinehint"),
+        "{ir}"
+    );
+
+    let ir = ir_at(1, Some(500));
+    assert!(ir.contains("alwaysinline"), "{ir}");
+    // Recursive functions only get a hint
+    assert!(ir.contains("inlinehint"), "{ir}");
+    assert!(ir.contains("\"function-inline-threshold\"=\"500\""), "{ir}");
+}
+
+#[test]
+fn test_sigint_handler_only_sets_a_flag() {
+    let ir = compile_ir("def f(x):\n    return x\n\nprint(f(1))\n");
+    let handler = ir
+        .split("define")
+        .find(|function| function.contains("@pycc_sigint_handler("))
+        .and_then(|function| function.split("\n}\n").next())
+        .expect("SIGINT handler is defined");
+    assert!(handler.contains("store volatile"), "{handler}");
+    assert!(!handler.contains("fflush"), "{handler}");
+    assert!(ir.contains("load volatile"), "{ir}");
+
+    let ir = compile_ir("print(1)\n");
+    assert!(!ir.contains("pycc_sigint_handler"), "{ir}");
+}
@@ -6,7 +6,8 @@
 #[path = "debug_print_suite.rs"]
 mod debug_print_suite;
 
-use debug_print_suite::run_debug_print_suite;
+use debug_print_suite::{Baseline, DebugPrintSuite};
+use std::path::Path;
 
 fn main() -> Result<(), String> {
     println!("🔧 PyCC Debug Print Test Runner");
@@ -14,25 +15,32 @@ fn main() -> Result<(), String> {
     println!("This runner will compile Python code with pycc and compare");
     println!("the output with CPython reference implementation.\n");
 
-    // Run the complete test suite
-    let summary = run_debug_print_suite()?;
+    // Results of the previous run, for reporting what changed
+    let json_path = "debug_print_test_results.json";
+    let baseline = if Path::new(json_path).exists() {
+        Some(Baseline::load(json_path)?)
+    } else {
+        None
+    };
 
-    // Export results to a file
-    let export_path = "debug_print_test_results.md";
-    let mut suite = debug_print_suite::DebugPrintSuite::new()
-        .map_err(|e| format!("Failed to create test suite for export: {}", e))?;
+    // Run the complete test suite
+    let mut suite =
+        DebugPrintSuite::new().map_err(|e| format!("Failed to create test suite: {}", e))?;
+    let summary = suite.run_all_tests()?;
 
-    // Note: We would need to run the tests again to export, but for now
-    // we'll just show the summary
+    suite.export_results_markdown("debug_print_test_results.md", baseline.as_ref())?;
+    suite.export_results_html("debug_print_test_results.html", baseline.as_ref())?;
+    suite.export_results_json(json_path)?;
 
     println!("\n📋 Test Summary:");
     println!("  Total tests: {}", summary.total_tests);
     println!("  Passed: {}", summary.passed_tests);
     println!("  Failed: {}", summary.failed_tests.len());
+    println!("  Timed out: {}", summary.timed_out_tests.len());
     println!("  Success rate: {:.1}%", summary.success_rate());
 
     // Exit with appropriate code
-    if summary.failed_tests.is_empty() {
+    if summary.failed_tests.is_empty() && summary.timed_out_tests.is_empty() {
         println!("\n🎉 All tests passed!");
         std::process::exit(0);
     } else {
@@ -9,51 +9,161 @@ mod debug_print_test_cases;
 #[path = "debug_print_tests.rs"]
 mod debug_print_tests;
 
-use debug_print_tests::{ComparisonResult, DebugPrintTester};
+use debug_print_tests::{
+    ComparisonResult, DebugPrintTester, PythonVersion, catch_panic, default_python,
+};
+use pycc::lsp::Json;
 use std::collections::HashMap;
+use std::fmt;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How the suite runs its tests
+#[derive(Debug, Clone)]
+pub struct SuiteConfig {
+    /// Number of tests run at once
+    pub jobs: usize,
+    /// Time a test may take to compile and run under both pycc and CPython
+    pub timeout: Duration,
+    /// Reference interpreter
+    pub python: PathBuf,
+    /// Oldest acceptable version of the reference interpreter
+    pub min_python_version: Option<PythonVersion>,
+}
+
+impl SuiteConfig {
+    /// The default configuration, overridden by `PYCC_SUITE_JOBS`,
+    /// `PYCC_SUITE_TIMEOUT` (in seconds), `PYCC_TEST_PYTHON` and
+    /// `PYCC_TEST_PYTHON_MIN_VERSION`
+    pub fn from_env() -> Self {
+        let mut config = SuiteConfig::default();
+        if let Some(jobs) = env_number("PYCC_SUITE_JOBS") {
+            config.jobs = jobs as usize;
+        }
+        if let Some(seconds) = env_number("PYCC_SUITE_TIMEOUT") {
+            config.timeout = Duration::from_secs(seconds);
+        }
+        if let Ok(version) = std::env::var("PYCC_TEST_PYTHON_MIN_VERSION") {
+            match PythonVersion::parse(&version) {
+                Ok(version) => config.min_python_version = Some(version),
+                Err(e) => println!("⚠️  Ignoring PYCC_TEST_PYTHON_MIN_VERSION: {}", e),
+            }
+        }
+        config
+    }
+}
+
+impl Default for SuiteConfig {
+    fn default() -> Self {
+        SuiteConfig {
+            jobs: thread::available_parallelism().map_or(1, |jobs| jobs.get()),
+            timeout: Duration::from_secs(60),
+            python: default_python(),
+            min_python_version: None,
+        }
+    }
+}
+
+fn env_number(name: &str) -> Option<u64> {
+    let value = std::env::var(name).ok()?;
+    match value.parse() {
+        Ok(number) if number > 0 => Some(number),
+        _ => {
+            println!(
+                "⚠️  Ignoring {}={}: expected a positive integer",
+                name, value
+            );
+            None
+        }
+    }
+}
 
 /// Comprehensive test suite runner for debug print tests
 pub struct DebugPrintSuite {
-    tester: DebugPrintTester,
+    tester: Arc<DebugPrintTester>,
+    config: SuiteConfig,
+    /// Version of the reference interpreter, checked at startup
+    python_version: PythonVersion,
+    pending: Vec<TestCase>,
+    next_header: Option<String>,
     results: Vec<TestSuiteResult>,
 }
 
+/// A queued test
+struct TestCase {
+    /// Category banner printed before this test's result
+    header: Option<String>,
+    name: String,
+    source: String,
+    category: String,
+    expected_failure: bool,
+}
+
+enum TestOutcome {
+    Finished(ComparisonResult),
+    TimedOut,
+    Error(String),
+}
+
 impl DebugPrintSuite {
-    /// Create a new debug print test suite
+    /// Create a new debug print test suite configured from the environment
     pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let tester = DebugPrintTester::new()?;
+        Self::with_config(SuiteConfig::from_env())
+    }
+
+    /// Create a new debug print test suite with an explicit configuration
+    ///
+    /// Fails if the reference interpreter cannot be run or is older than
+    /// `config.min_python_version`.
+    pub fn with_config(config: SuiteConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut tester = DebugPrintTester::new()?;
+        // Kill hung programs so abandoned tests do not keep running
+        tester.set_timeout(Some(config.timeout));
+        tester.set_python(&config.python);
+        let python_version = tester.check_python(config.min_python_version)?;
         Ok(DebugPrintSuite {
-            tester,
+            tester: Arc::new(tester),
+            config,
+            python_version,
+            pending: Vec::new(),
+            next_header: None,
             results: Vec::new(),
         })
     }
 
     /// Run all debug print tests and return comprehensive results
     pub fn run_all_tests(&mut self) -> Result<TestSuiteSummary, String> {
-        println!("🚀 Starting Debug Print Test Suite...\n");
+        println!("🚀 Starting Debug Print Test Suite...");
+        println!(
+            "🐍 Reference: CPython {} ({})\n",
+            self.python_version,
+            self.config.python.display()
+        );
 
         let mut summary = TestSuiteSummary::new();
 
         // Test categories
-        self.run_basic_print_tests(&mut summary)?;
-        self.run_variable_tests(&mut summary)?;
-        self.run_arithmetic_tests(&mut summary)?;
-        self.run_function_tests(&mut summary)?;
-        self.run_string_tests(&mut summary)?;
-        self.run_fstring_tests(&mut summary)?;
-        self.run_edge_case_tests(&mut summary)?;
-        self.run_existing_file_tests(&mut summary)?;
-        self.run_known_limitation_tests(&mut summary)?;
+        self.run_basic_print_tests()?;
+        self.run_variable_tests()?;
+        self.run_arithmetic_tests()?;
+        self.run_function_tests()?;
+        self.run_string_tests()?;
+        self.run_fstring_tests()?;
+        self.run_edge_case_tests()?;
+        self.run_existing_file_tests()?;
+        self.run_known_limitation_tests()?;
+        self.run_pending(&mut summary)?;
 
         self.print_summary(&summary);
         Ok(summary)
     }
 
     /// Run basic print statement tests
-    fn run_basic_print_tests(&mut self, summary: &mut TestSuiteSummary) -> Result<(), String> {
-        println!("📝 Running basic print tests...");
+    fn run_basic_print_tests(&mut self) -> Result<(), String> {
+        self.begin_category("📝 Running basic print tests...");
 
         let tests = vec![
             ("print_integer", "print(42)"),
@@ -69,15 +179,15 @@ impl DebugPrintSuite {
         ];
 
         for (name, source) in tests {
-            self.run_single_test(name, source, "Basic Print", summary)?;
+            self.queue_test(name, source, "Basic Print", false);
         }
 
         Ok(())
     }
 
     /// Run variable assignment and printing tests
-    fn run_variable_tests(&mut self, summary: &mut TestSuiteSummary) -> Result<(), String> {
-        println!("📦 Running variable tests...");
+    fn run_variable_tests(&mut self) -> Result<(), String> {
+        self.begin_category("📦 Running variable tests...");
 
         let tests = vec![
             (
@@ -124,15 +234,15 @@ print(is_false)
         ];
 
         for (name, source) in tests {
-            self.run_single_test(name, source, "Variables", summary)?;
+            self.queue_test(name, source, "Variables", false);
         }
 
         Ok(())
     }
 
     /// Run arithmetic operation tests
-    fn run_arithmetic_tests(&mut self, summary: &mut TestSuiteSummary) -> Result<(), String> {
-        println!("🔢 Running arithmetic tests...");
+    fn run_arithmetic_tests(&mut self) -> Result<(), String> {
+        self.begin_category("🔢 Running arithmetic tests...");
 
         let tests = vec![
             (
@@ -190,15 +300,15 @@ print(result)
         ];
 
         for (name, source) in tests {
-            self.run_single_test(name, source, "Arithmetic", summary)?;
+            self.queue_test(name, source, "Arithmetic", false);
         }
 
         Ok(())
     }
 
     /// Run function definition and call tests
-    fn run_function_tests(&mut self, summary: &mut TestSuiteSummary) -> Result<(), String> {
-        println!("🔧 Running function tests...");
+    fn run_function_tests(&mut self) -> Result<(), String> {
+        self.begin_category("🔧 Running function tests...");
 
         let tests = vec![
             (
@@ -238,15 +348,15 @@ print(result)
         ];
 
         for (name, source) in tests {
-            self.run_single_test(name, source, "Functions", summary)?;
+            self.queue_test(name, source, "Functions", false);
         }
 
         Ok(())
     }
 
     /// Run string operation tests
-    fn run_string_tests(&mut self, summary: &mut TestSuiteSummary) -> Result<(), String> {
-        println!("📄 Running string tests...");
+    fn run_string_tests(&mut self) -> Result<(), String> {
+        self.begin_category("📄 Running string tests...");
 
         let tests = vec![
             (
@@ -274,15 +384,15 @@ print(bool_var)
         ];
 
         for (name, source) in tests {
-            self.run_single_test(name, source, "Strings", summary)?;
+            self.queue_test(name, source, "Strings", false);
         }
 
         Ok(())
     }
 
     /// Run f-string tests
-    fn run_fstring_tests(&mut self, summary: &mut TestSuiteSummary) -> Result<(), String> {
-        println!("🎯 Running f-string tests...");
+    fn run_fstring_tests(&mut self) -> Result<(), String> {
+        self.begin_category("🎯 Running f-string tests...");
 
         let tests = vec![
             (
@@ -347,15 +457,15 @@ print(f"{a} * {b} = {a * b}")
         ];
 
         for (name, source) in tests {
-            self.run_single_test(name, source, "F-Strings", summary)?;
+            self.queue_test(name, source, "F-Strings", false);
         }
 
         Ok(())
     }
 
     /// Run edge case tests
-    fn run_edge_case_tests(&mut self, summary: &mut TestSuiteSummary) -> Result<(), String> {
-        println!("⚡ Running edge case tests...");
+    fn run_edge_case_tests(&mut self) -> Result<(), String> {
+        self.begin_category("⚡ Running edge case tests...");
 
         let tests = vec![
             (
@@ -396,15 +506,15 @@ print("Program finished.")
         ];
 
         for (name, source) in tests {
-            self.run_single_test(name, source, "Edge Cases", summary)?;
+            self.queue_test(name, source, "Edge Cases", false);
         }
 
         Ok(())
     }
 
     /// Run tests with existing Python files
-    fn run_existing_file_tests(&mut self, summary: &mut TestSuiteSummary) -> Result<(), String> {
-        println!("📁 Running existing file tests...");
+    fn run_existing_file_tests(&mut self) -> Result<(), String> {
+        self.begin_category("📁 Running existing file tests...");
 
         let files = vec![
             "tests/python_files/simple.py",
@@ -422,7 +532,7 @@ print("Program finished.")
                     Path::new(file_path).file_stem().unwrap().to_str().unwrap()
                 );
 
-                self.run_single_test(&test_name, &source, "Existing Files", summary)?;
+                self.queue_test(&test_name, &source, "Existing Files", false);
             } else {
                 println!("⚠️  File not found: {}", file_path);
             }
@@ -432,8 +542,8 @@ print("Program finished.")
     }
 
     /// Run tests that are known to fail due to current limitations
-    fn run_known_limitation_tests(&mut self, summary: &mut TestSuiteSummary) -> Result<(), String> {
-        println!("🔍 Running known limitation tests...");
+    fn run_known_limitation_tests(&mut self) -> Result<(), String> {
+        self.begin_category("🔍 Running known limitation tests...");
 
         let tests = vec![
             (
@@ -455,73 +565,153 @@ print(f"{name} says: f'I am {age} years old'")
         ];
 
         for (name, source) in tests {
-            self.run_single_test_expected_failure(name, source, "Known Limitations", summary)?;
+            self.queue_test(name, source, "Known Limitations", true);
         }
 
         Ok(())
     }
 
-    /// Run a single test and record the result
-    fn run_single_test(
-        &mut self,
-        name: &str,
-        source: &str,
-        category: &str,
-        summary: &mut TestSuiteSummary,
-    ) -> Result<(), String> {
-        let result = self.tester.compare_outputs(source, name)?;
-        let passed = result.outputs_match();
+    /// Print `header` before the results of the tests queued after it
+    fn begin_category(&mut self, header: &str) {
+        self.next_header = Some(header.to_string());
+    }
 
-        let test_result = TestSuiteResult {
+    /// Queue a test to run with the rest of the suite
+    fn queue_test(&mut self, name: &str, source: &str, category: &str, expected_failure: bool) {
+        self.pending.push(TestCase {
+            header: self.next_header.take(),
             name: name.to_string(),
+            source: source.to_string(),
             category: category.to_string(),
-            passed,
-            result: Some(result),
-            expected_failure: false,
-        };
+            expected_failure,
+        });
+    }
 
-        self.results.push(test_result.clone());
-        summary.add_test(test_result.clone());
+    /// Run the queued tests on `config.jobs` threads and record them in order
+    ///
+    /// A test that outlives `config.timeout` is recorded as timed out. Its
+    /// thread cannot be stopped and is abandoned, so a compiler that loops
+    /// forever only costs one worker.
+    fn run_pending(&mut self, summary: &mut TestSuiteSummary) -> Result<(), String> {
+        let cases = std::mem::take(&mut self.pending);
+        let mut outcomes: Vec<Option<TestOutcome>> = cases.iter().map(|_| None).collect();
+        let mut running: HashMap<usize, Instant> = HashMap::new();
+        let (sender, receiver) = mpsc::channel();
+        let mut next_to_start = 0;
+        let mut next_to_record = 0;
+
+        while next_to_record < cases.len() {
+            while running.len() < self.config.jobs.max(1) && next_to_start < cases.len() {
+                let case = &cases[next_to_start];
+                let tester = Arc::clone(&self.tester);
+                let sender = sender.clone();
+                let (index, name, source) = (next_to_start, case.name.clone(), case.source.clone());
+                thread::spawn(move || {
+                    // The receiver is gone once the suite has given up on this test
+                    let result = catch_panic(|| tester.compare_outputs(&source, &name))
+                        .unwrap_or_else(|report| Err(format!("The test panicked: {}", report)));
+                    let _ = sender.send((index, result));
+                });
+                running.insert(next_to_start, Instant::now());
+                next_to_start += 1;
+            }
 
-        if passed {
-            println!("  ✅ {}", name);
-        } else {
-            println!("  ❌ {}", name);
-            if let Some(comp_result) = &test_result.result {
-                println!("     PyCC: {}", comp_result.pycc_output.trim());
-                println!("     CPython: {}", comp_result.cpython_output.trim());
+            let wait = running
+                .values()
+                .map(|started| self.config.timeout.saturating_sub(started.elapsed()))
+                .min()
+                .unwrap_or_default();
+            match receiver.recv_timeout(wait) {
+                Ok((index, result)) => {
+                    // Results of tests already recorded as timed out are dropped
+                    if running.remove(&index).is_some() {
+                        outcomes[index] = Some(match result {
+                            Ok(result) if result.timed_out => TestOutcome::TimedOut,
+                            Ok(result) => TestOutcome::Finished(result),
+                            Err(e) => TestOutcome::Error(e),
+                        });
+                    }
+                }
+                Err(_) => {
+                    let timeout = self.config.timeout;
+                    running.retain(|&index, started| {
+                        let expired = started.elapsed() >= timeout;
+                        if expired {
+                            outcomes[index] = Some(TestOutcome::TimedOut);
+                        }
+                        !expired
+                    });
+                }
+            }
+
+            while let Some(Some(_)) = outcomes.get(next_to_record) {
+                let outcome = outcomes[next_to_record].take().unwrap();
+                self.record(&cases[next_to_record], outcome, summary)?;
+                next_to_record += 1;
             }
         }
 
         Ok(())
     }
 
-    /// Run a single test that is expected to fail
-    fn run_single_test_expected_failure(
+    /// Record and print the outcome of one test
+    fn record(
         &mut self,
-        name: &str,
-        source: &str,
-        category: &str,
+        case: &TestCase,
+        outcome: TestOutcome,
         summary: &mut TestSuiteSummary,
     ) -> Result<(), String> {
-        let result = self.tester.compare_outputs(source, name)?;
-        let passed = !result.outputs_match(); // Expected to fail, so pass when outputs don't match
+        if let Some(header) = &case.header {
+            println!("{}", header);
+        }
+
+        let (result, timed_out, error) = match outcome {
+            TestOutcome::Finished(result) => (Some(result), false, None),
+            TestOutcome::TimedOut => (None, true, None),
+            TestOutcome::Error(e) => (None, false, Some(e)),
+        };
+        // An expected failure passes when the outputs differ, but never by
+        // hanging or failing to run
+        let passed = result
+            .as_ref()
+            .is_some_and(|result| result.outputs_match() != case.expected_failure);
 
         let test_result = TestSuiteResult {
-            name: name.to_string(),
-            category: category.to_string(),
+            name: case.name.clone(),
+            category: case.category.clone(),
             passed,
-            result: Some(result),
-            expected_failure: true,
+            result,
+            expected_failure: case.expected_failure,
+            timed_out,
+            error,
         };
 
         self.results.push(test_result.clone());
         summary.add_test(test_result.clone());
 
-        if passed {
-            println!("  ✅ {} (expected failure)", name);
+        if timed_out {
+            println!(
+                "  ⏱️  {} (timed out after {}s)",
+                case.name,
+                self.config.timeout.as_secs_f64()
+            );
+        } else if let Some(error) = &test_result.error {
+            println!("  💥 {}", case.name);
+            println!("     {}", error.lines().next().unwrap_or_default());
+        } else if case.expected_failure {
+            if passed {
+                println!("  ✅ {} (expected failure)", case.name);
+            } else {
+                println!("  ❌ {} (unexpectedly passed)", case.name);
+            }
+        } else if passed {
+            println!("  ✅ {}", case.name);
         } else {
-            println!("  ❌ {} (unexpectedly passed)", name);
+            println!("  ❌ {}", case.name);
+            if let Some(comp_result) = &test_result.result {
+                println!("     PyCC: {}", comp_result.pycc_output.trim());
+                println!("     CPython: {}", comp_result.cpython_output.trim());
+            }
         }
 
         Ok(())
@@ -534,7 +724,9 @@ print(f"{name} says: f'I am {age} years old'")
         println!("Total tests: {}", summary.total_tests);
         println!("Passed: {}", summary.passed_tests);
         println!("Failed: {}", summary.failed_tests.len());
+        println!("Timed out: {}", summary.timed_out_tests.len());
         println!("Success rate: {:.1}%", summary.success_rate());
+        println!("Reused builds: {}", self.tester.cache_hits());
 
         if !summary.failed_tests.is_empty() {
             println!("\n❌ Failed Tests:");
@@ -543,6 +735,13 @@ print(f"{name} says: f'I am {age} years old'")
             }
         }
 
+        if !summary.timed_out_tests.is_empty() {
+            println!("\n⏱️  Timed Out Tests:");
+            for test in &summary.timed_out_tests {
+                println!("  - {} ({})", test.name, test.category);
+            }
+        }
+
         if !summary.category_results.is_empty() {
             println!("\n📈 Results by Category:");
             for (category, result) in &summary.category_results {
@@ -574,6 +773,10 @@ print(f"{name} says: f'I am {age} years old'")
             content.push_str(&format!("Category: {}\n", result.category));
             content.push_str(&format!("Passed: {}\n", result.passed));
             content.push_str(&format!("Expected Failure: {}\n", result.expected_failure));
+            content.push_str(&format!("Timed Out: {}\n", result.timed_out));
+            if let Some(error) = &result.error {
+                content.push_str(&format!("Error: {}\n", error));
+            }
 
             if let Some(ref comp_result) = result.result {
                 content.push_str(&format!(
@@ -596,6 +799,278 @@ print(f"{name} says: f'I am {age} years old'")
         println!("📄 Results exported to {}", file_path);
         Ok(())
     }
+
+    /// Results as JSON, the format `Baseline::load` reads back
+    pub fn results_json(&self) -> Json {
+        Json::object([
+            ("python_version", self.python_version.to_string().into()),
+            (
+                "tests",
+                Json::Array(
+                    self.results
+                        .iter()
+                        .map(|result| {
+                            Json::object([
+                                ("name", result.name.as_str().into()),
+                                ("category", result.category.as_str().into()),
+                                ("status", result.status().as_str().into()),
+                                ("expected_failure", result.expected_failure.into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+
+    /// Export results as JSON, for comparing a later run against this one
+    pub fn export_results_json(&self, file_path: &str) -> Result<(), String> {
+        fs::write(file_path, format!("{}\n", self.results_json()))
+            .map_err(|e| format!("Failed to write results to {}: {}", file_path, e))?;
+
+        println!("📄 Results exported to {}", file_path);
+        Ok(())
+    }
+
+    /// Export a Markdown report, with changes since `baseline` if given
+    pub fn export_results_markdown(
+        &self,
+        file_path: &str,
+        baseline: Option<&Baseline>,
+    ) -> Result<(), String> {
+        fs::write(file_path, self.render_markdown(baseline))
+            .map_err(|e| format!("Failed to write results to {}: {}", file_path, e))?;
+
+        println!("📄 Results exported to {}", file_path);
+        Ok(())
+    }
+
+    /// Export an HTML report, with changes since `baseline` if given
+    pub fn export_results_html(
+        &self,
+        file_path: &str,
+        baseline: Option<&Baseline>,
+    ) -> Result<(), String> {
+        fs::write(file_path, self.render_html(baseline))
+            .map_err(|e| format!("Failed to write results to {}: {}", file_path, e))?;
+
+        println!("📄 Results exported to {}", file_path);
+        Ok(())
+    }
+
+    /// Markdown report: totals, per-category table, changes since `baseline`
+    /// and a diff for every failure
+    pub fn render_markdown(&self, baseline: Option<&Baseline>) -> String {
+        let mut out = String::from("# Debug Print Test Report\n\n");
+        out.push_str(&format!(
+            "Reference interpreter: CPython {} (`{}`)\n\n",
+            self.python_version,
+            self.config.python.display()
+        ));
+        let trend = baseline.map(|baseline| self.trend(baseline));
+        let totals = StatusCounts::of(self.results.iter());
+
+        out.push_str("| Total | Passed | Failed | Timed out | Success rate |\n");
+        out.push_str("|------:|-------:|-------:|----------:|-------------:|\n");
+        out.push_str(&format!(
+            "| {} | {}{} | {} | {} | {:.1}% |\n\n",
+            totals.total,
+            totals.passed,
+            trend
+                .as_ref()
+                .map(|trend| format!(" ({})", signed(totals.passed, trend.previous_passed)))
+                .unwrap_or_default(),
+            totals.failed,
+            totals.timed_out,
+            totals.success_rate()
+        ));
+
+        out.push_str("## Categories\n\n");
+        out.push_str("| Category | Passed | Failed | Timed out | Success rate |\n");
+        out.push_str("|----------|-------:|-------:|----------:|-------------:|\n");
+        for (category, counts) in self.category_counts() {
+            out.push_str(&format!(
+                "| {} | {}/{} | {} | {} | {:.1}% |\n",
+                category,
+                counts.passed,
+                counts.total,
+                counts.failed,
+                counts.timed_out,
+                counts.success_rate()
+            ));
+        }
+
+        if let Some(trend) = &trend {
+            out.push_str("\n## Changes Since Previous Run\n\n");
+            if trend.is_empty() {
+                out.push_str("No changes.\n");
+            }
+            if let Some(note) = trend.python_note() {
+                out.push_str(&format!("{}\n\n", note));
+            }
+            for (title, names) in trend.sections() {
+                out.push_str(&format!("**{}**\n\n", title));
+                for name in names {
+                    out.push_str(&format!("- `{}`\n", name));
+                }
+                out.push('\n');
+            }
+        }
+
+        let failures: Vec<&TestSuiteResult> = self.results.iter().filter(|r| !r.passed).collect();
+        if !failures.is_empty() {
+            out.push_str("\n## Failures\n");
+        }
+        for failure in failures {
+            out.push_str(&format!(
+                "\n### `{}` ({})\n\n{}\n",
+                failure.name,
+                failure.category,
+                failure.description()
+            ));
+            if let Some(error) = &failure.error {
+                out.push_str(&format!("\n```\n{}\n```\n", error.trim_end()));
+            }
+            if let Some(result) = failure.mismatched_result() {
+                out.push_str("\n```diff\n");
+                for line in line_diff(&result.cpython_output, &result.pycc_output) {
+                    out.push_str(&format!("{}\n", line));
+                }
+                out.push_str("```\n");
+            }
+        }
+
+        out
+    }
+
+    /// Self-contained HTML version of `render_markdown`
+    pub fn render_html(&self, baseline: Option<&Baseline>) -> String {
+        let mut out = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Debug Print Test Report</title>\n<style>\n\
+             body { font-family: sans-serif; margin: 2em; }\n\
+             table { border-collapse: collapse; }\n\
+             th, td { border: 1px solid #ccc; padding: 0.3em 0.8em; text-align: right; }\n\
+             th:first-child, td:first-child { text-align: left; }\n\
+             pre { background: #f6f8fa; padding: 0.5em; }\n\
+             .added { color: #1a7f37; } .removed { color: #cf222e; }\n\
+             </style>\n</head>\n<body>\n<h1>Debug Print Test Report</h1>\n",
+        );
+        out.push_str(&format!(
+            "<p>Reference interpreter: CPython {} (<code>{}</code>)</p>\n",
+            self.python_version,
+            html_escape(&self.config.python.display().to_string())
+        ));
+        let trend = baseline.map(|baseline| self.trend(baseline));
+        let totals = StatusCounts::of(self.results.iter());
+
+        out.push_str(
+            "<table>\n<tr><th>Total</th><th>Passed</th><th>Failed</th>\
+             <th>Timed out</th><th>Success rate</th></tr>\n",
+        );
+        out.push_str(&format!(
+            "<tr><td>{}</td><td>{}{}</td><td>{}</td><td>{}</td><td>{:.1}%</td></tr>\n</table>\n",
+            totals.total,
+            totals.passed,
+            trend
+                .as_ref()
+                .map(|trend| format!(" ({})", signed(totals.passed, trend.previous_passed)))
+                .unwrap_or_default(),
+            totals.failed,
+            totals.timed_out,
+            totals.success_rate()
+        ));
+
+        out.push_str(
+            "<h2>Categories</h2>\n<table>\n<tr><th>Category</th><th>Passed</th>\
+             <th>Failed</th><th>Timed out</th><th>Success rate</th></tr>\n",
+        );
+        for (category, counts) in self.category_counts() {
+            out.push_str(&format!(
+                "<tr><td>{}</td><td>{}/{}</td><td>{}</td><td>{}</td><td>{:.1}%</td></tr>\n",
+                html_escape(category),
+                counts.passed,
+                counts.total,
+                counts.failed,
+                counts.timed_out,
+                counts.success_rate()
+            ));
+        }
+        out.push_str("</table>\n");
+
+        if let Some(trend) = &trend {
+            out.push_str("<h2>Changes Since Previous Run</h2>\n");
+            if trend.is_empty() {
+                out.push_str("<p>No changes.</p>\n");
+            }
+            if let Some(note) = trend.python_note() {
+                out.push_str(&format!("<p>{}</p>\n", note));
+            }
+            for (title, names) in trend.sections() {
+                out.push_str(&format!("<h3>{}</h3>\n<ul>\n", title));
+                for name in names {
+                    out.push_str(&format!("<li><code>{}</code></li>\n", html_escape(name)));
+                }
+                out.push_str("</ul>\n");
+            }
+        }
+
+        let failures: Vec<&TestSuiteResult> = self.results.iter().filter(|r| !r.passed).collect();
+        if !failures.is_empty() {
+            out.push_str("<h2>Failures</h2>\n");
+        }
+        for failure in failures {
+            out.push_str(&format!(
+                "<h3><code>{}</code> ({})</h3>\n<p>{}</p>\n",
+                html_escape(&failure.name),
+                html_escape(&failure.category),
+                html_escape(&failure.description())
+            ));
+            if let Some(error) = &failure.error {
+                out.push_str(&format!("<pre>{}</pre>\n", html_escape(error.trim_end())));
+            }
+            if let Some(result) = failure.mismatched_result() {
+                out.push_str("<pre>");
+                for line in line_diff(&result.cpython_output, &result.pycc_output) {
+                    let class = match line {
+                        DiffLine::Same(_) => "same",
+                        DiffLine::Removed(_) => "removed",
+                        DiffLine::Added(_) => "added",
+                    };
+                    out.push_str(&format!(
+                        "<span class=\"{}\">{}</span>\n",
+                        class,
+                        html_escape(&line.to_string())
+                    ));
+                }
+                out.push_str("</pre>\n");
+            }
+        }
+
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+
+    fn trend(&self, baseline: &Baseline) -> Trend {
+        Trend::between(baseline, &self.results, self.python_version)
+    }
+
+    /// Status counts per category, in the order categories first appear
+    fn category_counts(&self) -> Vec<(&str, StatusCounts)> {
+        let mut categories: Vec<&str> = Vec::new();
+        for result in &self.results {
+            if !categories.contains(&result.category.as_str()) {
+                categories.push(&result.category);
+            }
+        }
+        categories
+            .into_iter()
+            .map(|category| {
+                let results = self.results.iter().filter(|r| r.category == category);
+                (category, StatusCounts::of(results))
+            })
+            .collect()
+    }
 }
 
 /// Summary of test suite results
@@ -604,6 +1079,7 @@ pub struct TestSuiteSummary {
     pub total_tests: usize,
     pub passed_tests: usize,
     pub failed_tests: Vec<TestSuiteResult>,
+    pub timed_out_tests: Vec<TestSuiteResult>,
     pub category_results: HashMap<String, CategoryResult>,
 }
 
@@ -613,6 +1089,7 @@ impl TestSuiteSummary {
             total_tests: 0,
             passed_tests: 0,
             failed_tests: Vec::new(),
+            timed_out_tests: Vec::new(),
             category_results: HashMap::new(),
         }
     }
@@ -622,6 +1099,8 @@ impl TestSuiteSummary {
 
         if test.passed {
             self.passed_tests += 1;
+        } else if test.timed_out {
+            self.timed_out_tests.push(test.clone());
         } else {
             self.failed_tests.push(test.clone());
         }
@@ -685,6 +1164,324 @@ pub struct TestSuiteResult {
     pub passed: bool,
     pub result: Option<ComparisonResult>,
     pub expected_failure: bool,
+    pub timed_out: bool,
+    /// Why the test could not be run, such as a compile error or a panic with its backtrace
+    pub error: Option<String>,
+}
+
+impl TestSuiteResult {
+    pub fn status(&self) -> TestStatus {
+        if self.timed_out {
+            TestStatus::TimedOut
+        } else if self.passed {
+            TestStatus::Passed
+        } else {
+            TestStatus::Failed
+        }
+    }
+
+    /// Why the test did not pass, in one sentence
+    fn description(&self) -> String {
+        let result = match (&self.result, self.timed_out) {
+            (_, true) => return "Timed out.".to_string(),
+            (None, false) if self.error.is_some() => return "Could not be run:".to_string(),
+            (None, false) => return "No result.".to_string(),
+            (Some(result), false) => result,
+        };
+        if self.expected_failure && result.outputs_match() {
+            "Expected failure unexpectedly passed; the test can be promoted.".to_string()
+        } else if result.pycc_exit_code != result.cpython_exit_code {
+            format!(
+                "Exit code {:?} from pycc, {:?} from CPython. Output diff (`-` CPython, `+` pycc):",
+                result.pycc_exit_code, result.cpython_exit_code
+            )
+        } else {
+            "Output diff (`-` CPython, `+` pycc):".to_string()
+        }
+    }
+
+    /// The comparison to show as a diff, if the outputs differ
+    fn mismatched_result(&self) -> Option<&ComparisonResult> {
+        self.result
+            .as_ref()
+            .filter(|result| !self.expected_failure && !result.outputs_match())
+    }
+}
+
+/// Outcome of one test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    TimedOut,
+}
+
+impl TestStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TestStatus::Passed => "passed",
+            TestStatus::Failed => "failed",
+            TestStatus::TimedOut => "timed_out",
+        }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "passed" => Some(TestStatus::Passed),
+            "failed" => Some(TestStatus::Failed),
+            "timed_out" => Some(TestStatus::TimedOut),
+            _ => None,
+        }
+    }
+}
+
+/// Test statuses of a previous run, read from its JSON export
+#[derive(Debug, Clone, Default)]
+pub struct Baseline {
+    /// Test names and statuses in run order
+    tests: Vec<(String, TestStatus)>,
+    /// Reference interpreter of the run, if it was recorded
+    python_version: Option<PythonVersion>,
+}
+
+impl Baseline {
+    /// Read a file written by `export_results_json`
+    pub fn load(file_path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(file_path)
+            .map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+        Self::parse(&text).map_err(|e| format!("Invalid results in {}: {}", file_path, e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let json = Json::parse(text)?;
+        let tests = json
+            .get("tests")
+            .and_then(Json::as_array)
+            .ok_or("missing \"tests\" array")?;
+        let tests = tests
+            .iter()
+            .map(|test| {
+                let name = test.get("name").and_then(Json::as_str);
+                let status = test
+                    .get("status")
+                    .and_then(Json::as_str)
+                    .and_then(TestStatus::parse);
+                match (name, status) {
+                    (Some(name), Some(status)) => Ok((name.to_string(), status)),
+                    _ => Err(format!("malformed test entry {}", test)),
+                }
+            })
+            .collect::<Result<_, String>>()?;
+        let python_version = match json.get("python_version").and_then(Json::as_str) {
+            Some(version) => Some(PythonVersion::parse(version)?),
+            None => None,
+        };
+        Ok(Baseline {
+            tests,
+            python_version,
+        })
+    }
+
+    fn status(&self, name: &str) -> Option<TestStatus> {
+        self.tests
+            .iter()
+            .find(|(test, _)| test == name)
+            .map(|(_, status)| *status)
+    }
+}
+
+/// How a run differs from a baseline
+#[derive(Debug, Default)]
+pub struct Trend {
+    pub previous_passed: usize,
+    /// Reference interpreter versions before and now, if they differ
+    pub python_change: Option<(PythonVersion, PythonVersion)>,
+    /// Tests that passed before and no longer do
+    pub regressions: Vec<String>,
+    /// Tests that pass now and did not before
+    pub fixes: Vec<String>,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl Trend {
+    pub fn between(
+        baseline: &Baseline,
+        results: &[TestSuiteResult],
+        python_version: PythonVersion,
+    ) -> Self {
+        let mut trend = Trend {
+            previous_passed: baseline
+                .tests
+                .iter()
+                .filter(|(_, status)| *status == TestStatus::Passed)
+                .count(),
+            python_change: baseline
+                .python_version
+                .filter(|before| *before != python_version)
+                .map(|before| (before, python_version)),
+            ..Trend::default()
+        };
+        for result in results {
+            let now = result.status() == TestStatus::Passed;
+            match baseline.status(&result.name) {
+                None => trend.added.push(result.name.clone()),
+                Some(before) if before == TestStatus::Passed && !now => {
+                    trend.regressions.push(result.name.clone())
+                }
+                Some(before) if before != TestStatus::Passed && now => {
+                    trend.fixes.push(result.name.clone())
+                }
+                Some(_) => {}
+            }
+        }
+        for (name, _) in &baseline.tests {
+            if !results.iter().any(|result| &result.name == name) {
+                trend.removed.push(name.clone());
+            }
+        }
+        trend
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.python_change.is_none() && self.sections().is_empty()
+    }
+
+    fn python_note(&self) -> Option<String> {
+        self.python_change.map(|(before, now)| {
+            format!(
+                "The reference interpreter changed from CPython {} to {}, \
+                 which may explain some output differences.",
+                before, now
+            )
+        })
+    }
+
+    /// Non-empty lists of changed tests with their headings
+    fn sections(&self) -> Vec<(&'static str, &[String])> {
+        [
+            ("Regressions", self.regressions.as_slice()),
+            ("Fixed", self.fixes.as_slice()),
+            ("New tests", self.added.as_slice()),
+            ("Removed tests", self.removed.as_slice()),
+        ]
+        .into_iter()
+        .filter(|(_, names)| !names.is_empty())
+        .collect()
+    }
+}
+
+/// Number of tests with each status
+struct StatusCounts {
+    total: usize,
+    passed: usize,
+    failed: usize,
+    timed_out: usize,
+}
+
+impl StatusCounts {
+    fn of<'a>(results: impl Iterator<Item = &'a TestSuiteResult>) -> Self {
+        let mut counts = StatusCounts {
+            total: 0,
+            passed: 0,
+            failed: 0,
+            timed_out: 0,
+        };
+        for result in results {
+            counts.total += 1;
+            match result.status() {
+                TestStatus::Passed => counts.passed += 1,
+                TestStatus::Failed => counts.failed += 1,
+                TestStatus::TimedOut => counts.timed_out += 1,
+            }
+        }
+        counts
+    }
+
+    fn success_rate(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            (self.passed as f64 / self.total as f64) * 100.0
+        }
+    }
+}
+
+/// `now` relative to `before`, like `+2` or `-1`
+fn signed(now: usize, before: usize) -> String {
+    if now >= before {
+        format!("+{}", now - before)
+    } else {
+        format!("-{}", before - now)
+    }
+}
+
+/// One line of a diff from expected to actual output
+#[derive(Debug, Clone, PartialEq)]
+enum DiffLine {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+impl fmt::Display for DiffLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffLine::Same(line) => write!(f, " {}", line),
+            DiffLine::Removed(line) => write!(f, "-{}", line),
+            DiffLine::Added(line) => write!(f, "+{}", line),
+        }
+    }
+}
+
+/// Line diff from `expected` to `actual`, via their longest common subsequence
+fn line_diff(expected: &str, actual: &str) -> Vec<DiffLine> {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+
+    // common[i][j]: length of the longest common subsequence of expected[i..] and actual[j..]
+    let mut common = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            diff.push(DiffLine::Same(expected[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if i < expected.len() && (j == actual.len() || common[i + 1][j] >= common[i][j + 1])
+        {
+            diff.push(DiffLine::Removed(expected[i].to_string()));
+            i += 1;
+        } else {
+            diff.push(DiffLine::Added(actual[j].to_string()));
+            j += 1;
+        }
+    }
+    diff
+}
+
+fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
 }
 
 /// Run the complete debug print test suite
@@ -714,4 +1511,224 @@ mod tests {
         let summary = result.unwrap();
         assert!(summary.total_tests > 0);
     }
+
+    #[test]
+    fn test_hung_tests_time_out_without_stopping_the_suite() {
+        let config = SuiteConfig {
+            jobs: 2,
+            timeout: Duration::from_secs(2),
+            ..SuiteConfig::default()
+        };
+        let mut suite = DebugPrintSuite::with_config(config).expect("Failed to create suite");
+        suite.begin_category("⏱️  Running timeout tests...");
+        let hangs = "import os\nos.system(\"sleep 5 > /dev/null 2>&1\")\n";
+        suite.queue_test("hangs", hangs, "Timeouts", false);
+        suite.queue_test("finishes", "print(1)", "Timeouts", false);
+
+        let mut summary = TestSuiteSummary::new();
+        suite.run_pending(&mut summary).expect("Suite failed");
+
+        assert_eq!(summary.total_tests, 2);
+        assert_eq!(summary.passed_tests, 1);
+        assert!(summary.failed_tests.is_empty());
+        assert_eq!(summary.timed_out_tests.len(), 1);
+        assert_eq!(summary.timed_out_tests[0].name, "hangs");
+        let names: Vec<&str> = suite.results.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["hangs", "finishes"]);
+    }
+
+    #[test]
+    fn test_panicking_tests_fail_without_stopping_the_suite() {
+        let report = catch_panic(|| -> u8 { panic!("codegen exploded") }).unwrap_err();
+        assert!(report.contains("codegen exploded"), "{report}");
+        assert!(report.contains("Backtrace:"), "{report}");
+        assert_eq!(catch_panic(|| 42), Ok(42));
+
+        let mut suite = DebugPrintSuite::with_config(SuiteConfig::default()).unwrap();
+        let case = TestCase {
+            header: None,
+            name: "crashes".to_string(),
+            source: String::new(),
+            category: "Crashes".to_string(),
+            expected_failure: false,
+        };
+        let mut summary = TestSuiteSummary::new();
+        let error = format!("The test panicked: {}", report);
+        suite
+            .record(&case, TestOutcome::Error(error), &mut summary)
+            .expect("Suite failed");
+
+        assert_eq!(summary.failed_tests.len(), 1);
+        let report = suite.render_markdown(None);
+        assert!(
+            report.contains("Could not be run:\n\n```\nThe test panicked: "),
+            "{report}"
+        );
+        assert!(report.contains("codegen exploded"), "{report}");
+    }
+
+    fn finished(name: &str, category: &str, pycc: &str, cpython: &str) -> TestSuiteResult {
+        TestSuiteResult {
+            name: name.to_string(),
+            category: category.to_string(),
+            passed: pycc == cpython,
+            result: Some(ComparisonResult {
+                test_name: name.to_string(),
+                pycc_output: pycc.to_string(),
+                cpython_output: cpython.to_string(),
+                pycc_stderr: String::new(),
+                cpython_stderr: String::new(),
+                pycc_exit_code: Some(0),
+                cpython_exit_code: Some(0),
+                timed_out: false,
+                source: String::new(),
+            }),
+            expected_failure: false,
+            timed_out: false,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_line_diff() {
+        let diff: Vec<String> = line_diff("a\nb\nc\n", "a\nx\nc\nd\n")
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(diff, [" a", "-b", "+x", " c", "+d"]);
+    }
+
+    #[test]
+    fn test_markdown_report_with_trend() {
+        let mut suite = DebugPrintSuite::with_config(SuiteConfig::default()).unwrap();
+        suite.results = vec![
+            finished("adds", "Arithmetic", "2\n", "2\n"),
+            finished("divides", "Arithmetic", "2\n", "2\n"),
+            finished("greets", "Strings", "hi\n", "hi\n"),
+        ];
+        let baseline = Baseline::parse(&suite.results_json().to_string()).unwrap();
+
+        suite.results = vec![
+            finished("adds", "Arithmetic", "2\n", "2\n"),
+            finished("divides", "Arithmetic", "2.0\n", "2.5\n"),
+            finished("concatenates", "Strings", "ab\n", "ab\n"),
+        ];
+        let report = suite.render_markdown(Some(&baseline));
+
+        assert!(
+            report.contains("| 3 | 2 (-1) | 1 | 0 | 66.7% |"),
+            "{report}"
+        );
+        assert!(
+            report.contains("| Arithmetic | 1/2 | 1 | 0 | 50.0% |"),
+            "{report}"
+        );
+        assert!(
+            report.contains("| Strings | 1/1 | 0 | 0 | 100.0% |"),
+            "{report}"
+        );
+        assert!(
+            report.contains("**Regressions**\n\n- `divides`"),
+            "{report}"
+        );
+        assert!(
+            report.contains("**New tests**\n\n- `concatenates`"),
+            "{report}"
+        );
+        assert!(
+            report.contains("**Removed tests**\n\n- `greets`"),
+            "{report}"
+        );
+        assert!(report.contains("```diff\n-2.5\n+2.0\n```"), "{report}");
+    }
+
+    #[test]
+    fn test_html_report_escapes_output() {
+        let mut suite = DebugPrintSuite::with_config(SuiteConfig::default()).unwrap();
+        suite.results = vec![finished("compares", "Edge <Cases>", "a<b\n", "a&b\n")];
+        let report = suite.render_html(None);
+
+        assert!(report.contains("<td>Edge &lt;Cases&gt;</td>"), "{report}");
+        assert!(
+            report.contains("<span class=\"removed\">-a&amp;b</span>"),
+            "{report}"
+        );
+        assert!(
+            report.contains("<span class=\"added\">+a&lt;b</span>"),
+            "{report}"
+        );
+        assert!(!report.contains("Changes Since Previous Run"));
+    }
+
+    #[test]
+    fn test_python_version_parsing() {
+        let version = PythonVersion::parse("3.11.7").unwrap();
+        assert_eq!(version.to_string(), "3.11.7");
+        assert_eq!(PythonVersion::parse("3.8").unwrap().to_string(), "3.8.0");
+        assert!(PythonVersion::parse("3.10").unwrap() > PythonVersion::parse("3.9.18").unwrap());
+        assert!(PythonVersion::parse("three").is_err());
+        assert!(PythonVersion::parse("3.11.7.1").is_err());
+    }
+
+    #[test]
+    fn test_suite_checks_the_reference_interpreter() {
+        let too_new = SuiteConfig {
+            min_python_version: Some(PythonVersion::parse("99.0").unwrap()),
+            ..SuiteConfig::default()
+        };
+        let error = DebugPrintSuite::with_config(too_new).err().unwrap();
+        assert!(
+            error
+                .to_string()
+                .contains("but at least 99.0.0 is required"),
+            "{error}"
+        );
+
+        let missing = SuiteConfig {
+            python: PathBuf::from("/nonexistent/python3"),
+            ..SuiteConfig::default()
+        };
+        let error = DebugPrintSuite::with_config(missing).err().unwrap();
+        assert!(
+            error
+                .to_string()
+                .contains("Failed to run /nonexistent/python3"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_reports_record_the_reference_interpreter() {
+        let mut suite = DebugPrintSuite::with_config(SuiteConfig::default()).unwrap();
+        suite.results = vec![finished("adds", "Arithmetic", "2\n", "2\n")];
+        let version = suite.python_version.to_string();
+
+        let json = suite.results_json();
+        assert_eq!(
+            json.get("python_version").and_then(Json::as_str),
+            Some(version.as_str())
+        );
+        let report = suite.render_markdown(None);
+        assert!(
+            report.contains(&format!("Reference interpreter: CPython {}", version)),
+            "{report}"
+        );
+
+        let baseline = Baseline::parse(
+            r#"{"python_version":"2.7.18","tests":[{"name":"adds","status":"passed"}]}"#,
+        )
+        .unwrap();
+        let report = suite.render_markdown(Some(&baseline));
+        assert!(
+            report.contains(&format!("changed from CPython 2.7.18 to {}", version)),
+            "{report}"
+        );
+        assert!(!report.contains("No changes."));
+    }
+
+    #[test]
+    fn test_baseline_rejects_malformed_results() {
+        assert!(Baseline::parse("{}").is_err());
+        assert!(Baseline::parse(r#"{"tests":[{"name":"x","status":"maybe"}]}"#).is_err());
+    }
 }
@@ -2,6 +2,10 @@
 mod debug_print_tests;
 
 use debug_print_tests::DebugPrintTester;
+use pycc::bench;
+#[cfg(feature = "python")]
+use pycc::codegen::Fallback;
+use pycc::codegen::{CodeModel, CodegenOptions, Entry, RelocModel, Sanitizer};
 
 // Basic print tests
 #[test]
@@ -92,6 +96,20 @@ fn test_print_false() {
         .expect("Output mismatch between PyCC and CPython");
 }
 
+#[test]
+fn test_print_multiple_arguments() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"
+name = "pi"
+print(name, 3.14, 42, True, None)
+print()
+print("a", f"{1 + 1}", "c")
+"#;
+    tester
+        .assert_outputs_match(source, "test_print_multiple_arguments")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
 // Variable assignment and printing
 #[test]
 fn test_variable_assignment_integer() {
@@ -145,6 +163,22 @@ print(c)
         .expect("Output mismatch between PyCC and CPython");
 }
 
+#[test]
+fn test_non_ascii_names() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"
+def 面积(宽, 高):
+    return 宽 * 高
+
+café = 面积(3, 4)
+naïve_π = 3.14
+print(café, naïve_π)
+"#;
+    tester
+        .assert_outputs_match(source, "test_non_ascii_names")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
 // Arithmetic operations
 #[test]
 fn test_arithmetic_addition() {
@@ -423,6 +457,1007 @@ print(message)
         .expect("Output mismatch between PyCC and CPython");
 }
 
+#[test]
+fn test_long_fstring_is_not_truncated() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"
+word = "abcdefghij" * 40
+line = f"[{word}] has {400} letters"
+print(line)
+"#;
+    tester
+        .assert_outputs_match(source, "test_long_fstring_is_not_truncated")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_none_keeps_its_type() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"
+result = None
+print(result)
+print(f"result is {result}")
+print(repr(result))
+result = 3
+print(result + 1)
+"#;
+    tester
+        .assert_outputs_match(source, "test_none_keeps_its_type")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_reassignment_with_different_type() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"
+value = 1
+print(value)
+value = "one"
+print(value)
+value = 2.5
+print(value * 2)
+value = True
+print(value)
+value = value + 1
+print(f"now {value}")
+"#;
+    tester
+        .assert_outputs_match(source, "test_reassignment_with_different_type")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_reassignment_with_different_type_in_branches() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"
+label = 0
+flag = 0
+if flag:
+    label = "on"
+else:
+    label = "off"
+print(label)
+count = None
+if 1:
+    count = 4
+else:
+    count = 5
+print(count + 1)
+"#;
+    tester
+        .assert_outputs_match(source, "test_reassignment_with_different_type_in_branches")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_functions_without_return_value_give_none() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"
+def nothing():
+    return
+
+def show(n):
+    print(n * 2)
+
+result = nothing()
+print(result)
+print(show(21))
+"#;
+    tester
+        .assert_outputs_match(source, "test_functions_without_return_value_give_none")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_functions_defined_after_use_and_redefined() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"
+def outer(n):
+    return inner(n) + 1
+
+def inner(n):
+    return n * 10
+
+print(outer(4))
+
+def version():
+    return 1
+
+print(version())
+
+def version():
+    return 2
+
+print(version())
+"#;
+    tester
+        .assert_outputs_match(source, "test_functions_defined_after_use_and_redefined")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_conditional_function_definition() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"
+verbose = 1
+if verbose:
+    def describe(n):
+        return n + 100
+print(describe(1))
+"#;
+    tester
+        .assert_outputs_match(source, "test_conditional_function_definition")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_module_constants_visible_in_functions() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"
+LIMIT = 1000
+STEP = -2
+LABEL = "total"
+def scaled(n):
+    return n * LIMIT + STEP
+def report():
+    print(LABEL)
+    print(f"{LIMIT} max")
+print(scaled(3))
+report()
+"#;
+    tester
+        .assert_outputs_match(source, "test_module_constants_visible_in_functions")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_recursion_within_limit() {
+    let tester = DebugPrintTester::new().expect("Failed to create tester");
+    // Two calls of depth 900 only fit if returning releases the depth
+    let source = r#"
+def down(n):
+    if n:
+        return down(n - 1)
+    return n
+
+print(down(900))
+print(down(900))
+"#;
+    tester
+        .assert_outputs_match(source, "recursion_within_limit")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_unbounded_recursion_raises_recursion_error() {
+    let tester = DebugPrintTester::new().expect("Failed to create tester");
+    let source = r#"
+def forever(n):
+    return forever(n + 1)
+
+print("start")
+forever(0)
+"#;
+    let executable = tester
+        .compile_with_pycc(source, "unbounded_recursion")
+        .expect("Compilation failed");
+    let error = tester
+        .execute_compiled(&executable)
+        .expect_err("Unbounded recursion should fail");
+    assert!(
+        error.contains("RecursionError: maximum recursion depth exceeded"),
+        "{error}"
+    );
+}
+
+#[test]
+fn test_sigint_raises_keyboard_interrupt() {
+    let tester = DebugPrintTester::new().expect("Failed to create tester");
+    // Exponential recursion keeps the program busy until interrupted
+    let source = r#"
+def spin(n):
+    if n:
+        return spin(n - 1) + spin(n - 1)
+    return 1
+
+print("started")
+print(spin(60))
+"#;
+    let executable = tester
+        .compile_with_pycc(source, "sigint")
+        .expect("Compilation failed");
+    let child = std::process::Command::new(&executable)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to start compiled program");
+
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    std::process::Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .expect("Failed to send SIGINT");
+    let output = child.wait_with_output().unwrap();
+
+    assert_eq!(output.status.code(), Some(130));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "started\n");
+    assert!(
+        String::from_utf8_lossy(&output.stderr).ends_with("KeyboardInterrupt\n"),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_coverage_report() {
+    let tester = DebugPrintTester::new().expect("Failed to create tester");
+    let source = r#"def used(a):
+    return a + 1
+
+def unused(b):
+    return b * 2
+
+x = used(1)
+if x:
+    print(x)
+else:
+    print(0)
+"#;
+    let executable = tester
+        .compile_with_options(
+            source,
+            "coverage",
+            CodegenOptions {
+                coverage: true,
+                ..CodegenOptions::default()
+            },
+        )
+        .expect("Compilation failed");
+
+    let report_dir = tempfile::TempDir::new().unwrap();
+    let report_path = report_dir.path().join("report.lcov");
+    let output = std::process::Command::new(&executable)
+        .env("PYCC_COVERAGE_FILE", &report_path)
+        .output()
+        .expect("Failed to run compiled program");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "2\n");
+
+    let report = std::fs::read_to_string(&report_path).expect("No coverage report written");
+    assert!(report.starts_with("TN:\nSF:"), "{report}");
+    assert!(report.contains("coverage.py\n"), "{report}");
+    let lines: Vec<&str> = report.lines().filter(|l| l.starts_with("DA:")).collect();
+    assert_eq!(
+        lines,
+        vec![
+            "DA:1,1", "DA:2,1", "DA:4,1", "DA:5,0", "DA:7,1", "DA:8,1", "DA:9,1", "DA:11,0"
+        ]
+    );
+    assert!(report.ends_with("LF:8\nLH:6\nend_of_record\n"), "{report}");
+}
+
+#[test]
+fn test_instrument_profile_table() {
+    let tester = DebugPrintTester::new().expect("Failed to create tester");
+    let source = r#"def spin(n):
+    if n:
+        return spin(n - 1) + spin(n - 1)
+    return 1
+
+def unused():
+    return 0
+
+print(spin(3))
+"#;
+    let executable = tester
+        .compile_with_options(
+            source,
+            "profile",
+            CodegenOptions {
+                instrument_profile: true,
+                ..CodegenOptions::default()
+            },
+        )
+        .expect("Compilation failed");
+    let output = std::process::Command::new(&executable)
+        .output()
+        .expect("Failed to run compiled program");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "8\n");
+
+    let report = String::from_utf8_lossy(&output.stderr);
+    let rows: Vec<Vec<&str>> = report
+        .lines()
+        .skip(1)
+        .map(|row| row.split_whitespace().collect())
+        .collect();
+    assert_eq!(rows.len(), 2, "{report}");
+    // Recursive calls are counted but only timed once
+    assert_eq!(rows[0][0], "15", "{report}");
+    assert_eq!(rows[0][2..], ["spin", "(line", "1)"], "{report}");
+    assert_eq!(rows[1], ["0", "0.000", "unused", "(line", "6)"], "{report}");
+}
+
+#[test]
+fn test_heap_strings_are_freed_at_exit() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source = r#"
+greeting = "hello " + "world"
+print(greeting)
+echo = greeting * 3
+print(echo)
+print(repr(echo))
+print(repr(2.5))
+print(f"{greeting}!")
+"#;
+    let expected = tester.execute_with_cpython(source).unwrap();
+
+    // LeakSanitizer only needs its malloc interceptors, so linking it in is enough
+    let executable = tester
+        .compile_and_link(
+            source,
+            "test_heap_strings_are_freed_at_exit",
+            CodegenOptions::default(),
+            &["-fsanitize=leak"],
+        )
+        .unwrap();
+    let output = std::process::Command::new(&executable)
+        .env("LSAN_OPTIONS", "exitcode=23")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert!(!stderr.contains("LeakSanitizer"), "{stderr}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+}
+
+#[test]
+fn test_functions_named_like_libc_functions() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source = r#"
+def malloc(n):
+    return n * 2
+
+def exit(code):
+    return code + 1
+
+text = "ab" * 3
+print(malloc(21), exit(0), text)
+"#;
+    tester
+        .assert_outputs_match(source, "test_functions_named_like_libc_functions")
+        .unwrap();
+}
+
+#[test]
+fn test_string_helpers_pass_address_sanitizer() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source = r#"
+import os.path
+
+text = "ab" * 20
+text = text + text
+print(len(text))
+print(len(text * 3))
+print("-" * 10)
+print("" * 4 + "end")
+print(os.path.join("dir", "file.py"))
+print(os.path.join("dir/", "file.py"))
+print(os.path.join("", "file.py"))
+print(repr(2.0), repr(0.1))
+"#;
+    let expected = tester.execute_with_cpython(source).unwrap();
+
+    let options = CodegenOptions {
+        sanitize: Sanitizer::Address,
+        ..CodegenOptions::default()
+    };
+    let executable = tester
+        .compile_with_options(
+            source,
+            "test_string_helpers_pass_address_sanitizer",
+            options,
+        )
+        .unwrap();
+    let output = std::process::Command::new(&executable).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert!(!stderr.contains("AddressSanitizer"), "{stderr}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+}
+
+#[cfg(feature = "python")]
+#[test]
+fn test_interpreter_fallback_runs_unsupported_programs() {
+    let tester = DebugPrintTester::new().unwrap();
+    // String functions are not compiled natively yet
+    let source = r#"
+def greet(name: str) -> str:
+    return "hello " + name
+
+print(greet("bob"))
+"#;
+    assert!(tester.compile_with_pycc(source, "native").is_err());
+
+    let options = CodegenOptions {
+        fallback: Fallback::Interp,
+        ..CodegenOptions::default()
+    };
+    let executable = tester
+        .compile_with_options(source, "fallback", options.clone())
+        .unwrap();
+    assert_eq!(
+        tester.execute_compiled(&executable).unwrap(),
+        tester.execute_with_cpython(source).unwrap()
+    );
+
+    let failing = source.replace("greet(\"bob\")", "greet(1)");
+    let executable = tester
+        .compile_with_options(&failing, "fallback_error", options)
+        .unwrap();
+    let output = std::process::Command::new(&executable).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("TypeError"));
+}
+
+#[test]
+fn test_unpacked_arguments() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source = r#"
+def area(width, height):
+    return width * height
+
+print(area(*(3, 4)), area(2, *[5]), *["x", "y"], **{"flush": True})
+"#;
+    tester
+        .assert_outputs_match(source, "test_unpacked_arguments")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+// Containers built at run time are unpacked by the interpreter
+#[cfg(feature = "python")]
+#[test]
+fn test_unpacked_runtime_containers_fall_back() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source = r#"
+def area(width, height):
+    return width * height
+
+sizes = [3, 4]
+options = {"height": 5}
+print(area(*sizes), area(2, **options))
+"#;
+    assert!(tester.compile_with_pycc(source, "unpacked_native").is_err());
+    let options = CodegenOptions {
+        fallback: Fallback::Interp,
+        ..CodegenOptions::default()
+    };
+    let executable = tester
+        .compile_with_options(source, "unpacked_fallback", options)
+        .unwrap();
+    assert_eq!(tester.execute_compiled(&executable).unwrap(), "12 10\n");
+}
+
+#[test]
+fn test_module_dunders() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source = r#"
+def report():
+    print("running as", __name__)
+
+if __name__ == "__main__":
+    report()
+if __name__ != "__main__":
+    print("imported")
+"#;
+    tester
+        .assert_outputs_match(source, "test_module_dunders")
+        .expect("Output mismatch between PyCC and CPython");
+
+    // `__file__` is the path of the compiled source, which the tester keeps next
+    // to the executable, in both backends
+    let source = "print(__file__)\n";
+    let executable = tester.compile_with_pycc(source, "dunder_file").unwrap();
+    let script = std::path::Path::new(&executable).with_extension("py");
+    assert_eq!(
+        tester.execute_compiled(&executable).unwrap(),
+        format!("{}\n", script.display())
+    );
+
+    #[cfg(feature = "python")]
+    {
+        let options = CodegenOptions {
+            fallback: Fallback::Interp,
+            file_name: Some("/srv/app/main.py".to_string()),
+            ..CodegenOptions::default()
+        };
+        let source = "print(__name__, __file__, len([1, 2]))\n";
+        let executable = tester
+            .compile_with_options(source, "dunder_fallback", options)
+            .unwrap();
+        assert_eq!(
+            tester.execute_compiled(&executable).unwrap(),
+            "__main__ /srv/app/main.py 2\n"
+        );
+    }
+}
+
+#[test]
+fn test_identical_programs_share_a_build() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source =
+        "total = 0\ntotal = total + 3 * 7\ntotal = total + 7 * 7\nprint(\"shared build\", total)\n";
+    let first = tester.compile_with_pycc(source, "shared_first").unwrap();
+    let second = tester.compile_with_pycc(source, "shared_second").unwrap();
+    assert!(tester.cache_hits() >= 1);
+    assert_ne!(first, second);
+    for executable in [&first, &second] {
+        assert_eq!(
+            tester.execute_compiled(executable).unwrap(),
+            "shared build 70\n"
+        );
+    }
+
+    // Programs that read `__file__` are built for each source path
+    let source = "print(__file__)\n";
+    for name in ["file_first", "file_second"] {
+        let executable = tester.compile_with_pycc(source, name).unwrap();
+        let script = std::path::Path::new(&executable).with_extension("py");
+        assert_eq!(
+            tester.execute_compiled(&executable).unwrap(),
+            format!("{}\n", script.display())
+        );
+    }
+}
+
+#[cfg(feature = "python")]
+#[test]
+fn test_interpreter_fallback_runs_async_programs() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source = r#"
+import asyncio
+
+async def double(n):
+    await asyncio.sleep(0)
+    return n * 2
+
+async def main():
+    print(await double(21))
+
+asyncio.run(main())
+"#;
+    let options = CodegenOptions {
+        fallback: Fallback::Interp,
+        ..CodegenOptions::default()
+    };
+    let executable = tester
+        .compile_with_options(source, "async_fallback", options)
+        .unwrap();
+    assert_eq!(tester.execute_compiled(&executable).unwrap(), "42\n");
+}
+
+#[cfg(feature = "python")]
+#[test]
+fn test_python_interop_calls_cpython() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source = r#"
+import math
+import os.path
+
+print("start")
+root: float = math.sqrt(2.0)
+print(root)
+n: int = len("hello") + 1
+print(n)
+print(math.gcd(12, 18))
+print(os.path.join("a", "b"))
+print(sorted("pycc"))
+print("end")
+"#;
+    assert!(tester.compile_with_pycc(source, "native").is_err());
+
+    let options = CodegenOptions {
+        python_interop: true,
+        ..CodegenOptions::default()
+    };
+    let executable = tester
+        .compile_with_options(source, "interop", options.clone())
+        .unwrap();
+    assert_eq!(
+        tester.execute_compiled(&executable).unwrap(),
+        tester.execute_with_cpython(source).unwrap()
+    );
+
+    // Exceptions raised by CPython end the program like uncaught ones
+    let failing = "import math\nprint(\"before\")\nroot: float = math.sqrt(-1.0)\nprint(root)\n";
+    let executable = tester
+        .compile_with_options(failing, "interop_error", options)
+        .unwrap();
+    let output = std::process::Command::new(&executable).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "before\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("ValueError: math domain error"));
+}
+
+#[test]
+fn test_entry_function_gets_command_line_arguments() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source = r#"
+print("setup")
+
+def run(name: str, times: int, scale: float) -> int:
+    print(name)
+    print(times * scale)
+    return times + 1
+"#;
+    let options = CodegenOptions {
+        typed: true,
+        entry: Some(Entry::parse("tool:run").unwrap()),
+        ..CodegenOptions::default()
+    };
+    let executable = tester
+        .compile_with_options(source, "entry", options)
+        .unwrap();
+
+    let output = std::process::Command::new(&executable)
+        .args(["pycc", "3", "0.5"])
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "setup\npycc\n1.5\n"
+    );
+    assert_eq!(output.status.code(), Some(4));
+
+    let output = std::process::Command::new(&executable)
+        .arg("pycc")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "usage: entry name times scale\n"
+    );
+
+    let output = std::process::Command::new(&executable)
+        .args(["pycc", "three", "0.5"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("argument 'times'"));
+}
+
+#[test]
+fn test_os_environment_and_paths() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source = r#"
+import os
+import os.path
+
+print(os.environ["HOME"])
+print(os.getenv("HOME"))
+print(os.getenv("PYCC_TEST_UNSET", "fallback"))
+print(os.environ.get("PYCC_TEST_UNSET", "default"))
+if os.getenv("PYCC_TEST_UNSET"):
+    print("set")
+else:
+    print("unset")
+print(os.path.join("a", "b", "c"))
+print(os.path.join("a/", "/abs", "c"))
+print(os.path.join("", "b"))
+if os.path.exists("/"):
+    print("root exists")
+if not os.path.exists("/pycc/test/missing"):
+    print("missing")
+"#;
+    tester.assert_outputs_match(source, "os_module").unwrap();
+
+    let failing = "import os\nprint(\"before\")\nprint(os.environ[\"PYCC_TEST_UNSET\"])\n";
+    let executable = tester.compile_with_pycc(failing, "os_key_error").unwrap();
+    let output = std::process::Command::new(&executable).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "before\n");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "KeyError: 'PYCC_TEST_UNSET'\n"
+    );
+}
+
+#[test]
+fn test_chained_assignment_and_environ_targets() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source = r#"
+import os
+
+a = b = 0
+print(a)
+b = b + 2
+print(b)
+first = second = third = "shared"
+print(third)
+os.environ["PYCC_TEST_ASSIGNED"] = "assigned"
+print(os.environ["PYCC_TEST_ASSIGNED"])
+print(os.getenv("PYCC_TEST_ASSIGNED"))
+"#;
+    tester
+        .assert_outputs_match(source, "chained_assignment")
+        .unwrap();
+}
+
+#[test]
+fn test_print_file_routes_to_stderr() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source = r#"
+import sys
+
+print("to stdout")
+print("to stderr", file=sys.stderr)
+print(42, file=sys.stderr,)
+print(1.5, file=sys.stdout)
+print(file=sys.stderr)
+print(True)
+"#;
+    let result = tester.compare_outputs(source, "print_file").unwrap();
+    assert_eq!(result.pycc_exit_code, Some(0), "{}", result.pycc_stderr);
+    assert_eq!(result.pycc_output, result.cpython_output);
+    assert_eq!(result.pycc_stderr, result.cpython_stderr);
+    assert_eq!(result.pycc_stderr, "to stderr\n42\n\n");
+}
+
+#[test]
+fn test_stdout_buffering_and_flush_match_cpython() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source = r#"
+import sys
+
+print("out 1")
+print("err 1", file=sys.stderr)
+print("out 2", flush=True)
+print("err 2", file=sys.stderr)
+print("out 3")
+"#;
+    let executable = tester
+        .compile_with_pycc(source, "stdout_buffering")
+        .unwrap();
+    // The source is kept next to the executable
+    let script = std::path::Path::new(&executable).with_extension("py");
+
+    // Both streams share one pipe, so the order shows when stdout is flushed
+    let interleaved = |command: &str, unbuffered: bool| {
+        let mut shell = std::process::Command::new("sh");
+        shell.args(["-c", &format!("{command} 2>&1")]);
+        if unbuffered {
+            shell.env("PYTHONUNBUFFERED", "1");
+        } else {
+            shell.env_remove("PYTHONUNBUFFERED");
+        }
+        String::from_utf8(shell.output().unwrap().stdout).unwrap()
+    };
+    let python = format!("{} {}", tester.python().display(), script.display());
+    for unbuffered in [false, true] {
+        assert_eq!(
+            interleaved(&executable, unbuffered),
+            interleaved(&python, unbuffered),
+            "PYTHONUNBUFFERED set: {unbuffered}"
+        );
+    }
+    assert_eq!(
+        interleaved(&executable, false),
+        "err 1\nout 1\nout 2\nerr 2\nout 3\n"
+    );
+}
+
+#[test]
+fn test_sys_exit_status_matches_cpython() {
+    let tester = DebugPrintTester::new().unwrap();
+    let cases = [
+        ("sys.exit()", Some(0)),
+        ("sys.exit(None)", Some(0)),
+        ("sys.exit(3)", Some(3)),
+        ("sys.exit(False)", Some(0)),
+        ("sys.exit(\"fatal: no input\")", Some(1)),
+        ("def stop(code):\n    sys.exit(code)\nstop(7)", Some(7)),
+        ("if True:\n    sys.exit(5)", Some(5)),
+    ];
+    for (i, (exit, status)) in cases.into_iter().enumerate() {
+        let source = format!("import sys\nprint(\"before\")\n{exit}\nprint(\"after\")\n");
+        let result = tester
+            .compare_outputs(&source, &format!("sys_exit_{i}"))
+            .unwrap();
+        assert_eq!(result.cpython_exit_code, status, "{source}");
+        assert_eq!(result.pycc_exit_code, status, "{source}");
+        assert_eq!(result.pycc_output, result.cpython_output, "{source}");
+        assert_eq!(result.pycc_stderr, result.cpython_stderr, "{source}");
+    }
+}
+
+#[test]
+fn test_code_and_relocation_models_run() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source = "greeting = \"hello\"\ndef twice(n):\n    return n * 2\nprint(greeting)\nprint(twice(21))\n";
+    let models = [
+        (CodeModel::Small, RelocModel::Static),
+        (CodeModel::Medium, RelocModel::Pic),
+        (CodeModel::Large, RelocModel::Pic),
+    ];
+    for (i, (code_model, reloc_model)) in models.into_iter().enumerate() {
+        let options = CodegenOptions {
+            code_model,
+            reloc_model,
+            ..CodegenOptions::default()
+        };
+        let executable = tester
+            .compile_with_options(source, &format!("models_{i}"), options)
+            .unwrap();
+        let output = std::process::Command::new(&executable).output().unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "hello\n42\n",
+            "{code_model:?} {reloc_model:?}"
+        );
+    }
+}
+
+#[test]
+fn test_os_system_runs_shell_commands() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source = r#"
+import os
+
+print("before")
+status = os.system("echo from shell")
+print(status)
+print(os.system("exit 3") // 256)
+"#;
+    tester.assert_outputs_match(source, "os_system").unwrap();
+}
+
+#[test]
+fn test_missing_environ_key_fails_like_cpython() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"import os
+print("before")
+print(os.environ["PYCC_SURELY_UNSET_VARIABLE"])
+print("after")
+"#;
+    tester
+        .assert_fails_with(
+            source,
+            "test_missing_environ_key_fails_like_cpython",
+            "KeyError: 'PYCC_SURELY_UNSET_VARIABLE'",
+        )
+        .expect("Failure mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_recursion_error_fails_like_cpython() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"def forever(n):
+    return forever(n + 1)
+
+print(1)
+print(forever(0))
+"#;
+    tester
+        .assert_failures_match(source, "test_recursion_error_fails_like_cpython")
+        .expect("Failure mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_assert_outputs_match_rejects_failing_programs() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"import os
+print(os.environ["PYCC_SURELY_UNSET_VARIABLE"])
+"#;
+    let result = tester
+        .compare_outputs(source, "test_assert_outputs_match_rejects_failing_programs")
+        .expect("Failed to compare outputs");
+    assert_eq!(result.pycc_exit_code, Some(1));
+    assert_eq!(result.cpython_exit_code, Some(1));
+    assert!(result.errors_match());
+    assert!(
+        tester
+            .assert_outputs_match(source, "test_assert_outputs_match_rejects_failing_programs")
+            .is_err()
+    );
+}
+
+#[test]
+fn test_input_reads_stdin_lines() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"name = input("Name? ")
+print("Hello, " + name + "!")
+first = input()
+second = input()
+print(second + first)
+print(input())
+"#;
+    tester
+        .assert_outputs_match_with_stdin(
+            source,
+            "test_input_reads_stdin_lines",
+            "Ada\nab\ncd\nlast line without newline",
+        )
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_input_at_end_of_stdin_fails_like_cpython() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"print(input())
+print(input())
+"#;
+    tester
+        .assert_failures_match_with_stdin(
+            source,
+            "test_input_at_end_of_stdin_fails_like_cpython",
+            "only line\n",
+        )
+        .expect("Failure mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_hung_program_is_killed_after_timeout() {
+    let mut tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    tester.set_timeout(Some(std::time::Duration::from_millis(500)));
+    let source = r#"import os
+print("started")
+os.system("sleep 5 > /dev/null 2>&1")
+"#;
+    let result = tester
+        .compare_outputs(source, "test_hung_program_is_killed_after_timeout")
+        .expect("Failed to compare outputs");
+    assert!(result.timed_out);
+    assert_eq!(result.pycc_exit_code, None);
+    assert!(
+        tester
+            .assert_outputs_match(source, "test_hung_program_is_killed_after_timeout")
+            .is_err()
+    );
+}
+
+#[test]
+fn test_not_operator() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source = r#"
+print(not 0)
+print(not 2.5)
+print(not "")
+print(not not "text")
+x = -2 ** 2
+if not x + 4:
+    print("zero")
+else:
+    print("nonzero")
+"#;
+    tester.assert_outputs_match(source, "not_operator").unwrap();
+}
+
+#[test]
+fn test_multiline_calls_with_comments() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source = r#"
+def add(
+    a,  # left operand
+    b,  # right operand
+):
+    return (
+        a
+        + b
+    )
+
+print(
+    add(1, 2,),  # three
+)
+print(add(
+    # nested
+    add(3, 4),
+    5,
+))
+"#;
+    tester
+        .assert_outputs_match(source, "multiline_calls_with_comments")
+        .unwrap();
+}
+
 #[test]
 fn test_arithmetic_with_fstring_output() {
     let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
@@ -619,3 +1654,252 @@ print(f"Perimeter: {perimeter}")
         .assert_outputs_match(source, "large_program")
         .expect("Output mismatch for large program test");
 }
+
+#[test]
+fn test_multiline_function_body() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"
+def scale(a, b):
+    c = a * b
+    return c + \
+        1
+
+print(scale(
+    3,
+    4
+))
+"#;
+    tester
+        .assert_outputs_match(source, "test_multiline_function_body")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_semicolons_and_single_line_if() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"
+x = 1; y = 2; print(x + y)
+if x: print("yes")
+if 0: print("no")
+else: print("else branch")
+
+def pick(n):
+    if n:
+        r = 10
+    else:
+        r = 20
+    return r
+
+print(pick(1)); print(pick(0))
+"#;
+    tester
+        .assert_outputs_match(source, "test_semicolons_and_single_line_if")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_docstrings_are_not_executed() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"
+"""Example module."""
+
+def double(n):
+    """Return twice n."""
+    return n * 2
+
+print(double(21))
+print(__doc__)
+"#;
+    tester
+        .assert_outputs_match(source, "test_docstrings_are_not_executed")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_type_annotations_are_ignored() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"
+def scale(value: int, factor: int) -> int:
+    return value * factor
+
+count: int = 3
+label: str
+print(scale(count, 4))
+"#;
+    tester
+        .assert_outputs_match(source, "test_type_annotations_are_ignored")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_modulo_and_floor_division_with_negative_operands() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"
+a = -7
+b = 3
+print(a % b)
+print(-17 // 5)
+print(11 % -4)
+print(13 // -4)
+print(-9 % -4)
+print(-9 // -4)
+print(-20 // 4)
+print(-7.5 % 2.0)
+print(7.5 % -2.0)
+"#;
+    tester
+        .assert_outputs_match(
+            source,
+            "test_modulo_and_floor_division_with_negative_operands",
+        )
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_power_operator() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"
+print((-2) ** 3)
+print(2 ** -1)
+print(2 ** 62)
+print(-2 ** 2)
+print(3 ** 0)
+print(2.5 ** 2)
+e = 10
+print(7 ** e)
+print(2 ** 3 ** 2)
+print(6.25 ** 0.5)
+"#;
+    tester
+        .assert_outputs_match(source, "test_power_operator")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_boolean_arithmetic() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"
+print(True + 1)
+print(True + True)
+t = True
+f = False
+print(t * 5)
+print(f - 1)
+print(-t)
+print(t)
+print(f)
+print(1.5 + t)
+print(f"{t} and {f}")
+x = -3
+print(x)
+print(-2)
+if f:
+    print("no")
+else:
+    print("yes")
+print(int(True))
+print(int(False) + 2)
+print(int(7.9))
+"#;
+    tester
+        .assert_outputs_match(source, "test_boolean_arithmetic")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_repr_builtin() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"
+print(repr("hello"))
+print(repr("it's"))
+print(repr("say \"hi\""))
+print(repr("both ' and \""))
+print(repr("tab\there\nnew\\"))
+print(repr(42))
+print(repr(0.1))
+print(repr(5.0))
+print(repr(0.0001))
+print(repr(0.00001))
+print(repr(-2.5))
+print(repr(True))
+print(1 / 3)
+print(10 / 2)
+print(123456.789)
+x = 2.5
+print(f"x is {x}")
+s = "a" + "b"
+print(repr(s))
+print(repr(1.0 / 3.0 * 3.0))
+print(repr(-0.0))
+"#;
+    tester
+        .assert_outputs_match(source, "test_repr_builtin")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_hash_and_id_builtins() {
+    let tester = DebugPrintTester::new().expect("Failed to create debug print tester");
+    let source = r#"
+print(hash(42))
+print(hash(-1))
+print(hash(True))
+print(hash(2.0))
+print(hash(-0.0))
+print(hash(2305843009213693951))
+print(hash(2305843009213693952))
+print(hash("abc") - hash("a" + "bc"))
+print(hash(1.5) - hash(3.0 / 2))
+s = "text"
+print(id(s) - id(s))
+n = 7
+print(id(n) - id(n))
+"#;
+    tester
+        .assert_outputs_match(source, "test_hash_and_id_builtins")
+        .expect("Output mismatch between PyCC and CPython");
+}
+
+#[test]
+fn test_heap_stats_are_printed_at_exit() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source = r#"
+print(repr(2.5))
+print(repr(0.1))
+"#;
+    let executable = tester
+        .compile_with_options(source, "heap_stats", CodegenOptions::default())
+        .unwrap();
+
+    let (output, stats) = bench::measure(&mut std::process::Command::new(&executable)).unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "2.5\n0.1\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stats.types.len(), 1, "{stderr}");
+    assert_eq!(stats.types[0].type_name, "str");
+    // Each float repr gets a fixed-size buffer
+    assert_eq!(stats.allocations(), 2, "{stderr}");
+    assert_eq!(stats.bytes(), 2 * 48, "{stderr}");
+
+    // Without the variable nothing extra reaches stderr
+    let output = std::process::Command::new(&executable).output().unwrap();
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_constant_builtin_calls() {
+    let tester = DebugPrintTester::new().unwrap();
+    let source = r#"
+SIZE = len("hello")
+
+def scaled(n):
+    return n * SIZE + abs(-3)
+
+print(scaled(2))
+print(int(" -1_000 "), int(-2.7), bool(""), bool("x"), str(None))
+print(f"{len('abc')} {abs(-7)} {-7 // 2} {-7 % 3}")
+"#;
+    tester
+        .assert_outputs_match(source, "test_constant_builtin_calls")
+        .expect("Output mismatch between PyCC and CPython");
+}
@@ -1,110 +1,408 @@
-use inkwell::context::Context;
-use pycc::codegen::CodeGenerator;
-use pycc::lexer::Lexer;
-use pycc::parser::Parser;
+use pycc::cache::BuildCache;
+use pycc::codegen::{CodegenOptions, build};
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
 use std::fs;
-use std::process::Command;
+use std::io::{Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::CommandExt;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, Once};
+use std::thread;
+use std::time::{Duration, Instant};
 use tempfile::TempDir;
 
 /// Test utility for comparing pycc output with CPython output
 pub struct DebugPrintTester {
     temp_dir: TempDir,
+    /// Programs running longer than this are killed
+    timeout: Option<Duration>,
+    /// Distinguishes the CPython scripts of concurrent runs
+    cpython_runs: AtomicUsize,
+    /// Reference interpreter
+    python: PathBuf,
+    /// Executables this tester reused instead of compiling
+    cache_hits: AtomicUsize,
+}
+
+/// Interpreter used when `PYCC_TEST_PYTHON` is unset
+pub const DEFAULT_PYTHON: &str = "python3";
+
+/// The reference interpreter: `PYCC_TEST_PYTHON`, or `python3` from PATH
+pub fn default_python() -> PathBuf {
+    std::env::var_os("PYCC_TEST_PYTHON")
+        .filter(|path| !path.is_empty())
+        .map_or_else(|| PathBuf::from(DEFAULT_PYTHON), PathBuf::from)
+}
+
+/// Executables built by any tester in this process, by build key
+///
+/// The debug print tests compile many identical snippets, so a build is
+/// reused by every later test that asks for the same program.
+static BUILDS: LazyLock<Mutex<HashMap<String, Arc<[u8]>>>> = LazyLock::new(Mutex::default);
+
+/// Identity of the compiler under test: the test binary, which is relinked
+/// whenever pycc changes
+static COMPILER_STAMP: LazyLock<String> = LazyLock::new(|| {
+    let metadata = std::env::current_exe().and_then(fs::metadata);
+    let modified = metadata
+        .as_ref()
+        .ok()
+        .and_then(|metadata| metadata.modified().ok())
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |time| time.as_nanos());
+    let len = metadata.map_or(0, |metadata| metadata.len());
+    format!("{len}-{modified}")
+});
+
+/// Directory that keeps executables across test runs: `PYCC_TEST_CACHE`, if set
+fn persistent_cache_dir() -> Option<PathBuf> {
+    std::env::var_os("PYCC_TEST_CACHE")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Key of the executable built from `source` with `options`
+///
+/// `__file__` names the test's own temporary copy of the source, so the file
+/// name only counts for programs that mention it; otherwise the same snippet
+/// compiled by different tests shares one build.
+fn build_key(source: &str, options: &CodegenOptions) -> String {
+    let options = CodegenOptions {
+        file_name: options
+            .file_name
+            .clone()
+            .filter(|_| source.contains("__file__")),
+        ..options.clone()
+    };
+    let link_args = options.link_args.join("\0");
+    BuildCache::key(
+        source,
+        &format!(
+            "{};link={link_args};compiler={}",
+            options.fingerprint(),
+            *COMPILER_STAMP
+        ),
+    )
+}
+
+/// Write a cached executable to `path`
+fn write_executable(path: &Path, executable: &[u8]) -> std::io::Result<()> {
+    fs::write(path, executable)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+}
+
+thread_local! {
+    /// Whether this thread is inside `catch_panic`
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+    /// Report of the last panic caught on this thread
+    static PANIC_REPORT: Cell<Option<String>> = const { Cell::new(None) };
+}
+
+/// Run `f`, turning a panic into an error with its message, location and backtrace
+///
+/// A panic in pycc then fails the one test that hit it instead of the whole
+/// test binary or suite. The backtrace can only be captured while the panic
+/// hook runs, so a hook records it; panics outside `catch_panic` still reach
+/// the previous hook.
+pub fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if CATCHING.get() {
+                let backtrace = Backtrace::force_capture();
+                PANIC_REPORT.set(Some(format!("{info}\n\nBacktrace:\n{backtrace}")));
+            } else {
+                previous(info);
+            }
+        }));
+    });
+
+    let was_catching = CATCHING.replace(true);
+    let result = std::panic::catch_unwind(AssertUnwindSafe(f));
+    CATCHING.set(was_catching);
+    result.map_err(|payload| {
+        PANIC_REPORT
+            .take()
+            .unwrap_or_else(|| panic_message(&*payload))
+    })
+}
+
+/// Message a panic was raised with
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panic with a non-string payload".to_string()
+    }
+}
+
+/// The executable built for `key` earlier in this run, or in an earlier run
+fn cached_executable(key: &str) -> Option<Arc<[u8]>> {
+    if let Some(executable) = BUILDS.lock().unwrap().get(key) {
+        return Some(executable.clone());
+    }
+    let executable: Arc<[u8]> = fs::read(persistent_cache_dir()?.join(key)).ok()?.into();
+    BUILDS
+        .lock()
+        .unwrap()
+        .insert(key.to_string(), executable.clone());
+    Some(executable)
+}
+
+/// Remember the executable at `path` as the build for `key`
+fn store_executable(key: &str, path: &Path) -> std::io::Result<()> {
+    let executable: Arc<[u8]> = fs::read(path)?.into();
+    if let Some(dir) = persistent_cache_dir() {
+        // Write under a temporary name first, so concurrent runs never read half a file
+        fs::create_dir_all(&dir)?;
+        let partial = dir.join(format!("{key}.{}.partial", std::process::id()));
+        fs::write(&partial, &executable)?;
+        fs::rename(&partial, dir.join(key))?;
+    }
+    BUILDS.lock().unwrap().insert(key.to_string(), executable);
+    Ok(())
+}
+
+/// A CPython version such as `3.11.7`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PythonVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl PythonVersion {
+    /// Parse `3`, `3.11` or `3.11.7`; missing components are zero
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parts = text.trim().split('.');
+        let mut next = |required: bool| -> Result<u32, String> {
+            match parts.next() {
+                Some(part) => part
+                    .parse()
+                    .map_err(|_| format!("invalid Python version '{}'", text.trim())),
+                None if required => Err(format!("invalid Python version '{}'", text.trim())),
+                None => Ok(0),
+            }
+        };
+        let version = PythonVersion {
+            major: next(true)?,
+            minor: next(false)?,
+            patch: next(false)?,
+        };
+        if parts.next().is_some() {
+            return Err(format!("invalid Python version '{}'", text.trim()));
+        }
+        Ok(version)
+    }
+}
+
+impl fmt::Display for PythonVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
 }
 
 impl DebugPrintTester {
     /// Create a new debug print tester with a temporary directory
     pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
         let temp_dir = TempDir::new()?;
-        Ok(DebugPrintTester { temp_dir })
+        Ok(DebugPrintTester {
+            temp_dir,
+            timeout: None,
+            cpython_runs: AtomicUsize::new(0),
+            python: default_python(),
+            cache_hits: AtomicUsize::new(0),
+        })
     }
 
-    /// Compile Python source code with pycc and return the path to the executable
-    pub fn compile_with_pycc(&self, source: &str, executable_name: &str) -> Result<String, String> {
-        // Write source to temporary file
-        let source_path = self.temp_dir.path().join(format!("{}.py", executable_name));
-        fs::write(&source_path, source)
-            .map_err(|e| format!("Failed to write source file: {}", e))?;
+    /// Number of executables this tester reused from earlier builds
+    pub fn cache_hits(&self) -> usize {
+        self.cache_hits.load(Ordering::Relaxed)
+    }
 
-        // Parse the program
-        let lexer = Lexer::new(source);
-        let mut parser = Parser::new(lexer);
-        let program = parser.parse_program();
+    /// Run CPython programs with `python` instead of the default interpreter
+    pub fn set_python(&mut self, python: impl Into<PathBuf>) {
+        self.python = python.into();
+    }
 
-        // Generate LLVM IR
-        let context = Context::create();
-        let mut codegen = CodeGenerator::new(&context, "pycc_module");
+    /// The reference interpreter
+    pub fn python(&self) -> &Path {
+        &self.python
+    }
 
-        codegen
-            .compile(&program)
-            .map_err(|e| format!("Failed to compile to LLVM IR: {}", e))?;
+    /// Version of the reference interpreter, checked against `minimum`
+    pub fn check_python(&self, minimum: Option<PythonVersion>) -> Result<PythonVersion, String> {
+        let output = Command::new(&self.python)
+            .arg("--version")
+            .output()
+            .map_err(|e| format!("Failed to run {}: {}", self.python.display(), e))?;
+        // Python 2 printed its version to stderr
+        let text = String::from_utf8_lossy(if output.stdout.is_empty() {
+            &output.stderr
+        } else {
+            &output.stdout
+        })
+        .to_string();
+        let version = text
+            .trim()
+            .strip_prefix("Python ")
+            .ok_or_else(|| {
+                format!(
+                    "{} is not CPython: --version printed '{}'",
+                    self.python.display(),
+                    text.trim()
+                )
+            })
+            // Drop suffixes like the `+` of development builds or `rc1`
+            .map(|version| {
+                version
+                    .split(|c: char| !c.is_ascii_digit() && c != '.')
+                    .next()
+                    .unwrap_or_default()
+            })
+            .and_then(PythonVersion::parse)?;
+        match minimum {
+            Some(minimum) if version < minimum => Err(format!(
+                "{} is CPython {}, but at least {} is required",
+                self.python.display(),
+                version,
+                minimum
+            )),
+            _ => Ok(version),
+        }
+    }
 
-        // Generate object file
-        let object_file_name = format!("{}.o", executable_name);
-        let object_path = self.temp_dir.path().join(&object_file_name);
+    /// Kill compiled and CPython programs that run longer than `timeout`
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
 
-        codegen
-            .write_object_to_file(object_path.to_str().unwrap())
-            .map_err(|e| format!("Failed to generate object file: {}", e))?;
+    /// Compile Python source code with pycc and return the path to the executable
+    pub fn compile_with_pycc(&self, source: &str, executable_name: &str) -> Result<String, String> {
+        self.compile_with_options(source, executable_name, CodegenOptions::default())
+    }
 
-        // Link object file to create executable
-        let executable_path = self.temp_dir.path().join(executable_name);
-        let output = Command::new("cc")
-            .args([
-                object_path.to_str().unwrap(),
-                "-o",
-                executable_path.to_str().unwrap(),
-                "-no-pie",
-            ])
-            .output()
-            .map_err(|e| format!("Failed to execute linker: {}", e))?;
+    /// Like `compile_with_pycc`, with non-default code generation options
+    pub fn compile_with_options(
+        &self,
+        source: &str,
+        executable_name: &str,
+        options: CodegenOptions,
+    ) -> Result<String, String> {
+        self.compile_and_link(source, executable_name, options, &[])
+    }
 
-        if !output.status.success() {
-            return Err(format!(
-                "Linking failed: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ));
+    /// Like `compile_with_options`, passing extra flags to the linker driver
+    pub fn compile_and_link(
+        &self,
+        source: &str,
+        executable_name: &str,
+        options: CodegenOptions,
+        linker_flags: &[&str],
+    ) -> Result<String, String> {
+        // Write source to temporary file, which `__file__` names
+        let source_path = self.temp_dir.path().join(format!("{}.py", executable_name));
+        fs::write(&source_path, source)
+            .map_err(|e| format!("Failed to write source file: {}", e))?;
+
+        let options = CodegenOptions {
+            file_name: options
+                .file_name
+                .or_else(|| Some(source_path.to_string_lossy().into_owned())),
+            link_args: linker_flags
+                .iter()
+                .map(|flag| flag.to_string())
+                .chain(options.link_args)
+                .collect(),
+            ..options
+        };
+        let executable_path = self.temp_dir.path().join(executable_name);
+        let key = build_key(source, &options);
+        if let Some(executable) = cached_executable(&key) {
+            write_executable(&executable_path, &executable)
+                .map_err(|e| format!("Failed to write cached executable: {}", e))?;
+            self.cache_hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(executable_path.to_str().unwrap().to_string());
         }
 
+        let built = catch_panic(|| build::compile_and_link(source, &options))
+            .map_err(|report| format!("pycc panicked: {}", report))??;
+        // Keep the executable with the test's other files, so it is removed with them
+        fs::rename(&built, &executable_path)
+            .map_err(|e| format!("Failed to move executable: {}", e))?;
+        // Failing to cache only costs a rebuild later
+        let _ = store_executable(&key, &executable_path);
         Ok(executable_path.to_str().unwrap().to_string())
     }
 
     /// Execute Python code with CPython and return the output
     pub fn execute_with_cpython(&self, source: &str) -> Result<String, String> {
+        let run = self.run_with_cpython(source)?;
+        if !run.success() {
+            return Err(format!("CPython execution failed: {}", run.stderr));
+        }
+        Ok(run.stdout)
+    }
+
+    /// Execute Python code with CPython, keeping stderr and the exit code
+    pub fn run_with_cpython(&self, source: &str) -> Result<ProgramOutput, String> {
+        self.run_with_cpython_stdin(source, "")
+    }
+
+    /// Like `run_with_cpython`, feeding `stdin` to the program
+    pub fn run_with_cpython_stdin(
+        &self,
+        source: &str,
+        stdin: &str,
+    ) -> Result<ProgramOutput, String> {
         // Write source to temporary file
-        let source_path = self.temp_dir.path().join("test_cpython.py");
+        let run = self.cpython_runs.fetch_add(1, Ordering::Relaxed);
+        let source_path = self
+            .temp_dir
+            .path()
+            .join(format!("test_cpython_{}.py", run));
         fs::write(&source_path, source)
             .map_err(|e| format!("Failed to write source file: {}", e))?;
 
         // Execute with CPython
-        let output = Command::new("python3")
-            .arg(source_path)
-            .output()
-            .map_err(|e| format!("Failed to execute CPython: {}", e))?;
-
-        if !output.status.success() {
-            return Err(format!(
-                "CPython execution failed: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ));
-        }
-
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        let mut command = Command::new(&self.python);
+        command.arg(source_path);
+        self.run_program(command, stdin)
+            .map_err(|e| format!("Failed to execute CPython: {}", e))
     }
 
     /// Execute compiled code and return the output
     pub fn execute_compiled(&self, executable_path: &str) -> Result<String, String> {
-        let output = Command::new(executable_path)
-            .output()
-            .map_err(|e| format!("Failed to execute compiled program: {}", e))?;
-
-        if !output.status.success() {
-            return Err(format!(
-                "Compiled program execution failed: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ));
+        let run = self.run_compiled(executable_path)?;
+        if !run.success() {
+            return Err(format!("Compiled program execution failed: {}", run.stderr));
         }
+        Ok(run.stdout)
+    }
 
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    /// Execute compiled code, keeping stderr and the exit code
+    pub fn run_compiled(&self, executable_path: &str) -> Result<ProgramOutput, String> {
+        self.run_compiled_stdin(executable_path, "")
+    }
+
+    /// Like `run_compiled`, feeding `stdin` to the program
+    pub fn run_compiled_stdin(
+        &self,
+        executable_path: &str,
+        stdin: &str,
+    ) -> Result<ProgramOutput, String> {
+        self.run_program(Command::new(executable_path), stdin)
+            .map_err(|e| format!("Failed to execute compiled program: {}", e))
     }
 
     /// Compare outputs from pycc and CPython for given source code
@@ -112,40 +410,193 @@ impl DebugPrintTester {
         &self,
         source: &str,
         test_name: &str,
+    ) -> Result<ComparisonResult, String> {
+        self.compare_outputs_with_stdin(source, test_name, "")
+    }
+
+    /// Like `compare_outputs`, feeding the same `stdin` to both programs
+    pub fn compare_outputs_with_stdin(
+        &self,
+        source: &str,
+        test_name: &str,
+        stdin: &str,
     ) -> Result<ComparisonResult, String> {
         // Compile with pycc
         let executable_path = self.compile_with_pycc(source, test_name)?;
 
         // Execute with pycc
-        let pycc_output = self.execute_compiled(&executable_path)?;
+        let pycc = self.run_compiled_stdin(&executable_path, stdin)?;
 
         // Execute with CPython
-        let cpython_output = self.execute_with_cpython(source)?;
+        let cpython = self.run_with_cpython_stdin(source, stdin)?;
 
         Ok(ComparisonResult {
             test_name: test_name.to_string(),
-            pycc_output,
-            cpython_output,
+            pycc_output: pycc.stdout,
+            cpython_output: cpython.stdout,
+            pycc_stderr: pycc.stderr,
+            cpython_stderr: cpython.stderr,
+            pycc_exit_code: pycc.exit_code,
+            cpython_exit_code: cpython.exit_code,
+            timed_out: pycc.timed_out || cpython.timed_out,
             source: source.to_string(),
         })
     }
 
     /// Assert that pycc and CPython outputs match
     pub fn assert_outputs_match(&self, source: &str, test_name: &str) -> Result<(), String> {
+        self.assert_outputs_match_with_stdin(source, test_name, "")
+    }
+
+    /// Like `assert_outputs_match`, feeding the same `stdin` to both programs
+    pub fn assert_outputs_match_with_stdin(
+        &self,
+        source: &str,
+        test_name: &str,
+        stdin: &str,
+    ) -> Result<(), String> {
+        let result = self.compare_outputs_with_stdin(source, test_name, stdin)?;
+
+        if result.timed_out {
+            return Err(result.mismatch_report("Timed out"));
+        }
+        if result.pycc_exit_code != Some(0) {
+            return Err(format!(
+                "Compiled program execution failed: {}",
+                result.pycc_stderr
+            ));
+        }
+        if result.cpython_exit_code != Some(0) {
+            return Err(format!(
+                "CPython execution failed: {}",
+                result.cpython_stderr
+            ));
+        }
+
+        if result.outputs_match() {
+            Ok(())
+        } else {
+            Err(result.mismatch_report("Output mismatch"))
+        }
+    }
+
+    /// Assert that pycc and CPython both fail with the same exit code and
+    /// error, after printing the same output
+    pub fn assert_failures_match(&self, source: &str, test_name: &str) -> Result<(), String> {
         let result = self.compare_outputs(source, test_name)?;
+        check_failures_match(&result)
+    }
+
+    /// Like `assert_failures_match`, feeding the same `stdin` to both programs
+    pub fn assert_failures_match_with_stdin(
+        &self,
+        source: &str,
+        test_name: &str,
+        stdin: &str,
+    ) -> Result<(), String> {
+        let result = self.compare_outputs_with_stdin(source, test_name, stdin)?;
+        check_failures_match(&result)
+    }
 
-        if result.pycc_output.trim() == result.cpython_output.trim() {
+    /// Like `assert_failures_match`, also requiring the error to be `expected_error`
+    pub fn assert_fails_with(
+        &self,
+        source: &str,
+        test_name: &str,
+        expected_error: &str,
+    ) -> Result<(), String> {
+        let result = self.compare_outputs(source, test_name)?;
+        check_failures_match(&result)?;
+        if result.pycc_error() == Some(expected_error) {
             Ok(())
         } else {
-            Err(format!(
-                "Output mismatch for test '{}':\n\
-                 PyCC output:\n{}\n\
-                 CPython output:\n{}\n\
-                 Source code:\n{}",
-                result.test_name, result.pycc_output, result.cpython_output, result.source
-            ))
+            Err(result.mismatch_report(&format!("Expected error '{}'", expected_error)))
         }
     }
+    /// Run `command` to completion with `stdin` as its standard input,
+    /// killing it once it outlives the timeout
+    fn run_program(&self, mut command: Command, stdin: &str) -> std::io::Result<ProgramOutput> {
+        // Its own process group, so a timeout also kills the processes it started
+        let mut child = command
+            .process_group(0)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        // Feed and drain the pipes from other threads so a program that prints
+        // before reading everything cannot fill a pipe and deadlock
+        let mut stdin_pipe = child.stdin.take().expect("stdin is piped");
+        let input = stdin.to_string();
+        let writer = thread::spawn(move || {
+            // The program may exit without reading all of its input
+            let _ = stdin_pipe.write_all(input.as_bytes());
+        });
+        let stdout = drain(child.stdout.take().expect("stdout is piped"));
+        let stderr = drain(child.stderr.take().expect("stderr is piped"));
+
+        let started = Instant::now();
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break Some(status);
+            }
+            if self
+                .timeout
+                .is_some_and(|timeout| started.elapsed() >= timeout)
+            {
+                Command::new("kill")
+                    .args(["-KILL", "--", &format!("-{}", child.id())])
+                    .status()?;
+                child.wait()?;
+                break None;
+            }
+            thread::sleep(Duration::from_millis(5));
+        };
+        let _ = writer.join();
+
+        Ok(ProgramOutput {
+            stdout: String::from_utf8_lossy(&stdout.join().unwrap_or_default()).to_string(),
+            stderr: String::from_utf8_lossy(&stderr.join().unwrap_or_default()).to_string(),
+            exit_code: status.and_then(|status| status.code()),
+            timed_out: status.is_none(),
+        })
+    }
+}
+
+/// Read `pipe` to the end on another thread
+fn drain(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let _ = pipe.read_to_end(&mut bytes);
+        bytes
+    })
+}
+
+fn check_failures_match(result: &ComparisonResult) -> Result<(), String> {
+    if result.cpython_exit_code == Some(0) {
+        return Err(result.mismatch_report("CPython unexpectedly succeeded"));
+    }
+    if result.outputs_match() && result.errors_match() {
+        Ok(())
+    } else {
+        Err(result.mismatch_report("Failure mismatch"))
+    }
+}
+
+/// Everything a finished program produced
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgramOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// `None` when the program was killed by a signal
+    pub exit_code: Option<i32>,
+    /// Whether the program was killed for running too long
+    pub timed_out: bool,
+}
+
+impl ProgramOutput {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
 }
 
 /// Result of comparing pycc and CPython outputs
@@ -154,6 +605,12 @@ pub struct ComparisonResult {
     pub test_name: String,
     pub pycc_output: String,
     pub cpython_output: String,
+    pub pycc_stderr: String,
+    pub cpython_stderr: String,
+    pub pycc_exit_code: Option<i32>,
+    pub cpython_exit_code: Option<i32>,
+    /// Whether either program was killed for running too long
+    pub timed_out: bool,
     pub source: String,
 }
 
@@ -163,12 +620,59 @@ impl ComparisonResult {
         self.pycc_output.trim() == self.cpython_output.trim()
     }
 
+    /// Check if both programs exited the same way with the same error
+    ///
+    /// Only the last stderr line is compared, since CPython prints a traceback
+    /// before the exception.
+    pub fn errors_match(&self) -> bool {
+        self.pycc_exit_code == self.cpython_exit_code && self.pycc_error() == self.cpython_error()
+    }
+
+    /// Last line pycc wrote to stderr, such as `KeyError: 'HOME'`
+    pub fn pycc_error(&self) -> Option<&str> {
+        last_line(&self.pycc_stderr)
+    }
+
+    /// Last line CPython wrote to stderr
+    pub fn cpython_error(&self) -> Option<&str> {
+        last_line(&self.cpython_stderr)
+    }
+
+    /// Describe how the two runs differ
+    pub fn mismatch_report(&self, reason: &str) -> String {
+        format!(
+            "{} for test '{}':\n\
+             PyCC output:\n{}\n\
+             PyCC stderr (exit code {:?}):\n{}\n\
+             CPython output:\n{}\n\
+             CPython stderr (exit code {:?}):\n{}\n\
+             Source code:\n{}",
+            reason,
+            self.test_name,
+            self.pycc_output,
+            self.pycc_exit_code,
+            self.pycc_stderr,
+            self.cpython_output,
+            self.cpython_exit_code,
+            self.cpython_stderr,
+            self.source
+        )
+    }
+
     /// Print detailed comparison information
     pub fn print_comparison(&self) {
         println!("Test: {}", self.test_name);
         println!("Source code:\n{}\n", self.source);
         println!("PyCC output:\n{}", self.pycc_output);
         println!("CPython output:\n{}", self.cpython_output);
+        if !self.errors_match() {
+            println!("PyCC stderr:\n{}", self.pycc_stderr);
+            println!("CPython stderr:\n{}", self.cpython_stderr);
+        }
         println!("Match: {}\n", self.outputs_match());
     }
 }
+
+fn last_line(text: &str) -> Option<&str> {
+    text.lines().rev().find(|line| !line.trim().is_empty())
+}
@@ -0,0 +1,118 @@
+use pycc::doctest::{self, Example};
+use pycc::{Lexer, Parser};
+use std::path::Path;
+
+const SOURCE: &str = r#""""Geometry helpers
+
+>>> area(2, 3)
+6
+"""
+
+
+def area(width, height):
+    """Area of a rectangle
+
+    >>> area(4, 5)
+    20
+    >>> print(area(1, 1), "unit")
+    1 unit
+    >>> describe(2)
+    side 2
+    <BLANKLINE>
+    done
+    """
+    return width * height
+
+
+def describe(side) -> None:
+    """>>> area(side, 1)
+    Traceback (most recent call last):
+      ...
+    NameError: name 'side' is not defined
+    """
+    print("side", side)
+    print()
+    print("done")
+"#;
+
+fn examples() -> Vec<Example> {
+    let program = Parser::new(Lexer::new(SOURCE)).parse_program();
+    doctest::extract(&program, "geometry")
+}
+
+#[test]
+fn test_examples_are_extracted_from_docstrings() {
+    let examples = examples();
+    let summary: Vec<(&str, usize, &str, &str)> = examples
+        .iter()
+        .map(|example| {
+            (
+                example.name.as_str(),
+                example.line,
+                example.source.as_str(),
+                example.want.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("geometry", 3, "area(2, 3)", "6\n"),
+            ("geometry.area", 11, "area(4, 5)", "20\n"),
+            (
+                "geometry.area",
+                13,
+                "print(area(1, 1), \"unit\")",
+                "1 unit\n"
+            ),
+            ("geometry.area", 15, "describe(2)", "side 2\n\ndone\n"),
+            (
+                "geometry.describe",
+                24,
+                "area(side, 1)",
+                "Traceback (most recent call last):\n  ...\nNameError: name 'side' is not defined\n"
+            ),
+        ]
+    );
+
+    let report = examples[1].report(Path::new("geometry.py"), "21\n");
+    assert_eq!(
+        report,
+        "File \"geometry.py\", line 11, in geometry.area\nFailed example:\n    area(4, 5)\nExpected:\n    20\nGot:\n    21\n"
+    );
+}
+
+#[test]
+fn test_examples_run_in_the_interpreter() {
+    let examples = examples();
+    let outputs = doctest::run_interpreted(SOURCE, Path::new("geometry.py"), &examples).unwrap();
+    for (example, got) in examples.iter().zip(&outputs) {
+        assert!(example.matches(got), "{example:?}: {got:?}");
+    }
+    assert!(!examples[0].matches("7\n"));
+}
+
+#[test]
+fn test_compiled_examples_match_the_expected_output() {
+    let program = Parser::new(Lexer::new(SOURCE)).parse_program();
+    let examples = examples();
+    // The last example reads an undefined name, which pycc rejects at compile time
+    let error =
+        doctest::run_compiled_examples(&program, SOURCE, Path::new("geometry.py"), &examples)
+            .unwrap_err();
+    assert!(error.contains("side"), "{error}");
+
+    let examples = &examples[..4];
+    let outputs =
+        doctest::run_compiled_examples(&program, SOURCE, Path::new("geometry.py"), examples)
+            .unwrap();
+    for (example, got) in examples.iter().zip(&outputs) {
+        assert!(example.matches(got), "{example:?}: {got:?}");
+    }
+}
+
+#[test]
+fn test_outputs_are_split_per_example() {
+    let stdout = "module output\n--- pycc doctest example ---\n1\n--- pycc doctest example ---\n";
+    assert_eq!(doctest::outputs(stdout, 3), ["1\n", "", ""]);
+}
@@ -0,0 +1,68 @@
+use inkwell::context::Context;
+use pycc::codegen::{CodeGenerator, CodegenOptions};
+use pycc::explain::{explain, source_file};
+use pycc::{Lexer, Parser};
+use std::path::PathBuf;
+
+const SOURCE: &str = "def add(a, b):\n    return a + b\n\nx = add(2, 3)\nprint(x)\n";
+
+fn debug_ir(source: &str) -> String {
+    let lexer = Lexer::new(source);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    let context = Context::create();
+    let mut codegen = CodeGenerator::new(&context, "test_module");
+    codegen.set_options(CodegenOptions {
+        debug_info: true,
+        ..CodegenOptions::default()
+    });
+    codegen.set_source_file("/src/add.py");
+    codegen.compile(&program).unwrap();
+
+    let temp_file = tempfile::NamedTempFile::new().unwrap();
+    let path = temp_file.path().to_str().unwrap();
+    codegen.write_ir_to_file(path).unwrap();
+    std::fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn test_debug_info_records_statement_lines() {
+    let ir = debug_ir(SOURCE);
+    assert!(ir.contains("!DILocation(line: 2"), "{ir}");
+    assert!(ir.contains("!DILocation(line: 4"), "{ir}");
+    assert!(ir.contains("!DILocation(line: 5"), "{ir}");
+    assert!(ir.contains("name: \"add\""), "{ir}");
+}
+
+#[test]
+fn test_source_file_from_debug_info() {
+    let ir = debug_ir(SOURCE);
+    assert_eq!(source_file(&ir), Some(PathBuf::from("/src/add.py")));
+    assert_eq!(source_file("define i32 @main() {\n}\n"), None);
+}
+
+#[test]
+fn test_explain_interleaves_source_lines() {
+    let explained = explain(&debug_ir(SOURCE), SOURCE);
+
+    let call = explained.find("; 4: x = add(2, 3)").expect(&explained);
+    let print = explained.find("; 5: print(x)").expect(&explained);
+    assert!(call < print);
+    assert!(
+        explained[call..print].contains("call i64 @add"),
+        "{explained}"
+    );
+    assert!(explained.contains("; 2: return a + b"), "{explained}");
+    assert!(!explained.contains("!dbg"), "{explained}");
+    assert!(!explained.contains("!DILocation"), "{explained}");
+}
+
+#[test]
+fn test_explain_strips_attachments() {
+    let ir = "define i64 @f() !dbg !3 {\nentry:\n  ret i64 0, !dbg !7\n}\n\n!7 = !DILocation(line: 1, column: 0, scope: !3)\n";
+    assert_eq!(
+        explain(ir, "f()\n"),
+        "define i64 @f() {\nentry:\n; 1: f()\n  ret i64 0\n}\n"
+    );
+}
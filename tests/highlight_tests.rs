@@ -0,0 +1,131 @@
+use pycc::highlight::{self, Format, TokenClass};
+use pycc::lsp::{Json, Server};
+
+const SOURCE: &str = "def f(x: int) -> int:\n    \"\"\"a\nb\"\"\"\n    return x * 2  # double\n\nprint(f(3), None, \"<&>\")\n";
+
+#[test]
+fn test_tokens_are_classified_and_spanned() {
+    let tokens = highlight::tokenize(SOURCE);
+    let chars: Vec<char> = SOURCE.chars().collect();
+    let classified: Vec<(TokenClass, String)> = tokens
+        .iter()
+        .map(|token| (token.class, chars[token.start..token.end].iter().collect()))
+        .collect();
+    let expect = |class, text: &str| {
+        assert!(
+            classified.contains(&(class, text.to_string())),
+            "missing {class:?} {text:?} in {classified:?}"
+        )
+    };
+    expect(TokenClass::Keyword, "def");
+    expect(TokenClass::Function, "f");
+    expect(TokenClass::Function, "print");
+    expect(TokenClass::Identifier, "x");
+    expect(TokenClass::Operator, "->");
+    expect(TokenClass::String, "\"\"\"a\nb\"\"\"");
+    expect(TokenClass::Comment, "# double");
+    expect(TokenClass::Number, "2");
+    expect(TokenClass::Constant, "None");
+    expect(TokenClass::Punctuation, ",");
+
+    let comment = tokens
+        .iter()
+        .find(|token| token.class == TokenClass::Comment)
+        .unwrap();
+    assert_eq!((comment.line, comment.column), (4, 19));
+}
+
+#[test]
+fn test_html_escapes_and_preserves_text() {
+    let html = highlight::render(SOURCE, Format::Html);
+    assert!(
+        html.starts_with("<pre class=\"pycc-highlight\"><code><span class=\"keyword\">def</span> ")
+    );
+    assert!(html.contains("<span class=\"string\">&quot;&lt;&amp;&gt;&quot;</span>"));
+
+    // Stripping the escape codes gives back the source
+    let ansi = highlight::render(SOURCE, Format::Ansi);
+    let mut plain = String::new();
+    let mut rest = ansi.as_str();
+    while let Some(escape) = rest.find('\x1b') {
+        plain.push_str(&rest[..escape]);
+        rest = &rest[escape + rest[escape..].find('m').unwrap() + 1..];
+    }
+    plain.push_str(rest);
+    assert_eq!(plain, SOURCE);
+}
+
+#[test]
+fn test_json_lists_tokens() {
+    let json = Json::parse(&highlight::render("x = 1", Format::Json)).unwrap();
+    let tokens = json.as_array().unwrap();
+    assert_eq!(tokens.len(), 3);
+    assert_eq!(
+        tokens[1].get("class").and_then(Json::as_str),
+        Some("operator")
+    );
+    assert_eq!(tokens[2].get("text").and_then(Json::as_str), Some("1"));
+    assert_eq!(tokens[2].get("start").and_then(Json::as_u64), Some(4));
+    assert_eq!(tokens[2].get("column").and_then(Json::as_u64), Some(5));
+    assert!(Format::parse("svg").is_err());
+}
+
+#[test]
+fn test_lsp_semantic_tokens() {
+    let uri = "file:///tmp/highlight.py";
+    let mut server = Server::new();
+    server.handle(&Json::object([
+        ("jsonrpc", "2.0".into()),
+        ("method", "textDocument/didOpen".into()),
+        (
+            "params",
+            Json::object([(
+                "textDocument",
+                Json::object([("uri", uri.into()), ("text", "x = 1\n  # hi\n".into())]),
+            )]),
+        ),
+    ]));
+    let replies = server.handle(&Json::object([
+        ("jsonrpc", "2.0".into()),
+        ("id", 1.into()),
+        ("method", "textDocument/semanticTokens/full".into()),
+        (
+            "params",
+            Json::object([("textDocument", Json::object([("uri", uri.into())]))]),
+        ),
+    ]));
+    let data: Vec<u64> = replies[0]
+        .get("result")
+        .and_then(|result| result.get("data"))
+        .and_then(Json::as_array)
+        .unwrap()
+        .iter()
+        .filter_map(Json::as_u64)
+        .collect();
+    let index = |class| TokenClass::ALL.iter().position(|c| *c == class).unwrap() as u64;
+    assert_eq!(
+        data,
+        vec![
+            0,
+            0,
+            1,
+            index(TokenClass::Identifier),
+            0,
+            0,
+            2,
+            1,
+            index(TokenClass::Operator),
+            0,
+            0,
+            2,
+            1,
+            index(TokenClass::Number),
+            0,
+            1,
+            2,
+            4,
+            index(TokenClass::Comment),
+            0,
+        ]
+    );
+}
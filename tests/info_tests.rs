@@ -0,0 +1,59 @@
+use pycc::info::compiler_info;
+use pycc::lsp::Json;
+use std::process::Command;
+
+#[test]
+fn test_compiler_info_describes_the_build() {
+    let info = compiler_info();
+    assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+    assert!(
+        info.llvm_version.starts_with("21."),
+        "{}",
+        info.llvm_version
+    );
+    assert!(!info.default_target.is_empty());
+    assert!(
+        info.backends
+            .iter()
+            .any(|name| name == "x86-64" || name == "aarch64"),
+        "{:?}",
+        info.backends
+    );
+    assert!(info.features.iter().any(|feature| feature.name == "lto"));
+    assert!(info.supported.contains(&"f-strings"));
+
+    let report = info.render();
+    assert!(
+        report.starts_with(&format!("pycc {}\n", info.version)),
+        "{report}"
+    );
+    assert!(report.contains(&format!("LLVM version: {}\n", info.llvm_version)));
+    assert!(report.contains(&format!("Default target: {}\n", info.default_target)));
+}
+
+#[test]
+fn test_info_subcommand_prints_json() {
+    let output = Command::new(env!("CARGO_BIN_EXE_pycc"))
+        .args(["info", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let json = Json::parse(&String::from_utf8_lossy(&output.stdout)).unwrap();
+    let info = compiler_info();
+    assert_eq!(
+        json.get("version").and_then(Json::as_str),
+        Some(info.version.as_str())
+    );
+    assert_eq!(
+        json.get("default_target").and_then(Json::as_str),
+        Some(info.default_target.as_str())
+    );
+    let features = json.get("features").and_then(Json::as_array).unwrap();
+    assert_eq!(features.len(), info.features.len());
+    assert!(
+        features
+            .iter()
+            .all(|feature| feature.get("available").is_some())
+    );
+}
@@ -68,7 +68,7 @@ fn test_full_pipeline_arithmetic() {
 
 #[test]
 fn test_full_pipeline_function_definition() {
-    let source = "def add(x, y): return x + y; result = add(3, 4); print(result);";
+    let source = "def add(x, y): return x + y\nresult = add(3, 4); print(result);";
 
     // Test parser
     let lexer = Lexer::new(source);
@@ -76,6 +76,30 @@ fn test_identifiers_and_keywords() {
     }
 }
 
+#[test]
+fn test_non_ascii_identifiers() {
+    // XID_Continue admits combining marks, but they cannot start a name
+    let input = "café 变量 _naïve x\u{301} \u{301}y x²";
+    let mut lexer = Lexer::new(input);
+
+    let expected_tokens = vec![
+        Token::Identifier("café".to_string()),
+        Token::Identifier("变量".to_string()),
+        Token::Identifier("_naïve".to_string()),
+        Token::Identifier("x\u{301}".to_string()),
+        Token::Error("invalid character '\u{301}' (U+0301)".to_string()),
+        Token::Identifier("y".to_string()),
+        Token::Identifier("x".to_string()),
+        Token::Error("invalid character '²' (U+00B2)".to_string()),
+        Token::Eof,
+    ];
+
+    for expected in expected_tokens {
+        let token = lexer.next_token();
+        assert_eq!(token, expected, "Expected {expected:?}, got {token:?}");
+    }
+}
+
 #[test]
 fn test_numbers() {
     let input = "42 1.23456789012345 0.5 100";
@@ -95,6 +119,21 @@ fn test_numbers() {
     }
 }
 
+#[test]
+fn test_integer_literal_too_large() {
+    let mut lexer = Lexer::new("x = 9223372036854775807 + 9223372036854775808");
+    for _ in 0..2 {
+        lexer.next_token();
+    }
+    assert_eq!(lexer.next_token(), Token::Integer(i64::MAX));
+    assert_eq!(lexer.next_token(), Token::Plus);
+    assert_eq!(
+        lexer.next_token(),
+        Token::Error("integer literal too large".to_string())
+    );
+    assert_eq!(lexer.span(), 26..45);
+}
+
 #[test]
 fn test_strings() {
     let input = "\"hello\" 'world' \"123\"";
@@ -231,3 +270,293 @@ fn test_backslash_escape() {
         assert_eq!(token, expected, "Expected {expected:?}, got {token:?}");
     }
 }
+
+#[test]
+fn test_newline_and_indentation_tokens() {
+    let input = "def f(x):\n    y = x\n    return y\nprint(f(1))\n";
+    let mut lexer = Lexer::new(input);
+
+    let expected_tokens = vec![
+        Token::Def,
+        Token::Identifier("f".to_string()),
+        Token::LeftParen,
+        Token::Identifier("x".to_string()),
+        Token::RightParen,
+        Token::Colon,
+        Token::Newline,
+        Token::Indent,
+        Token::Identifier("y".to_string()),
+        Token::Assign,
+        Token::Identifier("x".to_string()),
+        Token::Newline,
+        Token::Return,
+        Token::Identifier("y".to_string()),
+        Token::Newline,
+        Token::Dedent,
+        Token::Identifier("print".to_string()),
+        Token::LeftParen,
+        Token::Identifier("f".to_string()),
+        Token::LeftParen,
+        Token::Integer(1),
+        Token::RightParen,
+        Token::RightParen,
+        Token::Newline,
+        Token::Eof,
+    ];
+
+    for expected in expected_tokens {
+        let token = lexer.next_token();
+        assert_eq!(token, expected, "Expected {expected:?}, got {token:?}");
+    }
+}
+
+#[test]
+fn test_dedent_at_end_of_input() {
+    let input = "def f():\n    return 1";
+    let mut lexer = Lexer::new(input);
+
+    let tokens: Vec<_> = std::iter::from_fn(|| {
+        let token = lexer.next_token();
+        if token != Token::Eof {
+            Some(token)
+        } else {
+            None
+        }
+    })
+    .collect();
+
+    assert_eq!(tokens.last(), Some(&Token::Dedent));
+    assert_eq!(tokens.iter().filter(|t| **t == Token::Indent).count(), 1);
+}
+
+#[test]
+fn test_unindent_to_an_unknown_level_is_an_error() {
+    let input = "if x:\n        a\n    b\n    c\n";
+    let mut lexer = Lexer::new(input);
+
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token();
+        if let Token::Error(_) = token {
+            assert_eq!(lexer.line(), 3);
+            assert_eq!(lexer.span(), 16..20);
+        }
+        if token == Token::Eof {
+            break;
+        }
+        tokens.push(token);
+    }
+
+    // The misaligned lines form one level, closed without another DEDENT
+    assert_eq!(
+        tokens,
+        [
+            Token::If,
+            Token::Identifier("x".to_string()),
+            Token::Colon,
+            Token::Newline,
+            Token::Indent,
+            Token::Identifier("a".to_string()),
+            Token::Newline,
+            Token::Dedent,
+            Token::Error("unindent does not match any outer indentation level".to_string()),
+            Token::Identifier("b".to_string()),
+            Token::Newline,
+            Token::Identifier("c".to_string()),
+            Token::Newline,
+        ]
+    );
+}
+
+#[test]
+fn test_implicit_line_joining_inside_brackets() {
+    let input = "x = (1 +\n     2)\ny = [3,\n  4]\n";
+    let mut lexer = Lexer::new(input);
+
+    let expected_tokens = vec![
+        Token::Identifier("x".to_string()),
+        Token::Assign,
+        Token::LeftParen,
+        Token::Integer(1),
+        Token::Plus,
+        Token::Integer(2),
+        Token::RightParen,
+        Token::Newline,
+        Token::Identifier("y".to_string()),
+        Token::Assign,
+        Token::LeftBracket,
+        Token::Integer(3),
+        Token::Comma,
+        Token::Integer(4),
+        Token::RightBracket,
+        Token::Newline,
+        Token::Eof,
+    ];
+
+    for expected in expected_tokens {
+        let token = lexer.next_token();
+        assert_eq!(token, expected, "Expected {expected:?}, got {token:?}");
+    }
+}
+
+#[test]
+fn test_backslash_line_continuation() {
+    let input = "x = 1 + \\\n    2\nprint(x)";
+    let mut lexer = Lexer::new(input);
+
+    let expected_tokens = vec![
+        Token::Identifier("x".to_string()),
+        Token::Assign,
+        Token::Integer(1),
+        Token::Plus,
+        Token::Integer(2),
+        Token::Newline,
+        Token::Identifier("print".to_string()),
+        Token::LeftParen,
+        Token::Identifier("x".to_string()),
+        Token::RightParen,
+        Token::Eof,
+    ];
+
+    for expected in expected_tokens {
+        let token = lexer.next_token();
+        assert_eq!(token, expected, "Expected {expected:?}, got {token:?}");
+    }
+}
+
+#[test]
+fn test_triple_quoted_strings() {
+    let input = "\"\"\"first\nsecond \"quoted\" end\"\"\" '''single'''";
+    let mut lexer = Lexer::new(input);
+
+    let expected_tokens = vec![
+        Token::String("first\nsecond \"quoted\" end".to_string()),
+        Token::String("single".to_string()),
+        Token::Eof,
+    ];
+
+    for expected in expected_tokens {
+        let token = lexer.next_token();
+        assert_eq!(token, expected, "Expected {expected:?}, got {token:?}");
+    }
+}
+
+#[test]
+fn test_annotation_tokens() {
+    let input = "-> a.b | -x";
+    let mut lexer = Lexer::new(input);
+
+    let expected_tokens = vec![
+        Token::Arrow,
+        Token::Identifier("a".to_string()),
+        Token::Dot,
+        Token::Identifier("b".to_string()),
+        Token::Pipe,
+        Token::Minus,
+        Token::Identifier("x".to_string()),
+        Token::Eof,
+    ];
+
+    for expected in expected_tokens {
+        let token = lexer.next_token();
+        assert_eq!(token, expected, "Expected {expected:?}, got {token:?}");
+    }
+}
+
+#[test]
+fn test_token_lines() {
+    let input = "x = 1\n\n# comment\ns = \"\"\"a\nb\"\"\"\nprint(s)\n";
+    let mut lexer = Lexer::new(input);
+
+    let mut lines = Vec::new();
+    loop {
+        let token = lexer.next_token();
+        if token == Token::Eof {
+            break;
+        }
+        if let Token::Identifier(name) = token {
+            lines.push((name, lexer.line()));
+        }
+    }
+    assert_eq!(
+        lines,
+        vec![
+            ("x".to_string(), 1),
+            ("s".to_string(), 4),
+            ("print".to_string(), 6),
+            ("s".to_string(), 6),
+        ]
+    );
+}
+
+#[test]
+fn test_import_keyword() {
+    let mut lexer = Lexer::new("import os.path");
+    assert_eq!(lexer.next_token(), Token::Import);
+    assert_eq!(lexer.next_token(), Token::Identifier("os".to_string()));
+    assert_eq!(lexer.next_token(), Token::Dot);
+    assert_eq!(lexer.next_token(), Token::Identifier("path".to_string()));
+}
+
+#[test]
+fn test_async_await_keywords() {
+    let mut lexer = Lexer::new("async def f(): await g()");
+    assert_eq!(lexer.next_token(), Token::Async);
+    assert_eq!(lexer.next_token(), Token::Def);
+    assert_eq!(lexer.next_token(), Token::Identifier("f".to_string()));
+    assert_eq!(lexer.next_token(), Token::LeftParen);
+    assert_eq!(lexer.next_token(), Token::RightParen);
+    assert_eq!(lexer.next_token(), Token::Colon);
+    assert_eq!(lexer.next_token(), Token::Await);
+    assert_eq!(lexer.next_token(), Token::Identifier("g".to_string()));
+}
+
+#[test]
+fn test_unterminated_strings_stop_at_end_of_line() {
+    let mut lexer = Lexer::new("x = \"abc\nprint(f'{x}\ny = 1\n");
+    assert_eq!(lexer.next_token(), Token::Identifier("x".to_string()));
+    assert_eq!(lexer.next_token(), Token::Assign);
+    assert_eq!(
+        lexer.next_token(),
+        Token::Error("unterminated string literal (detected at line 1)".to_string())
+    );
+    assert_eq!(lexer.span(), 4..8);
+    assert_eq!(lexer.next_token(), Token::Newline);
+    assert_eq!(lexer.next_token(), Token::Identifier("print".to_string()));
+    assert_eq!(lexer.next_token(), Token::LeftParen);
+    assert_eq!(
+        lexer.next_token(),
+        Token::Error("unterminated f-string literal (detected at line 2)".to_string())
+    );
+    assert_eq!(lexer.line(), 2);
+    // Lexing resumes on the next line
+    assert_eq!(lexer.next_token(), Token::Identifier("y".to_string()));
+}
+
+#[test]
+fn test_unterminated_triple_quoted_string() {
+    let mut lexer = Lexer::new("s = '''one\ntwo\n");
+    lexer.next_token();
+    lexer.next_token();
+    assert_eq!(
+        lexer.next_token(),
+        Token::Error("unterminated triple-quoted string literal (detected at line 3)".to_string())
+    );
+    assert_eq!(lexer.line(), 1);
+    assert_eq!(lexer.span(), 4..15);
+    assert_eq!(lexer.next_token(), Token::Eof);
+}
+
+#[test]
+fn test_invalid_character() {
+    let mut lexer = Lexer::new("a = 1 $ 2");
+    for _ in 0..3 {
+        lexer.next_token();
+    }
+    assert_eq!(
+        lexer.next_token(),
+        Token::Error("invalid character '$' (U+0024)".to_string())
+    );
+    assert_eq!(lexer.span(), 6..7);
+    assert_eq!(lexer.next_token(), Token::Integer(2));
+}
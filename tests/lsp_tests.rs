@@ -0,0 +1,273 @@
+use pycc::lsp::{self, Json, Server};
+use std::io::Cursor;
+
+const URI: &str = "file:///tmp/example.py";
+
+const SOURCE: &str = r#"def area(width: int, height: float) -> float:
+    """Area of a rectangle"""
+    scale = 2
+    return width * height
+
+size: int = 3
+label: str = size
+print(area(size, 1.5))
+"#;
+
+fn request(id: usize, method: &str, params: Json) -> Json {
+    Json::object([
+        ("jsonrpc", "2.0".into()),
+        ("id", id.into()),
+        ("method", method.into()),
+        ("params", params),
+    ])
+}
+
+fn position_params(line: usize, character: usize) -> Json {
+    Json::object([
+        ("textDocument", Json::object([("uri", URI.into())])),
+        (
+            "position",
+            Json::object([("line", line.into()), ("character", character.into())]),
+        ),
+    ])
+}
+
+fn open_server() -> (Server, Vec<Json>) {
+    open_document(SOURCE)
+}
+
+fn open_document(text: &str) -> (Server, Vec<Json>) {
+    let mut server = Server::new();
+    let opened = server.handle(&Json::object([
+        ("jsonrpc", "2.0".into()),
+        ("method", "textDocument/didOpen".into()),
+        (
+            "params",
+            Json::object([(
+                "textDocument",
+                Json::object([
+                    ("uri", URI.into()),
+                    ("languageId", "python".into()),
+                    ("version", 1.into()),
+                    ("text", text.into()),
+                ]),
+            )]),
+        ),
+    ]));
+    (server, opened)
+}
+
+fn result(replies: Vec<Json>) -> Json {
+    assert_eq!(replies.len(), 1, "{replies:?}");
+    replies[0].get("result").cloned().unwrap()
+}
+
+#[test]
+fn test_json_round_trip() {
+    let text = r#"{"a":[1,2.5,-3],"b":{"c":null,"d":true},"e":"q\"uote\né😀"}"#;
+    let value = Json::parse(text).unwrap();
+    assert_eq!(value.get("a").unwrap().as_array().unwrap().len(), 3);
+    assert_eq!(value.get("e").and_then(Json::as_str), Some("q\"uote\né😀"));
+    assert_eq!(Json::parse(&value.to_string()).unwrap(), value);
+    assert!(Json::parse("{\"a\":}").is_err());
+    assert!(Json::parse("[1] 2").is_err());
+}
+
+#[test]
+fn test_initialize_advertises_capabilities() {
+    let mut server = Server::new();
+    let capabilities = result(server.handle(&request(1, "initialize", Json::object([]))))
+        .get("capabilities")
+        .cloned()
+        .unwrap();
+    assert_eq!(capabilities.get("hoverProvider"), Some(&Json::Bool(true)));
+    assert_eq!(
+        capabilities.get("definitionProvider"),
+        Some(&Json::Bool(true))
+    );
+    assert_eq!(
+        capabilities.get("documentSymbolProvider"),
+        Some(&Json::Bool(true))
+    );
+
+    let unknown = server.handle(&request(2, "textDocument/rename", Json::object([])));
+    assert!(unknown[0].get("error").is_some());
+}
+
+#[test]
+fn test_diagnostics_are_published_on_open() {
+    let (_, opened) = open_server();
+    assert_eq!(opened.len(), 1);
+    let params = opened[0].get("params").unwrap();
+    assert_eq!(params.get("uri").and_then(Json::as_str), Some(URI));
+
+    let diagnostics = params.get("diagnostics").unwrap().as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    let diagnostic = &diagnostics[0];
+    assert_eq!(
+        diagnostic.get("message").and_then(Json::as_str),
+        Some("cannot assign int to 'label' declared as str")
+    );
+    let start = diagnostic.get("range").unwrap().get("start").unwrap();
+    assert_eq!(start.get("line").and_then(Json::as_u64), Some(6));
+}
+
+#[test]
+fn test_syntax_errors_are_published_with_their_span() {
+    let mut server = Server::new();
+    let opened = server.handle(&Json::object([
+        ("jsonrpc", "2.0".into()),
+        ("method", "textDocument/didOpen".into()),
+        (
+            "params",
+            Json::object([(
+                "textDocument",
+                Json::object([
+                    ("uri", URI.into()),
+                    ("languageId", "python".into()),
+                    ("version", 1.into()),
+                    ("text", "x: int = 1\nlabel: int = 'oops\n".into()),
+                ]),
+            )]),
+        ),
+    ]));
+
+    let params = opened[0].get("params").unwrap();
+    let diagnostics = params.get("diagnostics").unwrap().as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    assert_eq!(
+        diagnostics[0].get("message").and_then(Json::as_str),
+        Some("unterminated string literal (detected at line 2)")
+    );
+    let range = diagnostics[0].get("range").unwrap();
+    let position = |name: &str| {
+        let position = range.get(name).unwrap();
+        (
+            position.get("line").and_then(Json::as_u64),
+            position.get("character").and_then(Json::as_u64),
+        )
+    };
+    assert_eq!(position("start"), (Some(1), Some(13)));
+    assert_eq!(position("end"), (Some(1), Some(18)));
+}
+
+#[test]
+fn test_diagnostic_columns_count_utf16_code_units() {
+    let (_, opened) = open_document("größe: int = 3\nname: str = größe  # 😀 note\n");
+    let params = opened[0].get("params").unwrap();
+    let diagnostics = params.get("diagnostics").unwrap().as_array().unwrap();
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    let range = diagnostics[0].get("range").unwrap();
+    let character = |name: &str| range.get(name)?.get("character")?.as_u64();
+    assert_eq!(character("start"), Some(0));
+    // 27 characters and 32 bytes, but the emoji takes two UTF-16 code units
+    assert_eq!(character("end"), Some(28));
+}
+
+#[test]
+fn test_definition_resolves_functions_and_locals() {
+    let (mut server, _) = open_server();
+
+    // `area` in the call on the last line
+    let location = result(server.handle(&request(
+        1,
+        "textDocument/definition",
+        position_params(7, 7),
+    )));
+    let start = location.get("range").unwrap().get("start").unwrap();
+    assert_eq!(start.get("line").and_then(Json::as_u64), Some(0));
+    assert_eq!(start.get("character").and_then(Json::as_u64), Some(4));
+
+    // `width` inside the body is the parameter
+    let location = result(server.handle(&request(
+        2,
+        "textDocument/definition",
+        position_params(3, 12),
+    )));
+    let start = location.get("range").unwrap().get("start").unwrap();
+    assert_eq!(start.get("line").and_then(Json::as_u64), Some(0));
+    assert_eq!(start.get("character").and_then(Json::as_u64), Some(9));
+
+    // Builtins have no definition in the document
+    let location = result(server.handle(&request(
+        3,
+        "textDocument/definition",
+        position_params(7, 2),
+    )));
+    assert_eq!(location, Json::Null);
+}
+
+#[test]
+fn test_hover_shows_signatures_and_inferred_types() {
+    let (mut server, _) = open_server();
+
+    let hover = result(server.handle(&request(1, "textDocument/hover", position_params(7, 8))));
+    let value = hover.get("contents").unwrap().get("value").unwrap();
+    assert_eq!(
+        value.as_str(),
+        Some("```python\ndef area(width: int, height: float) -> float\n```\n\nArea of a rectangle")
+    );
+
+    // `scale` is never annotated, so its type is inferred
+    let hover = result(server.handle(&request(2, "textDocument/hover", position_params(2, 5))));
+    let value = hover.get("contents").unwrap().get("value").unwrap();
+    assert_eq!(
+        value.as_str(),
+        Some("```python\n(variable) scale: int\n```")
+    );
+
+    let hover = result(server.handle(&request(3, "textDocument/hover", position_params(3, 20))));
+    let value = hover.get("contents").unwrap().get("value").unwrap();
+    assert_eq!(
+        value.as_str(),
+        Some("```python\n(parameter) height: float\n```")
+    );
+}
+
+#[test]
+fn test_document_symbols_outline_the_module() {
+    let (mut server, _) = open_server();
+    let params = Json::object([("textDocument", Json::object([("uri", URI.into())]))]);
+    let symbols = result(server.handle(&request(1, "textDocument/documentSymbol", params)));
+    let symbols = symbols.as_array().unwrap();
+
+    let names: Vec<&str> = symbols
+        .iter()
+        .filter_map(|symbol| symbol.get("name").and_then(Json::as_str))
+        .collect();
+    assert_eq!(names, vec!["area", "size", "label"]);
+
+    let area = &symbols[0];
+    assert_eq!(area.get("kind").and_then(Json::as_u64), Some(12));
+    let end = area.get("range").unwrap().get("end").unwrap();
+    assert_eq!(end.get("line").and_then(Json::as_u64), Some(3));
+    let children = area.get("children").unwrap().as_array().unwrap();
+    assert_eq!(children.len(), 1);
+    assert_eq!(
+        children[0].get("name").and_then(Json::as_str),
+        Some("scale")
+    );
+}
+
+#[test]
+fn test_run_speaks_content_length_framing() {
+    let mut input = Vec::new();
+    for message in [
+        request(1, "initialize", Json::object([])),
+        request(2, "shutdown", Json::Null),
+        Json::object([("jsonrpc", "2.0".into()), ("method", "exit".into())]),
+    ] {
+        lsp::write_message(&mut input, &message).unwrap();
+    }
+
+    let mut output = Vec::new();
+    let shutdown_requested = lsp::run(&mut Cursor::new(input), &mut output).unwrap();
+    assert!(shutdown_requested);
+
+    let mut replies = Cursor::new(output);
+    let first = lsp::read_message(&mut replies).unwrap().unwrap();
+    assert_eq!(first.get("id").and_then(Json::as_u64), Some(1));
+    let second = lsp::read_message(&mut replies).unwrap().unwrap();
+    assert_eq!(second.get("result"), Some(&Json::Null));
+    assert!(lsp::read_message(&mut replies).unwrap().is_none());
+}
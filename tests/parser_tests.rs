@@ -1,6 +1,7 @@
 use pycc::ast::*;
 use pycc::lexer::Lexer;
-use pycc::parser::Parser;
+use pycc::parser::parser::DEFAULT_MAX_DEPTH;
+use pycc::parser::{ParseError, Parser};
 
 #[test]
 fn test_parse_integer_literal() {
@@ -54,15 +55,16 @@ fn test_parse_float_literal() {
 
 #[test]
 fn test_parse_string_literal() {
-    let input = "\"hello\";";
+    // A leading string would be the module docstring, so put one statement first
+    let input = "1;\n\"hello\";";
     let lexer = Lexer::new(input);
     let mut parser = Parser::new(lexer);
     let program = parser.parse_program();
 
     match program {
         Node::Program(prog) => {
-            assert_eq!(prog.statements.len(), 1);
-            match &prog.statements[0] {
+            assert_eq!(prog.statements.len(), 2);
+            match &prog.statements[1] {
                 Node::ExpressionStatement(expr_stmt) => match &*expr_stmt.expression {
                     Node::Literal(literal) => match &literal.value {
                         LiteralValue::String(value) => assert_eq!(value, "hello"),
@@ -207,7 +209,7 @@ fn test_parse_assignment() {
             assert_eq!(prog.statements.len(), 1);
             match &prog.statements[0] {
                 Node::Assignment(assignment) => {
-                    assert_eq!(assignment.name, "x");
+                    assert_eq!(assignment.names().collect::<Vec<_>>(), ["x"]);
                     match &*assignment.value {
                         Node::Literal(literal) => match &literal.value {
                             LiteralValue::Integer(value) => assert_eq!(*value, 42),
@@ -281,6 +283,27 @@ fn test_parse_function_definition() {
     }
 }
 
+#[test]
+fn test_parse_non_ascii_names() {
+    let input = "def 面积(宽, 高):\n    return 宽 * 高\n\ncafé = 面积(3, 4)\n";
+    let mut parser = Parser::new(Lexer::new(input));
+    let program = parser.parse_program();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+
+    let Node::Program(prog) = program else {
+        panic!("Expected program node");
+    };
+    let Node::Function(function) = &prog.statements[0] else {
+        panic!("Expected function definition");
+    };
+    assert_eq!(function.name, "面积");
+    assert_eq!(function.parameters, ["宽", "高"]);
+    let Node::Assignment(assignment) = &prog.statements[1] else {
+        panic!("Expected assignment");
+    };
+    assert_eq!(assignment.names(), ["café"]);
+}
+
 #[test]
 fn test_parse_function_call() {
     let input = "print(\"Hello, World!\");";
@@ -326,7 +349,7 @@ fn test_parse_complex_expression() {
             assert_eq!(prog.statements.len(), 1);
             match &prog.statements[0] {
                 Node::Assignment(assignment) => {
-                    assert_eq!(assignment.name, "x");
+                    assert_eq!(assignment.names().collect::<Vec<_>>(), ["x"]);
 
                     // Check the expression: 5 + 3 * 2
                     match &*assignment.value {
@@ -378,3 +401,1095 @@ fn test_parse_complex_expression() {
         _ => panic!("Expected program node"),
     }
 }
+
+#[test]
+fn test_parse_multiline_call_arguments() {
+    let input = "print(\n    1 +\n    2\n)\nx = 3\n";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    match program {
+        Node::Program(prog) => {
+            assert_eq!(prog.statements.len(), 2);
+            match &prog.statements[0] {
+                Node::ExpressionStatement(expr_stmt) => match &*expr_stmt.expression {
+                    Node::Call(call) => {
+                        assert_eq!(call.callee, "print");
+                        assert_eq!(call.arguments.len(), 1);
+                        assert!(matches!(call.arguments[0], Node::Binary(_)));
+                    }
+                    _ => panic!("Expected function call"),
+                },
+                _ => panic!("Expected expression statement"),
+            }
+            assert!(matches!(prog.statements[1], Node::Assignment(_)));
+        }
+        _ => panic!("Expected program node"),
+    }
+}
+
+#[test]
+fn test_parse_comments_and_trailing_commas_inside_groupings() {
+    let input = "def f(\n    a,  # first\n    b: int,\n):\n    return (\n        # sum\n        a + b\n    )\n\nprint(\n    f(1, 2,),  # call\n    env[\n        \"HOME\"\n    ],\n)\nx = 3\n";
+    let mut parser = Parser::new(Lexer::new(input));
+    let Node::Program(program) = parser.parse_program() else {
+        panic!("Expected program node");
+    };
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    assert_eq!(program.statements.len(), 3, "{:?}", program.statements);
+
+    let Node::Function(function) = &program.statements[0] else {
+        panic!("Expected function, got {:?}", program.statements[0]);
+    };
+    assert_eq!(function.parameters, ["a", "b"]);
+    assert!(matches!(&*function.body, Node::Return(_)));
+
+    let Node::ExpressionStatement(expr_stmt) = &program.statements[1] else {
+        panic!(
+            "Expected expression statement, got {:?}",
+            program.statements[1]
+        );
+    };
+    let Node::Call(call) = &*expr_stmt.expression else {
+        panic!("Expected call, got {:?}", expr_stmt.expression);
+    };
+    assert_eq!(call.arguments.len(), 2);
+    assert!(matches!(&call.arguments[0], Node::Call(inner) if inner.arguments.len() == 2));
+    assert!(matches!(&call.arguments[1], Node::Subscript(_)));
+    assert!(matches!(program.statements[2], Node::Assignment(_)));
+}
+
+#[test]
+fn test_parse_rejects_comma_without_argument() {
+    let mut parser = Parser::new(Lexer::new("print(,)\nprint(1,,)\n"));
+    let Node::Program(program) = parser.parse_program() else {
+        panic!("Expected program node");
+    };
+    assert!(
+        !program
+            .statements
+            .iter()
+            .any(|statement| matches!(statement, Node::ExpressionStatement(expr_stmt) if matches!(*expr_stmt.expression, Node::Call(_)))),
+        "{:?}",
+        program.statements
+    );
+}
+
+#[test]
+fn test_parse_container_literals() {
+    let single = |source: &str| -> Node {
+        let mut parser = Parser::new(Lexer::new(source));
+        let Node::Program(program) = parser.parse_program() else {
+            panic!("Expected program node");
+        };
+        assert!(
+            parser.errors().is_empty(),
+            "{source}: {:?}",
+            parser.errors()
+        );
+        match program.statements.as_slice() {
+            [Node::ExpressionStatement(expr_stmt)] => *expr_stmt.expression.clone(),
+            other => panic!("{source}: expected one expression, got {other:?}"),
+        }
+    };
+    let int = |value| {
+        Node::Literal(Literal {
+            value: LiteralValue::Integer(value),
+        })
+    };
+
+    assert_eq!(single("()"), Node::Tuple(Tuple { elements: vec![] }));
+    assert_eq!(single("(1)"), int(1));
+    assert_eq!(
+        single("(1,)"),
+        Node::Tuple(Tuple {
+            elements: vec![int(1)]
+        })
+    );
+    assert_eq!(
+        single("(1, 2,)"),
+        Node::Tuple(Tuple {
+            elements: vec![int(1), int(2)]
+        })
+    );
+    assert_eq!(single("[]"), Node::List(List { elements: vec![] }));
+    assert_eq!(
+        single("[1, 2,]"),
+        Node::List(List {
+            elements: vec![int(1), int(2)]
+        })
+    );
+    assert_eq!(single("{}"), Node::Dict(Dict { entries: vec![] }));
+    assert_eq!(
+        single("{1: 2, 3: 4,}"),
+        Node::Dict(Dict {
+            entries: vec![(int(1), int(2)), (int(3), int(4))]
+        })
+    );
+    assert_eq!(
+        single("{1}"),
+        Node::Set(Set {
+            elements: vec![int(1)]
+        })
+    );
+    assert_eq!(
+        single("{1, 2,}"),
+        Node::Set(Set {
+            elements: vec![int(1), int(2)]
+        })
+    );
+    assert_eq!(
+        single("f(1, 2,)"),
+        Node::Call(Call {
+            callee: "f".to_string(),
+            arguments: vec![int(1), int(2)],
+            keywords: vec![],
+        })
+    );
+    assert_eq!(
+        single("f()"),
+        Node::Call(Call {
+            callee: "f".to_string(),
+            arguments: vec![],
+            keywords: vec![],
+        })
+    );
+}
+
+#[test]
+fn test_parse_rejects_malformed_containers() {
+    for source in [
+        "(,)",
+        "(1,,)",
+        "[,]",
+        "[1,,]",
+        "{1: 2, 3}",
+        "{1, 2: 3}",
+        "{,}",
+    ] {
+        let mut parser = Parser::new(Lexer::new(source));
+        let Node::Program(program) = parser.parse_program() else {
+            panic!("Expected program node");
+        };
+        assert!(
+            !program.statements.iter().any(|statement| matches!(
+                statement,
+                Node::ExpressionStatement(expr_stmt) if matches!(
+                    *expr_stmt.expression,
+                    Node::Tuple(_) | Node::List(_) | Node::Dict(_) | Node::Set(_)
+                )
+            )),
+            "{source}: {:?}",
+            program.statements
+        );
+    }
+}
+
+#[test]
+fn test_parse_newline_ends_expression() {
+    // Without statement boundaries this used to parse as `x = 1 - 2`
+    let input = "x = 1\n-2\n";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    match program {
+        Node::Program(prog) => {
+            assert_eq!(prog.statements.len(), 2);
+            match &prog.statements[0] {
+                Node::Assignment(assignment) => {
+                    assert!(matches!(*assignment.value, Node::Literal(_)));
+                }
+                _ => panic!("Expected assignment"),
+            }
+        }
+        _ => panic!("Expected program node"),
+    }
+}
+
+#[test]
+fn test_parse_function_with_block_body() {
+    let input = "def f(a):\n    b = a + 1\n    return b\n\nresult = f(1)\n";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    match program {
+        Node::Program(prog) => {
+            assert_eq!(prog.statements.len(), 2);
+            match &prog.statements[0] {
+                Node::Function(function) => match &*function.body {
+                    Node::Block(block) => {
+                        assert_eq!(block.statements.len(), 2);
+                        assert!(matches!(block.statements[0], Node::Assignment(_)));
+                        assert!(matches!(block.statements[1], Node::Return(_)));
+                    }
+                    _ => panic!("Expected block body"),
+                },
+                _ => panic!("Expected function definition"),
+            }
+            assert!(matches!(prog.statements[1], Node::Assignment(_)));
+        }
+        _ => panic!("Expected program node"),
+    }
+}
+
+#[test]
+fn test_parse_semicolon_separated_statements() {
+    let input = "x = 1; y = 2; print(x + y)\n";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    match program {
+        Node::Program(prog) => {
+            assert_eq!(prog.statements.len(), 3);
+            assert!(matches!(prog.statements[0], Node::Assignment(_)));
+            assert!(matches!(prog.statements[1], Node::Assignment(_)));
+            assert!(matches!(prog.statements[2], Node::ExpressionStatement(_)));
+        }
+        _ => panic!("Expected program node"),
+    }
+}
+
+#[test]
+fn test_simple_statements_need_a_separator() {
+    for (source, span) in [("x = 1 y = 2\n", 6..7), ("print(1) print(2)\n", 9..14)] {
+        let mut parser = Parser::new(Lexer::new(source));
+        let Node::Program(program) = parser.parse_program() else {
+            panic!("Expected program node");
+        };
+        assert_eq!(
+            parser.errors(),
+            [ParseError {
+                line: 1,
+                span,
+                message: "invalid syntax".to_string(),
+            }],
+            "{source}"
+        );
+        assert!(program.statements.is_empty(), "{:?}", program.statements);
+    }
+}
+
+#[test]
+fn test_unexpected_indent_is_a_parse_error() {
+    let input = "x = 1\n    y = 2\ndef f():\n    a = 1\n        b = 2\n    return a\n";
+    let mut parser = Parser::new(Lexer::new(input));
+    let Node::Program(program) = parser.parse_program() else {
+        panic!("Expected program node");
+    };
+    assert_eq!(
+        parser.errors(),
+        [
+            ParseError {
+                line: 2,
+                span: 6..10,
+                message: "unexpected indent".to_string(),
+            },
+            ParseError {
+                line: 5,
+                span: 35..43,
+                message: "unexpected indent".to_string(),
+            },
+        ]
+    );
+    // The stray block's DEDENT does not end the function body
+    let [_, _, Node::Function(function)] = program.statements.as_slice() else {
+        panic!("Expected three statements, got {:?}", program.statements);
+    };
+    assert!(
+        matches!(function.body.as_ref(), Node::Block(block) if block.statements.len() == 3),
+        "{:?}",
+        function.body
+    );
+}
+
+#[test]
+fn test_unindent_to_an_unknown_level_is_a_parse_error() {
+    let input = "if x:\n        a = 1\n    b = 2\n    c = 3\n";
+    let mut parser = Parser::new(Lexer::new(input));
+    let Node::Program(program) = parser.parse_program() else {
+        panic!("Expected program node");
+    };
+    assert_eq!(
+        parser.errors(),
+        [ParseError {
+            line: 3,
+            span: 20..24,
+            message: "unindent does not match any outer indentation level".to_string(),
+        }]
+    );
+    // Later lines at the same column are not reported again
+    assert!(
+        matches!(
+            program.statements.as_slice(),
+            [Node::If(_), Node::Assignment(_)]
+        ),
+        "{:?}",
+        program.statements
+    );
+}
+
+#[test]
+fn test_parse_single_line_if_else() {
+    let input = "if x: print(1); print(2)\nelse: print(3)\n";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    match program {
+        Node::Program(prog) => {
+            assert_eq!(prog.statements.len(), 1);
+            match &prog.statements[0] {
+                Node::If(if_stmt) => {
+                    assert!(matches!(*if_stmt.condition, Node::Identifier(_)));
+                    match &*if_stmt.then_branch {
+                        Node::Block(block) => assert_eq!(block.statements.len(), 2),
+                        _ => panic!("Expected two statements in the then branch"),
+                    }
+                    assert!(matches!(
+                        if_stmt.else_branch.as_deref(),
+                        Some(Node::ExpressionStatement(_))
+                    ));
+                }
+                _ => panic!("Expected if statement"),
+            }
+        }
+        _ => panic!("Expected program node"),
+    }
+}
+
+#[test]
+fn test_parse_if_elif_else_blocks() {
+    let input = "if a:\n    x = 1\nelif b:\n    x = 2\nelse:\n    x = 3\nprint(x)\n";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    match program {
+        Node::Program(prog) => {
+            assert_eq!(prog.statements.len(), 2);
+            match &prog.statements[0] {
+                Node::If(if_stmt) => match if_stmt.else_branch.as_deref() {
+                    Some(Node::If(elif)) => {
+                        assert!(matches!(
+                            elif.else_branch.as_deref(),
+                            Some(Node::Assignment(_))
+                        ));
+                    }
+                    _ => panic!("Expected elif branch"),
+                },
+                _ => panic!("Expected if statement"),
+            }
+        }
+        _ => panic!("Expected program node"),
+    }
+}
+
+#[test]
+fn test_parse_module_and_function_docstrings() {
+    let input = r#""""Module docs."""
+
+def add(a, b):
+    """Add two numbers."""
+    return a + b
+
+def noop():
+    """Only a docstring."""
+"#;
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    match program {
+        Node::Program(prog) => {
+            assert_eq!(prog.docstring.as_deref(), Some("Module docs."));
+            assert_eq!(prog.statements.len(), 2);
+
+            match &prog.statements[0] {
+                Node::Function(function) => {
+                    assert_eq!(function.docstring.as_deref(), Some("Add two numbers."));
+                    assert!(matches!(*function.body, Node::Return(_)));
+                }
+                _ => panic!("Expected function definition"),
+            }
+
+            match &prog.statements[1] {
+                Node::Function(function) => {
+                    assert_eq!(function.docstring.as_deref(), Some("Only a docstring."));
+                    match &*function.body {
+                        Node::Block(block) => assert!(block.statements.is_empty()),
+                        _ => panic!("Expected empty body"),
+                    }
+                }
+                _ => panic!("Expected function definition"),
+            }
+        }
+        _ => panic!("Expected program node"),
+    }
+}
+
+#[test]
+fn test_parse_string_after_code_is_not_docstring() {
+    let input = "x = 1\n\"not a docstring\"\n";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    match program {
+        Node::Program(prog) => {
+            assert_eq!(prog.docstring, None);
+            assert_eq!(prog.statements.len(), 2);
+        }
+        _ => panic!("Expected program node"),
+    }
+}
+
+#[test]
+fn test_parse_variable_annotations() {
+    let input = "x: int = 5\ny: dict[str, list[int]]\nz: int | None = None\n";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    match program {
+        Node::Program(prog) => {
+            assert_eq!(prog.statements.len(), 3);
+
+            match &prog.statements[0] {
+                Node::Assignment(assignment) => {
+                    assert_eq!(assignment.names().collect::<Vec<_>>(), ["x"]);
+                    assert_eq!(assignment.annotation.as_deref(), Some("int"));
+                }
+                _ => panic!("Expected annotated assignment"),
+            }
+
+            match &prog.statements[1] {
+                Node::Declaration(declaration) => {
+                    assert_eq!(declaration.name, "y");
+                    assert_eq!(declaration.annotation, "dict[str, list[int]]");
+                }
+                _ => panic!("Expected bare annotation"),
+            }
+
+            match &prog.statements[2] {
+                Node::Assignment(assignment) => {
+                    assert_eq!(assignment.annotation.as_deref(), Some("int | None"));
+                }
+                _ => panic!("Expected annotated assignment"),
+            }
+        }
+        _ => panic!("Expected program node"),
+    }
+}
+
+#[test]
+fn test_parse_function_annotations() {
+    let input = "def f(a: int, b, c: typing.Optional[str]) -> str:\n    return b\n";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    match program {
+        Node::Program(prog) => match &prog.statements[0] {
+            Node::Function(function) => {
+                assert_eq!(function.parameters, vec!["a", "b", "c"]);
+                assert_eq!(
+                    function.parameter_types,
+                    vec![
+                        Some("int".to_string()),
+                        None,
+                        Some("typing.Optional[str]".to_string())
+                    ]
+                );
+                assert_eq!(function.return_type.as_deref(), Some("str"));
+            }
+            _ => panic!("Expected function definition"),
+        },
+        _ => panic!("Expected program node"),
+    }
+}
+
+#[test]
+fn test_parse_power_binds_tighter_than_unary_minus() {
+    let lexer = Lexer::new("-2 ** 2; 2 ** -1;");
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    match program {
+        Node::Program(prog) => {
+            match &prog.statements[0] {
+                Node::ExpressionStatement(expr_stmt) => match &*expr_stmt.expression {
+                    Node::Unary(unary) => {
+                        assert!(matches!(
+                            &*unary.operand,
+                            Node::Binary(Binary {
+                                operator: BinaryOperator::Power,
+                                ..
+                            })
+                        ));
+                    }
+                    other => panic!("Expected unary minus, got {other:?}"),
+                },
+                _ => panic!("Expected expression statement"),
+            }
+
+            match &prog.statements[1] {
+                Node::ExpressionStatement(expr_stmt) => match &*expr_stmt.expression {
+                    Node::Binary(binary) => {
+                        assert_eq!(binary.operator, BinaryOperator::Power);
+                        assert!(matches!(&*binary.right, Node::Unary(_)));
+                    }
+                    other => panic!("Expected power, got {other:?}"),
+                },
+                _ => panic!("Expected expression statement"),
+            }
+        }
+        _ => panic!("Expected program node"),
+    }
+}
+
+/// Fully parenthesized form of an expression, showing how operators grouped
+fn parenthesize(node: &Node) -> String {
+    match node {
+        Node::Binary(binary) => {
+            let operator = match binary.operator {
+                BinaryOperator::Add => "+",
+                BinaryOperator::Subtract => "-",
+                BinaryOperator::Multiply => "*",
+                BinaryOperator::Divide => "/",
+                BinaryOperator::FloorDivide => "//",
+                BinaryOperator::Modulo => "%",
+                BinaryOperator::Power => "**",
+                BinaryOperator::Equal => "==",
+                BinaryOperator::NotEqual => "!=",
+                BinaryOperator::Less => "<",
+                BinaryOperator::Greater => ">",
+                BinaryOperator::LessEqual => "<=",
+                BinaryOperator::GreaterEqual => ">=",
+                BinaryOperator::And => "and",
+                BinaryOperator::Or => "or",
+            };
+            format!(
+                "({} {} {})",
+                parenthesize(&binary.left),
+                operator,
+                parenthesize(&binary.right)
+            )
+        }
+        Node::Unary(unary) => {
+            let operator = match unary.operator {
+                UnaryOperator::Plus => "+",
+                UnaryOperator::Minus => "-",
+                UnaryOperator::Not => "not ",
+            };
+            format!("({}{})", operator, parenthesize(&unary.operand))
+        }
+        Node::Await(await_expr) => format!("(await {})", parenthesize(&await_expr.value)),
+        Node::Identifier(identifier) => identifier.name.clone(),
+        Node::Literal(Literal {
+            value: LiteralValue::Integer(value),
+        }) => value.to_string(),
+        Node::Call(call) => format!("{}()", call.callee),
+        other => panic!("Unexpected node {other:?}"),
+    }
+}
+
+#[test]
+fn test_parse_operator_precedence() {
+    let cases = [
+        ("a or b and c", "(a or (b and c))"),
+        ("a and b or c and d", "((a and b) or (c and d))"),
+        ("not a and b", "((not a) and b)"),
+        ("not a == b", "(not (a == b))"),
+        ("not not a", "(not (not a))"),
+        ("a + 1 < b * 2", "((a + 1) < (b * 2))"),
+        ("a != b or a >= b", "((a != b) or (a >= b))"),
+        ("a - b - c", "((a - b) - c)"),
+        ("a / b * c // d % e", "((((a / b) * c) // d) % e)"),
+        ("a + b * c ** d", "(a + (b * (c ** d)))"),
+        ("a ** b ** c", "(a ** (b ** c))"),
+        ("-a ** -b", "(-(a ** (-b)))"),
+        ("-a * b", "((-a) * b)"),
+        ("2 ** 3 * 4", "((2 ** 3) * 4)"),
+        ("await f() ** 2", "((await f()) ** 2)"),
+        ("(a or b) and c", "((a or b) and c)"),
+    ];
+    for (source, expected) in cases {
+        let mut parser = Parser::new(Lexer::new(source));
+        let Node::Program(program) = parser.parse_program() else {
+            panic!("Expected program node");
+        };
+        assert!(
+            parser.errors().is_empty(),
+            "{source}: {:?}",
+            parser.errors()
+        );
+        match program.statements.as_slice() {
+            [Node::ExpressionStatement(expr_stmt)] => {
+                assert_eq!(parenthesize(&expr_stmt.expression), expected, "{source}");
+            }
+            other => panic!("{source}: expected one expression, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_chained_comparison_is_a_parse_error() {
+    let mut parser = Parser::new(Lexer::new(
+        "print(a < b < c)
+x = 1
+",
+    ));
+    let Node::Program(program) = parser.parse_program() else {
+        panic!("Expected program node");
+    };
+    assert_eq!(
+        parser.errors(),
+        &[ParseError {
+            line: 1,
+            span: 12..13,
+            message: "chained comparisons are not supported".to_string(),
+        }]
+    );
+    assert!(matches!(
+        program.statements.as_slice(),
+        [Node::Assignment(_)]
+    ));
+}
+
+#[test]
+fn test_parse_import_and_dotted_call() {
+    let input = "import os.path\nprint(os.path.join(\"a\", \"b\"))\n";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    match program {
+        Node::Program(prog) => {
+            assert_eq!(prog.statements.len(), 2);
+            assert_eq!(
+                prog.statements[0],
+                Node::Import(Import {
+                    module: "os.path".to_string(),
+                    line: 1,
+                })
+            );
+
+            match &prog.statements[1] {
+                Node::ExpressionStatement(expr_stmt) => match &*expr_stmt.expression {
+                    Node::Call(call) => match &call.arguments[0] {
+                        Node::Call(inner) => {
+                            assert_eq!(inner.callee, "os.path.join");
+                            assert_eq!(inner.arguments.len(), 2);
+                        }
+                        other => panic!("Expected dotted call, got {other:?}"),
+                    },
+                    other => panic!("Expected call, got {other:?}"),
+                },
+                _ => panic!("Expected expression statement"),
+            }
+        }
+        _ => panic!("Expected program node"),
+    }
+}
+
+#[test]
+fn test_parse_subscript() {
+    let lexer = Lexer::new("home = os.environ[\"HOME\"]\n");
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    match program {
+        Node::Program(prog) => match &prog.statements[0] {
+            Node::Assignment(assignment) => assert_eq!(
+                *assignment.value,
+                Node::Subscript(Subscript {
+                    value: Box::new(Node::Identifier(Identifier {
+                        name: "os.environ".to_string(),
+                    })),
+                    index: Box::new(Node::Literal(Literal {
+                        value: LiteralValue::String("HOME".to_string()),
+                    })),
+                })
+            ),
+            other => panic!("Expected assignment, got {other:?}"),
+        },
+        _ => panic!("Expected program node"),
+    }
+}
+
+#[test]
+fn test_parse_assignment_targets() {
+    let mut parser = Parser::new(Lexer::new("a = b = 0\nd[\"k\"] = v\nobj.x = 1\n"));
+    let Node::Program(program) = parser.parse_program() else {
+        panic!("Expected program node");
+    };
+    assert!(parser.errors().is_empty());
+
+    let name = |name: &str| {
+        Node::Identifier(Identifier {
+            name: name.to_string(),
+        })
+    };
+    let targets: Vec<&[Node]> = program
+        .statements
+        .iter()
+        .map(|statement| match statement {
+            Node::Assignment(assignment) => assignment.targets.as_slice(),
+            other => panic!("Expected assignment, got {other:?}"),
+        })
+        .collect();
+    assert_eq!(targets[0], [name("a"), name("b")]);
+    assert_eq!(
+        targets[1],
+        [Node::Subscript(Subscript {
+            value: Box::new(name("d")),
+            index: Box::new(Node::Literal(Literal {
+                value: LiteralValue::String("k".to_string()),
+            })),
+        })]
+    );
+    assert_eq!(targets[2], [name("obj.x")]);
+}
+
+#[test]
+fn test_invalid_assignment_target_is_a_parse_error() {
+    let mut parser = Parser::new(Lexer::new("a = f() = 1\n1 = x\ny = 2\n"));
+    let Node::Program(program) = parser.parse_program() else {
+        panic!("Expected program node");
+    };
+    let messages: Vec<&str> = parser
+        .errors()
+        .iter()
+        .map(|error| error.message.as_str())
+        .collect();
+    assert_eq!(
+        messages,
+        ["cannot assign to function call", "cannot assign to literal"]
+    );
+    assert!(matches!(
+        program.statements.as_slice(),
+        [Node::Assignment(_)]
+    ));
+}
+
+#[test]
+fn test_parse_keyword_arguments() {
+    let mut parser = Parser::new(Lexer::new("print(\"msg\", file=sys.stderr)\n"));
+    let Node::Program(program) = parser.parse_program() else {
+        panic!("Expected program node");
+    };
+    let Node::ExpressionStatement(statement) = &program.statements[0] else {
+        panic!("Expected expression statement");
+    };
+    assert_eq!(
+        *statement.expression,
+        Node::Call(Call {
+            callee: "print".to_string(),
+            arguments: vec![Node::Literal(Literal {
+                value: LiteralValue::String("msg".to_string()),
+            })],
+            keywords: vec![(
+                Some("file".to_string()),
+                Node::Identifier(Identifier {
+                    name: "sys.stderr".to_string(),
+                })
+            )],
+        })
+    );
+}
+
+#[test]
+fn test_parse_unpacked_arguments() {
+    let mut parser = Parser::new(Lexer::new("f(1, *args, 2, a=3, *rest, **options, b=4)\n"));
+    let Node::Program(program) = parser.parse_program() else {
+        panic!("Expected program node");
+    };
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    let Node::ExpressionStatement(statement) = &program.statements[0] else {
+        panic!("Expected expression statement");
+    };
+    let name = |name: &str| {
+        Node::Identifier(Identifier {
+            name: name.to_string(),
+        })
+    };
+    let int = |value| {
+        Node::Literal(Literal {
+            value: LiteralValue::Integer(value),
+        })
+    };
+    let starred = |value: &str| {
+        Node::Starred(Starred {
+            value: Box::new(name(value)),
+        })
+    };
+    assert_eq!(
+        *statement.expression,
+        Node::Call(Call {
+            callee: "f".to_string(),
+            arguments: vec![int(1), starred("args"), int(2), starred("rest")],
+            keywords: vec![
+                (Some("a".to_string()), int(3)),
+                (None, name("options")),
+                (Some("b".to_string()), int(4)),
+            ],
+        })
+    );
+}
+
+#[test]
+fn test_invalid_keyword_arguments_are_parse_errors() {
+    for (source, message) in [
+        (
+            "f(a=1, 2)\n",
+            "positional argument follows keyword argument",
+        ),
+        ("f(a=1, a=2)\n", "keyword argument repeated: a"),
+        (
+            "f(**k, 2)\n",
+            "positional argument follows keyword argument unpacking",
+        ),
+        (
+            "f(**k, *a)\n",
+            "iterable argument unpacking follows keyword argument unpacking",
+        ),
+    ] {
+        let mut parser = Parser::new(Lexer::new(source));
+        parser.parse_program();
+        let messages: Vec<&str> = parser
+            .errors()
+            .iter()
+            .map(|error| error.message.as_str())
+            .collect();
+        assert_eq!(messages, [message], "{source}");
+    }
+}
+
+#[test]
+fn test_every_rejected_statement_is_a_parse_error() {
+    for (source, message, span) in [
+        ("print(1 2)\n", "invalid syntax", 8..9),
+        ("def f(x) print(x)\n", "expected ':'", 9..14),
+        ("if x print(x)\n", "expected ':'", 5..10),
+        ("def f():\nprint(1)\n", "expected an indented block", 9..14),
+        (
+            "d = {1: 2, 3 4}\n",
+            "':' expected after dictionary key",
+            13..14,
+        ),
+        ("async print(1)\n", "invalid syntax", 6..11),
+        ("def f():\n    return )\n", "invalid syntax", 20..21),
+        ("x: list[int = 1\n", "invalid syntax", 12..13),
+    ] {
+        let mut parser = Parser::new(Lexer::new(source));
+        parser.parse_program();
+        assert_eq!(
+            parser.errors(),
+            [ParseError {
+                line: source[..span.start].matches('\n').count() + 1,
+                span,
+                message: message.to_string(),
+            }],
+            "{source}"
+        );
+    }
+}
+
+#[test]
+fn test_rejected_statement_skips_its_block() {
+    let mut parser = Parser::new(Lexer::new("while x:\n    x = 0\nprint(x)\n"));
+    let Node::Program(program) = parser.parse_program() else {
+        panic!("Expected program node");
+    };
+    let messages: Vec<&str> = parser
+        .errors()
+        .iter()
+        .map(|error| error.message.as_str())
+        .collect();
+    assert_eq!(messages, ["invalid syntax"]);
+    // The statement after the block is not lost with it
+    assert!(matches!(
+        program.statements.as_slice(),
+        [Node::ExpressionStatement(_)]
+    ));
+}
+
+#[test]
+fn test_parse_async_function_and_await() {
+    let input = "async def fetch():\n    return await load(1)\n";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    match program {
+        Node::Program(prog) => match &prog.statements[0] {
+            Node::Function(function) => {
+                assert!(function.is_async);
+                assert_eq!(function.name, "fetch");
+                let Node::Return(return_stmt) = function.body.as_ref() else {
+                    panic!("Expected return, got {:?}", function.body);
+                };
+                match return_stmt.value.as_deref() {
+                    Some(Node::Await(await_expr)) => {
+                        assert!(
+                            matches!(*await_expr.value, Node::Call(ref call) if call.callee == "load")
+                        );
+                    }
+                    other => panic!("Expected await, got {other:?}"),
+                }
+            }
+            other => panic!("Expected function, got {other:?}"),
+        },
+        _ => panic!("Expected program node"),
+    }
+}
+
+#[test]
+fn test_lexer_errors_become_parse_errors() {
+    let input = "x = 1\ny = \"open\nprint(x) ? 2\n";
+    let mut parser = Parser::new(Lexer::new(input));
+    parser.parse_program();
+
+    assert_eq!(
+        parser.errors(),
+        &[
+            ParseError {
+                line: 2,
+                span: 10..15,
+                message: "unterminated string literal (detected at line 2)".to_string(),
+            },
+            ParseError {
+                line: 3,
+                span: 25..26,
+                message: "invalid character '?' (U+003F)".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_valid_program_has_no_parse_errors() {
+    let mut parser = Parser::new(Lexer::new(
+        "s = 'a'\nt = \"\"\"b\nc\"\"\"\nprint(f\"{s}\")\n",
+    ));
+    parser.parse_program();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+}
+
+#[test]
+fn test_deeply_nested_expression_is_a_parse_error() {
+    let depth = 100_000;
+    let input = format!(
+        "x = {}1{}\nprint(2)\n",
+        "(".repeat(depth),
+        ")".repeat(depth)
+    );
+    let mut parser = Parser::new(Lexer::new(&input));
+    let program = parser.parse_program();
+
+    assert_eq!(parser.errors().len(), 1, "{:?}", parser.errors());
+    let error = &parser.errors()[0];
+    assert_eq!(error.message, "expression too deeply nested");
+    assert_eq!(error.line, 1);
+    // Reported at the first parenthesis past the limit
+    assert_eq!(error.span, 4 + DEFAULT_MAX_DEPTH..5 + DEFAULT_MAX_DEPTH);
+
+    // Parsing resumes with the next statement
+    let Node::Program(program) = program else {
+        panic!("Expected program node");
+    };
+    assert_eq!(program.statements.len(), 1, "{:?}", program.statements);
+    assert!(matches!(
+        &program.statements[0],
+        Node::ExpressionStatement(_)
+    ));
+}
+
+#[test]
+fn test_nesting_within_the_limit_parses() {
+    // The outermost expression is the first level
+    let depth = DEFAULT_MAX_DEPTH - 1;
+    let input = format!("x = {}1{}\n", "(".repeat(depth), ")".repeat(depth));
+    let mut parser = Parser::new(Lexer::new(&input));
+    parser.parse_program();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+}
+
+#[test]
+fn test_max_depth_is_configurable() {
+    let input = "x = - - 1\ndef a():\n    def b():\n        def c():\n            return\n";
+    let mut parser = Parser::new(Lexer::new(input));
+    parser.set_max_depth(2);
+    parser.parse_program();
+    let messages: Vec<&str> = parser
+        .errors()
+        .iter()
+        .map(|error| error.message.as_str())
+        .collect();
+    assert_eq!(
+        messages,
+        ["expression too deeply nested", "block too deeply nested"]
+    );
+}
+
+#[test]
+fn test_unclosed_bracket_stops_at_the_token_budget() {
+    // Everything after the bracket is part of one long statement
+    let input = format!("print(1{}\n", ", 1".repeat(600_000));
+    let mut parser = Parser::new(Lexer::new(&input));
+    let program = parser.parse_program();
+
+    assert_eq!(parser.errors().len(), 1, "{:?}", parser.errors().first());
+    let error = &parser.errors()[0];
+    assert_eq!(error.message, "'(' was never closed");
+    assert_eq!(error.line, 1);
+    assert_eq!(error.span, 5..6);
+    let Node::Program(program) = program else {
+        panic!("Expected program node");
+    };
+    assert!(program.statements.is_empty());
+}
+
+#[test]
+fn test_statement_token_budget_is_configurable() {
+    let input = format!("x = {}1\nprint(x)\n", "1 + ".repeat(600));
+    let mut parser = Parser::new(Lexer::new(&input));
+    parser.set_max_statement_tokens(1000);
+    parser.parse_program();
+    let messages: Vec<&str> = parser
+        .errors()
+        .iter()
+        .map(|error| error.message.as_str())
+        .collect();
+    assert_eq!(messages, ["statement too long (more than 1000 tokens)"]);
+
+    // Each logical line gets its own budget
+    let input = "x = 1 + 2\n".repeat(1000);
+    let mut parser = Parser::new(Lexer::new(&input));
+    parser.set_max_statement_tokens(10);
+    parser.parse_program();
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+}
+
+#[test]
+fn test_programs_over_the_size_limit_are_not_parsed() {
+    let mut parser = Parser::new(Lexer::new("print(1)\nprint(2)\n"));
+    parser.set_max_source_len(10);
+    let program = parser.parse_program();
+    assert_eq!(
+        parser.errors(),
+        [ParseError {
+            line: 1,
+            span: 0..0,
+            message: "program too long (18 characters, the limit is 10)".to_string(),
+        }]
+    );
+    let Node::Program(program) = program else {
+        panic!("Expected program node");
+    };
+    assert!(program.statements.is_empty());
+}
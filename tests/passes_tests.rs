@@ -0,0 +1,87 @@
+use pycc::ast::{LiteralValue, Node};
+use pycc::passes::{Diagnostics, Pass, PassManager, TypeCheck};
+use pycc::{Lexer, Parser};
+
+/// Warns about module-level names that are not UPPER_CASE
+struct ConstantNames;
+
+impl Pass for ConstantNames {
+    fn name(&self) -> &str {
+        "constant-names"
+    }
+
+    fn run(&mut self, program: &mut Node, diagnostics: &mut Diagnostics) {
+        let Node::Program(program) = program else {
+            return;
+        };
+        for statement in &program.statements {
+            if let Node::Assignment(assignment) = statement {
+                for name in assignment.names() {
+                    if name != name.to_uppercase() {
+                        diagnostics.warning(assignment.line, format!("'{name}' is not UPPER_CASE"));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Replaces every integer literal with its double
+struct DoubleIntegers;
+
+impl Pass for DoubleIntegers {
+    fn name(&self) -> &str {
+        "double-integers"
+    }
+
+    fn run(&mut self, program: &mut Node, _diagnostics: &mut Diagnostics) {
+        let Node::Program(program) = program else {
+            return;
+        };
+        for statement in &mut program.statements {
+            if let Node::Assignment(assignment) = statement
+                && let Node::Literal(literal) = assignment.value.as_mut()
+                && let LiteralValue::Integer(value) = &mut literal.value
+            {
+                *value *= 2;
+            }
+        }
+    }
+}
+
+fn parse(source: &str) -> Node {
+    Parser::new(Lexer::new(source)).parse_program()
+}
+
+#[test]
+fn test_passes_run_in_registration_order() {
+    let mut passes = PassManager::new();
+    passes
+        .register(TypeCheck)
+        .register(DoubleIntegers)
+        .register_before("typecheck", ConstantNames);
+    assert_eq!(
+        passes.names(),
+        ["constant-names", "typecheck", "double-integers"]
+    );
+
+    let mut program = parse("LIMIT = 2\nlimit: int = \"high\"\n");
+    let diagnostics = passes.run(&mut program);
+    assert!(diagnostics.has_errors());
+    assert_eq!(diagnostics.warnings.len(), 1);
+    assert_eq!(diagnostics.warnings[0].pass, "constant-names");
+    assert_eq!(diagnostics.warnings[0].diagnostic.line, 2);
+    assert_eq!(diagnostics.errors.len(), 1);
+    assert_eq!(diagnostics.errors[0].pass, "typecheck");
+}
+
+#[test]
+fn test_passes_can_rewrite_the_program() {
+    let mut passes = PassManager::new();
+    passes.register(DoubleIntegers);
+
+    let mut program = parse("X = 21\n");
+    let diagnostics = passes.run(&mut program);
+    assert!(!diagnostics.has_errors());
+    assert_eq!(program, parse("X = 42\n"));
+}
@@ -0,0 +1,124 @@
+use pycc::ast::{LiteralValue, Node};
+use pycc::codegen::CodegenOptions;
+use pycc::lexer::Token;
+use pycc::pipeline::{self, Pipeline};
+use std::cell::RefCell;
+
+#[test]
+fn test_hooks_run_after_each_phase() {
+    let phases = RefCell::new(Vec::new());
+    let mut token_count = 0;
+    let mut function_names = Vec::new();
+
+    let mut pipeline = Pipeline::new(CodegenOptions::default());
+    pipeline
+        .on_module(|module| {
+            phases.borrow_mut().push("module");
+            function_names.extend(
+                module
+                    .get_functions()
+                    .map(|function| function.get_name().to_string_lossy().into_owned()),
+            );
+        })
+        .on_analysis(|_, diagnostics| {
+            phases.borrow_mut().push("analysis");
+            assert!(diagnostics.is_empty());
+        })
+        .on_ast(|_| phases.borrow_mut().push("ast"))
+        .on_tokens(|tokens| {
+            phases.borrow_mut().push("tokens");
+            token_count = tokens.len();
+            assert_eq!(tokens[0], Token::Identifier("print".to_string()));
+        });
+
+    let ir = pipeline.compile("print(42)\n").unwrap();
+    drop(pipeline);
+    assert!(ir.contains("define i32 @main("), "{ir}");
+    assert_eq!(phases.into_inner(), ["tokens", "ast", "analysis", "module"]);
+    // print ( 42 ) and the newline
+    assert_eq!(token_count, 5);
+    assert!(function_names.contains(&"main".to_string()));
+}
+
+#[test]
+fn test_ast_hooks_can_rewrite_the_program() {
+    let mut pipeline = Pipeline::new(CodegenOptions::default());
+    pipeline.on_ast(|program| {
+        if let Node::Program(program) = program
+            && let Node::ExpressionStatement(statement) = &mut program.statements[0]
+            && let Node::Call(call) = statement.expression.as_mut()
+            && let Node::Literal(literal) = &mut call.arguments[0]
+        {
+            literal.value = LiteralValue::Integer(1234);
+        }
+    });
+    let ir = pipeline.compile("print(42)\n").unwrap();
+    assert!(ir.contains("1234"), "{ir}");
+}
+
+#[test]
+fn test_failed_phases_stop_the_pipeline() {
+    let mut reached_module = false;
+    let mut pipeline = Pipeline::new(CodegenOptions {
+        typed: true,
+        ..CodegenOptions::default()
+    });
+    pipeline.on_module(|_| reached_module = true);
+
+    let errors = pipeline.compile("x: int = \"text\"\n").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line, 1);
+
+    let errors = pipeline.compile("print(\n").unwrap_err();
+    assert!(!errors.is_empty());
+    drop(pipeline);
+    assert!(!reached_module);
+}
+
+#[test]
+fn test_programs_compile_on_several_threads_at_once() {
+    let builds: Vec<_> = (0..8)
+        .map(|i| {
+            std::thread::spawn(move || {
+                let source = format!("def f(n):\n    return n * {i}\n\nprint(f(3))\n");
+                let path = std::env::temp_dir()
+                    .join(format!("pycc-pipeline-{}-{i}", std::process::id()))
+                    .to_string_lossy()
+                    .into_owned();
+                let ir = pipeline::compile_to_ir(&source, &CodegenOptions::default()).unwrap();
+                pipeline::build_executable(&source, &CodegenOptions::default(), &path).unwrap();
+                let output = std::process::Command::new(&path).output().unwrap();
+                let _ = std::fs::remove_file(&path);
+                (ir, String::from_utf8_lossy(&output.stdout).into_owned())
+            })
+        })
+        .collect();
+
+    for (i, build) in builds.into_iter().enumerate() {
+        let (ir, output) = build.join().unwrap();
+        assert!(ir.contains("define i32 @main("), "{ir}");
+        assert_eq!(output, format!("{}\n", 3 * i));
+    }
+}
+
+#[test]
+fn test_compiled_programs_read_their_input() {
+    let source = "name = input()\nprint(\"hello\", name)\n";
+    let executable = pipeline::compile_and_link(source, &CodegenOptions::default()).unwrap();
+    let output = pipeline::run_compiled(&executable, b"world\n");
+    let _ = std::fs::remove_file(&executable);
+    let output = output.unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hello world\n");
+
+    let error = pipeline::compile_and_link("print(\n", &CodegenOptions::default()).unwrap_err();
+    assert!(error.contains("syntax error"), "{error}");
+
+    // Extra linker arguments reach the same linker command as `pycc compile`
+    let options = CodegenOptions {
+        link_args: vec!["-Wl,--pycc-no-such-flag".to_string()],
+        ..CodegenOptions::default()
+    };
+    let error = pipeline::compile_and_link("print(1)\n", &options).unwrap_err();
+    assert!(error.contains("Linking failed"), "{error}");
+}
@@ -0,0 +1,207 @@
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn pycc_run(source: &str, args: &[&str]) -> std::process::Output {
+    let temp_dir = TempDir::new().unwrap();
+    let source_path = temp_dir.path().join("program.py");
+    fs::write(&source_path, source).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_pycc"))
+        .arg("run")
+        .arg(&source_path)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_run_prints_program_output() {
+    let output = pycc_run("print(6 * 7)\n", &[]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "42\n");
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_run_reports_compile_errors_on_stderr() {
+    let output = pycc_run("print(missing(1))\n", &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.ends_with("program.py:1: error: Undefined function: missing\n"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn test_run_reports_syntax_errors() {
+    let output = pycc_run("print(1)\nname = \"pycc\nprint(name)\n", &[]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(
+            "program.py:2: syntax error: unterminated string literal (detected at line 2)\n"
+        ),
+        "{stderr}"
+    );
+    assert!(stderr.ends_with("Found 1 syntax error(s)\n"), "{stderr}");
+}
+
+#[test]
+fn test_run_reports_every_syntax_error() {
+    let output = pycc_run("print(1 2)\nx = 1\nif x print(x)\nprint(x)\n", &[]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("program.py:1: syntax error: invalid syntax\n"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("program.py:3: syntax error: expected ':'\n"),
+        "{stderr}"
+    );
+    assert!(stderr.ends_with("Found 2 syntax error(s)\n"), "{stderr}");
+}
+
+#[test]
+fn test_run_warns_about_statements_with_no_effect() {
+    let output = pycc_run("x = 1\nx + 2\nprint(x)\n", &[]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.ends_with("program.py:2: warning: statement has no effect\n"),
+        "{stderr}"
+    );
+    assert_eq!(stderr.lines().count(), 1, "{stderr}");
+}
+
+#[test]
+fn test_run_forwards_sys_exit_status() {
+    let output = pycc_run(
+        "import sys\nprint(\"bye\")\nsys.exit(4)\nprint(\"unreachable\")\n",
+        &[],
+    );
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "bye\n");
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_run_keeps_output_before_runtime_errors() {
+    let source = "print(\"before\")\ndef forever(n):\n    return forever(n)\nprint(forever(1))\n";
+    let output = pycc_run(source, &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "before\n");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "RecursionError: maximum recursion depth exceeded\n"
+    );
+}
+
+#[test]
+fn test_run_raises_zero_division_error_for_runtime_divisors() {
+    let functions = "def div(a, b):\n    return a // b\n\ndef mod(a, b):\n    return a % b\n\n";
+    let source = format!(
+        "{functions}smallest = -9223372036854775807 - 1\nprint(div(7, -1), mod(smallest, -1), div(-7, 2), mod(-7, 2))\nprint(mod(7, 0))\n"
+    );
+    let output = pycc_run(&source, &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "-7 0 -4 1\n");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "ZeroDivisionError: integer modulo by zero\n"
+    );
+
+    let output = pycc_run(&format!("{functions}print(div(1, 0))\n"), &[]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "ZeroDivisionError: integer division or modulo by zero\n"
+    );
+}
+
+#[test]
+fn test_run_propagates_exit_status() {
+    let output = pycc_run("import os\nprint(os.system(\"exit 1\") // 256)\n", &[]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n");
+
+    let output = pycc_run("import os\nstatus = os.system(\"kill -9 $PPID\")\n", &[]);
+    assert_eq!(output.status.code(), Some(128 + 9));
+}
+
+#[test]
+fn test_run_reads_stdin_from_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let source_path = temp_dir.path().join("filter.py");
+    fs::write(&source_path, "line = input()\nprint(line + line)\n").unwrap();
+    let input_path = temp_dir.path().join("input.txt");
+    fs::write(&input_path, "ab\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_pycc"))
+        .arg("run")
+        .arg("--stdin")
+        .arg(&input_path)
+        .arg(&source_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "abab\n");
+}
+
+#[test]
+fn test_run_trace_logs_statements_and_assigned_values() {
+    let temp_dir = TempDir::new().unwrap();
+    let source_path = temp_dir.path().join("program.py");
+    fs::write(&source_path, "x = 6 * 7\nprint(x)\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_pycc"))
+        .arg("run")
+        .arg("--trace")
+        .arg(&source_path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "42\n");
+    let path = source_path.display();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        format!("{path}:1: x = 6 * 7\n{path}:1: x = 42\n{path}:2: print(x)\n")
+    );
+}
+
+#[test]
+fn test_run_stats_reports_resource_usage() {
+    let temp_dir = TempDir::new().unwrap();
+    let source_path = temp_dir.path().join("program.py");
+    fs::write(&source_path, "import sys\nprint(1)\nsys.exit(3)\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_pycc"))
+        .arg("run")
+        .arg("--stats")
+        .arg(&source_path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("pycc: elapsed "), "{stderr}");
+    assert!(
+        stderr.contains(", user ") && stderr.contains(", sys "),
+        "{stderr}"
+    );
+    // `import sys`, `print(1)` and `sys.exit(3)`
+    assert!(stderr.trim_end().ends_with(" KiB, 3 steps"), "{stderr}");
+}
+
+#[test]
+fn test_run_parameters_shadow_module_constants() {
+    let source = "N = 10\n\ndef digits(N):\n    return len(str(N))\n\nprint(digits(12345), N)\n";
+    let output = pycc_run(source, &[]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "5 10\n");
+}
@@ -0,0 +1,108 @@
+use pycc::codegen::CodegenOptions;
+use pycc::session::Session;
+use std::time::Duration;
+
+#[test]
+fn test_chunks_share_state_and_report_their_own_output() {
+    let mut session = Session::new();
+
+    let first = session.eval("x = 40\nprint(x)");
+    assert!(first.is_ok(), "{first:?}");
+    assert_eq!(first.output, "40\n");
+
+    let second = session.eval("def bump(n):\n    return n + 2\n");
+    assert!(second.is_ok(), "{second:?}");
+    assert_eq!(second.output, "");
+
+    let third = session.eval("print(bump(x))\n");
+    assert!(third.is_ok(), "{third:?}");
+    assert_eq!(third.output, "42\n");
+    assert_eq!(session.output(), "40\n42\n");
+}
+
+#[test]
+fn test_failed_chunks_are_discarded() {
+    let mut session = Session::new();
+    assert!(session.eval("x = 1\n").is_ok());
+
+    let failed = session.eval("print(missing(x))\n");
+    assert!(!failed.is_ok());
+    assert_eq!(failed.diagnostics.len(), 1);
+
+    let runtime =
+        session.eval("def forever(n):\n    return forever(n)\nprint(x)\nprint(forever(x))\n");
+    assert!(!runtime.is_ok(), "{runtime:?}");
+    assert_eq!(runtime.output, "1\n");
+    assert!(runtime.diagnostics[0].message.contains("RecursionError"));
+
+    assert_eq!(session.source(), "x = 1\n");
+    assert_eq!(session.eval("print(x + 1)").output, "2\n");
+}
+
+#[test]
+fn test_type_errors_use_chunk_lines() {
+    let options = CodegenOptions {
+        typed: true,
+        ..CodegenOptions::default()
+    };
+    let mut session = Session::with_options(options);
+    assert!(session.eval("a: int = 1\nb: int = 2\n").is_ok());
+
+    let result = session.eval("c: int = 3\nd: str = a\n");
+    assert_eq!(result.diagnostics.len(), 1, "{result:?}");
+    assert_eq!(result.diagnostics[0].line, 2);
+    assert_eq!(
+        result.diagnostics[0].message,
+        "cannot assign int to 'd' declared as str"
+    );
+}
+
+#[test]
+fn test_fork_and_reset() {
+    let mut session = Session::new();
+    assert!(session.eval("x = 1\n").is_ok());
+
+    let mut fork = session.clone();
+    assert!(fork.eval("x = 5\n").is_ok());
+    assert_eq!(fork.eval("print(x)\n").output, "5\n");
+    assert_eq!(session.eval("print(x)\n").output, "1\n");
+
+    session.reset();
+    assert_eq!(session.source(), "");
+    assert!(!session.eval("print(x)\n").is_ok());
+}
+
+#[test]
+fn test_long_running_chunks_time_out() {
+    let mut session = Session::new();
+    session.set_timeout(Duration::from_millis(200));
+    assert!(session.eval("import os\n").is_ok());
+
+    let result = session.eval("os.system(\"sleep 5 > /dev/null 2>&1\")\n");
+    assert!(!result.is_ok());
+    assert!(
+        result.diagnostics[0].message.contains("timed out"),
+        "{result:?}"
+    );
+    assert_eq!(session.source(), "import os\n");
+}
+
+#[test]
+fn test_changed_output_of_accepted_chunks_is_reported() {
+    let mut session = Session::new();
+    assert!(session.eval("import os\n").is_ok());
+    // The counter file grows by one line per run, so the chunk prints something new each time
+    let counter_file = tempfile::NamedTempFile::new().unwrap();
+    let counter = counter_file.path().display();
+    let chunk = format!("os.system(\"echo x >> '{counter}'; wc -l < '{counter}'\")\n");
+    assert!(session.eval(&chunk).is_ok());
+
+    let result = session.eval("print(1)\n");
+    assert!(!result.is_ok());
+    assert!(
+        result.diagnostics[0]
+            .message
+            .contains("printed different output"),
+        "{result:?}"
+    );
+}
@@ -0,0 +1,113 @@
+use pycc::lexer::Lexer;
+use pycc::parser::Parser;
+use pycc::typecheck::{self, Type};
+
+fn check(input: &str) -> Vec<String> {
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+    typecheck::check(&program)
+}
+
+#[test]
+fn test_annotation_types() {
+    assert_eq!(Type::from_annotation("int"), Type::Int);
+    assert_eq!(Type::from_annotation("str"), Type::Str);
+    assert_eq!(Type::from_annotation("list[int]"), Type::Any);
+
+    assert!(Type::Float.accepts(Type::Int));
+    assert!(Type::Int.accepts(Type::Bool));
+    assert!(!Type::Int.accepts(Type::Float));
+    assert!(Type::Str.accepts(Type::Any));
+}
+
+#[test]
+fn test_consistent_program_has_no_diagnostics() {
+    let input = r#"
+def area(width: int, height: float) -> float:
+    return width * height
+
+size: int = 3
+name = "box"
+print(area(size, 2))
+print(name + "!")
+"#;
+    assert_eq!(check(input), Vec::<String>::new());
+}
+
+#[test]
+fn test_unannotated_code_is_not_checked() {
+    let input = "def f(x):\n    return x + 1\n\ny = f(\"a\")\ny = 2.5\n";
+    assert!(check(input).is_empty());
+}
+
+#[test]
+fn test_assignment_mismatch() {
+    let diagnostics = check("count: int = \"three\"\ncount = 4.5\n");
+    assert_eq!(
+        diagnostics,
+        vec![
+            "cannot assign str to 'count' declared as int",
+            "cannot assign float to 'count' declared as int",
+        ]
+    );
+}
+
+#[test]
+fn test_call_argument_and_arity_mismatch() {
+    let input = "def greet(name: str) -> str:\n    return \"hi \" + name\n\ngreet(1)\ngreet(\"a\", \"b\")\n";
+    assert_eq!(
+        check(input),
+        vec![
+            "argument 1 of 'greet' expects str, got int",
+            "'greet' expects 1 argument(s), got 2",
+        ]
+    );
+}
+
+#[test]
+fn test_return_and_operand_mismatch() {
+    let input = "def f(x: int) -> str:\n    if x:\n        return x\n    return x + \"a\"\n";
+    assert_eq!(
+        check(input),
+        vec![
+            "in function 'f': returns int, expected str",
+            "in function 'f': unsupported operand types for +: int and str",
+        ]
+    );
+}
+
+#[test]
+fn test_diagnostics_carry_statement_lines() {
+    let input = "x: int = 1\n\ndef f(a: str) -> int:\n    return a\n";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+
+    let diagnostics = typecheck::diagnose(&program);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].line, 4);
+    assert_eq!(
+        diagnostics[0].message,
+        "in function 'f': returns str, expected int"
+    );
+
+    let types = typecheck::variable_types(&program);
+    assert_eq!(types.get(&(None, "x".to_string())), Some(&Type::Int));
+    assert_eq!(
+        types.get(&(Some("f".to_string()), "a".to_string())),
+        Some(&Type::Str)
+    );
+}
+
+#[test]
+fn test_builtins_are_typed_from_the_registry() {
+    let input = "n: int = hash(\"a\")\ns: str = repr(n)\nt: int = input()\nprint(id(n, s))\n";
+    assert_eq!(
+        check(input),
+        vec![
+            "cannot assign str to 't' declared as int",
+            "id() takes exactly one argument (2 given)",
+        ]
+    );
+}